@@ -0,0 +1,117 @@
+#ifndef RSTS_H
+#define RSTS_H
+
+/* 由 cbindgen 生成，请勿手工修改 */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * ABI 版本，不兼容的修改时递增
+ */
+#define RSTS_ABI_VERSION 1
+
+/**
+ * 调用结果
+ */
+typedef enum RstsStatus {
+  RSTS_STATUS_OK = 0,
+  /**
+   * 参数为空、不是合法 UTF-8 或取值无效
+   */
+  RSTS_STATUS_INVALID_ARGUMENT = 1,
+  /**
+   * 导出失败
+   */
+  RSTS_STATUS_EXPORT_FAILED = 2,
+  /**
+   * SQL 转换失败
+   */
+  RSTS_STATUS_CONVERT_FAILED = 3,
+  /**
+   * 图像处理失败
+   */
+  RSTS_STATUS_IMAGE_FAILED = 4,
+  /**
+   * 运行时初始化失败
+   */
+  RSTS_STATUS_RUNTIME_FAILED = 5,
+  /**
+   * 库内部 panic
+   */
+  RSTS_STATUS_PANIC = 6,
+} RstsStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * 返回 ABI 版本
+ */
+uint32_t rsts_abi_version(void);
+
+/**
+ * 最近一次失败的错误信息；没有错误时返回 NULL。
+ * 返回的指针归库所有，不要释放，在同一线程的下一次调用前有效。
+ */
+const char *rsts_last_error(void);
+
+/**
+ * 按 JSON 参数导出 PostgreSQL 表到 CSV，参数格式同 `pg_export_from_json`。
+ * 成功时 `*out_report` 为结果 JSON（行数、文件路径、耗时等），须用 `rsts_string_free` 释放。
+ *
+ * # Safety
+ * `json` 须为以 NUL 结尾的有效字符串，`out_report` 须为有效的可写指针
+ */
+enum RstsStatus rsts_pg_export_from_json(const char *json,
+                                         char **out_report);
+
+/**
+ * 将 SQL 从 `from_db` 方言转换为 `to_db` 方言（mysql/postgresql/sqlite/sqlserver/oracle）。
+ * 成功时 `*out_sql` 须用 `rsts_string_free` 释放。
+ *
+ * # Safety
+ * 字符串参数须为以 NUL 结尾的有效字符串，`out_sql` 须为有效的可写指针
+ */
+enum RstsStatus rsts_convert_sql(const char *sql,
+                                 const char *from_db,
+                                 const char *to_db,
+                                 char **out_sql);
+
+/**
+ * 对图像数据（PNG/JPEG 等）做 Sobel 边缘检测，输出 PNG。
+ * 成功时 `*out_data`/`*out_len` 为结果缓冲，须用 `rsts_bytes_free(out_data, out_len)` 释放。
+ *
+ * # Safety
+ * `data` 须指向至少 `len` 字节的可读内存，输出指针须为有效的可写指针
+ */
+enum RstsStatus rsts_sobel_apply(const uint8_t *data,
+                                 size_t len,
+                                 uint8_t **out_data,
+                                 size_t *out_len);
+
+/**
+ * 释放库返回的字符串，传入 NULL 无操作
+ *
+ * # Safety
+ * `s` 须为本库返回且尚未释放的指针
+ */
+void rsts_string_free(char *s);
+
+/**
+ * 释放库返回的字节缓冲，`len` 须与返回时一致，传入 NULL 无操作
+ *
+ * # Safety
+ * `data`/`len` 须为本库返回且尚未释放的缓冲
+ */
+void rsts_bytes_free(uint8_t *data, size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RSTS_H */
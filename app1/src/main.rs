@@ -5,8 +5,9 @@ use tokio::runtime::Builder;
 use tokio::signal;
 use tokio::task::LocalSet;
 
-// 导入modules目录
-mod modules;
+// 模块树由库（app1_core）编译，二进制只负责启动，避免重复编译与库专用代码的未使用告警
+use app1_core::modules;
+use app1_core::{log_error, log_info};
 
 // 主函数 - 管理程序生命周期
 fn main() {
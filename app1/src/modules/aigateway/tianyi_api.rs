@@ -8,6 +8,8 @@ use serde::Deserialize;
 use serde_json::Value;
 use std::time::{Duration, Instant};
 
+use crate::modules::web::ws_auth::{self, TokenExpired, WsUser};
+
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
 const TIANYI_API_URL: &str = "https://wishub-x6.ctyun.cn/v1/chat/completions";
@@ -94,6 +96,15 @@ impl StreamHandler<Result<Bytes, reqwest::Error>> for TianyiSession {
     }
 }
 
+/// 访问令牌过期时关闭连接
+impl Handler<TokenExpired> for TianyiSession {
+    type Result = ();
+
+    fn handle(&mut self, _: TokenExpired, ctx: &mut Self::Context) {
+        ws_auth::close_expired(ctx);
+    }
+}
+
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for TianyiSession {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
         match msg {
@@ -227,5 +238,6 @@ pub async fn tianyi_ws_route(
     req: HttpRequest,
     stream: web::Payload,
 ) -> Result<HttpResponse, Error> {
-    ws::start(TianyiSession::new(), &req, stream)
+    let user = WsUser::from_request(&req)?;
+    ws_auth::start(TianyiSession::new(), &user, &req, stream)
 }
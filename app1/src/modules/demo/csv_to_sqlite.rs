@@ -1 +1,598 @@
+//! SQLite 导入导出：CSV/JSON 导入到表、表或查询导出为 CSV/JSON、`.dump` 风格的 SQL 脚本
+//!
+//! 导入时目标表不存在则按数据推断列类型建表；表已存在则按列名（不区分大小写）或 `column_map` 映射。
+//! 值以原样绑定，由 SQLite 按列亲和性转换（如文本 "12" 写入 INTEGER 列会存为整数）。
+use anyhow::{Context, Result, bail};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{Connection, params_from_iter};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use utoipa::ToSchema;
 
+use crate::modules::web::sqlite_registry::quote_ident;
+
+/// 数据格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFormat {
+    Csv,
+    Json,
+}
+
+/// 导入选项
+#[derive(Debug, Clone)]
+pub struct SqliteImportOptions {
+    pub table: String,
+    /// CSV 首行为表头；为 false 时列名为 column1、column2…
+    pub header: bool,
+    pub delimiter: u8,
+    /// 导入前清空目标表
+    pub truncate: bool,
+    /// 源列名 → 表列名，映射为空字符串表示跳过该列
+    pub column_map: HashMap<String, String>,
+}
+
+impl Default for SqliteImportOptions {
+    fn default() -> Self {
+        Self {
+            table: String::new(),
+            header: true,
+            delimiter: b',',
+            truncate: false,
+            column_map: HashMap::new(),
+        }
+    }
+}
+
+/// 导入的列
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ImportedColumn {
+    pub source: String,
+    pub target: String,
+    /// 目标列的声明类型
+    pub data_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SqliteImportReport {
+    pub table: String,
+    pub rows: usize,
+    /// 是否新建了表
+    pub created: bool,
+    pub columns: Vec<ImportedColumn>,
+}
+
+/// 从 CSV 导入，空字段按 NULL 处理
+pub fn import_csv(
+    conn: &mut Connection,
+    reader: impl Read,
+    opts: &SqliteImportOptions,
+) -> Result<SqliteImportReport> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(opts.header)
+        .delimiter(opts.delimiter)
+        .from_reader(reader);
+    let mut headers: Vec<String> = if opts.header {
+        rdr.headers()?
+            .iter()
+            .map(|h| h.trim().to_string())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let mut rows = Vec::new();
+    for record in rdr.records() {
+        let record = record.context("parse csv failed")?;
+        if headers.is_empty() {
+            headers = (1..=record.len()).map(|i| format!("column{}", i)).collect();
+        }
+        rows.push(
+            record
+                .iter()
+                .map(|v| match v {
+                    "" => SqlValue::Null,
+                    v => SqlValue::Text(v.to_string()),
+                })
+                .collect(),
+        );
+    }
+    import_rows(conn, headers, rows, opts)
+}
+
+/// 从 JSON 对象数组导入，列为所有对象键的并集（按首次出现顺序）
+pub fn import_json(
+    conn: &mut Connection,
+    data: &Value,
+    opts: &SqliteImportOptions,
+) -> Result<SqliteImportReport> {
+    let Some(items) = data.as_array() else {
+        bail!("json data must be an array of objects");
+    };
+    let mut headers: Vec<String> = Vec::new();
+    for item in items {
+        let Some(obj) = item.as_object() else {
+            bail!("json data must be an array of objects");
+        };
+        for key in obj.keys() {
+            if !headers.contains(key) {
+                headers.push(key.clone());
+            }
+        }
+    }
+    let rows = items
+        .iter()
+        .filter_map(|item| item.as_object())
+        .map(|obj| {
+            headers
+                .iter()
+                .map(|h| json_to_sql(obj.get(h).unwrap_or(&Value::Null)))
+                .collect()
+        })
+        .collect();
+    import_rows(conn, headers, rows, opts)
+}
+
+fn json_to_sql(v: &Value) -> SqlValue {
+    match v {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        // 嵌套结构按 JSON 文本保存
+        Value::Array(_) | Value::Object(_) => SqlValue::Text(v.to_string()),
+    }
+}
+
+/// 推断列类型：全为整数 → INTEGER，全为数值 → REAL，否则 TEXT；全空时为 TEXT
+fn infer_type<'a>(values: impl Iterator<Item = &'a SqlValue>) -> &'static str {
+    let mut ty = None;
+    for v in values {
+        let this = match v {
+            SqlValue::Null => continue,
+            SqlValue::Integer(_) => "INTEGER",
+            SqlValue::Real(_) => "REAL",
+            SqlValue::Text(s) if s.trim().parse::<i64>().is_ok() => "INTEGER",
+            SqlValue::Text(s) if s.trim().parse::<f64>().is_ok() => "REAL",
+            _ => return "TEXT",
+        };
+        ty = match (ty, this) {
+            (None, t) => Some(t),
+            (Some("REAL"), _) | (_, "REAL") => Some("REAL"),
+            (t, _) => t,
+        };
+    }
+    ty.unwrap_or("TEXT")
+}
+
+fn import_rows(
+    conn: &mut Connection,
+    headers: Vec<String>,
+    rows: Vec<Vec<SqlValue>>,
+    opts: &SqliteImportOptions,
+) -> Result<SqliteImportReport> {
+    if opts.table.trim().is_empty() {
+        bail!("table name is required");
+    }
+    if headers.is_empty() {
+        bail!("no columns to import");
+    }
+    let table = quote_ident(&opts.table);
+    let existing: Vec<(String, String)> = {
+        let mut stmt = conn.prepare("SELECT name, type FROM pragma_table_info(?1)")?;
+        stmt.query_map([&opts.table], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?
+    };
+    let created = existing.is_empty();
+
+    // 源列下标与目标列
+    let mut columns: Vec<(usize, ImportedColumn)> = Vec::new();
+    let mut unknown = Vec::new();
+    for (i, source) in headers.iter().enumerate() {
+        let mapped = opts.column_map.get(source).map(|t| t.trim());
+        if mapped == Some("") {
+            continue;
+        }
+        let wanted = mapped.unwrap_or(source);
+        let column = if created {
+            Some(ImportedColumn {
+                source: source.clone(),
+                target: wanted.to_string(),
+                data_type: infer_type(rows.iter().filter_map(|r| r.get(i))).to_string(),
+            })
+        } else {
+            existing
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(wanted))
+                .map(|(name, ty)| ImportedColumn {
+                    source: source.clone(),
+                    target: name.clone(),
+                    data_type: ty.clone(),
+                })
+        };
+        match column {
+            Some(c) => columns.push((i, c)),
+            None => unknown.push(source.clone()),
+        }
+    }
+    if !unknown.is_empty() {
+        bail!(
+            "columns not found in table {}: {}",
+            opts.table,
+            unknown.join(", ")
+        );
+    }
+    if columns.is_empty() {
+        bail!("no columns to import");
+    }
+
+    let tx = conn.transaction()?;
+    if created {
+        let defs: Vec<String> = columns
+            .iter()
+            .map(|(_, c)| format!("{} {}", quote_ident(&c.target), c.data_type))
+            .collect();
+        tx.execute(&format!("CREATE TABLE {} ({})", table, defs.join(", ")), [])?;
+    } else if opts.truncate {
+        tx.execute(&format!("DELETE FROM {}", table), [])?;
+    }
+    let names: Vec<String> = columns
+        .iter()
+        .map(|(_, c)| quote_ident(&c.target))
+        .collect();
+    let placeholders: Vec<String> = (1..=names.len()).map(|i| format!("?{}", i)).collect();
+    let sql = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        table,
+        names.join(", "),
+        placeholders.join(", ")
+    );
+    {
+        let mut stmt = tx.prepare(&sql)?;
+        for (n, row) in rows.iter().enumerate() {
+            let values = columns
+                .iter()
+                .map(|(i, _)| row.get(*i).cloned().unwrap_or(SqlValue::Null));
+            stmt.execute(params_from_iter(values))
+                .with_context(|| format!("insert row {} failed", n + 1))?;
+        }
+    }
+    tx.commit()?;
+
+    Ok(SqliteImportReport {
+        table: opts.table.clone(),
+        rows: rows.len(),
+        created,
+        columns: columns.into_iter().map(|(_, c)| c).collect(),
+    })
+}
+
+/// 导出表或只读查询，返回行数。BLOB 以十六进制文本输出
+pub fn export_query(
+    conn: &Connection,
+    sql: &str,
+    params: &[SqlValue],
+    format: DataFormat,
+    delimiter: u8,
+    out: &mut impl Write,
+) -> Result<usize> {
+    let mut stmt = conn.prepare(sql)?;
+    if !stmt.readonly() {
+        bail!("only read-only queries can be exported");
+    }
+    let names: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
+    let mut rows = stmt.query(params_from_iter(params))?;
+    let mut count = 0usize;
+    match format {
+        DataFormat::Csv => {
+            let mut wtr = csv::WriterBuilder::new()
+                .delimiter(delimiter)
+                .from_writer(out);
+            wtr.write_record(&names)?;
+            while let Some(row) = rows.next()? {
+                let mut record = Vec::with_capacity(names.len());
+                for i in 0..names.len() {
+                    record.push(match row.get_ref(i)? {
+                        ValueRef::Null => String::new(),
+                        ValueRef::Integer(v) => v.to_string(),
+                        ValueRef::Real(v) => v.to_string(),
+                        ValueRef::Text(t) => String::from_utf8_lossy(t).to_string(),
+                        ValueRef::Blob(b) => to_hex(b),
+                    });
+                }
+                wtr.write_record(&record)?;
+                count += 1;
+            }
+            wtr.flush()?;
+        }
+        DataFormat::Json => {
+            out.write_all(b"[")?;
+            while let Some(row) = rows.next()? {
+                let mut record = Map::new();
+                for (i, name) in names.iter().enumerate() {
+                    let v = match row.get_ref(i)? {
+                        ValueRef::Null => Value::Null,
+                        ValueRef::Integer(v) => Value::from(v),
+                        ValueRef::Real(v) => Value::from(v),
+                        ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).to_string()),
+                        ValueRef::Blob(b) => Value::String(to_hex(b)),
+                    };
+                    record.insert(name.clone(), v);
+                }
+                if count > 0 {
+                    out.write_all(b",")?;
+                }
+                serde_json::to_writer(&mut *out, &Value::Object(record))?;
+                count += 1;
+            }
+            out.write_all(b"]")?;
+        }
+    }
+    Ok(count)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut s, b| {
+            let _ = write!(s, "{:02x}", b);
+            s
+        })
+}
+
+/// SQL 字面量，浮点数保留小数点以免恢复后变成整数
+fn sql_literal(v: ValueRef<'_>) -> String {
+    match v {
+        ValueRef::Null => "NULL".to_string(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) if f.is_nan() => "NULL".to_string(),
+        ValueRef::Real(f) if f.is_infinite() => {
+            if f > 0.0 { "1e999" } else { "-1e999" }.to_string()
+        }
+        ValueRef::Real(f) => format!("{:?}", f),
+        ValueRef::Text(t) => format!("'{}'", String::from_utf8_lossy(t).replace('\'', "''")),
+        ValueRef::Blob(b) => format!("X'{}'", to_hex(b)),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SqliteDumpReport {
+    pub tables: usize,
+    pub rows: usize,
+}
+
+/// 生成 `.dump` 风格的脚本：表结构与数据、自增序列，最后是索引、触发器与视图
+pub fn dump_sql(conn: &Connection, out: &mut impl Write) -> Result<SqliteDumpReport> {
+    writeln!(out, "PRAGMA foreign_keys=OFF;")?;
+    writeln!(out, "BEGIN TRANSACTION;")?;
+    let tables: Vec<(String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT name, sql FROM sqlite_master \
+             WHERE type = 'table' AND sql IS NOT NULL \
+             AND (name NOT LIKE 'sqlite_%' OR name = 'sqlite_sequence') \
+             ORDER BY name = 'sqlite_sequence', rowid",
+        )?;
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?
+    };
+    // 虚拟表（如 FTS5）的影子表由 CREATE VIRTUAL TABLE 自动创建，数据经虚拟表本身恢复
+    let shadow: HashSet<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT name FROM pragma_table_list WHERE schema = 'main' AND type = 'shadow'",
+        )?;
+        stmt.query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?
+    };
+    let mut report = SqliteDumpReport { tables: 0, rows: 0 };
+    for (name, sql) in &tables {
+        if shadow.contains(name) {
+            continue;
+        }
+        if name == "sqlite_sequence" {
+            // 序列表由 AUTOINCREMENT 建表时自动创建，只恢复数据
+            writeln!(out, "DELETE FROM sqlite_sequence;")?;
+        } else {
+            writeln!(out, "{};", sql)?;
+            report.tables += 1;
+        }
+        let table = quote_ident(name);
+        let is_virtual = sql
+            .trim_start()
+            .to_uppercase()
+            .starts_with("CREATE VIRTUAL TABLE");
+        // 虚拟表按列名插入并保留 rowid（FTS 常按 rowid 与内容表关联）；不支持 rowid 的只导出各列
+        let select = format!("SELECT * FROM {}", table);
+        let mut stmt = if is_virtual {
+            conn.prepare(&format!("SELECT rowid, * FROM {}", table))
+                .or_else(|_| conn.prepare(&select))?
+        } else {
+            conn.prepare(&select)?
+        };
+        let target = if is_virtual {
+            let names: Vec<String> = stmt.column_names().into_iter().map(quote_ident).collect();
+            format!("{}({})", table, names.join(","))
+        } else {
+            table
+        };
+        let columns = stmt.column_count();
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let mut values = Vec::with_capacity(columns);
+            for i in 0..columns {
+                values.push(sql_literal(row.get_ref(i)?));
+            }
+            writeln!(out, "INSERT INTO {} VALUES({});", target, values.join(","))?;
+            report.rows += 1;
+        }
+    }
+    let mut stmt = conn.prepare(
+        "SELECT sql FROM sqlite_master \
+         WHERE type IN ('index', 'trigger', 'view') AND sql IS NOT NULL ORDER BY rowid",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        writeln!(out, "{};", row.get::<_, String>(0)?)?;
+    }
+    writeln!(out, "COMMIT;")?;
+    Ok(report)
+}
+
+/// 执行 SQL 脚本（如 `dump_sql` 的输出），失败时回滚脚本中未提交的事务
+pub fn restore_sql(conn: &Connection, script: &str) -> Result<()> {
+    if let Err(e) = conn.execute_batch(script) {
+        if !conn.is_autocommit() {
+            let _ = conn.execute_batch("ROLLBACK");
+        }
+        return Err(e).context("restore sql script failed");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_csv_infer_and_map() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let opts = SqliteImportOptions {
+            table: "people".to_string(),
+            ..SqliteImportOptions::default()
+        };
+        let csv_data = "id,name,score\n1,\"Li, Lei\",9.5\n2,Han,\n";
+        let report = import_csv(&mut conn, csv_data.as_bytes(), &opts).unwrap();
+        assert!(report.created);
+        assert_eq!(report.rows, 2);
+        let types: Vec<&str> = report
+            .columns
+            .iter()
+            .map(|c| c.data_type.as_str())
+            .collect();
+        assert_eq!(types, vec!["INTEGER", "TEXT", "REAL"]);
+
+        // 已有表：按映射与不区分大小写的列名导入，整数文本按亲和性存为整数
+        let opts = SqliteImportOptions {
+            table: "people".to_string(),
+            column_map: HashMap::from([("full_name".to_string(), "name".to_string())]),
+            ..SqliteImportOptions::default()
+        };
+        let json: Value = serde_json::json!([{ "ID": "3", "full_name": "Wei" }]);
+        let report = import_json(&mut conn, &json, &opts).unwrap();
+        assert!(!report.created);
+        let (id, score): (i64, Option<f64>) = conn
+            .query_row(
+                "SELECT typeof(id) = 'integer', score FROM people WHERE id = 3",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((id, score), (1, None));
+
+        let bad: Value = serde_json::json!([{ "nope": 1 }]);
+        assert!(import_json(&mut conn, &bad, &opts).is_err());
+    }
+
+    #[test]
+    fn test_export_and_dump_restore() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE t (id INTEGER PRIMARY KEY AUTOINCREMENT, v TEXT, r REAL, b BLOB);
+             INSERT INTO t (v, r, b) VALUES ('it''s', 2.0, X'00ff'), (NULL, 0.1, NULL);
+             CREATE INDEX t_v ON t (v);
+             CREATE VIEW tv AS SELECT v FROM t;",
+        )
+        .unwrap();
+
+        let mut buf = Vec::new();
+        let n = export_query(
+            &conn,
+            "SELECT * FROM t",
+            &[],
+            DataFormat::Csv,
+            b',',
+            &mut buf,
+        )
+        .unwrap();
+        assert_eq!(n, 2);
+        assert!(
+            String::from_utf8(buf)
+                .unwrap()
+                .starts_with("id,v,r,b\n1,it's,2,00ff\n")
+        );
+        let mut buf = Vec::new();
+        export_query(
+            &conn,
+            "SELECT v FROM t ORDER BY id",
+            &[],
+            DataFormat::Json,
+            b',',
+            &mut buf,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"[{"v":"it's"},{"v":null}]"#
+        );
+        assert!(
+            export_query(
+                &conn,
+                "DELETE FROM t",
+                &[],
+                DataFormat::Json,
+                b',',
+                &mut Vec::new()
+            )
+            .is_err()
+        );
+
+        let mut script = Vec::new();
+        let report = dump_sql(&conn, &mut script).unwrap();
+        assert_eq!(report.rows, 3); // 2 行数据 + 1 行 sqlite_sequence
+        let script = String::from_utf8(script).unwrap();
+        let restored = Connection::open_in_memory().unwrap();
+        restore_sql(&restored, &script).unwrap();
+        let (r, b, seq): (String, Vec<u8>, i64) = restored
+            .query_row(
+                "SELECT typeof(r), b, (SELECT seq FROM sqlite_sequence) FROM t WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((r.as_str(), b, seq), ("real", vec![0, 255], 2));
+        assert!(restore_sql(&restored, &script).is_err());
+        assert!(restored.is_autocommit());
+    }
+
+    #[test]
+    fn test_dump_restore_fts5() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE docs USING fts5(title, body);
+             INSERT INTO docs (rowid, title, body) VALUES (7, 'it''s', 'full text search');
+             INSERT INTO docs (rowid, title, body) VALUES (9, 'other', 'plain words');",
+        )
+        .unwrap();
+
+        // 影子表不单独建表，数据经虚拟表写回并保留 rowid
+        let mut script = Vec::new();
+        let report = dump_sql(&conn, &mut script).unwrap();
+        assert_eq!((report.tables, report.rows), (1, 2));
+        let script = String::from_utf8(script).unwrap();
+        assert!(!script.contains("docs_data"));
+        let restored = Connection::open_in_memory().unwrap();
+        restore_sql(&restored, &script).unwrap();
+        let (rowid, title): (i64, String) = restored
+            .query_row(
+                "SELECT rowid, title FROM docs WHERE docs MATCH 'search'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((rowid, title.as_str()), (7, "it's"));
+    }
+}
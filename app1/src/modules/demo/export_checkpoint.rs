@@ -0,0 +1,315 @@
+#![allow(dead_code)]
+//! CSV 导出检查点
+//!
+//! 分区导出时在输出文件旁写入 `<文件名>.manifest.json`，记录分区区间、完成状态、
+//! 行数、字节数、CRC32 校验值以及合并后各分区在最终文件中的偏移。
+//! 重新执行同一导出时读取清单，校验已完成的分片后跳过，只导出未完成的分区。
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use super::pg_to_csv::{PartitionOutput, PartitionRange};
+
+/// 清单格式版本
+const MANIFEST_VERSION: u32 = 1;
+
+/// 检查点清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub version: u32,
+    /// 导出的表（含 schema）
+    pub table: String,
+    /// 数据源（已去除密码的连接串）
+    pub source: String,
+    /// 最终输出文件
+    pub output: String,
+    pub gzip: bool,
+    pub merge_parts: bool,
+    /// 整个导出是否已完成（含合并）
+    pub completed: bool,
+    pub created_at: String,
+    pub updated_at: String,
+    pub partitions: Vec<ManifestPartition>,
+}
+
+/// 清单中的分区记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestPartition {
+    pub index: usize,
+    pub range: PartitionRange,
+    /// 分片文件路径
+    pub path: String,
+    pub completed: bool,
+    pub rows: usize,
+    /// 分片字节数（未压缩）
+    pub bytes: u64,
+    /// 合并后在最终文件中的起始字节偏移（未压缩）
+    pub offset: Option<u64>,
+    /// 分片内容（未压缩）的 CRC32
+    pub crc32: Option<u32>,
+    pub finished_at: Option<String>,
+}
+
+impl ExportManifest {
+    pub fn new(
+        table: &str,
+        dsn: &str,
+        output: &str,
+        gzip: bool,
+        merge_parts: bool,
+        partitions: Vec<(PartitionRange, String)>,
+    ) -> Self {
+        let now = chrono::Local::now().to_rfc3339();
+        Self {
+            version: MANIFEST_VERSION,
+            table: table.to_string(),
+            source: redact_dsn(dsn),
+            output: output.to_string(),
+            gzip,
+            merge_parts,
+            completed: false,
+            created_at: now.clone(),
+            updated_at: now,
+            partitions: partitions
+                .into_iter()
+                .enumerate()
+                .map(|(index, (range, path))| ManifestPartition {
+                    index,
+                    range,
+                    path,
+                    completed: false,
+                    rows: 0,
+                    bytes: 0,
+                    offset: None,
+                    crc32: None,
+                    finished_at: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// 读取清单，文件不存在时返回 `None`
+    pub fn load(path: &str) -> Result<Option<Self>> {
+        if !Path::new(path).exists() {
+            return Ok(None);
+        }
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("read manifest failed: {}", path))?;
+        let manifest: ExportManifest = serde_json::from_str(&text)
+            .with_context(|| format!("parse manifest failed: {}", path))?;
+        if manifest.version != MANIFEST_VERSION {
+            bail!(
+                "unsupported manifest version {} in {}",
+                manifest.version,
+                path
+            );
+        }
+        Ok(Some(manifest))
+    }
+
+    /// 先写临时文件再重命名，避免中途崩溃留下半截清单
+    pub fn save(&self, path: &str) -> Result<()> {
+        let tmp = format!("{}.tmp", path);
+        let data = serde_json::to_vec_pretty(self).context("serialize manifest failed")?;
+        std::fs::write(&tmp, data).with_context(|| format!("write manifest failed: {}", tmp))?;
+        std::fs::rename(&tmp, path).with_context(|| format!("rename manifest failed: {}", path))?;
+        Ok(())
+    }
+
+    /// 判断清单是否可用于继续当前导出；属于其他导出时报错而不是覆盖
+    pub fn check_resumable(
+        &self,
+        table: &str,
+        dsn: &str,
+        gzip: bool,
+        merge_parts: bool,
+    ) -> Result<bool> {
+        if self.completed {
+            return Ok(false);
+        }
+        if self.table != table
+            || self.source != redact_dsn(dsn)
+            || self.gzip != gzip
+            || self.merge_parts != merge_parts
+        {
+            bail!(
+                "checkpoint manifest for {} belongs to a different export ({} from {}), remove it to start over",
+                self.output,
+                self.table,
+                self.source
+            );
+        }
+        Ok(true)
+    }
+
+    /// 已完成分区的导出结果
+    pub fn completed_part(&self, index: usize) -> Option<PartitionOutput> {
+        self.partitions
+            .iter()
+            .find(|p| p.index == index && p.completed)
+            .map(|p| PartitionOutput {
+                index: p.index,
+                path: p.path.clone(),
+                rows: p.rows,
+                bytes: p.bytes,
+                crc32: p.crc32.unwrap_or(0),
+            })
+    }
+
+    /// 记录一个分区已完成
+    pub fn record(&mut self, part: &PartitionOutput) {
+        let now = chrono::Local::now().to_rfc3339();
+        if let Some(p) = self.partitions.iter_mut().find(|p| p.index == part.index) {
+            p.path = part.path.clone();
+            p.completed = true;
+            p.rows = part.rows;
+            p.bytes = part.bytes;
+            p.crc32 = Some(part.crc32);
+            p.finished_at = Some(now.clone());
+        }
+        self.updated_at = now;
+    }
+
+    /// 标记导出完成，并按分区顺序计算各分区在最终文件中的偏移
+    pub fn finish(&mut self) {
+        let mut offset = 0u64;
+        self.partitions.sort_by_key(|p| p.index);
+        for p in self.partitions.iter_mut() {
+            p.offset = Some(offset);
+            offset += p.bytes;
+        }
+        self.completed = true;
+        self.updated_at = chrono::Local::now().to_rfc3339();
+    }
+}
+
+/// 清单文件名：与输出文件同目录，`<文件名主干>.manifest.json`
+pub fn manifest_path_for(filename: &str) -> String {
+    let path = Path::new(filename);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(filename);
+    let name = format!("{}.manifest.json", stem);
+    match path.parent() {
+        Some(parent) => parent.join(name).to_string_lossy().to_string(),
+        None => name,
+    }
+}
+
+/// 去掉连接串中的密码（支持 key=value 与 URL 两种格式）
+pub fn redact_dsn(dsn: &str) -> String {
+    if let Some(scheme_end) = dsn.find("://") {
+        let rest = &dsn[scheme_end + 3..];
+        if let Some(at) = rest.find('@') {
+            let userinfo = &rest[..at];
+            let user = userinfo.split(':').next().unwrap_or("");
+            return format!("{}{}{}", &dsn[..scheme_end + 3], user, &rest[at..]);
+        }
+        return dsn.to_string();
+    }
+    dsn.split_whitespace()
+        .filter(|kv| !kv.starts_with("password="))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 计算文件内容（.gz 按解压后内容）的长度与 CRC32
+pub fn file_checksum(path: &str) -> Result<(u64, u32)> {
+    use flate2::read::GzDecoder;
+    let f = File::open(path).with_context(|| format!("open part for checksum failed: {}", path))?;
+    let mut reader: Box<dyn Read> = if path.ends_with(".gz") {
+        Box::new(GzDecoder::new(f))
+    } else {
+        Box::new(f)
+    };
+    let mut crc = flate2::Crc::new();
+    let mut len = 0u64;
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .with_context(|| format!("read part for checksum failed: {}", path))?;
+        if n == 0 {
+            break;
+        }
+        crc.update(&buf[..n]);
+        len += n as u64;
+    }
+    Ok((len, crc.sum()))
+}
+
+/// 校验已完成分片：文件存在且长度、CRC32 与清单一致
+pub fn verify_part(part: &PartitionOutput) -> bool {
+    match file_checksum(&part.path) {
+        Ok((len, crc)) => len == part.bytes && crc == part.crc32,
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_dsn() {
+        assert_eq!(
+            redact_dsn("host=h port=5432 user=u password=secret dbname=d"),
+            "host=h port=5432 user=u dbname=d"
+        );
+        assert_eq!(
+            redact_dsn("postgres://u:secret@h:5432/d"),
+            "postgres://u@h:5432/d"
+        );
+    }
+
+    #[test]
+    fn test_manifest_roundtrip_and_resume() {
+        let dir = std::env::temp_dir().join(format!("rsts_ckpt_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let part_path = dir.join("t.part0000.csv").to_string_lossy().to_string();
+        std::fs::write(&part_path, b"1,a\n2,b\n").unwrap();
+        let (bytes, crc32) = file_checksum(&part_path).unwrap();
+
+        let range = PartitionRange::Ctid { lo: 0, hi: None };
+        let mut m = ExportManifest::new(
+            "t",
+            "host=h password=x",
+            "t.csv",
+            false,
+            true,
+            vec![(range, part_path.clone())],
+        );
+        let part = PartitionOutput {
+            index: 0,
+            path: part_path.clone(),
+            rows: 2,
+            bytes,
+            crc32,
+        };
+        m.record(&part);
+        let manifest_path = dir.join("t.manifest.json").to_string_lossy().to_string();
+        m.save(&manifest_path).unwrap();
+
+        let loaded = ExportManifest::load(&manifest_path).unwrap().unwrap();
+        assert!(
+            loaded
+                .check_resumable("t", "host=h password=y", false, true)
+                .unwrap()
+        );
+        assert!(
+            loaded
+                .check_resumable("other", "host=h", false, true)
+                .is_err()
+        );
+        let done = loaded.completed_part(0).unwrap();
+        assert!(verify_part(&done));
+
+        // 分片被篡改后校验失败
+        std::fs::write(&part_path, b"1,a\n").unwrap();
+        assert!(!verify_part(&done));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -2,8 +2,12 @@
 // 注意：以下模块可能存在编译问题，仅作为模块结构展示
 
 // 先只暴露第一个模块以验证模块结构
+pub mod csv_to_sqlite;
 pub mod db_datatype_trans;
+pub mod export_checkpoint;
 pub mod math;
 pub mod pg_to_csv;
 pub mod test1;
+pub mod transfer_control;
+#[cfg(windows)]
 pub mod winapi;
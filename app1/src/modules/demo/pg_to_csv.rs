@@ -1,30 +1,66 @@
 #![allow(dead_code)]
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use bytes::Bytes;
-use serde::Deserialize;
-use std::io::Write;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
 use std::path::Path;
-use std::sync::Arc;
-use std::sync::atomic::AtomicUsize;
-use std::time::Duration;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio_postgres::NoTls;
-/// 异步将PostgreSQL数据库表中的数据转换为CSV文件
-///
-/// # Arguments
-///
-/// * `tableName` - 数据库表名
-/// * `filename` - 要写入的CSV文件名
-///
-/// # Returns
+
+use super::export_checkpoint::{ExportManifest, manifest_path_for, verify_part};
+use super::transfer_control::{TransferControl, TransferProgress};
+
+/// 进度回调的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 分区切分策略
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PartitionStrategy {
+    /// 优先按单列整数主键切分，无合适主键时回退到 ctid
+    Auto,
+    /// 按单列整数主键的取值区间切分（分区内按主键排序）
+    PrimaryKey,
+    /// 按物理块（ctid）区间切分
+    Ctid,
+}
+
+impl FromStr for PartitionStrategy {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "auto" => Ok(PartitionStrategy::Auto),
+            "pk" | "primary_key" | "primarykey" => Ok(PartitionStrategy::PrimaryKey),
+            "ctid" => Ok(PartitionStrategy::Ctid),
+            other => bail!("unsupported partition strategy: {}", other),
+        }
+    }
+}
+
+/// CSV 导出选项
 ///
-/// * `Ok(ret)` - 成功将数据写入CSV文件
-/// * `Err(e)` - 写入CSV文件失败，返回错误信息
+/// * `partitions` - 并行连接数（>1 时启用分区导出）
+/// * `rows_per_query` - 每个分区的目标行数（设置后按行数决定分区数量）
+/// * `partition_strategy` - 分区切分方式（主键区间或 ctid 区间）
+/// * `merge_parts` - 是否将有序分片合并为单个输出文件
+/// * `checkpoint` - 是否写入检查点清单并支持断点续传
+/// * `allow_inconsistent_snapshot` - 无法导出快照时是否允许多个分区各自使用独立快照（默认报错）
+/// * `control` - 取消标记与进度监听器
 pub struct CsvExportOptions {
     pub gzip: bool,
     pub buf_size_bytes: usize,
     pub partitions: usize,
     pub rows_per_query: Option<usize>,
+    pub partition_strategy: PartitionStrategy,
+    pub merge_parts: bool,
+    pub checkpoint: bool,
+    pub allow_inconsistent_snapshot: bool,
+    pub control: TransferControl,
 }
 
 impl Default for CsvExportOptions {
@@ -34,10 +70,236 @@ impl Default for CsvExportOptions {
             buf_size_bytes: 32 << 20,
             partitions: 1,
             rows_per_query: None,
+            partition_strategy: PartitionStrategy::Auto,
+            merge_parts: true,
+            checkpoint: false,
+            allow_inconsistent_snapshot: false,
+            control: TransferControl::default(),
+        }
+    }
+}
+
+/// 单个分区的导出结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PartitionOutput {
+    /// 分区序号（决定分片顺序）
+    pub index: usize,
+    /// 分片文件路径
+    pub path: String,
+    /// 写入的CSV行数
+    pub rows: usize,
+    /// 写入的字节数（未压缩）
+    pub bytes: u64,
+    /// 写入内容（未压缩）的 CRC32
+    pub crc32: u32,
+}
+
+/// 导出结果汇总
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvExportReport {
+    /// 输出文件（合并模式为单个文件，否则为按序排列的分片）
+    pub files: Vec<String>,
+    /// 数据库中（同一快照内）统计的行数
+    pub db_rows: i64,
+    /// CSV 中实际写出的行数
+    pub csv_rows: usize,
+    /// 写出的字节数（未压缩）
+    pub bytes: u64,
+    /// 分区明细（单连接导出时为空）
+    pub partitions: Vec<PartitionOutput>,
+    /// 导出使用的快照ID（未导出快照时为空）
+    pub snapshot_id: Option<String>,
+    /// 快照导出失败的原因；非空时各分区使用独立快照，分区间不保证一致
+    pub snapshot_error: Option<String>,
+    /// 检查点清单路径（未启用检查点时为空）
+    pub manifest: Option<String>,
+    /// 从检查点恢复、未重新导出的分区数
+    pub resumed_partitions: usize,
+    /// 耗时（毫秒）
+    pub elapsed_ms: u64,
+}
+
+/// 分区的取值范围，上界为 `None` 表示不设上限
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PartitionRange {
+    /// 主键区间 `[lo, hi)`
+    Key {
+        column: String,
+        lo: Option<i64>,
+        hi: Option<i64>,
+    },
+    /// 物理块区间 `[lo, hi)`
+    Ctid { lo: i64, hi: Option<i64> },
+}
+
+impl PartitionRange {
+    /// 构建分区查询语句
+    fn select_sql(&self, table_full: &str) -> String {
+        match self {
+            PartitionRange::Key { column, lo, hi } => {
+                let col = quote_ident(column);
+                let mut conds = Vec::new();
+                if let Some(lo) = lo {
+                    conds.push(format!("{} >= {}", col, lo));
+                }
+                if let Some(hi) = hi {
+                    conds.push(format!("{} < {}", col, hi));
+                }
+                let where_sql = if conds.is_empty() {
+                    String::new()
+                } else {
+                    format!(" WHERE {}", conds.join(" AND "))
+                };
+                format!("SELECT * FROM {}{} ORDER BY {}", table_full, where_sql, col)
+            }
+            PartitionRange::Ctid { lo, hi } => {
+                let mut sql = format!(
+                    "SELECT * FROM {} WHERE ctid >= '({},0)'::tid",
+                    table_full, lo
+                );
+                if let Some(hi) = hi {
+                    sql.push_str(&format!(" AND ctid < '({},0)'::tid", hi));
+                }
+                sql
+            }
         }
     }
 }
 
+/// 对标识符加双引号（内部双引号转义）
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn copy_sql(select_sql: &str) -> String {
+    format!(
+        "COPY ({}) TO STDOUT WITH (FORMAT csv, DELIMITER ',', QUOTE '\"', ESCAPE '\"')",
+        select_sql
+    )
+}
+
+/// 将 `[lo, hi]` 闭区间尽量均匀地切分为 `n` 段，返回左闭右开区间列表
+fn split_key_range(lo: i64, hi: i64, n: usize) -> Vec<(i64, i64)> {
+    if hi < lo || n == 0 {
+        return Vec::new();
+    }
+    let span = (hi as i128) - (lo as i128) + 1;
+    let n = (n as i128).min(span).max(1);
+    let step = (span + n - 1) / n;
+    let mut ranges = Vec::new();
+    let mut start = lo as i128;
+    let end = hi as i128 + 1;
+    while start < end {
+        let stop = (start + step).min(end);
+        ranges.push((start as i64, stop as i64));
+        start = stop;
+    }
+    ranges
+}
+
+/// 规划分区：首尾分区不设边界，保证快照内所有行都被覆盖
+fn plan_key_partitions(column: &str, lo: i64, hi: i64, n: usize) -> Vec<PartitionRange> {
+    let ranges = split_key_range(lo, hi, n);
+    let last = ranges.len().saturating_sub(1);
+    ranges
+        .into_iter()
+        .enumerate()
+        .map(|(i, (l, h))| PartitionRange::Key {
+            column: column.to_string(),
+            lo: if i == 0 { None } else { Some(l) },
+            hi: if i == last { None } else { Some(h) },
+        })
+        .collect()
+}
+
+fn plan_ctid_partitions(blocks: i64, n: usize) -> Vec<PartitionRange> {
+    let ranges = split_key_range(0, blocks.max(1) - 1, n);
+    let last = ranges.len().saturating_sub(1);
+    ranges
+        .into_iter()
+        .enumerate()
+        .map(|(i, (l, h))| PartitionRange::Ctid {
+            lo: l,
+            hi: if i == last { None } else { Some(h) },
+        })
+        .collect()
+}
+
+/// 查找可用于区间切分的单列整数主键
+async fn find_integer_pk(
+    client: &tokio_postgres::Client,
+    table_full: &str,
+) -> Result<Option<String>> {
+    let rows = client
+        .query(
+            "SELECT a.attname::text, format_type(a.atttypid, a.atttypmod) \
+             FROM pg_index i \
+             JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey) \
+             WHERE i.indrelid = $1::text::regclass AND i.indisprimary",
+            &[&table_full],
+        )
+        .await
+        .context("primary key lookup failed")?;
+    if rows.len() != 1 {
+        return Ok(None);
+    }
+    let name: String = rows[0].get(0);
+    let ty: String = rows[0].get(1);
+    match ty.as_str() {
+        "smallint" | "integer" | "bigint" => Ok(Some(name)),
+        _ => Ok(None),
+    }
+}
+
+/// 根据策略在当前快照内生成分区
+async fn plan_partitions(
+    client: &tokio_postgres::Client,
+    table_full: &str,
+    strategy: PartitionStrategy,
+    n: usize,
+) -> Result<Vec<PartitionRange>> {
+    let pk = match strategy {
+        PartitionStrategy::Ctid => None,
+        _ => find_integer_pk(client, table_full).await?,
+    };
+    if let Some(column) = pk {
+        let row = client
+            .query_one(
+                &format!(
+                    "SELECT min({0})::bigint, max({0})::bigint FROM {1}",
+                    quote_ident(&column),
+                    table_full
+                ),
+                &[],
+            )
+            .await
+            .context("primary key bounds query failed")?;
+        let lo: Option<i64> = row.get(0);
+        let hi: Option<i64> = row.get(1);
+        return Ok(match (lo, hi) {
+            (Some(lo), Some(hi)) => plan_key_partitions(&column, lo, hi, n),
+            // 空表：单个无边界分区
+            _ => plan_key_partitions(&column, 0, 0, 1),
+        });
+    }
+    if strategy == PartitionStrategy::PrimaryKey {
+        bail!(
+            "table {} has no single-column integer primary key, use ctid strategy",
+            table_full
+        );
+    }
+    let row = client
+        .query_one(
+            "SELECT (pg_relation_size($1::text::regclass) / current_setting('block_size')::bigint)::bigint",
+            &[&table_full],
+        )
+        .await
+        .context("relation size query failed")?;
+    let blocks: i64 = row.get(0);
+    Ok(plan_ctid_partitions(blocks, n))
+}
+
 fn build_timestamped_filename(filename: &str, gzip: bool) -> String {
     let ts = chrono::Local::now().format("%Y%m%d%H%M%S").to_string();
     let path = Path::new(filename);
@@ -62,41 +324,127 @@ fn build_timestamped_filename(filename: &str, gzip: bool) -> String {
     final_path.to_string_lossy().to_string()
 }
 
+/// 分片文件名：`<基名>.partNNNN.csv[.gz]`
+fn build_part_filename(final_filename: &str, index: usize, gzip: bool) -> String {
+    let base = final_filename.strip_suffix(".gz").unwrap_or(final_filename);
+    let base = base.strip_suffix(".csv").unwrap_or(base);
+    if gzip {
+        format!("{}.part{:04}.csv.gz", base, index)
+    } else {
+        format!("{}.part{:04}.csv", base, index)
+    }
+}
+
+/// 输出文件写入器（普通或gzip）
+enum CsvSink {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl CsvSink {
+    fn create(path: &str, gzip: bool, buf_size: usize) -> Result<Self> {
+        if let Some(dir) = Path::new(path).parent() {
+            std::fs::create_dir_all(dir).ok();
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)
+            .with_context(|| format!("open output file failed: {}", path))?;
+        let writer = BufWriter::with_capacity(buf_size, file);
+        Ok(if gzip {
+            CsvSink::Gzip(GzEncoder::new(writer, Compression::fast()))
+        } else {
+            CsvSink::Plain(writer)
+        })
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            CsvSink::Plain(mut w) => w.flush().context("flush writer failed"),
+            CsvSink::Gzip(gz) => gz
+                .finish()
+                .context("finish gzip writer failed")?
+                .flush()
+                .context("flush gzip writer failed"),
+        }
+    }
+}
+
+impl Write for CsvSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            CsvSink::Plain(w) => w.write(buf),
+            CsvSink::Gzip(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            CsvSink::Plain(w) => w.flush(),
+            CsvSink::Gzip(w) => w.flush(),
+        }
+    }
+}
+
+/// 单次COPY写入统计
+struct CopyStats {
+    bytes: u64,
+    rows: usize,
+    crc32: u32,
+}
+
+/// 执行COPY并写入文件，返回写入统计
 async fn copy_to_file<W: std::io::Write + Send>(
     client: &tokio_postgres::Client,
     sql: String,
     writer: &mut W,
-    _total_rows: &Arc<AtomicUsize>,
     buf_tick: Duration,
     out_name: &str,
-) -> Result<()> {
+    control: &TransferControl,
+) -> Result<CopyStats> {
     use futures::StreamExt;
     let stream = client.copy_out(&sql).await.context("copy_out failed")?;
     futures::pin_mut!(stream);
-    let mut last = std::time::Instant::now();
-    let mut bytes_written: usize = 0;
+    let mut last = Instant::now();
+    let mut last_report = Instant::now();
+    let mut bytes_written: u64 = 0;
+    let mut scanner = CsvRowScanner::default();
+    let mut rows = 0usize;
+    let mut crc = flate2::Crc::new();
     while let Some(chunk_res) = stream.next().await {
+        control.check()?;
         let chunk: Bytes = chunk_res.context("stream chunk error")?;
         writer
             .write_all(&chunk)
             .with_context(|| format!("write chunk failed: {}", out_name))?;
-        bytes_written += chunk.len();
+        bytes_written += chunk.len() as u64;
+        rows += scanner.count_rows_chunk(&chunk);
+        crc.update(&chunk);
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            control.report(TransferProgress {
+                stage: "export",
+                path: out_name.to_string(),
+                bytes: bytes_written,
+                rows,
+            });
+            last_report = Instant::now();
+        }
         if last.elapsed() >= buf_tick {
             let mb = (bytes_written as f64) / (1024.0 * 1024.0);
-            println!("CSV写入进度: 已写入 {:.2} MB", mb);
-            last = std::time::Instant::now();
+            eprintln!("CSV写入进度[{}]: 已写入 {:.2} MB", out_name, mb);
+            last = Instant::now();
         }
     }
-    Ok(())
+    Ok(CopyStats {
+        bytes: bytes_written,
+        rows,
+        crc32: crc.sum(),
+    })
 }
 
-pub async fn pg_transfor_to_csv_async_with_options_dsn(
-    table_full: &str,
-    filename: &str,
-    opts: CsvExportOptions,
-    dsn: &str,
-) -> Result<()> {
-    let final_filename = build_timestamped_filename(filename, opts.gzip);
+async fn connect(dsn: &str) -> Result<tokio_postgres::Client> {
     let (client, connection) =
         tokio::time::timeout(Duration::from_secs(10), tokio_postgres::connect(dsn, NoTls))
             .await
@@ -105,379 +453,426 @@ pub async fn pg_transfor_to_csv_async_with_options_dsn(
     tokio::spawn(async move {
         let _ = connection.await;
     });
-    let total_rows = Arc::new(AtomicUsize::new(0));
-    if opts.partitions <= 1 {
-        use std::fs::OpenOptions;
-        use std::io::BufWriter;
-        if opts.gzip {
-            use flate2::Compression;
-            use flate2::write::GzEncoder;
-            if let Some(dir) = Path::new(&final_filename).parent() {
-                std::fs::create_dir_all(dir).ok();
+    Ok(client)
+}
+
+/// 单个分区的导出任务
+struct PartitionJob {
+    dsn: String,
+    table_full: String,
+    snapshot_id: Option<String>,
+    range: PartitionRange,
+    index: usize,
+    part_path: String,
+    gzip: bool,
+    buf_size: usize,
+    control: TransferControl,
+}
+
+/// 在独立连接上导出一个分区；若提供快照ID则导入同一快照，保证各分区数据一致
+async fn export_partition(job: PartitionJob) -> Result<PartitionOutput> {
+    let PartitionJob {
+        dsn,
+        table_full,
+        snapshot_id,
+        range,
+        index,
+        part_path,
+        gzip,
+        buf_size,
+        control,
+    } = job;
+    let client = connect(&dsn).await?;
+    client
+        .batch_execute("BEGIN ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .await
+        .context("begin partition transaction failed")?;
+    if let Some(id) = &snapshot_id {
+        client
+            .batch_execute(&format!(
+                "SET TRANSACTION SNAPSHOT '{}'",
+                id.replace('\'', "''")
+            ))
+            .await
+            .context("import snapshot failed")?;
+    }
+    let mut sink = CsvSink::create(&part_path, gzip, buf_size)?;
+    let stats = copy_to_file(
+        &client,
+        copy_sql(&range.select_sql(&table_full)),
+        &mut sink,
+        Duration::from_secs(5),
+        &part_path,
+        &control,
+    )
+    .await?;
+    sink.finish()?;
+    client
+        .batch_execute("COMMIT")
+        .await
+        .context("commit partition transaction failed")?;
+    Ok(PartitionOutput {
+        index,
+        path: part_path,
+        rows: stats.rows,
+        bytes: stats.bytes,
+        crc32: stats.crc32,
+    })
+}
+
+/// 按分区序号将分片依次写入最终文件，合并后删除分片
+fn merge_parts(
+    parts: &[PartitionOutput],
+    final_filename: &str,
+    gzip: bool,
+    buf_size: usize,
+) -> Result<()> {
+    use std::io::{BufReader, Read};
+    let mut out = CsvSink::create(final_filename, gzip, buf_size)?;
+    let mut buf = vec![0u8; 4 << 20];
+    for part in parts {
+        let f = File::open(&part.path)
+            .with_context(|| format!("open part file failed: {}", part.path))?;
+        let mut rdr = BufReader::new(f);
+        loop {
+            let n = rdr.read(&mut buf).context("read part file failed")?;
+            if n == 0 {
+                break;
             }
-            let file = OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open(&final_filename)
-                .context("open output file failed")?;
-            let mut writer = GzEncoder::new(
-                BufWriter::with_capacity(opts.buf_size_bytes, file),
-                Compression::fast(),
-            );
-            if let Some(rows) = opts.rows_per_query {
-                let count_row = client
-                    .query_one(&format!("select count(*) from {}", table_full), &[])
-                    .await
-                    .context("count query failed")?;
-                let total: i64 = count_row.get(0);
-                let mut offset: i64 = 0;
-                while offset < total {
-                    let limit = std::cmp::min(rows as i64, total - offset);
-                    let sql = format!(
-                        "COPY (SELECT * FROM {} LIMIT {} OFFSET {}) TO STDOUT WITH (FORMAT csv, DELIMITER ',', QUOTE '\"', ESCAPE '\"')",
-                        table_full, limit, offset
-                    );
-                    copy_to_file(
-                        &client,
-                        sql,
-                        &mut writer,
-                        &total_rows,
-                        Duration::from_secs(5),
-                        &final_filename,
-                    )
-                    .await?;
-                    offset += limit;
-                }
-            } else {
-                let sql = format!(
-                    "COPY (SELECT * FROM {}) TO STDOUT WITH (FORMAT csv, DELIMITER ',', QUOTE '\"', ESCAPE '\"')",
-                    table_full
-                );
-                copy_to_file(
-                    &client,
-                    sql,
-                    &mut writer,
-                    &total_rows,
-                    Duration::from_secs(5),
-                    &final_filename,
-                )
-                .await?;
+            out.write_all(&buf[..n]).context("write merge failed")?;
+        }
+    }
+    out.finish()?;
+    for part in parts {
+        let _ = std::fs::remove_file(&part.path);
+    }
+    Ok(())
+}
+
+/// 统计CSV文件（支持 .gz）中的行数
+fn count_csv_rows(path: &str) -> Result<usize> {
+    use flate2::read::GzDecoder;
+    use std::io::{BufReader, Read};
+    let f = File::open(path).with_context(|| format!("open file for counting failed: {}", path))?;
+    let mut reader: Box<dyn Read> = if path.ends_with(".gz") {
+        Box::new(BufReader::new(GzDecoder::new(f)))
+    } else {
+        Box::new(BufReader::new(f))
+    };
+    let mut buf = vec![0u8; 4 << 20];
+    let mut scanner = CsvRowScanner::default();
+    let mut rows = 0usize;
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .context("read file for counting failed")?;
+        if n == 0 {
+            break;
+        }
+        rows += scanner.count_rows_chunk(&buf[..n]);
+    }
+    Ok(rows)
+}
+
+/// 导出快照；失败时返回原因，由调用方决定是否回退为各连接独立快照
+async fn export_snapshot(client: &tokio_postgres::Client) -> Result<Result<String, String>> {
+    match client.query_one("SELECT pg_export_snapshot()", &[]).await {
+        Ok(row) => Ok(Ok(row.get(0))),
+        Err(e) => {
+            client
+                .batch_execute("ROLLBACK; BEGIN ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+                .await
+                .context("restart transaction failed")?;
+            Ok(Err(e.to_string()))
+        }
+    }
+}
+
+/// 快照不可用时，多个分区只有在调用方明确允许时才各自使用独立快照
+fn check_snapshot_fallback(partition_count: usize, allow: bool, reason: &str) -> Result<()> {
+    if partition_count > 1 && !allow {
+        bail!(
+            "export snapshot failed ({}); partitions would not be consistent, \
+             set allow_inconsistent_snapshot to export anyway",
+            reason
+        );
+    }
+    eprintln!("导出快照失败，分区间不保证一致性: {}", reason);
+    Ok(())
+}
+
+/// 异步将PostgreSQL数据库表中的数据导出为CSV文件
+///
+/// 单连接模式直接 COPY 整表；当 `partitions > 1`、设置了 `rows_per_query` 或启用检查点时，
+/// 按主键/ctid 区间切分，在多个连接上并行导出，各连接通过 `pg_export_snapshot()`
+/// 共享同一快照，最后按分区顺序合并（或保留有序分片）。
+///
+/// 启用 `checkpoint` 时在输出文件旁维护清单（见 `export_checkpoint`），
+/// 中断后以相同参数重新执行会沿用原输出文件名与分区划分，校验并跳过已完成的分片。
+/// 续传的分区来自新的快照，与先前分片之间不保证一致。
+///
+/// # Arguments
+///
+/// * `table_full` - 数据库表名（可带 schema）
+/// * `filename` - 要写入的CSV文件名（会追加时间戳）
+/// * `opts` - 导出选项
+/// * `dsn` - 连接串
+///
+/// # Returns
+///
+/// * `Ok(report)` - 导出结果汇总
+/// * `Err(e)` - 导出失败，返回错误信息
+pub async fn pg_transfor_to_csv_async_with_options_dsn(
+    table_full: &str,
+    filename: &str,
+    opts: CsvExportOptions,
+    dsn: &str,
+) -> Result<CsvExportReport> {
+    let started = Instant::now();
+    let manifest_path = opts.checkpoint.then(|| manifest_path_for(filename));
+    let resume = match &manifest_path {
+        Some(path) => match ExportManifest::load(path)? {
+            Some(m) if m.check_resumable(table_full, dsn, opts.gzip, opts.merge_parts)? => {
+                eprintln!("发现检查点 {}，继续导出 {}", path, m.output);
+                Some(m)
             }
-            writer.flush().context("flush gzip writer failed")?;
-        } else {
-            if let Some(dir) = Path::new(&final_filename).parent() {
-                std::fs::create_dir_all(dir).ok();
+            _ => None,
+        },
+        None => None,
+    };
+    let final_filename = match &resume {
+        Some(m) => m.output.clone(),
+        None => build_timestamped_filename(filename, opts.gzip),
+    };
+    let client = connect(dsn).await?;
+    // 整个导出在一个可重复读事务内完成，行数校验与数据处于同一快照
+    client
+        .batch_execute("BEGIN ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .await
+        .context("begin transaction failed")?;
+    let count_row = client
+        .query_one(&format!("select count(*) from {}", table_full), &[])
+        .await
+        .context("count query failed")?;
+    let db_count: i64 = count_row.get(0);
+
+    let partition_count = match opts.rows_per_query {
+        Some(rows) if rows > 0 => {
+            let by_rows = (db_count.max(0) as usize).div_ceil(rows);
+            by_rows.max(opts.partitions).max(1)
+        }
+        _ => opts.partitions.max(1),
+    };
+
+    let mut resumed_partitions = 0usize;
+    let mut snapshot_error = None;
+    let (files, csv_count, bytes, partitions, snapshot_id) = if partition_count <= 1
+        && !opts.checkpoint
+    {
+        let mut sink = CsvSink::create(&final_filename, opts.gzip, opts.buf_size_bytes)?;
+        let stats = copy_to_file(
+            &client,
+            copy_sql(&format!("SELECT * FROM {}", table_full)),
+            &mut sink,
+            Duration::from_secs(5),
+            &final_filename,
+            &opts.control,
+        )
+        .await?;
+        sink.finish()?;
+        let csv_count = count_csv_rows(&final_filename)?;
+        (
+            vec![final_filename.clone()],
+            csv_count,
+            stats.bytes,
+            Vec::new(),
+            None,
+        )
+    } else {
+        let partition_total = resume
+            .as_ref()
+            .map_or(partition_count, |m| m.partitions.len());
+        let snapshot_id = match export_snapshot(&client).await? {
+            Ok(id) => Some(id),
+            Err(reason) => {
+                check_snapshot_fallback(
+                    partition_total,
+                    opts.allow_inconsistent_snapshot,
+                    &reason,
+                )?;
+                snapshot_error = Some(reason);
+                None
             }
-            let file = OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open(&final_filename)
-                .context("open output file failed")?;
-            let mut writer = BufWriter::with_capacity(opts.buf_size_bytes, file);
-            if let Some(rows) = opts.rows_per_query {
-                let count_row = client
-                    .query_one(&format!("select count(*) from {}", table_full), &[])
-                    .await
-                    .context("count query failed")?;
-                let total: i64 = count_row.get(0);
-                let mut offset: i64 = 0;
-                while offset < total {
-                    let limit = std::cmp::min(rows as i64, total - offset);
-                    let sql = format!(
-                        "COPY (SELECT * FROM {} LIMIT {} OFFSET {}) TO STDOUT WITH (FORMAT csv, DELIMITER ',', QUOTE '\"', ESCAPE '\"')",
-                        table_full, limit, offset
-                    );
-                    copy_to_file(
-                        &client,
-                        sql,
-                        &mut writer,
-                        &total_rows,
-                        Duration::from_secs(5),
-                        &final_filename,
-                    )
-                    .await?;
-                    offset += limit;
-                }
-            } else {
-                let sql = format!(
-                    "COPY (SELECT * FROM {}) TO STDOUT WITH (FORMAT csv, DELIMITER ',', QUOTE '\"', ESCAPE '\"')",
-                    table_full
-                );
-                copy_to_file(
+        };
+        let part_gzip = opts.gzip && !opts.merge_parts;
+        // 续传时沿用清单中的分区划分，保证分片边界不变
+        let manifest = match resume {
+            Some(m) => m,
+            None => {
+                let ranges = plan_partitions(
                     &client,
-                    sql,
-                    &mut writer,
-                    &total_rows,
-                    Duration::from_secs(5),
-                    &final_filename,
+                    table_full,
+                    opts.partition_strategy,
+                    partition_count,
                 )
                 .await?;
+                let entries = ranges
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, range)| {
+                        (
+                            range,
+                            build_part_filename(&final_filename, index, part_gzip),
+                        )
+                    })
+                    .collect();
+                ExportManifest::new(
+                    table_full,
+                    dsn,
+                    &final_filename,
+                    opts.gzip,
+                    opts.merge_parts,
+                    entries,
+                )
             }
-            writer.flush().context("flush writer failed")?;
+        };
+        if let Some(path) = &manifest_path {
+            manifest.save(path)?;
         }
-    } else {
-        let count_row = client
-            .query_one(&format!("select count(*) from {}", table_full), &[])
-            .await
-            .context("count query failed")?;
-        let total: i64 = count_row.get(0);
-        let parts = opts.partitions as i64;
-        let per = ((total + parts - 1) / parts) as i64;
+        let pending: Vec<(usize, PartitionRange, String)> = manifest
+            .partitions
+            .iter()
+            .map(|p| (p.index, p.range.clone(), p.path.clone()))
+            .collect();
+        let manifest = Arc::new(Mutex::new(manifest));
+
+        let concurrency = opts.partitions.max(1);
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(concurrency));
+        let mut parts = Vec::with_capacity(pending.len());
         let mut handles = Vec::new();
-        for i in 0..parts {
-            let offset = i * per;
-            let limit = std::cmp::min(per, total - offset);
-            if limit <= 0 {
-                continue;
+        for (index, range, part_path) in pending {
+            let done = manifest.lock().unwrap().completed_part(index);
+            if let Some(done) = done {
+                if verify_part(&done) {
+                    resumed_partitions += 1;
+                    parts.push(done);
+                    continue;
+                }
+                eprintln!("分片 {} 校验失败，重新导出", done.path);
             }
-            let dsn_owned = dsn.to_string();
-            let table_owned = table_full.to_string();
-            let total_rows_clone = total_rows.clone();
-            let part_name = {
-                let p = Path::new(&final_filename);
-                let parent = p.parent().map(|p| p.to_path_buf());
-                let stem = p
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or(&final_filename);
-                let ext = if opts.gzip {
-                    "csv"
-                } else {
-                    p.extension().and_then(|s| s.to_str()).unwrap_or("csv")
-                };
-                let name = format!("{}.part{}.{}", stem, i, ext);
-                let pp = if let Some(parent) = parent {
-                    parent.join(name)
-                } else {
-                    std::path::PathBuf::from(name)
-                };
-                pp.to_string_lossy().to_string()
-            };
-            let buf_size = opts.buf_size_bytes;
-            let handle = tokio::spawn(async move {
-                use std::fs::OpenOptions;
-                use std::io::BufWriter;
-                let sql = format!(
-                    "COPY (SELECT * FROM {} LIMIT {} OFFSET {}) TO STDOUT WITH (FORMAT csv, DELIMITER ',', QUOTE '\"', ESCAPE '\"')",
-                    table_owned, limit, offset
-                );
-                let (client_local, conn_local) = match tokio::time::timeout(
-                    Duration::from_secs(10),
-                    tokio_postgres::connect(&dsn_owned, NoTls),
-                )
+            opts.control.check()?;
+            let permit = semaphore
+                .clone()
+                .acquire_owned()
                 .await
-                {
-                    Ok(Ok(v)) => v,
-                    _ => return,
-                };
-                tokio::spawn(async move {
-                    let _ = conn_local.await;
-                });
-                let file = match OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .truncate(true)
-                    .open(&part_name)
-                {
-                    Ok(f) => f,
-                    Err(_) => return,
-                };
-                let mut writer = BufWriter::with_capacity(buf_size, file);
-                let _ = copy_to_file(
-                    &client_local,
-                    sql,
-                    &mut writer,
-                    &total_rows_clone,
-                    Duration::from_secs(5),
-                    &part_name,
-                )
-                .await;
-                let _ = writer.flush();
+                .context("acquire partition permit failed")?;
+            let fut = export_partition(PartitionJob {
+                dsn: dsn.to_string(),
+                table_full: table_full.to_string(),
+                snapshot_id: snapshot_id.clone(),
+                range,
+                index,
+                part_path,
+                gzip: part_gzip,
+                buf_size: opts.buf_size_bytes,
+                control: opts.control.clone(),
             });
-            handles.push(handle);
-        }
-        for h in handles {
-            let _ = h.await;
+            let manifest = manifest.clone();
+            let manifest_path = manifest_path.clone();
+            handles.push((
+                index,
+                tokio::spawn(async move {
+                    let _permit = permit;
+                    let part = fut.await?;
+                    // 每完成一个分区立即落盘清单
+                    if let Some(path) = manifest_path {
+                        let mut m = manifest.lock().unwrap();
+                        m.record(&part);
+                        m.save(&path)?;
+                    }
+                    Ok::<_, anyhow::Error>(part)
+                }),
+            ));
         }
-        if opts.gzip {
-            use flate2::Compression;
-            use flate2::write::GzEncoder;
-            use std::fs::{File, OpenOptions};
-            use std::io::{BufReader, BufWriter, Read, Write};
-            let tmp_merge = {
-                let p = Path::new(&final_filename);
-                let parent = p.parent().map(|p| p.to_path_buf());
-                let stem = p
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or(&final_filename);
-                let name = format!("{}.merge.csv", stem);
-                let pp = if let Some(parent) = parent {
-                    parent.join(name)
-                } else {
-                    std::path::PathBuf::from(name)
-                };
-                pp.to_string_lossy().to_string()
-            };
-            let mut out = BufWriter::with_capacity(
-                opts.buf_size_bytes,
-                OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .truncate(true)
-                    .open(&tmp_merge)
-                    .context("open tmp merge failed")?,
-            );
-            for i in 0..parts {
-                let part_name = {
-                    let p = Path::new(&final_filename);
-                    let parent = p.parent().map(|p| p.to_path_buf());
-                    let stem = p
-                        .file_stem()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or(&final_filename);
-                    let name = format!("{}.part{}.csv", stem, i);
-                    let pp = if let Some(parent) = parent {
-                        parent.join(name)
-                    } else {
-                        std::path::PathBuf::from(name)
-                    };
-                    pp.to_string_lossy().to_string()
-                };
-                let f = File::open(&part_name).context("open part file failed")?;
-                let mut rdr = BufReader::new(f);
-                let mut buf = vec![0u8; 32 << 20];
-                loop {
-                    let n = rdr.read(&mut buf).context("read part file failed")?;
-                    if n == 0 {
-                        break;
+        let mut first_err: Option<anyhow::Error> = None;
+        for (index, h) in handles {
+            match h.await {
+                Ok(Ok(part)) => parts.push(part),
+                Ok(Err(e)) => {
+                    if first_err.is_none() {
+                        first_err = Some(e.context(format!("partition {} failed", index)));
                     }
-                    out.write_all(&buf[..n]).context("write merge failed")?;
-                }
-            }
-            out.flush().context("flush merge failed")?;
-            let gz_file = OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open(&final_filename)
-                .context("open final gzip file failed")?;
-            let mut gz = GzEncoder::new(
-                BufWriter::with_capacity(opts.buf_size_bytes, gz_file),
-                Compression::fast(),
-            );
-            let mut src =
-                BufReader::new(File::open(&tmp_merge).context("open tmp merge for gzip failed")?);
-            let mut buf = vec![0u8; 32 << 20];
-            loop {
-                let n = src.read(&mut buf).context("read tmp merge failed")?;
-                if n == 0 {
-                    break;
                 }
-                gz.write_all(&buf[..n]).context("write gzip failed")?;
-            }
-            gz.flush().context("flush gzip failed")?;
-        } else {
-            use std::fs::{File, OpenOptions};
-            use std::io::{BufReader, BufWriter, Read, Write};
-            let mut out = BufWriter::with_capacity(
-                opts.buf_size_bytes,
-                OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .truncate(true)
-                    .open(&final_filename)
-                    .context("open final file failed")?,
-            );
-            for i in 0..parts {
-                let part_name = {
-                    let p = Path::new(&final_filename);
-                    let parent = p.parent().map(|p| p.to_path_buf());
-                    let stem = p
-                        .file_stem()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or(&final_filename);
-                    let ext = p.extension().and_then(|s| s.to_str()).unwrap_or("csv");
-                    let name = format!("{}.part{}.{}", stem, i, ext);
-                    let pp = if let Some(parent) = parent {
-                        parent.join(name)
-                    } else {
-                        std::path::PathBuf::from(name)
-                    };
-                    pp.to_string_lossy().to_string()
-                };
-                let f = File::open(&part_name).context("open part file failed")?;
-                let mut rdr = BufReader::new(f);
-                let mut buf = vec![0u8; 32 << 20];
-                loop {
-                    let n = rdr.read(&mut buf).context("read part file failed")?;
-                    if n == 0 {
-                        break;
+                Err(e) => {
+                    if first_err.is_none() {
+                        first_err = Some(anyhow::anyhow!("partition {} panicked: {}", index, e));
                     }
-                    out.write_all(&buf[..n])
-                        .context("write final file failed")?;
                 }
             }
-            out.flush().context("flush final file failed")?;
         }
-    }
-
-    let count_row = client
-        .query_one(&format!("select count(*) from {}", table_full), &[])
-        .await
-        .context("count query failed")?;
-    let db_count: i64 = count_row.get(0);
-    let csv_count: usize = if final_filename.ends_with(".gz") {
-        use flate2::read::GzDecoder;
-        use std::fs::File;
-        use std::io::{BufReader, Read};
-        let f = File::open(&final_filename).context("open final gzip for counting failed")?;
-        let dec = GzDecoder::new(f);
-        let mut reader = BufReader::new(dec);
-        let mut buf = vec![0u8; 32 << 20];
-        let mut scanner = CsvRowScanner::default();
-        let mut rows = 0usize;
-        loop {
-            let n = reader
-                .read(&mut buf)
-                .context("read gzip for counting failed")?;
-            if n == 0 {
-                break;
-            }
-            rows += scanner.count_rows_chunk(&buf[..n]);
+        if let Some(e) = first_err {
+            return Err(e);
         }
-        rows
-    } else {
-        use std::fs::File;
-        use std::io::{BufReader, Read};
-        let f = File::open(&final_filename).context("open final file for counting failed")?;
-        let mut reader = BufReader::new(f);
-        let mut buf = vec![0u8; 32 << 20];
-        let mut scanner = CsvRowScanner::default();
-        let mut rows = 0usize;
-        loop {
-            let n = reader
-                .read(&mut buf)
-                .context("read final file for counting failed")?;
-            if n == 0 {
-                break;
-            }
-            rows += scanner.count_rows_chunk(&buf[..n]);
+        parts.sort_by_key(|p| p.index);
+        let bytes: u64 = parts.iter().map(|p| p.bytes).sum();
+        let (files, csv_count) = if opts.merge_parts {
+            opts.control.check()?;
+            opts.control.report(TransferProgress {
+                stage: "merge",
+                path: final_filename.clone(),
+                bytes,
+                rows: parts.iter().map(|p| p.rows).sum(),
+            });
+            merge_parts(&parts, &final_filename, opts.gzip, opts.buf_size_bytes)?;
+            (
+                vec![final_filename.clone()],
+                count_csv_rows(&final_filename)?,
+            )
+        } else {
+            (
+                parts.iter().map(|p| p.path.clone()).collect(),
+                parts.iter().map(|p| p.rows).sum(),
+            )
+        };
+        if let Some(path) = &manifest_path {
+            let mut m = manifest.lock().unwrap();
+            m.finish();
+            m.save(path)?;
         }
-        rows
+        (files, csv_count, bytes, parts, snapshot_id)
     };
-    println!(
+
+    client
+        .batch_execute("COMMIT")
+        .await
+        .context("commit transaction failed")?;
+    eprintln!(
         "数据库行数: {}, CSV行数: {}, 是否一致: {}",
         db_count,
         csv_count,
         (db_count as usize) == csv_count
     );
-    Ok(())
+    opts.control.report(TransferProgress {
+        stage: "done",
+        path: files.join(","),
+        bytes,
+        rows: csv_count,
+    });
+    Ok(CsvExportReport {
+        files,
+        db_rows: db_count,
+        csv_rows: csv_count,
+        bytes,
+        partitions,
+        snapshot_id,
+        snapshot_error,
+        manifest: manifest_path,
+        resumed_partitions,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
 }
 
-pub async fn pg_transfor_to_csv_async(table_name: &str, filename: &str) -> Result<()> {
+pub async fn pg_transfor_to_csv_async(table_name: &str, filename: &str) -> Result<CsvExportReport> {
     let dsn = "host=localhost port=15432 user=gaussdb password=123456 dbname=yebt_province password=Enmo@123";
     pg_transfor_to_csv_async_with_options_dsn(
         table_name,
@@ -488,13 +883,24 @@ pub async fn pg_transfor_to_csv_async(table_name: &str, filename: &str) -> Resul
     .await
 }
 
+/// JSON 参数中的连接信息，导入导出共用
 #[derive(Deserialize)]
-pub struct PgExportJsonParams {
+pub struct PgConnJsonParams {
     pub host: String,
     pub port: String,
     pub username: String,
     pub password: String,
     pub dbname: String,
+    pub sslmode: Option<String>,
+    pub connect_timeout: Option<u64>,
+    pub hostaddr: Option<String>,
+    pub dsn: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PgExportJsonParams {
+    #[serde(flatten)]
+    pub conn: PgConnJsonParams,
     pub table_name: String,
     pub current_schema: Option<String>,
     pub output_file_path: String,
@@ -502,13 +908,17 @@ pub struct PgExportJsonParams {
     pub buf_size_bytes: Option<usize>,
     pub partitions: Option<usize>,
     pub rows_per_query: Option<usize>,
-    pub sslmode: Option<String>,
-    pub connect_timeout: Option<u64>,
-    pub hostaddr: Option<String>,
-    pub dsn: Option<String>,
+    /// 分区策略：auto | pk | ctid
+    pub partition_strategy: Option<String>,
+    /// 是否合并分片（默认合并）
+    pub merge_parts: Option<bool>,
+    /// 是否启用检查点（断点续传）
+    pub checkpoint: Option<bool>,
+    /// 无法导出快照时是否允许分区各自使用独立快照
+    pub allow_inconsistent_snapshot: Option<bool>,
 }
 
-fn build_dsn_from_json(p: &PgExportJsonParams) -> String {
+fn build_dsn_from_json(p: &PgConnJsonParams) -> String {
     if let Some(d) = p.dsn.as_ref().filter(|s| !s.is_empty()) {
         return d.clone();
     }
@@ -544,18 +954,29 @@ fn build_dsn_from_json(p: &PgExportJsonParams) -> String {
     parts.join(" ")
 }
 
-fn build_table_full(p: &PgExportJsonParams) -> String {
-    match p.current_schema.as_ref().filter(|s| !s.is_empty()) {
-        Some(s) => format!("\"{}\".\"{}\"", s, p.table_name),
-        None => p.table_name.clone(),
+fn build_table_full(schema: Option<&str>, table_name: &str) -> String {
+    match schema.filter(|s| !s.is_empty()) {
+        Some(s) => format!("\"{}\".\"{}\"", s, table_name),
+        None => table_name.to_string(),
     }
 }
 
-pub async fn pg_export_from_json(json: &str) -> Result<()> {
+pub async fn pg_export_from_json(json: &str) -> Result<CsvExportReport> {
+    pg_export_from_json_with_control(json, TransferControl::default()).await
+}
+
+/// 同 `pg_export_from_json`，可传入取消标记与进度监听器
+pub async fn pg_export_from_json_with_control(
+    json: &str,
+    control: TransferControl,
+) -> Result<CsvExportReport> {
     let params: PgExportJsonParams = serde_json::from_str(json).context("invalid json")?;
-    let dsn = build_dsn_from_json(&params);
-    let table_full = build_table_full(&params);
-    let mut opts = CsvExportOptions::default();
+    let dsn = build_dsn_from_json(&params.conn);
+    let table_full = build_table_full(params.current_schema.as_deref(), &params.table_name);
+    let mut opts = CsvExportOptions {
+        control,
+        ..CsvExportOptions::default()
+    };
     if let Some(g) = params.gzip {
         opts.gzip = g;
     }
@@ -574,9 +995,192 @@ pub async fn pg_export_from_json(json: &str) -> Result<()> {
             opts.rows_per_query = Some(r);
         }
     }
+    if let Some(s) = params.partition_strategy.as_deref() {
+        opts.partition_strategy = s.parse()?;
+    }
+    if let Some(m) = params.merge_parts {
+        opts.merge_parts = m;
+    }
+    if let Some(c) = params.checkpoint {
+        opts.checkpoint = c;
+    }
+    if let Some(a) = params.allow_inconsistent_snapshot {
+        opts.allow_inconsistent_snapshot = a;
+    }
     pg_transfor_to_csv_async_with_options_dsn(&table_full, &params.output_file_path, opts, &dsn)
         .await
 }
+
+/// CSV导入选项
+#[derive(Clone, Debug)]
+pub struct CsvImportOptions {
+    /// 首行是否为表头
+    pub header: bool,
+    /// 导入前是否清空目标表（与导入在同一事务内）
+    pub truncate: bool,
+    /// 分隔符
+    pub delimiter: u8,
+    pub buf_size_bytes: usize,
+    /// 取消标记与进度监听器
+    pub control: TransferControl,
+}
+
+impl Default for CsvImportOptions {
+    fn default() -> Self {
+        Self {
+            header: false,
+            truncate: false,
+            delimiter: b',',
+            buf_size_bytes: 8 * 1024 * 1024,
+            control: TransferControl::default(),
+        }
+    }
+}
+
+/// CSV导入结果
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvImportReport {
+    pub file: String,
+    pub table: String,
+    pub rows: u64,
+    /// 读取的字节数（.gz 为解压后）
+    pub bytes: u64,
+    pub elapsed_ms: u64,
+}
+
+/// 通过 `COPY ... FROM STDIN` 将CSV文件（支持 .gz）导入PostgreSQL表
+///
+/// 整个导入在一个事务内完成，失败或被取消时目标表保持不变。
+pub async fn pg_import_csv_dsn(
+    table_full: &str,
+    filename: &str,
+    opts: CsvImportOptions,
+    dsn: &str,
+) -> Result<CsvImportReport> {
+    use futures::SinkExt;
+    use std::io::Read;
+    let started = Instant::now();
+    let file =
+        File::open(filename).with_context(|| format!("open input file failed: {}", filename))?;
+    let mut reader: Box<dyn Read + Send> = if filename.ends_with(".gz") {
+        Box::new(flate2::read::GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    let client = connect(dsn).await?;
+    client
+        .batch_execute("BEGIN")
+        .await
+        .context("begin transaction failed")?;
+    if opts.truncate {
+        client
+            .batch_execute(&format!("TRUNCATE TABLE {}", table_full))
+            .await
+            .context("truncate failed")?;
+    }
+    let sql = format!(
+        "COPY {} FROM STDIN WITH (FORMAT csv, HEADER {}, DELIMITER E'\\x{:02x}')",
+        table_full, opts.header, opts.delimiter
+    );
+    let sink = client
+        .copy_in::<_, Bytes>(&sql)
+        .await
+        .context("copy_in failed")?;
+    futures::pin_mut!(sink);
+    let mut buf = vec![0u8; opts.buf_size_bytes.max(64 * 1024)];
+    let mut bytes_read = 0u64;
+    let mut last = Instant::now();
+    let mut last_report = Instant::now();
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .with_context(|| format!("read input file failed: {}", filename))?;
+        if n == 0 {
+            break;
+        }
+        opts.control.check()?;
+        bytes_read += n as u64;
+        sink.send(Bytes::copy_from_slice(&buf[..n]))
+            .await
+            .context("copy send failed")?;
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            opts.control.report(TransferProgress {
+                stage: "import",
+                path: filename.to_string(),
+                bytes: bytes_read,
+                rows: 0,
+            });
+            last_report = Instant::now();
+        }
+        if last.elapsed() >= Duration::from_secs(5) {
+            let mb = (bytes_read as f64) / (1024.0 * 1024.0);
+            eprintln!("CSV导入进度[{}]: 已读取 {:.2} MB", filename, mb);
+            last = Instant::now();
+        }
+    }
+    let rows = sink.as_mut().finish().await.context("copy finish failed")?;
+    client
+        .batch_execute("COMMIT")
+        .await
+        .context("commit transaction failed")?;
+    opts.control.report(TransferProgress {
+        stage: "done",
+        path: filename.to_string(),
+        bytes: bytes_read,
+        rows: rows as usize,
+    });
+    Ok(CsvImportReport {
+        file: filename.to_string(),
+        table: table_full.to_string(),
+        rows,
+        bytes: bytes_read,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[derive(Deserialize)]
+pub struct PgImportJsonParams {
+    #[serde(flatten)]
+    pub conn: PgConnJsonParams,
+    pub table_name: String,
+    pub current_schema: Option<String>,
+    pub input_file_path: String,
+    /// 首行是否为表头
+    pub header: Option<bool>,
+    /// 导入前是否清空目标表
+    pub truncate: Option<bool>,
+    /// 分隔符（单个 ASCII 字符）
+    pub delimiter: Option<String>,
+    pub buf_size_bytes: Option<usize>,
+}
+
+/// 按 JSON 参数导入CSV，参数格式与导出一致（`output_file_path` 换为 `input_file_path`）
+pub async fn pg_import_from_json(json: &str, control: TransferControl) -> Result<CsvImportReport> {
+    let params: PgImportJsonParams = serde_json::from_str(json).context("invalid json")?;
+    let dsn = build_dsn_from_json(&params.conn);
+    let table_full = build_table_full(params.current_schema.as_deref(), &params.table_name);
+    let mut opts = CsvImportOptions {
+        control,
+        ..CsvImportOptions::default()
+    };
+    if let Some(h) = params.header {
+        opts.header = h;
+    }
+    if let Some(t) = params.truncate {
+        opts.truncate = t;
+    }
+    if let Some(d) = params.delimiter.as_deref() {
+        match d.as_bytes() {
+            [b] if b.is_ascii() => opts.delimiter = *b,
+            _ => bail!("delimiter must be a single ASCII character: {:?}", d),
+        }
+    }
+    if let Some(b) = params.buf_size_bytes.filter(|b| *b > 0) {
+        opts.buf_size_bytes = b;
+    }
+    pg_import_csv_dsn(&table_full, &params.input_file_path, opts, &dsn).await
+}
+
 struct CsvRowScanner {
     in_quotes: bool,
     pending_quote: bool,
@@ -655,3 +1259,62 @@ pub fn run_test() {
         println!("转换CSV文件成功: {}", filename);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 验证主键区间切分覆盖完整且不重叠
+    #[test]
+    fn test_split_key_range() {
+        let ranges = split_key_range(1, 10, 3);
+        assert_eq!(ranges, vec![(1, 5), (5, 9), (9, 11)]);
+        // 分区数大于取值个数时按取值个数切分
+        assert_eq!(split_key_range(5, 6, 8), vec![(5, 6), (6, 7)]);
+        assert!(split_key_range(3, 2, 4).is_empty());
+        // 极值不溢出
+        let ranges = split_key_range(i64::MIN, i64::MAX - 1, 2);
+        assert_eq!(ranges.len(), 2);
+    }
+
+    /// 验证首尾分区不设边界、SQL 使用主键排序
+    #[test]
+    fn test_plan_key_partitions_sql() {
+        let parts = plan_key_partitions("id", 1, 100, 4);
+        assert_eq!(parts.len(), 4);
+        let first = parts[0].select_sql("t");
+        assert_eq!(first, "SELECT * FROM t WHERE \"id\" < 26 ORDER BY \"id\"");
+        let last = parts[3].select_sql("t");
+        assert_eq!(last, "SELECT * FROM t WHERE \"id\" >= 76 ORDER BY \"id\"");
+        let ctid = plan_ctid_partitions(10, 2);
+        assert_eq!(
+            ctid[0].select_sql("t"),
+            "SELECT * FROM t WHERE ctid >= '(0,0)'::tid AND ctid < '(5,0)'::tid"
+        );
+        assert_eq!(
+            ctid[1].select_sql("t"),
+            "SELECT * FROM t WHERE ctid >= '(5,0)'::tid"
+        );
+    }
+
+    #[test]
+    fn test_part_filename() {
+        assert_eq!(
+            build_part_filename("out/a_1.csv.gz", 2, false),
+            "out/a_1.part0002.csv"
+        );
+        assert_eq!(
+            build_part_filename("out/a_1.csv", 0, true),
+            "out/a_1.part0000.csv.gz"
+        );
+    }
+
+    /// 验证快照不可用时多分区默认报错，单分区或显式允许时回退
+    #[test]
+    fn test_snapshot_fallback() {
+        let err = check_snapshot_fallback(4, false, "unsupported").unwrap_err();
+        assert!(err.to_string().contains("unsupported"));
+        assert!(check_snapshot_fallback(4, true, "unsupported").is_ok());
+        assert!(check_snapshot_fallback(1, false, "unsupported").is_ok());
+    }
+}
@@ -0,0 +1,91 @@
+//! 导入导出任务的取消与进度回调
+//!
+//! `TransferControl` 可在调用方与导出任务之间共享：调用方随时 `cancel()`，
+//! 任务在每个数据块之间检查取消标记，并通过监听器上报进度。
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 进度事件
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferProgress {
+    /// export | import | merge | done
+    pub stage: &'static str,
+    /// 当前处理的文件
+    pub path: String,
+    /// 已处理字节数（未压缩）
+    pub bytes: u64,
+    /// 已处理行数（导入时为 0，COPY 结束后才知道行数）
+    pub rows: usize,
+}
+
+pub type ProgressListener = Arc<dyn Fn(&TransferProgress) + Send + Sync>;
+
+/// 取消标记与进度监听器，克隆后共享同一状态
+#[derive(Clone, Default)]
+pub struct TransferControl {
+    cancelled: Arc<AtomicBool>,
+    listener: Option<ProgressListener>,
+}
+
+impl Debug for TransferControl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TransferControl")
+            .field("cancelled", &self.is_cancelled())
+            .field("listener", &self.listener.is_some())
+            .finish()
+    }
+}
+
+impl TransferControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置进度监听器
+    pub fn with_listener(mut self, listener: ProgressListener) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 已取消时返回错误，供任务在数据块之间调用
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            bail!(TransferCancelled);
+        }
+        Ok(())
+    }
+
+    pub fn report(&self, progress: TransferProgress) {
+        if let Some(listener) = &self.listener {
+            listener(&progress);
+        }
+    }
+}
+
+/// 任务被取消时返回的错误，可通过 `downcast_ref` 识别
+#[derive(Debug, Clone, Copy)]
+pub struct TransferCancelled;
+
+impl std::fmt::Display for TransferCancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "transfer cancelled")
+    }
+}
+
+impl std::error::Error for TransferCancelled {}
+
+/// 判断错误链中是否包含取消
+pub fn is_cancelled_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|e| e.is::<TransferCancelled>())
+}
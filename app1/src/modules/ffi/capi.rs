@@ -0,0 +1,309 @@
+//! C ABI 导出函数（头文件见 `app1/include/rsts.h`，由 cbindgen 生成）
+//!
+//! 约定：
+//! - 所有函数返回 `RstsStatus`，成功为 `RSTS_STATUS_OK`，结果通过输出参数返回；
+//! - 失败时输出参数置空，错误信息可通过 `rsts_last_error()` 获取（线程内有效，直到下一次调用）；
+//! - 库返回的字符串须用 `rsts_string_free` 释放，字节缓冲须用 `rsts_bytes_free` 释放；
+//! - 传入的字符串须为 UTF-8 且以 NUL 结尾，库不会持有调用方的指针。
+//!
+//! 重新生成头文件：`cbindgen --config cbindgen.toml --output include/rsts.h`（在 app1 目录执行）
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::ptr;
+
+use super::runtime;
+use crate::modules::demo::db_datatype_trans::convert_sql;
+use crate::modules::demo::pg_to_csv::pg_export_from_json;
+use crate::modules::operators::SobelOperator;
+
+/// ABI 版本，不兼容的修改时递增
+pub const RSTS_ABI_VERSION: u32 = 1;
+
+/// 调用结果
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RstsStatus {
+    Ok = 0,
+    /// 参数为空、不是合法 UTF-8 或取值无效
+    InvalidArgument = 1,
+    /// 导出失败
+    ExportFailed = 2,
+    /// SQL 转换失败
+    ConvertFailed = 3,
+    /// 图像处理失败
+    ImageFailed = 4,
+    /// 运行时初始化失败
+    RuntimeFailed = 5,
+    /// 库内部 panic
+    Panic = 6,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(msg: impl Into<String>) {
+    let msg = msg.into().replace('\0', " ");
+    LAST_ERROR.with(|e| *e.borrow_mut() = CString::new(msg).ok());
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|e| *e.borrow_mut() = None);
+}
+
+/// 捕获 panic，避免跨越 FFI 边界展开
+fn guard(f: impl FnOnce() -> RstsStatus) -> RstsStatus {
+    clear_last_error();
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(status) => status,
+        Err(_) => {
+            set_last_error("panic in rsts library");
+            RstsStatus::Panic
+        }
+    }
+}
+
+/// 读取调用方传入的 C 字符串
+///
+/// # Safety
+/// `s` 须为空指针或指向以 NUL 结尾的有效字符串
+unsafe fn read_str<'a>(s: *const c_char, name: &str) -> Result<&'a str, RstsStatus> {
+    if s.is_null() {
+        set_last_error(format!("{} is null", name));
+        return Err(RstsStatus::InvalidArgument);
+    }
+    unsafe { CStr::from_ptr(s) }.to_str().map_err(|_| {
+        set_last_error(format!("{} is not valid UTF-8", name));
+        RstsStatus::InvalidArgument
+    })
+}
+
+/// 把结果字符串交给调用方
+fn give_string(out: *mut *mut c_char, value: String) -> RstsStatus {
+    match CString::new(value) {
+        Ok(c) => {
+            unsafe { *out = c.into_raw() };
+            RstsStatus::Ok
+        }
+        Err(_) => {
+            set_last_error("result contains NUL byte");
+            RstsStatus::InvalidArgument
+        }
+    }
+}
+
+/// 返回 ABI 版本
+#[unsafe(no_mangle)]
+pub extern "C" fn rsts_abi_version() -> u32 {
+    RSTS_ABI_VERSION
+}
+
+/// 最近一次失败的错误信息；没有错误时返回 NULL。
+/// 返回的指针归库所有，不要释放，在同一线程的下一次调用前有效。
+#[unsafe(no_mangle)]
+pub extern "C" fn rsts_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+/// 按 JSON 参数导出 PostgreSQL 表到 CSV，参数格式同 `pg_export_from_json`。
+/// 成功时 `*out_report` 为结果 JSON（行数、文件路径、耗时等），须用 `rsts_string_free` 释放。
+///
+/// # Safety
+/// `json` 须为以 NUL 结尾的有效字符串，`out_report` 须为有效的可写指针
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsts_pg_export_from_json(
+    json: *const c_char,
+    out_report: *mut *mut c_char,
+) -> RstsStatus {
+    guard(|| {
+        if out_report.is_null() {
+            set_last_error("out_report is null");
+            return RstsStatus::InvalidArgument;
+        }
+        unsafe { *out_report = ptr::null_mut() };
+        let json = match unsafe { read_str(json, "json") } {
+            Ok(s) => s,
+            Err(status) => return status,
+        };
+        let rt = match runtime() {
+            Ok(rt) => rt,
+            Err(e) => {
+                set_last_error(format!("{:#}", e));
+                return RstsStatus::RuntimeFailed;
+            }
+        };
+        let report = rt
+            .block_on(pg_export_from_json(json))
+            .and_then(|r| Ok(serde_json::to_string(&r)?));
+        match report {
+            Ok(body) => give_string(out_report, body),
+            Err(e) => {
+                set_last_error(format!("{:#}", e));
+                if e.downcast_ref::<serde_json::Error>().is_some() {
+                    RstsStatus::InvalidArgument
+                } else {
+                    RstsStatus::ExportFailed
+                }
+            }
+        }
+    })
+}
+
+/// 将 SQL 从 `from_db` 方言转换为 `to_db` 方言（mysql/postgresql/sqlite/sqlserver/oracle）。
+/// 成功时 `*out_sql` 须用 `rsts_string_free` 释放。
+///
+/// # Safety
+/// 字符串参数须为以 NUL 结尾的有效字符串，`out_sql` 须为有效的可写指针
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsts_convert_sql(
+    sql: *const c_char,
+    from_db: *const c_char,
+    to_db: *const c_char,
+    out_sql: *mut *mut c_char,
+) -> RstsStatus {
+    guard(|| {
+        if out_sql.is_null() {
+            set_last_error("out_sql is null");
+            return RstsStatus::InvalidArgument;
+        }
+        unsafe { *out_sql = ptr::null_mut() };
+        let args = unsafe {
+            (
+                read_str(sql, "sql"),
+                read_str(from_db, "from_db"),
+                read_str(to_db, "to_db"),
+            )
+        };
+        let (sql, from_db, to_db) = match args {
+            (Ok(a), Ok(b), Ok(c)) => (a, b, c),
+            (Err(s), _, _) | (_, Err(s), _) | (_, _, Err(s)) => return s,
+        };
+        match convert_sql(sql, from_db, to_db) {
+            Ok(out) => give_string(out_sql, out),
+            Err(e) => {
+                set_last_error(format!("{:#}", e));
+                RstsStatus::ConvertFailed
+            }
+        }
+    })
+}
+
+/// 对图像数据（PNG/JPEG 等）做 Sobel 边缘检测，输出 PNG。
+/// 成功时 `*out_data`/`*out_len` 为结果缓冲，须用 `rsts_bytes_free(out_data, out_len)` 释放。
+///
+/// # Safety
+/// `data` 须指向至少 `len` 字节的可读内存，输出指针须为有效的可写指针
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsts_sobel_apply(
+    data: *const u8,
+    len: usize,
+    out_data: *mut *mut u8,
+    out_len: *mut usize,
+) -> RstsStatus {
+    guard(|| {
+        if out_data.is_null() || out_len.is_null() {
+            set_last_error("output pointer is null");
+            return RstsStatus::InvalidArgument;
+        }
+        unsafe {
+            *out_data = ptr::null_mut();
+            *out_len = 0;
+        }
+        if data.is_null() || len == 0 {
+            set_last_error("image data is empty");
+            return RstsStatus::InvalidArgument;
+        }
+        let input = unsafe { std::slice::from_raw_parts(data, len) };
+        match SobelOperator::apply_to_buffer(input) {
+            Ok(png) => {
+                let png = png.into_boxed_slice();
+                unsafe {
+                    *out_len = png.len();
+                    *out_data = Box::into_raw(png) as *mut u8;
+                }
+                RstsStatus::Ok
+            }
+            Err(e) => {
+                set_last_error(format!("{:#}", e));
+                RstsStatus::ImageFailed
+            }
+        }
+    })
+}
+
+/// 释放库返回的字符串，传入 NULL 无操作
+///
+/// # Safety
+/// `s` 须为本库返回且尚未释放的指针
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsts_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// 释放库返回的字节缓冲，`len` 须与返回时一致，传入 NULL 无操作
+///
+/// # Safety
+/// `data`/`len` 须为本库返回且尚未释放的缓冲
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rsts_bytes_free(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_sql_and_free() {
+        let sql = CString::new("SELECT `a` FROM t LIMIT 10").unwrap();
+        let from = CString::new("mysql").unwrap();
+        let to = CString::new("postgresql").unwrap();
+        let mut out: *mut c_char = ptr::null_mut();
+        let status =
+            unsafe { rsts_convert_sql(sql.as_ptr(), from.as_ptr(), to.as_ptr(), &mut out) };
+        assert_eq!(status, RstsStatus::Ok);
+        assert!(!out.is_null());
+        unsafe { rsts_string_free(out) };
+    }
+
+    #[test]
+    fn test_error_reporting() {
+        let mut out: *mut c_char = ptr::null_mut();
+        let to = CString::new("postgresql").unwrap();
+        let status = unsafe { rsts_convert_sql(ptr::null(), to.as_ptr(), to.as_ptr(), &mut out) };
+        assert_eq!(status, RstsStatus::InvalidArgument);
+        assert!(out.is_null());
+        let msg = unsafe { CStr::from_ptr(rsts_last_error()) };
+        assert_eq!(msg.to_str().unwrap(), "sql is null");
+
+        let mut data: *mut u8 = ptr::null_mut();
+        let mut len = 0usize;
+        let status = unsafe { rsts_sobel_apply(b"nope".as_ptr(), 4, &mut data, &mut len) };
+        assert_eq!(status, RstsStatus::ImageFailed);
+        assert!(data.is_null());
+    }
+
+    #[test]
+    fn test_header_is_up_to_date() {
+        let dir = env!("CARGO_MANIFEST_DIR");
+        let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", dir)).unwrap();
+        let mut generated = Vec::new();
+        cbindgen::generate_with_config(dir, config)
+            .unwrap()
+            .write(&mut generated);
+        let generated = String::from_utf8(generated).unwrap();
+        assert!(generated.contains("#define RSTS_ABI_VERSION 1"));
+        let checked_in = std::fs::read_to_string(format!("{}/include/rsts.h", dir))
+            .unwrap()
+            .replace("\r\n", "\n");
+        assert!(
+            generated == checked_in,
+            "include/rsts.h 已过期，请在 app1 目录执行 cbindgen --config cbindgen.toml --output include/rsts.h"
+        );
+    }
+}
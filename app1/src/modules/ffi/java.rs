@@ -0,0 +1,279 @@
+//! JNI 导出函数
+//!
+//! 对应的 Java 声明：
+//!
+//! ```java
+//! package org.rsts;
+//!
+//! public interface ProgressListener { void onProgress(String json); }
+//!
+//! public final class CsvExporter {
+//!     public static native String export(String json);
+//!     public static native long createTask();
+//!     public static native boolean cancel(long taskId);
+//!     public static native String exportWithTask(long taskId, String json, ProgressListener listener);
+//!     public static native String importCsv(long taskId, String json, ProgressListener listener);
+//! }
+//!
+//! public final class SqlConverter {
+//!     public static native String convert(String sql, String fromDb, String toDb);
+//! }
+//! ```
+//!
+//! 导入导出返回结果 JSON（行数、文件路径、耗时等）；参数错误抛 `IllegalArgumentException`，
+//! 被取消抛 `CancellationException`，其他失败（含 Rust 侧 panic）抛 `RuntimeException`。
+//! `taskId` 为 0 表示不需要取消；非 0 时须先由 `createTask` 创建，任务结束后自动释放。
+//! 所有调用共享同一个 Tokio 运行时。
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use anyhow::{Context, Result, bail};
+use jni::JNIEnv;
+use jni::objects::{JClass, JObject, JString, JValue};
+use jni::sys::{JNI_FALSE, JNI_TRUE, jboolean, jlong, jstring};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use super::runtime;
+use crate::modules::demo::db_datatype_trans::convert_sql;
+use crate::modules::demo::pg_to_csv::{pg_export_from_json_with_control, pg_import_from_json};
+use crate::modules::demo::transfer_control::{
+    TransferControl, TransferProgress, is_cancelled_error,
+};
+
+/// 可取消的任务：taskId -> 取消标记
+static TASKS: Lazy<Mutex<HashMap<jlong, TransferControl>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_TASK_ID: AtomicI64 = AtomicI64::new(1);
+
+const ILLEGAL_ARGUMENT: &str = "java/lang/IllegalArgumentException";
+const RUNTIME_EXCEPTION: &str = "java/lang/RuntimeException";
+const CANCELLATION: &str = "java/util/concurrent/CancellationException";
+
+/// 抛出异常并返回 null
+fn throw(env: &mut JNIEnv, class: &str, msg: &str) -> jstring {
+    let _ = env.throw_new(class, msg);
+    std::ptr::null_mut()
+}
+
+/// 捕获 panic，避免跨越 JNI 边界展开
+fn guard(env: &mut JNIEnv, f: impl FnOnce(&mut JNIEnv) -> jstring) -> jstring {
+    match catch_unwind(AssertUnwindSafe(|| f(env))) {
+        Ok(ret) => ret,
+        Err(_) => throw(env, RUNTIME_EXCEPTION, "panic in rsts library"),
+    }
+}
+
+/// 任务结束时释放 taskId（出错、panic 时同样释放）
+struct TaskSlot(jlong);
+
+impl Drop for TaskSlot {
+    fn drop(&mut self) {
+        if self.0 != 0 {
+            TASKS
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(&self.0);
+        }
+    }
+}
+
+fn read_string(env: &mut JNIEnv, s: &JString) -> Option<String> {
+    env.get_string(s)
+        .ok()
+        .map(|v| v.to_string_lossy().into_owned())
+}
+
+fn new_jstring(env: &mut JNIEnv, s: &str) -> jstring {
+    match env.new_string(s) {
+        Ok(js) => js.into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// 取出任务的取消标记，并把 Java 监听器包装为进度回调
+fn task_control(env: &mut JNIEnv, task_id: jlong, listener: &JObject) -> Result<TransferControl> {
+    let control = if task_id == 0 {
+        TransferControl::new()
+    } else {
+        match TASKS.lock().unwrap().get(&task_id) {
+            Some(c) => c.clone(),
+            None => bail!("unknown task id: {}", task_id),
+        }
+    };
+    if listener.is_null() {
+        return Ok(control);
+    }
+    let vm = env.get_java_vm().context("get JavaVM failed")?;
+    let listener = env
+        .new_global_ref(listener)
+        .context("create listener ref failed")?;
+    Ok(
+        control.with_listener(Arc::new(move |progress: &TransferProgress| {
+            // 回调可能来自运行时的工作线程，需挂接到 JVM
+            let Ok(mut env) = vm.attach_current_thread_permanently() else {
+                return;
+            };
+            let json = serde_json::to_string(progress).unwrap_or_default();
+            // 挂接的线程不会返回 Java，局部引用须在本地帧内释放，否则每次回调泄漏一个
+            let ret = env.with_local_frame(4, |env| -> jni::errors::Result<()> {
+                let js = env.new_string(json)?;
+                env.call_method(
+                    listener.as_obj(),
+                    "onProgress",
+                    "(Ljava/lang/String;)V",
+                    &[JValue::Object(&js)],
+                )?;
+                Ok(())
+            });
+            if ret.is_err() {
+                // 监听器异常不影响导出
+                let _ = env.exception_clear();
+            }
+        })),
+    )
+}
+
+/// 在共享运行时上执行任务，结果序列化为 JSON 返回
+fn run_task<T, F, Fut>(
+    env: &mut JNIEnv,
+    task_id: jlong,
+    json: &JString,
+    listener: &JObject,
+    task: F,
+) -> jstring
+where
+    T: Serialize,
+    F: FnOnce(String, TransferControl) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let _slot = TaskSlot(task_id);
+    guard(env, |env| {
+        let Some(json) = read_string(env, json) else {
+            return throw(env, ILLEGAL_ARGUMENT, "invalid json");
+        };
+        let control = match task_control(env, task_id, listener) {
+            Ok(c) => c,
+            Err(e) => return throw(env, ILLEGAL_ARGUMENT, &format!("{:#}", e)),
+        };
+        let rt = match runtime() {
+            Ok(rt) => rt,
+            Err(e) => return throw(env, RUNTIME_EXCEPTION, &format!("{:#}", e)),
+        };
+        let result = rt.block_on(task(json, control));
+        match result.and_then(|v| serde_json::to_string(&v).context("serialize result failed")) {
+            Ok(body) => new_jstring(env, &body),
+            Err(e) if is_cancelled_error(&e) => throw(env, CANCELLATION, &format!("{:#}", e)),
+            Err(e) => throw(env, RUNTIME_EXCEPTION, &format!("{:#}", e)),
+        }
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_org_rsts_CsvExporter_export(
+    mut env: JNIEnv,
+    _class: JClass,
+    json: JString,
+) -> jstring {
+    run_task(
+        &mut env,
+        0,
+        &json,
+        &JObject::null(),
+        |json, control| async move {
+            pg_export_from_json_with_control(&json, control)
+                .await
+                .context("CSV export failed")
+        },
+    )
+}
+
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_org_rsts_CsvExporter_createTask(_env: JNIEnv, _class: JClass) -> jlong {
+    let id = NEXT_TASK_ID.fetch_add(1, Ordering::SeqCst);
+    TASKS.lock().unwrap().insert(id, TransferControl::new());
+    id
+}
+
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_org_rsts_CsvExporter_cancel(
+    _env: JNIEnv,
+    _class: JClass,
+    task_id: jlong,
+) -> jboolean {
+    match TASKS.lock().unwrap().get(&task_id) {
+        Some(control) => {
+            control.cancel();
+            JNI_TRUE
+        }
+        None => JNI_FALSE,
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_org_rsts_CsvExporter_exportWithTask(
+    mut env: JNIEnv,
+    _class: JClass,
+    task_id: jlong,
+    json: JString,
+    listener: JObject,
+) -> jstring {
+    run_task(
+        &mut env,
+        task_id,
+        &json,
+        &listener,
+        |json, control| async move {
+            pg_export_from_json_with_control(&json, control)
+                .await
+                .context("CSV export failed")
+        },
+    )
+}
+
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_org_rsts_CsvExporter_importCsv(
+    mut env: JNIEnv,
+    _class: JClass,
+    task_id: jlong,
+    json: JString,
+    listener: JObject,
+) -> jstring {
+    run_task(
+        &mut env,
+        task_id,
+        &json,
+        &listener,
+        |json, control| async move {
+            pg_import_from_json(&json, control)
+                .await
+                .context("CSV import failed")
+        },
+    )
+}
+
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_org_rsts_SqlConverter_convert(
+    mut env: JNIEnv,
+    _class: JClass,
+    sql: JString,
+    from_db: JString,
+    to_db: JString,
+) -> jstring {
+    guard(&mut env, |env| {
+        let (Some(sql), Some(from_db), Some(to_db)) = (
+            read_string(env, &sql),
+            read_string(env, &from_db),
+            read_string(env, &to_db),
+        ) else {
+            return throw(env, ILLEGAL_ARGUMENT, "invalid argument");
+        };
+        match convert_sql(&sql, &from_db, &to_db) {
+            Ok(out) => new_jstring(env, &out),
+            Err(e) => throw(env, ILLEGAL_ARGUMENT, &format!("{:#}", e)),
+        }
+    })
+}
@@ -0,0 +1,14 @@
+// 对外的本地接口（JNI 与 C ABI）
+pub mod capi;
+pub mod java;
+
+use anyhow::{Context, Result};
+use once_cell::sync::OnceCell;
+use tokio::runtime::Runtime;
+
+static RUNTIME: OnceCell<Runtime> = OnceCell::new();
+
+/// JNI 与 C ABI 共享的 Tokio 运行时，首次调用时创建
+pub(crate) fn runtime() -> Result<&'static Runtime> {
+    RUNTIME.get_or_try_init(|| Runtime::new().context("runtime init failed"))
+}
@@ -4,6 +4,7 @@
 pub mod aigateway;
 pub mod config;
 pub mod demo;
+pub mod ffi;
 pub mod logging;
 pub mod operators;
 pub mod proxy;
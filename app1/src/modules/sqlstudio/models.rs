@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct SqlConnection {
     pub id: Option<i64>,
     pub name: String,
@@ -14,7 +15,7 @@ pub struct SqlConnection {
     pub updated_at: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct TestConnectionRequest {
     pub db_type: String,
     pub host: String,
@@ -25,7 +26,7 @@ pub struct TestConnectionRequest {
     pub database: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateConnectionRequest {
     pub name: String,
     pub db_type: String,
@@ -37,7 +38,7 @@ pub struct CreateConnectionRequest {
     pub database: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateConnectionRequest {
     pub id: i64,
     pub name: String,
@@ -50,12 +51,12 @@ pub struct UpdateConnectionRequest {
     pub database: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct DeleteConnectionRequest {
     pub id: i64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct MetadataRequest {
     pub connection_id: i64,
     pub action: String, // databases, schemas, tables, views, functions
@@ -63,13 +64,13 @@ pub struct MetadataRequest {
     pub schema: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct MetadataResponse {
     pub name: String,
     pub object_type: String, // database, schema, table, view, function
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct TableDataRequest {
     pub connection_id: i64,
     pub database: String,
@@ -81,14 +82,14 @@ pub struct TableDataRequest {
     pub sort_order: Option<String>, // ASC, DESC
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct ExecuteSqlRequest {
     pub connection_id: i64,
     pub database: String,
     pub sql: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ExecuteSqlResponse {
     pub columns: Option<Vec<String>>,
     pub rows: Option<Vec<serde_json::Value>>,
@@ -0,0 +1,131 @@
+//! 无界面的 SSH 命令执行（基于 ssh2，阻塞调用）
+//!
+//! 供命令行工具与监控接口复用：按别名或 ID 读取已保存的服务器，执行单条命令并返回输出与退出码。
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use ssh2::{MethodType, Session as Ssh2Session};
+use std::io::{ErrorKind, Read};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use crate::modules::config::config::ServerConfig;
+use crate::modules::web::database::Database;
+use crate::modules::web::models::SshServer;
+
+/// 命令执行结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecOutput {
+    pub server: String,
+    pub command: String,
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_status: i32,
+}
+
+/// 建立 ssh2 会话并使用密码认证
+pub fn ssh2_connect(host: &str, port: u16, username: &str, password: &str) -> Result<Ssh2Session> {
+    let addr = format!("{}:{}", host, port);
+    let tcp = TcpStream::connect(addr)?;
+    tcp.set_read_timeout(Some(Duration::from_secs(30)))?;
+    tcp.set_write_timeout(Some(Duration::from_secs(30)))?;
+    let mut sess = Ssh2Session::new()?;
+    sess.set_tcp_stream(tcp);
+    // 扩大兼容算法集合
+    let _ = sess.method_pref(
+        MethodType::Kex,
+        "curve25519-sha256,curve25519-sha256@libssh.org,ecdh-sha2-nistp256,ecdh-sha2-nistp384,ecdh-sha2-nistp521,diffie-hellman-group-exchange-sha256,diffie-hellman-group16-sha512,diffie-hellman-group18-sha512,diffie-hellman-group14-sha256",
+    );
+    let _ = sess.method_pref(
+        MethodType::HostKey,
+        "ecdsa-sha2-nistp256,ecdsa-sha2-nistp384,ecdsa-sha2-nistp521,ssh-ed25519,rsa-sha2-512,rsa-sha2-256,ssh-rsa",
+    );
+    let crypt = "aes128-ctr,aes192-ctr,aes256-ctr,aes128-gcm@openssh.com,aes256-gcm@openssh.com,chacha20-poly1305@openssh.com";
+    let _ = sess.method_pref(MethodType::CryptCs, crypt);
+    let _ = sess.method_pref(MethodType::CryptSc, crypt);
+    let mac =
+        "hmac-sha2-256,hmac-sha2-512,hmac-sha2-256-etm@openssh.com,hmac-sha2-512-etm@openssh.com";
+    let _ = sess.method_pref(MethodType::MacCs, mac);
+    let _ = sess.method_pref(MethodType::MacSc, mac);
+    sess.handshake()?;
+    sess.set_blocking(true);
+    sess.userauth_password(username, password)?;
+    if !sess.authenticated() {
+        bail!("ssh auth failed");
+    }
+    Ok(sess)
+}
+
+/// 按 ID 或别名读取已保存的服务器（按配置的元数据库后端，与 Web 端共用仓储层）
+pub async fn load_saved_server(config: &ServerConfig, key: &str) -> Result<SshServer> {
+    let db = Database::connect(config)
+        .await
+        .context("open metadata store failed")?;
+    db.ssh_servers()
+        .find_server(key.to_string())
+        .await?
+        .with_context(|| format!("ssh server not found: {}", key))
+}
+
+/// 读取流中当前可用的数据，返回是否读到了内容
+fn drain(stream: &mut impl Read, out: &mut Vec<u8>) -> Result<bool> {
+    let mut buf = [0u8; 8192];
+    let mut progressed = false;
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => return Ok(progressed),
+            Ok(n) => {
+                out.extend_from_slice(&buf[..n]);
+                progressed = true;
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(progressed),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// 在服务器上执行一条命令，等待结束并收集 stdout/stderr 与退出码
+///
+/// `idle_timeout` 为连续无输出的最长时间，超过后放弃等待；`None` 表示不限制。
+pub fn exec_on_server(
+    server: &SshServer,
+    command: &str,
+    idle_timeout: Option<Duration>,
+) -> Result<ExecOutput> {
+    let port = u16::try_from(server.port).context("invalid ssh port")?;
+    let password = server.password.clone().unwrap_or_default();
+    let sess =
+        ssh2_connect(&server.hostname, port, &server.username, &password).with_context(|| {
+            format!(
+                "ssh connect failed: {}@{}",
+                server.username, server.hostname
+            )
+        })?;
+    let mut ch = sess.channel_session()?;
+    ch.exec(command)?;
+    // 非阻塞轮流读取两个流：只读 stdout 时，stderr 写满窗口会让远端阻塞，双方互相等待
+    sess.set_blocking(false);
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let mut last_data = Instant::now();
+    loop {
+        let got_out = drain(&mut ch, &mut stdout)?;
+        let got_err = drain(&mut ch.stderr(), &mut stderr)?;
+        if got_out || got_err {
+            last_data = Instant::now();
+        } else if ch.eof() {
+            break;
+        } else if let Some(limit) = idle_timeout.filter(|l| last_data.elapsed() > *l) {
+            bail!("ssh command timed out: no output for {}s", limit.as_secs());
+        } else {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+    sess.set_blocking(true);
+    ch.wait_close()?;
+    Ok(ExecOutput {
+        server: server.alias.clone(),
+        command: command.to_string(),
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        exit_status: ch.exit_status()?,
+    })
+}
@@ -8,6 +8,8 @@ pub mod ssh_client;
 pub mod service;
 // 导出示例代码
 pub mod examples;
+// 导出无界面命令执行
+pub mod exec;
 
 // 重新导出常用类型和函数
 pub use service::SshService;
@@ -1,19 +1,29 @@
-use actix_web::{HttpResponse, Responder, web};
+use actix_web::web;
 use serde::Deserialize;
+use utoipa::ToSchema;
 
-use crate::modules::task::service::{TaskConfig, TaskManager};
+use crate::modules::task::service::{TaskConfig, TaskManager, TaskStatusDto};
+use crate::modules::web::models::Response;
+use crate::modules::web::response::ApiResult;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct StartTaskPayload {
     pub threads: Option<usize>,
     pub pause_ms: Option<u64>,
     pub duration_seconds: Option<u64>, // None 表示无限制
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/task/start",
+    tag = "task",
+    summary = "启动压测任务",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
 pub async fn start_task(
     task_mgr: web::Data<TaskManager>,
     payload: web::Json<StartTaskPayload>,
-) -> impl Responder {
+) -> ApiResult<()> {
     let cfg = TaskConfig {
         threads: payload
             .threads
@@ -22,15 +32,28 @@ pub async fn start_task(
         duration_seconds: payload.duration_seconds,
     };
     task_mgr.start(cfg).await;
-    HttpResponse::Ok().json(serde_json::json!({"status":"started"}))
+    Ok(Response::message("started"))
 }
 
-pub async fn stop_task(task_mgr: web::Data<TaskManager>) -> impl Responder {
+#[utoipa::path(
+    post,
+    path = "/api/task/stop",
+    tag = "task",
+    summary = "停止压测任务",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn stop_task(task_mgr: web::Data<TaskManager>) -> ApiResult<()> {
     task_mgr.stop().await;
-    HttpResponse::Ok().json(serde_json::json!({"status":"stopped"}))
+    Ok(Response::message("stopped"))
 }
 
-pub async fn task_status(task_mgr: web::Data<TaskManager>) -> impl Responder {
-    let status = task_mgr.status().await;
-    HttpResponse::Ok().json(status)
+#[utoipa::path(
+    get,
+    path = "/api/task/status",
+    tag = "task",
+    summary = "压测任务状态",
+    responses((status = 200, body = Response<TaskStatusDto>))
+)]
+pub async fn task_status(task_mgr: web::Data<TaskManager>) -> ApiResult<TaskStatusDto> {
+    Ok(Response::ok(task_mgr.status().await))
 }
@@ -150,7 +150,7 @@ impl TaskManager {
     }
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, utoipa::ToSchema)]
 pub struct TaskStatusDto {
     pub running: bool,
     pub threads: usize,
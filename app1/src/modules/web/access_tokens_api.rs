@@ -0,0 +1,170 @@
+// 个人访问令牌 API：当前用户创建、列出、吊销自己的令牌；管理员可吊销某个用户的全部令牌
+//
+// 令牌供脚本与 CI 调用 API，权限限定在创建时选择的范围内（不超过创建者自身的权限）。
+// 管理令牌需要交互式登录，不能用个人访问令牌再创建令牌。
+use actix_web::{HttpMessage, HttpRequest, web};
+use chrono::Utc;
+use serde_json::json;
+use std::sync::Arc;
+
+use super::auth_utils::{Claims, generate_personal_token};
+use super::database::Database;
+use super::models::{AccessTokenCreateInput, AccessTokenCreated, AccessTokenInfo, Response};
+use super::rbac::{PERMISSIONS, Permissions};
+use super::repository::NewAccessToken;
+use super::response::{ApiError, ApiResult};
+use crate::modules::config::config::Config;
+
+// 当前交互式登录的 (id, 用户名)；个人访问令牌认证的请求被拒绝
+fn session_user(req: &HttpRequest) -> Result<(i64, String), ApiError> {
+    let extensions = req.extensions();
+    extensions
+        .get::<Claims>()
+        .filter(|claims| claims.token_type == "access")
+        .and_then(|claims| Some((claims.sub.parse().ok()?, claims.username.clone())))
+        .ok_or_else(|| ApiError::Forbidden("请登录后管理访问令牌".to_string()))
+}
+
+// GET /api/user/tokens
+#[utoipa::path(
+    get,
+    path = "/api/user/tokens",
+    tag = "user",
+    summary = "个人访问令牌列表",
+    responses((status = 200, body = Response<Vec<AccessTokenInfo>>))
+)]
+pub async fn list_tokens(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+) -> ApiResult<Vec<AccessTokenInfo>> {
+    let (user_id, _) = session_user(&req)?;
+    Ok(Response::ok(db.access_tokens().list(user_id).await?))
+}
+
+// POST /api/user/tokens：令牌明文只在创建时返回一次
+#[utoipa::path(
+    post,
+    path = "/api/user/tokens",
+    tag = "user",
+    summary = "创建个人访问令牌",
+    responses((status = 200, body = Response<AccessTokenCreated>))
+)]
+pub async fn create_token(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+    config: web::Data<Config>,
+    body: web::Json<AccessTokenCreateInput>,
+) -> ApiResult<AccessTokenCreated> {
+    let (user_id, username) = session_user(&req)?;
+    let input = body.into_inner();
+    let name = input.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 64 {
+        return Err(ApiError::InvalidParams(
+            "名称不能为空且不超过 64 个字符".to_string(),
+        ));
+    }
+    let settings = &config.personal_access_tokens;
+    let days = input.expires_in_days.unwrap_or(settings.default_days);
+    if days == 0 || days > settings.max_days {
+        return Err(ApiError::InvalidParams(format!(
+            "有效期需在 1-{} 天之间",
+            settings.max_days
+        )));
+    }
+
+    let mut scopes = input.scopes;
+    scopes.sort();
+    scopes.dedup();
+    if scopes.is_empty() {
+        return Err(ApiError::InvalidParams("至少选择一个权限".to_string()));
+    }
+    if let Some(unknown) = scopes
+        .iter()
+        .find(|scope| !PERMISSIONS.iter().any(|(code, _)| code == scope))
+    {
+        return Err(ApiError::InvalidParams(format!("权限不存在: {}", unknown)));
+    }
+    // 不能授予自己没有的权限
+    let own = Permissions::new(db.rbac().user_permissions(&username).await?);
+    if let Some(missing) = scopes.iter().find(|scope| !own.allows(scope)) {
+        return Err(ApiError::Forbidden(format!("没有权限: {}", missing)));
+    }
+
+    let (token, token_hash, prefix) = generate_personal_token();
+    let expires_at = Utc::now().timestamp() + i64::from(days) * 86_400;
+    let new_token = NewAccessToken {
+        user_id,
+        name,
+        token_hash,
+        prefix,
+        scopes,
+        expires_at,
+    };
+    let Some(info) = db
+        .access_tokens()
+        .create(new_token, settings.max_per_user)
+        .await?
+    else {
+        return Err(ApiError::Conflict(format!(
+            "最多持有 {} 个有效令牌",
+            settings.max_per_user
+        )));
+    };
+    log::info!(
+        "user {} created personal access token {} ({})",
+        username,
+        info.id,
+        info.name
+    );
+    let created = AccessTokenCreated { token, info };
+    Ok(Response::with_msg("访问令牌已创建", created))
+}
+
+// DELETE /api/user/tokens/{id}
+#[utoipa::path(
+    delete,
+    path = "/api/user/tokens/{id}",
+    tag = "user",
+    summary = "吊销个人访问令牌",
+    params(("id" = i64, Path, description = "ID")),
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn revoke_token(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+    path: web::Path<i64>,
+) -> ApiResult<()> {
+    let (user_id, username) = session_user(&req)?;
+    let id = path.into_inner();
+    if !db.access_tokens().revoke(user_id, id).await? {
+        return Err(ApiError::NotFound("令牌不存在".to_string()));
+    }
+    log::info!("user {} revoked personal access token {}", username, id);
+    Ok(Response::message("访问令牌已吊销"))
+}
+
+// DELETE /api/users/{username}/tokens：管理员吊销用户的全部令牌
+#[utoipa::path(
+    delete,
+    path = "/api/users/{username}/tokens",
+    tag = "users",
+    summary = "吊销用户的全部访问令牌",
+    params(("username" = String, Path, description = "用户名")),
+    responses((status = 200, body = Response))
+)]
+pub async fn revoke_user_tokens(
+    db: web::Data<Arc<Database>>,
+    path: web::Path<String>,
+) -> ApiResult {
+    let username = path.into_inner();
+    let revoked = db.access_tokens().revoke_user(&username).await?;
+    log::info!(
+        "{} personal access tokens of user {} revoked",
+        revoked,
+        username
+    );
+    Ok(Response::with_msg(
+        "已吊销该用户的访问令牌",
+        json!({ "revoked": revoked }),
+    ))
+}
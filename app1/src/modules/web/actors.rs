@@ -5,8 +5,10 @@ use std::sync::{Arc, atomic::AtomicBool, atomic::AtomicUsize};
 use std::time::Instant;
 use tokio::sync::Mutex;
 
+use super::audit::{AuditEvent, Auditor};
 use super::database::Database;
 use super::models::{AnyPtyClient, Message};
+use super::repository::ChatWriter;
 use crate::modules::ssh::SshService;
 
 // 聊天服务器 Actor
@@ -16,6 +18,7 @@ pub struct ChatServer {
     pub rooms: HashMap<String, HashSet<u64>>,
     pub visitor_count: Arc<AtomicUsize>,
     pub db: Arc<Database>,
+    pub chat_writer: ChatWriter,
     pub id_to_session_id: HashMap<u64, String>,
     pub id_to_username: HashMap<u64, String>,
 }
@@ -26,7 +29,8 @@ pub struct WsChatSession {
     pub id: u64,
     pub hb: Instant,
     pub room: String,
-    pub name: Option<String>,
+    /// 登录用户名（连接时由访问令牌确定）
+    pub name: String,
     pub addr: Addr<ChatServer>,
 }
 
@@ -36,6 +40,10 @@ pub struct WsSshSession {
     pub ssh_service: Arc<Mutex<SshService>>,
     pub ssh_client_id: Option<usize>,
     pub connected_to_ssh: Arc<AtomicBool>,
+    /// 审计日志（握手时确定登录用户）
+    pub auditor: Option<Auditor>,
+    /// 当前 SSH 会话的关闭事件，计时从发起连接开始
+    pub session_audit: Option<AuditEvent>,
 }
 
 // SSH PTY WebSocket 会话 Actor
@@ -47,4 +55,8 @@ pub struct WsSshPtySession {
     pub last_resize_at: Option<Instant>,
     pub resize_deferred: bool,
     pub pending_resize: Option<(u32, u32)>,
+    /// 审计日志（握手时确定登录用户）
+    pub auditor: Option<Auditor>,
+    /// 当前 SSH 会话的关闭事件，计时从发起连接开始
+    pub session_audit: Option<AuditEvent>,
 }
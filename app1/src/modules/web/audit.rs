@@ -0,0 +1,156 @@
+// 审计日志：记录谁在何时、从哪里对什么执行了特权操作，以及结果与耗时
+//
+// 覆盖 SQL Studio 执行、SSH 终端会话、SFTP 文件操作、SSH 服务器配置变更与登录事件。
+// 操作类型按 "模块.动作" 命名，查询时 action=sftp 可匹配全部 SFTP 操作。
+// 写入失败只记录错误日志，不影响操作本身；查询与导出见 audit_api.rs。
+use actix_web::{HttpMessage, HttpRequest, web};
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::auth_utils::Claims;
+use super::database::Database;
+use super::login_guard::LoginGuard;
+use super::repository::NewAuditEntry;
+
+pub const LOGIN: &str = "login";
+pub const SQL_EXECUTE: &str = "sql.execute";
+pub const SSH_SESSION_OPEN: &str = "ssh.session.open";
+pub const SSH_SESSION_CLOSE: &str = "ssh.session.close";
+pub const SSH_GROUP_CREATE: &str = "ssh.group.create";
+pub const SSH_GROUP_UPDATE: &str = "ssh.group.update";
+pub const SSH_GROUP_DELETE: &str = "ssh.group.delete";
+pub const SSH_SERVER_CREATE: &str = "ssh.server.create";
+pub const SSH_SERVER_UPDATE: &str = "ssh.server.update";
+pub const SSH_SERVER_DELETE: &str = "ssh.server.delete";
+pub const SFTP_SESSION_OPEN: &str = "sftp.session.open";
+pub const SFTP_WRITE: &str = "sftp.write";
+pub const SFTP_UPLOAD: &str = "sftp.upload";
+pub const SFTP_DOWNLOAD: &str = "sftp.download";
+pub const SFTP_DELETE: &str = "sftp.delete";
+pub const SFTP_RENAME: &str = "sftp.rename";
+pub const SFTP_MKDIR: &str = "sftp.mkdir";
+pub const SFTP_CHMOD: &str = "sftp.chmod";
+
+// detail（如 SQL 文本）保留的最大字符数
+const DETAIL_MAX_CHARS: usize = 4000;
+
+// 客户端地址；是否信任 X-Forwarded-For 与登录保护的配置一致
+pub fn client_ip(req: &HttpRequest) -> Option<String> {
+    let ip = match req.app_data::<web::Data<LoginGuard>>() {
+        Some(guard) => guard.client_ip(req),
+        None => req.peer_addr().map(|addr| addr.ip()),
+    };
+    ip.map(|ip| ip.to_string())
+}
+
+/// 操作人与来源地址
+#[derive(Debug, Clone, Default)]
+pub struct AuditActor {
+    pub username: String,
+    pub ip: Option<String>,
+}
+
+impl AuditActor {
+    pub fn new(username: impl Into<String>, ip: Option<String>) -> Self {
+        Self {
+            username: username.into(),
+            ip,
+        }
+    }
+
+    // 已认证请求的用户（认证中间件注入的 Claims）与客户端地址
+    pub fn from_request(req: &HttpRequest) -> Self {
+        let username = req
+            .extensions()
+            .get::<Claims>()
+            .map(|claims| claims.username.clone())
+            .unwrap_or_default();
+        Self::new(username, client_ip(req))
+    }
+}
+
+/// 一次被审计的操作，创建时开始计时
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    action: &'static str,
+    target: String,
+    detail: Option<String>,
+    started: Instant,
+}
+
+impl AuditEvent {
+    pub fn start(action: &'static str, target: impl Into<String>) -> Self {
+        Self {
+            action,
+            target: target.into(),
+            detail: None,
+            started: Instant::now(),
+        }
+    }
+
+    // 补充信息，过长时截断
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        let detail = detail.into();
+        self.detail = Some(match detail.char_indices().nth(DETAIL_MAX_CHARS) {
+            Some((end, _)) => format!("{}…", &detail[..end]),
+            None => detail,
+        });
+        self
+    }
+
+    // 写入审计日志；error 为 None 表示操作成功
+    pub async fn record(&self, db: &Database, actor: &AuditActor, error: Option<String>) {
+        let entry = NewAuditEntry {
+            username: actor.username.clone(),
+            ip: actor.ip.clone(),
+            action: self.action.to_string(),
+            target: self.target.clone(),
+            duration_ms: self.started.elapsed().as_millis() as i64,
+            detail: self.detail.clone(),
+            error,
+        };
+        if let Err(e) = db.audit().append(entry).await {
+            log::error!(
+                "failed to write audit log ({} {} by {}): {}",
+                self.action,
+                self.target,
+                actor.username,
+                e
+            );
+        }
+    }
+
+    // 按操作结果写入审计日志
+    pub async fn finish<T, E: Display>(
+        &self,
+        db: &Database,
+        actor: &AuditActor,
+        result: &Result<T, E>,
+    ) {
+        let error = result.as_ref().err().map(ToString::to_string);
+        self.record(db, actor, error).await;
+    }
+}
+
+/// WebSocket 会话等长连接使用：握手时确定操作人，之后在会话中按需记录
+#[derive(Debug, Clone)]
+pub struct Auditor {
+    db: Arc<Database>,
+    actor: AuditActor,
+}
+
+impl Auditor {
+    // 应用未注册 Database 时返回 None（不记录）
+    pub fn from_request(req: &HttpRequest) -> Option<Self> {
+        let db = req.app_data::<web::Data<Arc<Database>>>()?;
+        Some(Self {
+            db: db.get_ref().clone(),
+            actor: AuditActor::from_request(req),
+        })
+    }
+
+    pub async fn record(&self, event: &AuditEvent, error: Option<String>) {
+        event.record(&self.db, &self.actor, error).await;
+    }
+}
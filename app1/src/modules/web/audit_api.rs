@@ -0,0 +1,133 @@
+// 审计日志 API：按条件查询与导出 CSV（需要 audit:read 权限）
+use actix_web::{HttpResponse, web};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+use super::database::Database;
+use super::models::{AuditEntry, AuditQuery, Response};
+use super::response::{ApiError, ApiResult};
+
+// 单次导出的最大条数
+const EXPORT_MAX_ROWS: i64 = 100_000;
+
+// GET /api/audit?username=&action=&outcome=&ip=&target=&from=&to=&limit=&offset=
+#[utoipa::path(
+    get,
+    path = "/api/audit",
+    tag = "audit",
+    summary = "查询审计日志",
+    params(AuditQuery),
+    responses((status = 200, body = Response<Vec<AuditEntry>>))
+)]
+pub async fn list_audit(
+    db: web::Data<Arc<Database>>,
+    query: web::Query<AuditQuery>,
+) -> ApiResult<Vec<AuditEntry>> {
+    let query = query.into_inner();
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let offset = query.offset.unwrap_or(0).max(0);
+    Ok(Response::ok(db.audit().list(query, limit, offset).await?))
+}
+
+// GET /api/audit/export：条件同查询接口，忽略 limit / offset
+#[utoipa::path(
+    get,
+    path = "/api/audit/export",
+    tag = "audit",
+    summary = "导出审计日志（CSV）",
+    params(AuditQuery),
+    responses((status = 200, description = "文件内容", content_type = "text/csv", body = String))
+)]
+pub async fn export_audit(
+    db: web::Data<Arc<Database>>,
+    query: web::Query<AuditQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let entries = db
+        .audit()
+        .list(query.into_inner(), EXPORT_MAX_ROWS, 0)
+        .await?;
+    let body = audit_csv(&entries).map_err(|e| ApiError::Internal(format!("导出失败: {}", e)))?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header((
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"audit-{}.csv\"",
+                Utc::now().format("%Y%m%d%H%M%S")
+            ),
+        ))
+        .body(body))
+}
+
+// 以 = + - @ 等开头的单元格在电子表格中会被当作公式，加单引号按文本显示
+fn csv_cell(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn audit_csv(entries: &[AuditEntry]) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record([
+        "id",
+        "time",
+        "username",
+        "ip",
+        "action",
+        "target",
+        "outcome",
+        "duration_ms",
+        "detail",
+        "error",
+    ])?;
+    for entry in entries {
+        let time = DateTime::from_timestamp(entry.created_at, 0)
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_default();
+        writer.write_record([
+            entry.id.to_string(),
+            time,
+            csv_cell(&entry.username),
+            entry.ip.clone().unwrap_or_default(),
+            entry.action.clone(),
+            csv_cell(&entry.target),
+            entry.outcome.clone(),
+            entry.duration_ms.to_string(),
+            csv_cell(entry.detail.as_deref().unwrap_or_default()),
+            csv_cell(entry.error.as_deref().unwrap_or_default()),
+        ])?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| csv::Error::from(e.into_error()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_export_neutralizes_formulas() {
+        let entry = AuditEntry {
+            id: 7,
+            created_at: 0,
+            username: "admin".to_string(),
+            ip: Some("10.0.0.1".to_string()),
+            action: "sftp.delete".to_string(),
+            target: "=HYPERLINK(\"x\")".to_string(),
+            outcome: "success".to_string(),
+            duration_ms: 12,
+            detail: Some("a, \"b\"".to_string()),
+            error: None,
+        };
+        let body = String::from_utf8(audit_csv(&[entry]).unwrap()).unwrap();
+        let mut lines = body.lines();
+        assert!(lines.next().unwrap().starts_with("id,time,username"));
+        assert_eq!(
+            lines.next().unwrap(),
+            "7,1970-01-01T00:00:00+00:00,admin,10.0.0.1,sftp.delete,\"'=HYPERLINK(\"\"x\"\")\",success,12,\"a, \"\"b\"\"\","
+        );
+    }
+}
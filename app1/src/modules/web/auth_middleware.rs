@@ -1,14 +1,108 @@
 use std::future::{Ready, ready};
 use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::modules::web::auth_utils::{CODE_TOKEN_EXPIRED, CODE_TOKEN_INVALID, verify_token};
-use crate::modules::web::models::Response;
+use crate::modules::web::auth_utils::{
+    Claims, hash_personal_token, is_personal_token, verify_token,
+};
+use crate::modules::web::database::Database;
+use crate::modules::web::rbac::{Permissions, TokenScopes};
+use crate::modules::web::response::ApiError;
+use crate::modules::web::ws_auth;
 use actix_web::{
     Error, HttpMessage,
     dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::Method,
+    web,
 };
 use futures::future::LocalBoxFuture;
 
+// 取出 Authorization: Bearer 令牌（WebSocket 升级请求也可放在子协议中）
+fn request_token(req: &ServiceRequest) -> String {
+    let header_token = req
+        .headers()
+        .get("Authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header_str| header_str.strip_prefix("Bearer "))
+        .map(str::to_string);
+    match header_token {
+        Some(token) => token,
+        None if ws_auth::is_upgrade(req.request()) => {
+            ws_auth::request_token(req.request()).unwrap_or_default()
+        }
+        None => String::new(),
+    }
+}
+
+// 校验访问令牌：个人访问令牌查库，其余按 JWT 校验
+async fn authenticate(req: &ServiceRequest) -> Result<Claims, Error> {
+    let token = request_token(req);
+    if token.is_empty() {
+        log::warn!("Access denied: No token provided");
+        return Err(ApiError::TokenInvalid("未提供访问令牌".to_string()).into());
+    }
+    if is_personal_token(&token) {
+        return authenticate_personal_token(req, &token).await;
+    }
+
+    match verify_token(&token) {
+        Ok(claims) => {
+            if claims.token_type != "access" {
+                log::warn!(
+                    "Access denied: Invalid token type for user {}",
+                    claims.username
+                );
+                return Err(ApiError::TokenInvalid("访问令牌无效".to_string()).into());
+            }
+            Ok(claims)
+        }
+        Err(err) => {
+            log::warn!("Access denied: Token verification failed - {}", err);
+            if matches!(
+                err.kind(),
+                jsonwebtoken::errors::ErrorKind::ExpiredSignature
+            ) {
+                Err(ApiError::TokenExpired("访问令牌已过期".to_string()).into())
+            } else {
+                Err(ApiError::TokenInvalid("访问令牌无效".to_string()).into())
+            }
+        }
+    }
+}
+
+// 个人访问令牌：按哈希查库，授予的权限以 TokenScopes 存入 extensions，
+// 生成 token_type = "pat" 的 Claims 供后续处理使用
+async fn authenticate_personal_token(req: &ServiceRequest, token: &str) -> Result<Claims, Error> {
+    let Some(db) = req.app_data::<web::Data<Arc<Database>>>() else {
+        log::error!("AuthMiddleware: Database is not registered as app data");
+        return Err(ApiError::Internal("认证数据不可用".to_string()).into());
+    };
+    match db
+        .access_tokens()
+        .authenticate(&hash_personal_token(token))
+        .await
+    {
+        Ok(Some(owner)) => {
+            req.extensions_mut().insert(TokenScopes(owner.scopes));
+            Ok(Claims {
+                sub: owner.user_id.to_string(),
+                username: owner.username,
+                token_type: "pat".to_string(),
+                exp: owner.expires_at.max(0) as usize,
+                jti: None,
+            })
+        }
+        Ok(None) => {
+            log::warn!("Access denied: unknown, revoked or expired personal access token");
+            Err(ApiError::TokenInvalid("访问令牌无效".to_string()).into())
+        }
+        Err(err) => {
+            log::error!("personal access token lookup failed: {}", err);
+            Err(ApiError::Store(err).into())
+        }
+    }
+}
+
 // There are two steps in middleware processing.
 // 1. Middleware initialization, middleware factory gets called with
 //    next service in chain as parameter.
@@ -66,83 +160,238 @@ where
         let svc = self.service.clone();
 
         Box::pin(async move {
-            // Check for Authorization header
-            let auth_header = req.headers().get("Authorization");
-
-            let token = match auth_header {
-                Some(header) => match header.to_str() {
-                    Ok(header_str) => {
-                        if header_str.starts_with("Bearer ") {
-                            &header_str[7..]
-                        } else {
-                            ""
-                        }
-                    }
-                    Err(_) => "",
-                },
-                None => "",
+            let claims = authenticate(&req).await?;
+            // Insert claims into request extensions for handlers to use
+            req.extensions_mut().insert(claims);
+
+            let res = svc.call(req).await?;
+            Ok(res)
+        })
+    }
+}
+
+/// 只允许交互式登录的会话，个人访问令牌一律拒绝。
+///
+/// 用于当前用户的账号设置（改密、两步验证、退出全部会话等），这些操作不属于任何令牌权限范围。
+/// 未经 AuthMiddleware 认证的请求会先在这里校验令牌；停用的账号一律拒绝，
+/// 必须先修改密码的账号仍可访问（否则无法完成改密）。
+pub struct RequireSession;
+
+impl<S, B> Transform<S, ServiceRequest> for RequireSession
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequireSessionMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireSessionMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RequireSessionMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireSessionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut core::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let svc = self.service.clone();
+
+        Box::pin(async move {
+            if req.method() == Method::OPTIONS {
+                return svc.call(req).await;
+            }
+            let session = req
+                .extensions()
+                .get::<Claims>()
+                .map(|claims| (claims.token_type.clone(), claims.username.clone()));
+            let (token_type, username) = match session {
+                Some(session) => session,
+                None => {
+                    let claims = authenticate(&req).await?;
+                    let session = (claims.token_type.clone(), claims.username.clone());
+                    req.extensions_mut().insert(claims);
+                    session
+                }
             };
+            if token_type != "access" {
+                log::warn!("Access denied: personal access token used for account settings");
+                return Err(ApiError::Forbidden("请登录后操作".to_string()).into());
+            }
+            let Some(db) = req.app_data::<web::Data<Arc<Database>>>() else {
+                log::error!("RequireSession: Database is not registered as app data");
+                return Err(ApiError::Internal("账号数据不可用".to_string()).into());
+            };
+            // 访问令牌有效期内被停用的账号不能再修改账号设置
+            match db.users().account_state(&username).await {
+                Ok(Some(state)) if state.disabled => {
+                    return Err(ApiError::AccountDisabled.into());
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    return Err(ApiError::TokenInvalid("用户不存在".to_string()).into());
+                }
+                Err(err) => {
+                    log::error!("load account state for {} failed: {}", username, err);
+                    return Err(ApiError::Store(err).into());
+                }
+            }
+            svc.call(req).await
+        })
+    }
+}
+
+/// 要求登录用户拥有指定权限（admin 权限视为拥有全部权限）。
+///
+/// 未经 AuthMiddleware 认证的请求会先在这里校验令牌；停用或必须先修改密码的账号一律拒绝。
+/// 使用个人访问令牌时，权限限定在令牌授予的范围内。
+/// 用户权限按请求从数据库加载，并以 [`Permissions`] 存入 extensions，
+/// 同一请求上叠加多个 RequirePermission 时只查询一次。
+/// CORS 预检（OPTIONS）不携带令牌，直接放行。
+pub struct RequirePermission(pub &'static str);
 
-            if token.is_empty() {
-                log::warn!("Access denied: No token provided");
-                let resp = Response {
-                    code: CODE_TOKEN_INVALID.to_string(),
-                    msg: "未提供访问令牌".to_string(),
-                    data: None,
-                };
-                return Err(actix_web::error::ErrorUnauthorized(
-                    serde_json::to_string(&resp).unwrap(),
-                ));
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequirePermissionMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequirePermissionMiddleware {
+            service: Rc::new(service),
+            permission: self.0,
+        }))
+    }
+}
+
+pub struct RequirePermissionMiddleware<S> {
+    service: Rc<S>,
+    permission: &'static str,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut core::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let svc = self.service.clone();
+        let permission = self.permission;
+
+        Box::pin(async move {
+            if req.method() == Method::OPTIONS {
+                return svc.call(req).await;
             }
 
-            match verify_token(token) {
-                Ok(claims) => {
-                    if claims.token_type != "access" {
-                        log::warn!(
-                            "Access denied: Invalid token type for user {}",
-                            claims.username
-                        );
-                        let resp = Response {
-                            code: CODE_TOKEN_INVALID.to_string(),
-                            msg: "访问令牌无效".to_string(),
-                            data: None,
-                        };
-                        return Err(actix_web::error::ErrorUnauthorized(
-                            serde_json::to_string(&resp).unwrap(),
-                        ));
-                    }
-                    // Insert claims into request extensions for handlers to use
+            let username = req
+                .extensions()
+                .get::<Claims>()
+                .map(|claims| claims.username.clone());
+            let username = match username {
+                Some(username) => username,
+                None => {
+                    let claims = authenticate(&req).await?;
+                    let username = claims.username.clone();
                     req.extensions_mut().insert(claims);
-
-                    let res = svc.call(req).await?;
-                    Ok(res)
+                    username
                 }
-                Err(err) => {
-                    log::warn!("Access denied: Token verification failed - {}", err);
-                    if matches!(
-                        err.kind(),
-                        jsonwebtoken::errors::ErrorKind::ExpiredSignature
-                    ) {
-                        let resp = Response {
-                            code: CODE_TOKEN_EXPIRED.to_string(),
-                            msg: "访问令牌已过期".to_string(),
-                            data: None,
-                        };
-                        Err(actix_web::error::ErrorUnauthorized(
-                            serde_json::to_string(&resp).unwrap(),
-                        ))
-                    } else {
-                        let resp = Response {
-                            code: CODE_TOKEN_INVALID.to_string(),
-                            msg: "访问令牌无效".to_string(),
-                            data: None,
-                        };
-                        Err(actix_web::error::ErrorUnauthorized(
-                            serde_json::to_string(&resp).unwrap(),
-                        ))
+            };
+
+            let cached = req.extensions().get::<Permissions>().cloned();
+            let permissions = match cached {
+                Some(permissions) => permissions,
+                None => {
+                    let Some(db) = req.app_data::<web::Data<Arc<Database>>>() else {
+                        log::error!("RequirePermission: Database is not registered as app data");
+                        return Err(ApiError::Internal("权限数据不可用".to_string()).into());
+                    };
+                    // 停用的账号、必须先修改密码的账号不能访问受保护的功能
+                    match db.users().account_state(&username).await {
+                        Ok(Some(state)) if state.disabled => {
+                            return Err(ApiError::AccountDisabled.into());
+                        }
+                        Ok(Some(state)) if state.must_change_password => {
+                            return Err(ApiError::PasswordChangeRequired("请先修改密码".to_string()).into());
+                        }
+                        Ok(Some(_)) => {}
+                        Ok(None) => {
+                            return Err(ApiError::TokenInvalid("用户不存在".to_string()).into());
+                        }
+                        Err(err) => {
+                            log::error!("load account state for {} failed: {}", username, err);
+                            return Err(ApiError::Store(err).into());
+                        }
+                    }
+                    match db.rbac().user_permissions(&username).await {
+                        Ok(list) => {
+                            let mut permissions = Permissions::new(list);
+                            let scopes = req.extensions().get::<TokenScopes>().cloned();
+                            if let Some(TokenScopes(scopes)) = scopes {
+                                permissions = permissions.restrict(&scopes);
+                            }
+                            req.extensions_mut().insert(permissions.clone());
+                            permissions
+                        }
+                        Err(err) => {
+                            log::error!("load permissions for {} failed: {}", username, err);
+                            return Err(ApiError::Store(err).into());
+                        }
                     }
                 }
+            };
+
+            if !permissions.allows(permission) {
+                log::warn!(
+                    "Access denied: user {} lacks permission {} for {}",
+                    username,
+                    permission,
+                    req.path()
+                );
+                return Err(ApiError::Forbidden(format!("没有权限: {}", permission)).into());
             }
+
+            svc.call(req).await
         })
     }
 }
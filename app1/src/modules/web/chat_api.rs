@@ -1,8 +1,9 @@
-use actix_web::{HttpResponse, Responder, web};
+use actix_web::web;
 use std::fs;
 use std::path::PathBuf;
 
-use super::models::UploadChatMediaPayload;
+use super::models::{Response, UploadChatMediaPayload};
+use super::response::{ApiError, ApiResult};
 // 数据模型已迁移至 models.rs
 
 fn ensure_upload_dir() -> std::io::Result<PathBuf> {
@@ -13,19 +14,22 @@ fn ensure_upload_dir() -> std::io::Result<PathBuf> {
     Ok(base)
 }
 
-pub async fn upload_media(payload: web::Json<UploadChatMediaPayload>) -> impl Responder {
+#[utoipa::path(
+    post,
+    path = "/api/chat/upload",
+    tag = "chat",
+    summary = "上传聊天媒体",
+    responses((status = 200, body = Response))
+)]
+pub async fn upload_media(payload: web::Json<UploadChatMediaPayload>) -> ApiResult {
     // 基本校验
     let media_type = payload.media_type.as_str();
     if media_type != "image" && media_type != "video" && media_type != "audio" {
-        return HttpResponse::BadRequest().body("invalid media_type");
+        return Err(ApiError::InvalidParams("invalid media_type".to_string()));
     }
 
-    let upload_dir = match ensure_upload_dir() {
-        Ok(p) => p,
-        Err(e) => {
-            return HttpResponse::InternalServerError().body(format!("create dir error: {}", e));
-        }
-    };
+    let upload_dir = ensure_upload_dir()
+        .map_err(|e| ApiError::Internal(format!("create dir error: {}", e)))?;
 
     // 解析base64（允许带data URL前缀）
     let b64 = if let Some(idx) = payload.content_base64.find(",") {
@@ -36,7 +40,12 @@ pub async fn upload_media(payload: web::Json<UploadChatMediaPayload>) -> impl Re
 
     let bytes = match base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &b64) {
         Ok(b) => b,
-        Err(e) => return HttpResponse::BadRequest().body(format!("base64 decode error: {}", e)),
+        Err(e) => {
+            return Err(ApiError::InvalidParams(format!(
+                "base64 decode error: {}",
+                e
+            )));
+        }
     };
 
     // 生成安全文件名
@@ -58,13 +67,13 @@ pub async fn upload_media(payload: web::Json<UploadChatMediaPayload>) -> impl Re
     path.push(&fname);
 
     if let Err(e) = fs::write(&path, &bytes) {
-        return HttpResponse::InternalServerError().body(format!("write file error: {}", e));
+        return Err(ApiError::Internal(format!("write file error: {}", e)));
     }
 
     // 构造可访问的URL（静态文件根为/static 映射到 /）
     let url = format!("/uploads/chat/{}", fname);
-    HttpResponse::Ok().json(serde_json::json!({
+    Ok(Response::ok(serde_json::json!({
         "url": url,
         "media_type": media_type,
-    }))
+    })))
 }
@@ -1,42 +1,34 @@
-use bcrypt;
-use chrono::Utc;
-use rusqlite::{Connection, Error, Result, params};
-use std::path::Path; 
+use rusqlite::Error;
+use std::path::Path;
 use std::sync::Arc;
-use std::sync::Mutex;
 
-// 数据库连接管理结构体
+use super::migrations;
+use super::models::MigrationStatus;
+use super::repository::{
+    AccessTokenRepository, ApiEndpointRepository, AuditRepository, ChatRepository,
+    LoginAttemptRepository, OidcIdentityRepository, RbacRepository, RefreshTokenRepository,
+    SqlConnectionRepository, SqlitePool, SshServerRepository, Store, StoreError, TotpRepository,
+    UserRepository, chat, users,
+};
+use crate::modules::config::config::{DatabaseBackend, ServerConfig};
+
+/// 默认连接池大小
+pub const DEFAULT_POOL_SIZE: usize = 8;
+
+// 数据库连接管理结构体（rsts.db 元数据库，各领域仓储共用同一个连接池）
+// 后端由 server.database_backend 选择：SQLite 文件或 PostgreSQL
 #[derive(Debug)]
 pub struct Database {
-    conn: Arc<Mutex<Connection>>,
+    store: Store,
 }
 
 impl Database {
-    // 获取用户终端配置
-    pub fn get_user_terminal_config(&self, username: &str) -> Result<Option<String>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT terminal_setting_config FROM users WHERE username = ?1")?;
-        let mut rows = stmt.query(params![username])?;
-
-        if let Some(row) = rows.next()? {
-            let config: Option<String> = row.get(0)?;
-            Ok(config)
-        } else {
-            Ok(None)
-        }
-    }
-
-    // 更新用户终端配置
-    pub fn update_user_terminal_config(&self, username: &str, config: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "UPDATE users SET terminal_setting_config = ?1 WHERE username = ?2",
-            params![config, username],
-        )?;
-        Ok(())
+    pub fn new(db_path: &str) -> rusqlite::Result<Self> {
+        Self::with_pool_size(db_path, DEFAULT_POOL_SIZE)
     }
 
-    pub fn new(db_path: &str) -> Result<Self> {
+    // 打开 SQLite 元数据库
+    pub fn with_pool_size(db_path: &str, pool_size: usize) -> rusqlite::Result<Self> {
         // 确保数据库文件存在的目录
         if let Some(parent) = Path::new(db_path).parent() {
             if !parent.exists() {
@@ -47,331 +39,248 @@ impl Database {
             }
         }
 
-        let conn = Connection::open(db_path)?;
-
-        // 初始化数据库表（如果不存在）
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS users (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                username TEXT NOT NULL UNIQUE,
-                password TEXT NOT NULL,
-                email TEXT,
-                theme_config TEXT,
-                terminal_setting_config TEXT
-            )",
-            [],
-        )?;
-        
-        // 确保 terminal_setting_config 列存在 (针对已有数据库迁移)
-        let _ = conn.execute("ALTER TABLE users ADD COLUMN terminal_setting_config TEXT", []);
-
-        // 创建websocket会话表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS ws_session (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                session_id TEXT NOT NULL UNIQUE,
-                username TEXT,
-                room TEXT,
-                connected_at TEXT NOT NULL,
-                last_active TEXT NOT NULL
-            )",
-            [],
-        )?;
-
-        // 创建websocket消息表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS ws_message (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                session_id TEXT NOT NULL,
-                username TEXT NOT NULL,
-                room TEXT,
-                content TEXT NOT NULL,
-                timestamp TEXT NOT NULL
-            )",
-            [],
-        )?;
-
-        // 创建SQLStudio连接配置表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS sql_connections (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL,
-                db_type TEXT NOT NULL,
-                host TEXT NOT NULL,
-                port INTEGER NOT NULL,
-                username TEXT NOT NULL,
-                password TEXT,
-                database TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            )",
-            [],
-        )?;
+        let pool = Arc::new(SqlitePool::new(db_path, pool_size));
+        let mut conn = pool.get()?;
+
+        // 按版本执行表结构迁移（见 migrations.rs）
+        migrations::run_migrations(&mut conn)?;
 
         // 检查是否有用户，如果没有则创建一个默认用户用于测试
-        let count: i64 = conn.query_row("SELECT COUNT(*) FROM users", [], |row| row.get(0))?;
-        if count == 0 {
-            // 使用bcrypt算法加密存储默认用户密码
-            let admin_pwd = "password123";
-            let user_pwd = "user123";
-            let admin_hash = bcrypt::hash(admin_pwd, bcrypt::DEFAULT_COST)
-                .unwrap_or_else(|_| admin_pwd.to_string());
-            let user_hash = bcrypt::hash(user_pwd, bcrypt::DEFAULT_COST)
-                .unwrap_or_else(|_| user_pwd.to_string());
-            conn.execute(
-                "INSERT INTO users (username, password, email) VALUES (?1, ?2, ?3)",
-                params!["admin", admin_hash, "admin@example.com"],
-            )?;
-            conn.execute(
-                "INSERT INTO users (username, password, email) VALUES (?1, ?2, ?3)",
-                params!["user1", user_hash, "user1@example.com"],
-            )?;
-        }
+        users::seed_default_users(&conn)?;
 
+        drop(conn);
         Ok(Self {
-            conn: Arc::new(Mutex::new(conn)),
+            store: Store::Sqlite(pool),
         })
     }
 
-    pub fn get_conn(&self) -> Arc<Mutex<Connection>> {
-        self.conn.clone()
+    // 打开 PostgreSQL 元数据库
+    pub async fn with_postgres(url: &str, pool_size: usize) -> Result<Self, StoreError> {
+        let pg = Store::connect_postgres(url, pool_size).await?;
+        migrations::run_pg_migrations(&pg).await?;
+        users::pg_seed_default_users(&pg).await?;
+        Ok(Self {
+            store: Store::Postgres(pg),
+        })
     }
 
-    // 验证用户凭据（支持明文和bcrypt哈希）
-    pub fn validate_user(&self, username: &str, password: &str) -> Result<bool> {
-        let conn = self.conn.lock().unwrap();
+    // 按服务配置选择后端
+    pub async fn connect(config: &ServerConfig) -> Result<Self, StoreError> {
+        match config.database_backend {
+            DatabaseBackend::Sqlite => {
+                let path = config.database_path.clone();
+                let size = config.db_pool_size;
+                Ok(
+                    tokio::task::spawn_blocking(move || Self::with_pool_size(&path, size))
+                        .await??,
+                )
+            }
+            DatabaseBackend::Postgres => {
+                let url = config.database_url.as_deref().ok_or_else(|| {
+                    StoreError::Precondition("server.database_url 未配置".to_string())
+                })?;
+                Self::with_postgres(url, config.db_pool_size).await
+            }
+        }
+    }
 
-        let mut stmt = conn.prepare("SELECT password FROM users WHERE username = ?1")?;
-        let mut rows = stmt.query(params![username])?;
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
 
-        if let Some(row) = rows.next()? {
-            let stored: String = row.get(0)?;
+    pub fn backend(&self) -> &'static str {
+        self.store.backend()
+    }
 
-            // 如果是bcrypt哈希（以$2开头），使用bcrypt验证；否则回退为明文比较
-            if stored.starts_with("$2") {
-                let valid = bcrypt::verify(password, &stored).unwrap_or(false);
-                Ok(valid)
-            } else {
-                Ok(stored == password)
+    // 当前 schema 版本与各迁移的应用状态
+    pub async fn migration_status(&self) -> Result<(i64, Vec<MigrationStatus>), StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(|conn| {
+                    Ok((
+                        migrations::current_version(conn)?,
+                        migrations::migration_status(conn)?,
+                    ))
+                })
+                .await
             }
-        } else {
-            Ok(false)
+            Store::Postgres(pg) => Ok(migrations::pg_migration_status(pg).await?),
         }
     }
 
-    // 获取用户信息（成功登录后）
-    pub fn get_user_info(&self, username: &str) -> Result<Option<(i32, String, String)>> {
-        let conn = self.conn.lock().unwrap();
-
-        let mut stmt = conn.prepare("SELECT id, username, email FROM users WHERE username = ?")?;
-        let mut rows = stmt.query([username])?;
-
-        if let Some(row) = rows.next()? {
-            let id: i32 = row.get(0)?;
-            let username: String = row.get(1)?;
-            let email: String = row.get(2)?;
-            Ok(Some((id, username, email)))
-        } else {
-            Ok(None)
-        }
+    pub fn users(&self) -> UserRepository {
+        UserRepository::new(self.store.clone())
     }
 
-    // 保存会话信息
-    pub fn save_session(
-        &self,
-        session_id: &str,
-        username: Option<&str>,
-        room: Option<&str>,
-    ) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        let now = Utc::now().to_rfc3339();
-
-        conn.execute(
-            "INSERT OR REPLACE INTO ws_session (session_id, username, room, connected_at, last_active) \
-             VALUES (?1, ?2, ?3, ?4, ?4)",
-            params![session_id, username, room, now],
-        )?;
+    pub fn rbac(&self) -> RbacRepository {
+        RbacRepository::new(self.store.clone())
+    }
 
-        Ok(())
+    pub fn refresh_tokens(&self) -> RefreshTokenRepository {
+        RefreshTokenRepository::new(self.store.clone())
     }
 
-    // 加载所有会话信息
-    pub fn load_sessions(&self) -> Result<Vec<(String, Option<String>, Option<String>)>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT session_id, username, room FROM ws_session")?;
+    pub fn login_attempts(&self) -> LoginAttemptRepository {
+        LoginAttemptRepository::new(self.store.clone())
+    }
 
-        let session_iter = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    pub fn totp(&self) -> TotpRepository {
+        TotpRepository::new(self.store.clone())
+    }
 
-        let mut sessions = Vec::new();
-        for session in session_iter {
-            sessions.push(session?);
-        }
+    pub fn oidc_identities(&self) -> OidcIdentityRepository {
+        OidcIdentityRepository::new(self.store.clone())
+    }
 
-        Ok(sessions)
+    pub fn access_tokens(&self) -> AccessTokenRepository {
+        AccessTokenRepository::new(self.store.clone())
     }
 
-    // 移除会话
-    pub fn remove_session(&self, session_id: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+    pub fn api_endpoints(&self) -> ApiEndpointRepository {
+        ApiEndpointRepository::new(self.store.clone())
+    }
 
-        conn.execute(
-            "DELETE FROM ws_session WHERE session_id = ?1",
-            params![session_id],
-        )?;
+    pub fn audit(&self) -> AuditRepository {
+        AuditRepository::new(self.store.clone())
+    }
 
-        Ok(())
+    pub fn ssh_servers(&self) -> SshServerRepository {
+        SshServerRepository::new(self.store.clone())
     }
 
-    // 保存消息
-    pub fn save_message(
-        &self,
-        session_id: &str,
-        username: &str,
-        room: Option<&str>,
-        content: &str,
-    ) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        let now = Utc::now().to_rfc3339();
+    pub fn sql_connections(&self) -> SqlConnectionRepository {
+        SqlConnectionRepository::new(self.store.clone())
+    }
 
-        conn.execute(
-            "INSERT INTO ws_message (session_id, username, room, content, timestamp) \
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![session_id, username, room, content, now],
-        )?;
+    pub fn chat(&self) -> ChatRepository {
+        ChatRepository::new(self.store.clone())
+    }
 
-        Ok(())
+    // 验证用户凭据（支持明文和bcrypt哈希）
+    pub async fn validate_user(&self, username: &str, password: &str) -> Result<bool, StoreError> {
+        self.users().validate_user(username, password).await
     }
 
-    // 加载消息列表
-    pub fn load_messages(
+    // 获取用户信息（成功登录后）
+    pub async fn get_user_info(
         &self,
-        room: Option<&str>,
-        limit: i32,
-    ) -> Result<Vec<(String, String, Option<String>, String, String)>> {
-        let conn = self.conn.lock().unwrap();
-
-        let query = match room {
-            Some(_r) => {
-                "SELECT session_id, username, room, content, timestamp FROM ws_message \
-                         WHERE room = ?1 ORDER BY timestamp DESC LIMIT ?2"
-            }
-            None => {
-                "SELECT session_id, username, room, content, timestamp FROM ws_message \
-                     ORDER BY timestamp DESC LIMIT ?1"
-            }
-        };
-
-        let mut stmt = conn.prepare(query)?;
-
-        let message_iter: Box<
-            dyn Iterator<
-                Item = Result<(String, String, Option<String>, String, String), rusqlite::Error>,
-            >,
-        > = match room {
-            Some(r) => Box::new(stmt.query_map(params![r, limit], |row| {
-                Ok((
-                    row.get(0)?,
-                    row.get(1)?,
-                    row.get(2)?,
-                    row.get(3)?,
-                    row.get(4)?,
-                ))
-            })?),
-            None => Box::new(stmt.query_map(params![limit], |row| {
-                Ok((
-                    row.get(0)?,
-                    row.get(1)?,
-                    row.get(2)?,
-                    row.get(3)?,
-                    row.get(4)?,
-                ))
-            })?),
-        };
-
-        let mut messages = Vec::new();
-        for message in message_iter {
-            messages.push(message?);
-        }
-
-        // 反转消息顺序，让最早的消息在前面
-        messages.reverse();
-
-        Ok(messages)
+        username: &str,
+    ) -> Result<Option<(i32, String, String)>, StoreError> {
+        self.users().get_user_info(username).await
     }
 
     // 获取用户主题配置
-    pub fn get_user_theme_config(&self, username: &str) -> Result<Option<String>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT theme_config FROM users WHERE username = ?1")?;
-        let mut rows = stmt.query(params![username])?;
-
-        if let Some(row) = rows.next()? {
-            let theme_config: Option<String> = row.get(0)?;
-            Ok(theme_config)
-        } else {
-            Ok(None)
-        }
+    pub async fn get_user_theme_config(
+        &self,
+        username: &str,
+    ) -> Result<Option<String>, StoreError> {
+        self.users().get_theme_config(username).await
     }
 
     // 更新用户主题配置
-    pub fn update_user_theme_config(&self, username: &str, theme_config: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "UPDATE users SET theme_config = ?1 WHERE username = ?2",
-            params![theme_config, username],
-        )?;
-        Ok(())
+    pub async fn update_user_theme_config(
+        &self,
+        username: &str,
+        theme_config: &str,
+    ) -> Result<(), StoreError> {
+        self.users()
+            .update_theme_config(username, theme_config)
+            .await
+    }
+
+    // 获取用户终端配置
+    pub async fn get_user_terminal_config(
+        &self,
+        username: &str,
+    ) -> Result<Option<String>, StoreError> {
+        self.users().get_terminal_config(username).await
+    }
+
+    // 更新用户终端配置
+    pub async fn update_user_terminal_config(
+        &self,
+        username: &str,
+        config: &str,
+    ) -> Result<(), StoreError> {
+        self.users().update_terminal_config(username, config).await
     }
 }
 
 // 实现接口抽象
+#[async_trait::async_trait]
 impl super::database_models::DatabaseService for Database {
-    fn validate_user(&self, username: &str, password: &str) -> Result<bool> {
-        self.validate_user(username, password)
+    async fn validate_user(&self, username: &str, password: &str) -> Result<bool, StoreError> {
+        self.validate_user(username, password).await
     }
-    fn get_user_info(&self, username: &str) -> Result<Option<(i32, String, String)>> {
-        self.get_user_info(username)
+    async fn get_user_info(
+        &self,
+        username: &str,
+    ) -> Result<Option<(i32, String, String)>, StoreError> {
+        self.get_user_info(username).await
     }
-    fn save_session(
+    async fn save_session(
         &self,
         session_id: &str,
         username: Option<&str>,
         room: Option<&str>,
-    ) -> Result<()> {
-        self.save_session(session_id, username, room)
+    ) -> Result<(), StoreError> {
+        self.chat()
+            .save_session(
+                session_id.to_string(),
+                username.map(str::to_string),
+                room.map(str::to_string),
+            )
+            .await
     }
-    fn load_sessions(&self) -> Result<Vec<(String, Option<String>, Option<String>)>> {
-        self.load_sessions()
+    async fn load_sessions(&self) -> Result<Vec<chat::SessionRow>, StoreError> {
+        self.chat().load_sessions().await
     }
-    fn remove_session(&self, session_id: &str) -> Result<()> {
-        self.remove_session(session_id)
+    async fn remove_session(&self, session_id: &str) -> Result<(), StoreError> {
+        self.chat().remove_session(session_id.to_string()).await
     }
-    fn save_message(
+    async fn save_message(
         &self,
         session_id: &str,
         username: &str,
         room: Option<&str>,
         content: &str,
-    ) -> Result<()> {
-        self.save_message(session_id, username, room, content)
+    ) -> Result<(), StoreError> {
+        self.chat()
+            .save_message(
+                session_id.to_string(),
+                username.to_string(),
+                room.map(str::to_string),
+                content.to_string(),
+            )
+            .await
     }
-    fn load_messages(
+    async fn load_messages(
         &self,
         room: Option<&str>,
         limit: i32,
-    ) -> Result<Vec<(String, String, Option<String>, String, String)>> {
-        self.load_messages(room, limit)
+    ) -> Result<Vec<chat::MessageRow>, StoreError> {
+        self.chat()
+            .load_messages(room.map(str::to_string), limit)
+            .await
     }
-    fn get_user_theme_config(&self, username: &str) -> Result<Option<String>> {
-        self.get_user_theme_config(username)
+    async fn get_user_theme_config(&self, username: &str) -> Result<Option<String>, StoreError> {
+        self.get_user_theme_config(username).await
     }
-    fn update_user_theme_config(&self, username: &str, theme_config: &str) -> Result<()> {
-        self.update_user_theme_config(username, theme_config)
+    async fn update_user_theme_config(
+        &self,
+        username: &str,
+        theme_config: &str,
+    ) -> Result<(), StoreError> {
+        self.update_user_theme_config(username, theme_config).await
     }
-    fn get_user_terminal_config(&self, username: &str) -> Result<Option<String>> {
-        self.get_user_terminal_config(username)
+    async fn get_user_terminal_config(&self, username: &str) -> Result<Option<String>, StoreError> {
+        self.get_user_terminal_config(username).await
     }
-    fn update_user_terminal_config(&self, username: &str, config: &str) -> Result<()> {
-        self.update_user_terminal_config(username, config)
+    async fn update_user_terminal_config(
+        &self,
+        username: &str,
+        config: &str,
+    ) -> Result<(), StoreError> {
+        self.update_user_terminal_config(username, config).await
     }
 }
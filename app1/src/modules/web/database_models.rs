@@ -1,32 +1,45 @@
-use rusqlite::Result;
+use super::repository::StoreError;
+use super::repository::chat::{MessageRow, SessionRow};
 
-// Database 接口抽象：提供统一的数据库操作能力
+// Database 接口抽象：提供统一的数据库操作能力（SQLite / PostgreSQL 后端共用）
 #[allow(dead_code)]
+#[async_trait::async_trait]
 pub trait DatabaseService: Send + Sync {
-    fn validate_user(&self, username: &str, password: &str) -> Result<bool>;
-    fn get_user_info(&self, username: &str) -> Result<Option<(i32, String, String)>>;
-    fn save_session(
+    async fn validate_user(&self, username: &str, password: &str) -> Result<bool, StoreError>;
+    async fn get_user_info(
+        &self,
+        username: &str,
+    ) -> Result<Option<(i32, String, String)>, StoreError>;
+    async fn save_session(
         &self,
         session_id: &str,
         username: Option<&str>,
         room: Option<&str>,
-    ) -> Result<()>;
-    fn load_sessions(&self) -> Result<Vec<(String, Option<String>, Option<String>)>>;
-    fn remove_session(&self, session_id: &str) -> Result<()>;
-    fn save_message(
+    ) -> Result<(), StoreError>;
+    async fn load_sessions(&self) -> Result<Vec<SessionRow>, StoreError>;
+    async fn remove_session(&self, session_id: &str) -> Result<(), StoreError>;
+    async fn save_message(
         &self,
         session_id: &str,
         username: &str,
         room: Option<&str>,
         content: &str,
-    ) -> Result<()>;
-    fn load_messages(
+    ) -> Result<(), StoreError>;
+    async fn load_messages(
         &self,
         room: Option<&str>,
         limit: i32,
-    ) -> Result<Vec<(String, String, Option<String>, String, String)>>;
-    fn get_user_theme_config(&self, username: &str) -> Result<Option<String>>;
-    fn update_user_theme_config(&self, username: &str, theme_config: &str) -> Result<()>;
-    fn get_user_terminal_config(&self, username: &str) -> Result<Option<String>>;
-    fn update_user_terminal_config(&self, username: &str, config: &str) -> Result<()>;
+    ) -> Result<Vec<MessageRow>, StoreError>;
+    async fn get_user_theme_config(&self, username: &str) -> Result<Option<String>, StoreError>;
+    async fn update_user_theme_config(
+        &self,
+        username: &str,
+        theme_config: &str,
+    ) -> Result<(), StoreError>;
+    async fn get_user_terminal_config(&self, username: &str) -> Result<Option<String>, StoreError>;
+    async fn update_user_terminal_config(
+        &self,
+        username: &str,
+        config: &str,
+    ) -> Result<(), StoreError>;
 }
@@ -0,0 +1,103 @@
+// 登录防暴力破解：按客户端 IP 限制尝试频率（进程内计数），账号锁定见 repository/login_attempts.rs
+use actix_web::HttpRequest;
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::modules::config::config::LoginProtectionConfig;
+
+// 记录的 IP 超过该数量时顺带清理已过期的条目
+const PRUNE_THRESHOLD: usize = 1024;
+
+pub struct LoginGuard {
+    pub policy: LoginProtectionConfig,
+    attempts: Mutex<HashMap<IpAddr, VecDeque<Instant>>>,
+}
+
+impl LoginGuard {
+    pub fn new(policy: LoginProtectionConfig) -> Self {
+        Self {
+            policy,
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // 客户端地址；开启 trust_forwarded_for 时取 X-Forwarded-For 的最后一个地址，
+    // 即紧邻的反向代理记录的对端地址（左侧的条目由客户端自行填写，可以伪造）
+    pub fn client_ip(&self, req: &HttpRequest) -> Option<IpAddr> {
+        if self.policy.trust_forwarded_for
+            && let Some(ip) = req
+                .headers()
+                .get_all("x-forwarded-for")
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(|v| v.split(','))
+                .last()
+                .and_then(|v| v.trim().parse().ok())
+        {
+            return Some(ip);
+        }
+        req.peer_addr().map(|addr| addr.ip())
+    }
+
+    // 记录一次尝试；窗口内超过上限时返回需要等待的时间
+    pub fn check_ip(&self, ip: IpAddr) -> Result<(), Duration> {
+        let max = self.policy.max_attempts_per_ip;
+        if max == 0 {
+            return Ok(());
+        }
+        let window = Duration::from_secs(self.policy.ip_window_secs);
+        let now = Instant::now();
+        let mut attempts = self.attempts.lock().unwrap();
+        if attempts.len() > PRUNE_THRESHOLD {
+            attempts.retain(|_, hits| hits.back().is_some_and(|t| now - *t < window));
+        }
+        let hits = attempts.entry(ip).or_default();
+        while hits.front().is_some_and(|t| now - *t >= window) {
+            hits.pop_front();
+        }
+        if hits.len() >= max {
+            let oldest = hits.front().copied().unwrap_or(now);
+            return Err(window.saturating_sub(now - oldest));
+        }
+        hits.push_back(now);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ip_throttle() {
+        let guard = LoginGuard::new(LoginProtectionConfig {
+            max_attempts_per_ip: 3,
+            ..LoginProtectionConfig::default()
+        });
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        for _ in 0..3 {
+            assert!(guard.check_ip(ip).is_ok());
+        }
+        let wait = guard.check_ip(ip).unwrap_err();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_secs(300));
+        // 其他 IP 不受影响
+        assert!(guard.check_ip("10.0.0.2".parse().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn forwarded_for_uses_nearest_proxy_entry() {
+        let req = actix_web::test::TestRequest::default()
+            .peer_addr("192.168.1.1:4000".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "1.2.3.4, 203.0.113.7"))
+            .to_http_request();
+        let direct = LoginGuard::new(LoginProtectionConfig::default());
+        assert_eq!(direct.client_ip(&req), "192.168.1.1".parse().ok());
+        let proxied = LoginGuard::new(LoginProtectionConfig {
+            trust_forwarded_for: true,
+            ..LoginProtectionConfig::default()
+        });
+        // 客户端伪造的 1.2.3.4 被忽略
+        assert_eq!(proxied.client_ip(&req), "203.0.113.7".parse().ok());
+    }
+}
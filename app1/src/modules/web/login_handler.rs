@@ -1,425 +1,613 @@
-use actix_web::{HttpMessage, HttpRequest, HttpResponse, Responder, web};
+use actix_web::{HttpMessage, HttpRequest, web};
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use chrono::{Duration, Utc};
-use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
 
-use super::models::{LoginRequest, Response};
+use super::audit::{self, AuditActor, AuditEvent};
+use super::login_guard::LoginGuard;
+use super::models::{LoginRequest, LoginTotpRequest, Response};
+use super::repository::RotateOutcome;
+use super::response::{ApiError, ApiResult};
+use super::totp_api::verify_second_factor;
+use crate::modules::config::config::Config;
 use crate::modules::web::auth_utils::{
-    ACCESS_TOKEN_EXPIRE_MINUTES, CODE_INVALID_CREDENTIALS, CODE_REFRESH_TOKEN_INVALID,
-    CODE_SUCCESS, CODE_TOKEN_INVALID, CODE_USER_NOT_FOUND, Claims, JWT_SECRET,
-    REFRESH_TOKEN_EXPIRE_DAYS,
+    ACCESS_TOKEN_EXPIRE_MINUTES, CODE_TOTP_REQUIRED, Claims, MFA_TOKEN_EXPIRE_MINUTES,
+    REFRESH_TOKEN_EXPIRE_DAYS, create_token, sign_claims, verify_token,
 };
 use crate::modules::web::database::Database;
 
-#[derive(Debug, Serialize)]
-struct LoginToken {
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LoginToken {
     token: String,
     #[serde(rename = "refreshToken")]
     refresh_token: String,
+    /// 必须先修改密码（此时只能访问修改密码等个人接口）
+    #[serde(rename = "mustChangePassword")]
+    must_change_password: bool,
 }
 
-#[derive(Debug, Serialize)]
-struct FrontendUserInfo {
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FrontendUserInfo {
     #[serde(rename = "userId")]
     user_id: String,
     #[serde(rename = "userName")]
     user_name: String,
     roles: Vec<String>,
     buttons: Vec<String>,
+    #[serde(rename = "mustChangePassword")]
+    must_change_password: bool,
 }
 
-fn build_user_info(id: i32, username: &str) -> FrontendUserInfo {
-    let mut roles = vec!["R_USER".to_string()];
-    if username.eq_ignore_ascii_case("admin") {
-        roles.push("R_SUPER".to_string());
-        roles.push("R_ADMIN".to_string());
-    } else if username.eq_ignore_ascii_case("super") {
-        roles.push("R_SUPER".to_string());
-    }
-
+// roles 为用户的角色名（前端按角色控制路由），buttons 为权限码（前端按钮级权限）
+fn build_user_info(
+    id: i32,
+    username: &str,
+    roles: Vec<String>,
+    permissions: Vec<String>,
+    must_change_password: bool,
+) -> FrontendUserInfo {
     FrontendUserInfo {
         user_id: id.to_string(),
         user_name: username.to_string(),
         roles,
-        buttons: vec![],
+        buttons: permissions,
+        must_change_password,
     }
 }
 
-fn generate_tokens(user_id: i32, username: &str) -> Result<LoginToken, HttpResponse> {
+// 签发访问令牌与刷新令牌；刷新令牌需以 refresh_jti 登记到 refresh_tokens 表后才能使用
+fn generate_tokens(
+    user_id: i32,
+    username: &str,
+    must_change_password: bool,
+    refresh_jti: &str,
+    refresh_exp: i64,
+) -> Result<LoginToken, ApiError> {
     let now = Utc::now();
     let access_exp = (now + Duration::minutes(ACCESS_TOKEN_EXPIRE_MINUTES)).timestamp() as usize;
-    let refresh_exp = (now + Duration::days(REFRESH_TOKEN_EXPIRE_DAYS)).timestamp() as usize;
 
     let access_claims = Claims {
         sub: user_id.to_string(),
         username: username.to_string(),
         token_type: "access".to_string(),
         exp: access_exp,
+        jti: None,
     };
 
     let refresh_claims = Claims {
         sub: user_id.to_string(),
         username: username.to_string(),
         token_type: "refresh".to_string(),
-        exp: refresh_exp,
+        exp: refresh_exp as usize,
+        jti: Some(refresh_jti.to_string()),
     };
 
-    let header = Header::new(Algorithm::HS256);
-
-    let access_token = encode(
-        &header,
-        &access_claims,
-        &EncodingKey::from_secret(JWT_SECRET),
-    )
-    .map_err(|_e| HttpResponse::InternalServerError().finish())?;
-
-    let refresh_token = encode(
-        &header,
-        &refresh_claims,
-        &EncodingKey::from_secret(JWT_SECRET),
-    )
-    .map_err(|_e| HttpResponse::InternalServerError().finish())?;
+    let access_token = sign_claims(&access_claims).map_err(sign_error)?;
+    let refresh_token = sign_claims(&refresh_claims).map_err(sign_error)?;
 
     Ok(LoginToken {
         token: access_token,
         refresh_token,
+        must_change_password,
     })
 }
 
-pub async fn get_user_info(req: HttpRequest, db: web::Data<Arc<Database>>) -> impl Responder {
-    // 这里的Claims是由AuthMiddleware注入的
-    if let Some(claims) = req.extensions().get::<Claims>() {
-        match db.get_user_info(&claims.username) {
-            Ok(Some((id, username_in_db, _email))) => {
-                let user_info = build_user_info(id, &username_in_db);
-                let response = Response {
-                    code: CODE_SUCCESS.to_string(),
-                    msg: "获取用户信息成功".to_string(),
-                    data: Some(serde_json::json!(user_info)),
-                };
-                HttpResponse::Ok().json(response)
-            }
-            Ok(None) => {
-                let response = Response {
-                    code: CODE_USER_NOT_FOUND.to_string(),
-                    msg: "用户不存在".to_string(),
-                    data: None,
-                };
-                HttpResponse::Ok().json(response)
-            }
-            Err(_e) => {
-                let response = Response {
-                    code: "500".to_string(),
-                    msg: "数据库错误".to_string(),
-                    data: None,
-                };
-                HttpResponse::Ok().json(response)
-            }
+fn sign_error(e: jsonwebtoken::errors::Error) -> ApiError {
+    ApiError::Internal(format!("签发令牌失败: {}", e))
+}
+
+fn refresh_expires_at() -> i64 {
+    (Utc::now() + Duration::days(REFRESH_TOKEN_EXPIRE_DAYS)).timestamp()
+}
+
+// 登录成功：开启新的刷新令牌族并签发令牌
+async fn issue_session(
+    db: &Database,
+    user_id: i32,
+    username: &str,
+    must_change_password: bool,
+) -> Result<LoginToken, ApiError> {
+    let jti = uuid::Uuid::new_v4().to_string();
+    let expires_at = refresh_expires_at();
+    let tokens = generate_tokens(user_id, username, must_change_password, &jti, expires_at)?;
+    db.refresh_tokens()
+        .issue(&jti, user_id as i64, expires_at)
+        .await?;
+    Ok(tokens)
+}
+
+// 认证中间件注入的当前用户
+fn current_username(req: &HttpRequest) -> Result<String, ApiError> {
+    req.extensions()
+        .get::<Claims>()
+        .map(|claims| claims.username.clone())
+        .ok_or_else(|| ApiError::TokenInvalid("未授权访问".to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/getUserInfo",
+    tag = "auth",
+    summary = "当前用户信息",
+    responses((status = 200, body = Response<FrontendUserInfo>))
+)]
+pub async fn get_user_info(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+) -> ApiResult<FrontendUserInfo> {
+    // Claims 由 AuthMiddleware 注入；先取出用户名，避免在 await 期间持有 extensions 的借用
+    let username = current_username(&req)?;
+    let Some((id, username_in_db, _email)) = db.get_user_info(&username).await? else {
+        return Err(ApiError::UserNotFound);
+    };
+    let rbac = db.rbac();
+    let roles = rbac.user_roles(&username_in_db).await.unwrap_or_default();
+    let permissions = rbac
+        .user_permissions(&username_in_db)
+        .await
+        .unwrap_or_default();
+    let must_change = db
+        .users()
+        .account_state(&username_in_db)
+        .await
+        .ok()
+        .flatten()
+        .is_some_and(|state| state.must_change_password);
+    let user_info = build_user_info(id, &username_in_db, roles, permissions, must_change);
+    Ok(Response::with_msg("获取用户信息成功", user_info))
+}
+
+// 登录失败统一返回的错误，不区分用户不存在与密码错误
+fn invalid_credentials() -> ApiError {
+    ApiError::InvalidCredentials("用户名或密码错误".to_string())
+}
+
+// 登录事件的审计信息：target 为登录方式（password、totp、oidc），操作人为登录时输入的用户名
+pub(super) struct LoginAudit {
+    event: AuditEvent,
+    actor: AuditActor,
+}
+
+impl LoginAudit {
+    pub(super) fn new(req: &HttpRequest, method: &str, username: &str) -> Self {
+        Self {
+            event: AuditEvent::start(audit::LOGIN, method),
+            actor: AuditActor::new(username, audit::client_ip(req)),
+        }
+    }
+
+    pub(super) async fn record(&self, db: &Database, error: Option<&str>) {
+        self.event
+            .record(db, &self.actor, error.map(str::to_string))
+            .await;
+    }
+}
+
+// 写入失败记录与审计日志；记录失败不影响登录响应
+async fn record_failure(
+    db: &Database,
+    guard: &LoginGuard,
+    audit: &LoginAudit,
+    reason: &'static str,
+) {
+    let retention = guard.policy.failure_retention_days;
+    let (username, ip) = (&audit.actor.username, audit.actor.ip.as_deref());
+    if let Err(e) = db
+        .login_attempts()
+        .log_failure(username, ip, reason, retention)
+        .await
+    {
+        log::error!("failed to record login failure for {}: {}", username, e);
+    }
+    audit.record(db, Some(reason)).await;
+}
+
+// 按客户端 IP 限速，超过上限时拒绝
+fn check_ip(guard: &LoginGuard, ip: Option<IpAddr>) -> Result<(), ApiError> {
+    let Some(ip) = ip else {
+        return Ok(());
+    };
+    let Err(wait) = guard.check_ip(ip) else {
+        return Ok(());
+    };
+    log::warn!("login throttled for ip: {}", ip);
+    let secs = wait.as_secs().max(1);
+    Err(ApiError::LoginThrottled {
+        msg: format!("登录尝试过于频繁，请 {} 秒后再试", secs),
+        retry_after: secs,
+    })
+}
+
+// 锁定期内不再校验密码或验证码
+async fn check_locked(
+    db: &Database,
+    guard: &LoginGuard,
+    audit: &LoginAudit,
+) -> Result<(), ApiError> {
+    let Some(secs) = db.login_attempts().locked_for(&audit.actor.username).await? else {
+        return Ok(());
+    };
+    record_failure(db, guard, audit, "locked").await;
+    let minutes = (secs + 59) / 60;
+    Err(ApiError::LoginThrottled {
+        msg: format!("登录失败次数过多，请 {} 分钟后再试", minutes),
+        retry_after: secs as u64,
+    })
+}
+
+// 累计一次失败，达到上限时锁定账号
+async fn count_failure(db: &Database, guard: &LoginGuard, username: &str) {
+    match db
+        .login_attempts()
+        .count_failure(username, &guard.policy)
+        .await
+    {
+        Ok(Some(secs)) => log::warn!("user {} locked for {}s", username, secs),
+        Ok(None) => {}
+        Err(e) => log::error!("failed to count login failure for {}: {}", username, e),
+    }
+}
+
+// 密码正确但已启用两步验证：签发短期的 mfa 令牌，凭它和验证码完成登录
+// 这是登录流程的一步而不是错误，以 HTTP 200 返回
+fn totp_required(user_id: i32, username: &str) -> ApiResult {
+    let expire = Duration::minutes(MFA_TOKEN_EXPIRE_MINUTES);
+    let mfa_token = create_token(&user_id.to_string(), username, "mfa", expire).map_err(sign_error)?;
+    Ok(Response {
+        code: CODE_TOTP_REQUIRED.to_string(),
+        msg: "请输入两步验证码".to_string(),
+        data: Some(json!({ "mfaToken": mfa_token })),
+    })
+}
+
+// 通过全部校验：清除失败计数并签发令牌
+pub(super) async fn login_success(
+    db: &Database,
+    user_id: i32,
+    username: &str,
+    must_change_password: bool,
+    audit: &LoginAudit,
+) -> ApiResult {
+    if let Err(e) = db.login_attempts().clear(username).await {
+        log::error!("failed to reset login failures for {}: {}", username, e);
+    }
+    match issue_session(db, user_id, username, must_change_password).await {
+        Ok(tokens) => {
+            log::info!("login success for user: {}", username);
+            audit.record(db, None).await;
+            Ok(Response::with_msg("登录成功", json!(tokens)))
+        }
+        Err(e) => {
+            audit.record(db, Some("session_error")).await;
+            Err(e)
         }
-    } else {
-        // 如果没有Claims，说明中间件未生效或未正确配置，或者请求未经过认证
-        // 理论上AuthMiddleware会拦截无效请求，这里只是兜底
-        let response = Response {
-            code: CODE_TOKEN_INVALID.to_string(),
-            msg: "未授权访问".to_string(),
-            data: None,
-        };
-        HttpResponse::Unauthorized().json(response)
     }
 }
 
 // 处理登录请求
+#[utoipa::path(
+    post,
+    path = "/api/login",
+    tag = "auth",
+    summary = "用户名密码登录",
+    responses((status = 200, body = Response)),
+    security(())
+)]
 pub async fn login(
+    req: HttpRequest,
     login_data: web::Json<LoginRequest>,
     db: web::Data<Arc<Database>>,
-) -> impl Responder {
+    guard: web::Data<LoginGuard>,
+) -> ApiResult {
     let username = &login_data.username;
     let password = &login_data.password;
     log::info!("login attempt for user: {}", username);
 
-    match db.get_user_info(username) {
-        Ok(Some((id, username_in_db, _email))) => {
-            match db.validate_user(&username_in_db, password) {
-                Ok(true) => match generate_tokens(id, &username_in_db) {
-                    Ok(tokens) => {
-                        log::info!("login success for user: {}", username_in_db);
-                        let response = Response {
-                            code: CODE_SUCCESS.to_string(),
-                            msg: "登录成功".to_string(),
-                            data: Some(serde_json::json!(tokens)),
-                        };
-                        HttpResponse::Ok().json(response)
-                    }
-                    Err(err_resp) => err_resp,
-                },
-                Ok(false) => {
-                    log::warn!("login failed for user: {} (invalid credentials)", username);
-                    let response = Response {
-                        code: CODE_INVALID_CREDENTIALS.to_string(),
-                        msg: "用户名或密码错误".to_string(),
-                        data: None,
-                    };
-                    HttpResponse::Ok().json(response)
-                }
-                Err(_e) => {
-                    let response = Response {
-                        code: "500".to_string(),
-                        msg: "验证用户失败".to_string(),
-                        data: None,
-                    };
-                    HttpResponse::Ok().json(response)
-                }
-            }
-        }
-        Ok(None) => {
-            log::warn!("login failed, user not found: {}", username);
-            let response = Response {
-                code: CODE_USER_NOT_FOUND.to_string(),
-                msg: "用户不存在".to_string(),
-                data: None,
-            };
-            HttpResponse::Ok().json(response)
-        }
-        Err(_e) => {
-            let response = Response {
-                code: "500".to_string(),
-                msg: "数据库错误".to_string(),
-                data: None,
-            };
-            HttpResponse::Ok().json(response)
-        }
+    check_ip(&guard, guard.client_ip(&req))?;
+    let audit = LoginAudit::new(&req, "password", username);
+    check_locked(&db, &guard, &audit).await?;
+
+    let user = db.get_user_info(username).await?;
+    if !db.validate_user(username, password).await? {
+        log::warn!("login failed for user: {} (invalid credentials)", username);
+        record_failure(&db, &guard, &audit, "bad_credentials").await;
+        count_failure(&db, &guard, username).await;
+        return Err(invalid_credentials());
+    }
+    let Some((id, username_in_db, _email)) = user else {
+        return Err(invalid_credentials());
+    };
+
+    let Some(state) = db.users().account_state(&username_in_db).await? else {
+        return Err(ApiError::UserNotFound);
+    };
+    if state.disabled {
+        log::warn!("login rejected for disabled user: {}", username_in_db);
+        record_failure(&db, &guard, &audit, "disabled").await;
+        return Err(ApiError::AccountDisabled);
+    }
+    // 已启用两步验证时先不清除失败计数，验证码错误同样累计
+    if db.totp().state(id as i64).await?.is_some_and(|totp| totp.enabled) {
+        log::info!("login of user {} requires totp", username_in_db);
+        return totp_required(id, &username_in_db);
+    }
+    login_success(&db, id, &username_in_db, state.must_change_password, &audit).await
+}
+
+// 两步验证登录：提交第一步返回的 mfaToken 与验证码（或恢复码）
+#[utoipa::path(
+    post,
+    path = "/api/login/totp",
+    tag = "auth",
+    summary = "提交两步验证码完成登录",
+    responses((status = 200, body = Response)),
+    security(())
+)]
+pub async fn login_totp(
+    req: HttpRequest,
+    body: web::Json<LoginTotpRequest>,
+    db: web::Data<Arc<Database>>,
+    guard: web::Data<LoginGuard>,
+    config: web::Data<Config>,
+) -> ApiResult {
+    let expired = || ApiError::InvalidCredentials("验证已过期，请重新登录".to_string());
+    check_ip(&guard, guard.client_ip(&req))?;
+    let claims = match verify_token(&body.mfa_token) {
+        Ok(claims) if claims.token_type == "mfa" => claims,
+        _ => return Err(expired()),
+    };
+    let username = claims.username;
+    let Ok(user_id) = claims.sub.parse::<i32>() else {
+        return Err(expired());
+    };
+    let audit = LoginAudit::new(&req, "totp", &username);
+    check_locked(&db, &guard, &audit).await?;
+
+    // 第一步之后两步验证被重置时要求重新登录，不能跳过校验
+    let totp = match db.totp().state(user_id as i64).await? {
+        Some(totp) if totp.enabled => totp,
+        _ => return Err(expired()),
+    };
+    let skew = config.totp.skew_steps;
+    if !verify_second_factor(&db, user_id as i64, &totp, &body.code, skew).await? {
+        log::warn!("login failed for user: {} (invalid totp code)", username);
+        record_failure(&db, &guard, &audit, "bad_totp").await;
+        count_failure(&db, &guard, &username).await;
+        return Err(ApiError::InvalidCredentials("验证码错误".to_string()));
+    }
+
+    // mfa 令牌有效期内账号可能已被停用
+    let Some(state) = db.users().account_state(&username).await? else {
+        return Err(ApiError::UserNotFound);
+    };
+    if state.disabled {
+        record_failure(&db, &guard, &audit, "disabled").await;
+        return Err(ApiError::AccountDisabled);
     }
+    login_success(&db, user_id, &username, state.must_change_password, &audit).await
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct ThemeConfigRequest {
     pub theme_config: String,
 }
 
 // 获取用户主题配置
+#[utoipa::path(
+    get,
+    path = "/api/user/theme-config",
+    tag = "user",
+    summary = "获取主题配置",
+    responses((status = 200, body = Response))
+)]
 pub async fn get_user_theme_config_handler(
     req: HttpRequest,
     db: web::Data<Arc<Database>>,
-) -> impl Responder {
-    if let Some(claims) = req.extensions().get::<Claims>() {
-        match db.get_user_theme_config(&claims.username) {
-            Ok(Some(config)) => {
-                let response = Response {
-                    code: CODE_SUCCESS.to_string(),
-                    msg: "获取主题配置成功".to_string(),
-                    data: Some(serde_json::json!({ "themeConfig": config })),
-                };
-                HttpResponse::Ok().json(response)
-            }
-            Ok(None) => {
-                let response = Response {
-                    code: CODE_SUCCESS.to_string(),
-                    msg: "未找到主题配置".to_string(),
-                    data: None,
-                };
-                HttpResponse::Ok().json(response)
-            }
-            Err(e) => {
-                let response = Response {
-                    code: "500".to_string(),
-                    msg: format!("数据库错误: {}", e),
-                    data: None,
-                };
-                HttpResponse::Ok().json(response)
-            }
-        }
-    } else {
-        HttpResponse::Unauthorized().finish()
-    }
+) -> ApiResult {
+    let username = current_username(&req)?;
+    Ok(match db.get_user_theme_config(&username).await? {
+        Some(config) => Response::with_msg("获取主题配置成功", json!({ "themeConfig": config })),
+        None => Response::message("未找到主题配置"),
+    })
 }
 
 // 更新用户主题配置
+#[utoipa::path(
+    post,
+    path = "/api/user/theme-config",
+    tag = "user",
+    summary = "保存主题配置",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
 pub async fn update_user_theme_config_handler(
     req: HttpRequest,
     body: web::Json<ThemeConfigRequest>,
     db: web::Data<Arc<Database>>,
-) -> impl Responder {
-    if let Some(claims) = req.extensions().get::<Claims>() {
-        match db.update_user_theme_config(&claims.username, &body.theme_config) {
-            Ok(_) => {
-                let response = Response {
-                    code: CODE_SUCCESS.to_string(),
-                    msg: "更新主题配置成功".to_string(),
-                    data: None,
-                };
-                HttpResponse::Ok().json(response)
-            }
-            Err(e) => {
-                let response = Response {
-                    code: "500".to_string(),
-                    msg: format!("数据库错误: {}", e),
-                    data: None,
-                };
-                HttpResponse::Ok().json(response)
-            }
-        }
-    } else {
-        HttpResponse::Unauthorized().finish()
-    }
+) -> ApiResult<()> {
+    let username = current_username(&req)?;
+    db.update_user_theme_config(&username, &body.theme_config)
+        .await?;
+    Ok(Response::message("更新主题配置成功"))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct TerminalConfigRequest {
     pub config: String,
 }
 
 // 获取用户终端配置
+#[utoipa::path(
+    get,
+    path = "/api/user/terminal-config",
+    tag = "user",
+    summary = "获取终端配置",
+    responses((status = 200, body = Response))
+)]
 pub async fn get_user_terminal_config_handler(
     req: HttpRequest,
     db: web::Data<Arc<Database>>,
-) -> impl Responder {
-    if let Some(claims) = req.extensions().get::<Claims>() {
-        match db.get_user_terminal_config(&claims.username) {
-            Ok(Some(config)) => {
-                let response = Response {
-                    code: CODE_SUCCESS.to_string(),
-                    msg: "获取终端配置成功".to_string(),
-                    data: Some(serde_json::json!({ "config": config })),
-                };
-                HttpResponse::Ok().json(response)
-            }
-            Ok(None) => {
-                let response = Response {
-                    code: CODE_SUCCESS.to_string(),
-                    msg: "未找到终端配置".to_string(),
-                    data: None,
-                };
-                HttpResponse::Ok().json(response)
-            }
-            Err(e) => {
-                let response = Response {
-                    code: "500".to_string(),
-                    msg: format!("数据库错误: {}", e),
-                    data: None,
-                };
-                HttpResponse::Ok().json(response)
-            }
-        }
-    } else {
-        HttpResponse::Unauthorized().finish()
-    }
+) -> ApiResult {
+    let username = current_username(&req)?;
+    Ok(match db.get_user_terminal_config(&username).await? {
+        Some(config) => Response::with_msg("获取终端配置成功", json!({ "config": config })),
+        None => Response::message("未找到终端配置"),
+    })
 }
 
 // 更新用户终端配置
+#[utoipa::path(
+    post,
+    path = "/api/user/terminal-config",
+    tag = "user",
+    summary = "保存终端配置",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
 pub async fn update_user_terminal_config_handler(
     req: HttpRequest,
     body: web::Json<TerminalConfigRequest>,
     db: web::Data<Arc<Database>>,
-) -> impl Responder {
-    if let Some(claims) = req.extensions().get::<Claims>() {
-        match db.update_user_terminal_config(&claims.username, &body.config) {
-            Ok(_) => {
-                let response = Response {
-                    code: CODE_SUCCESS.to_string(),
-                    msg: "更新终端配置成功".to_string(),
-                    data: None,
-                };
-                HttpResponse::Ok().json(response)
-            }
-            Err(e) => {
-                let response = Response {
-                    code: "500".to_string(),
-                    msg: format!("数据库错误: {}", e),
-                    data: None,
-                };
-                HttpResponse::Ok().json(response)
-            }
-        }
-    } else {
-        HttpResponse::Unauthorized().finish()
-    }
+) -> ApiResult<()> {
+    let username = current_username(&req)?;
+    db.update_user_terminal_config(&username, &body.config)
+        .await?;
+    Ok(Response::message("更新终端配置成功"))
 }
 
+fn refresh_token_param(payload: &serde_json::Value) -> Result<String, ApiError> {
+    payload
+        .get("refreshToken")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| ApiError::RefreshTokenInvalid("缺少refreshToken".to_string()))
+}
+
+fn refresh_rejected(msg: &str) -> ApiError {
+    ApiError::RefreshTokenInvalid(msg.to_string())
+}
+
+// 刷新令牌只能使用一次：每次刷新都作废旧令牌并签发新令牌，旧令牌再次出现时吊销整个会话
+#[utoipa::path(
+    post,
+    path = "/auth/refreshToken",
+    tag = "auth",
+    summary = "刷新访问令牌",
+    request_body(content = Object, description = "{ \"refreshToken\": \"...\" }"),
+    responses((status = 200, body = Response<LoginToken>)),
+    security(())
+)]
 pub async fn refresh_token(
     payload: web::Json<serde_json::Value>,
     db: web::Data<Arc<Database>>,
-) -> impl Responder {
-    let refresh_token = match payload.get("refreshToken").and_then(|v| v.as_str()) {
-        Some(t) => t.to_string(),
-        None => {
-            let response = Response {
-                code: CODE_REFRESH_TOKEN_INVALID.to_string(),
-                msg: "缺少refreshToken".to_string(),
-                data: None,
-            };
-            return HttpResponse::BadRequest().json(response);
+) -> ApiResult<LoginToken> {
+    let refresh_token = refresh_token_param(&payload)?;
+    let claims = verify_token(&refresh_token).map_err(|err| {
+        log::warn!("refresh token failed: {}", err);
+        if matches!(
+            err.kind(),
+            jsonwebtoken::errors::ErrorKind::ExpiredSignature
+        ) {
+            refresh_rejected("refreshToken已过期")
+        } else {
+            refresh_rejected("refreshToken无效")
         }
+    })?;
+    let Some(jti) = claims.jti.filter(|_| claims.token_type == "refresh") else {
+        return Err(refresh_rejected("refreshToken无效"));
     };
 
-    let mut validation = Validation::new(Algorithm::HS256);
-    validation.validate_exp = true;
-    validation.leeway = 0; // Strict expiration check
-
-    match decode::<Claims>(
-        &refresh_token,
-        &DecodingKey::from_secret(JWT_SECRET),
-        &validation,
-    ) {
-        Ok(data) => {
-            if data.claims.token_type != "refresh" {
-                let response = Response {
-                    code: CODE_REFRESH_TOKEN_INVALID.to_string(),
-                    msg: "refreshToken无效".to_string(),
-                    data: None,
-                };
-                return HttpResponse::Unauthorized().json(response);
-            }
-
-            // 可选：检查用户是否仍然存在
-            if let Ok(Some((id, username, _email))) = db.get_user_info(&data.claims.username) {
-                match generate_tokens(id, &username) {
-                    Ok(tokens) => {
-                        log::info!("refresh token success for user: {}", username);
-                        let response = Response {
-                            code: CODE_SUCCESS.to_string(),
-                            msg: "刷新令牌成功".to_string(),
-                            data: Some(serde_json::json!(tokens)),
-                        };
-                        HttpResponse::Ok().json(response)
-                    }
-                    Err(err_resp) => err_resp,
-                }
-            } else {
-                let response = Response {
-                    code: CODE_USER_NOT_FOUND.to_string(),
-                    msg: "用户不存在".to_string(),
-                    data: None,
-                };
-                HttpResponse::Ok().json(response)
-            }
+    // 检查用户是否仍然存在且未停用
+    let Some((id, username, _email)) = db.get_user_info(&claims.username).await? else {
+        return Err(ApiError::UserNotFound);
+    };
+    let state = db.users().account_state(&username).await.ok().flatten();
+    if state.is_some_and(|state| state.disabled) {
+        return Err(refresh_rejected("账号已停用"));
+    }
+    let must_change = state.is_some_and(|state| state.must_change_password);
+    let next_jti = uuid::Uuid::new_v4().to_string();
+    let expires_at = refresh_expires_at();
+    let tokens = generate_tokens(id, &username, must_change, &next_jti, expires_at)?;
+    match db
+        .refresh_tokens()
+        .rotate(&jti, &next_jti, expires_at)
+        .await?
+    {
+        RotateOutcome::Rotated => {
+            log::info!("refresh token success for user: {}", username);
+            Ok(Response::with_msg("刷新令牌成功", tokens))
         }
-        Err(err) => {
-            log::warn!("refresh token failed: {}", err);
-            if matches!(
-                err.kind(),
-                jsonwebtoken::errors::ErrorKind::ExpiredSignature
-            ) {
-                let response = Response {
-                    code: CODE_REFRESH_TOKEN_INVALID.to_string(),
-                    msg: "refreshToken已过期".to_string(),
-                    data: None,
-                };
-                HttpResponse::Unauthorized().json(response)
-            } else {
-                let response = Response {
-                    code: CODE_REFRESH_TOKEN_INVALID.to_string(),
-                    msg: "refreshToken无效".to_string(),
-                    data: None,
-                };
-                HttpResponse::Unauthorized().json(response)
-            }
+        RotateOutcome::Reused => {
+            log::warn!(
+                "refresh token reuse detected for user: {}, session revoked",
+                username
+            );
+            Err(refresh_rejected("refreshToken已被使用，请重新登录"))
+        }
+        RotateOutcome::Revoked | RotateOutcome::Unknown => {
+            Err(refresh_rejected("refreshToken已失效"))
         }
     }
 }
 
+// 退出登录：吊销当前会话的刷新令牌（访问令牌在过期前仍然有效）
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    summary = "退出登录",
+    request_body(content = Object, description = "{ \"refreshToken\": \"...\" }"),
+    responses((status = 200, description = "成功，data 为空", body = Response)),
+    security(())
+)]
+pub async fn logout(
+    payload: web::Json<serde_json::Value>,
+    db: web::Data<Arc<Database>>,
+) -> ApiResult<()> {
+    let refresh_token = refresh_token_param(&payload)?;
+    // 已过期的令牌无需吊销
+    if let Ok(Claims {
+        jti: Some(jti),
+        username,
+        ..
+    }) = verify_token(&refresh_token)
+    {
+        db.refresh_tokens().revoke_family(&jti).await?;
+        log::info!("logout for user: {}", username);
+    }
+    Ok(Response::message("已退出登录"))
+}
+
+// 退出全部会话：吊销当前用户的所有刷新令牌
+#[utoipa::path(
+    post,
+    path = "/api/user/logout-all",
+    tag = "user",
+    summary = "退出全部会话",
+    responses((status = 200, body = Response))
+)]
+pub async fn logout_all(req: HttpRequest, db: web::Data<Arc<Database>>) -> ApiResult {
+    let username = current_username(&req)?;
+    let revoked = db.refresh_tokens().revoke_user(&username).await?;
+    log::info!("user {} logged out of all sessions ({})", username, revoked);
+    Ok(Response::with_msg(
+        "已退出全部会话",
+        json!({ "revoked": revoked }),
+    ))
+}
+
 // 健康检查端点（用于测试服务是否正常运行）
-pub async fn health_check() -> impl Responder {
-    HttpResponse::Ok().json(serde_json::json!({"status": "ok"}))
+#[utoipa::path(
+    get,
+    path = "/api/health",
+    tag = "system",
+    summary = "健康检查",
+    responses((status = 200, body = Response)),
+    security(())
+)]
+pub async fn health_check() -> ApiResult {
+    Ok(Response::ok(json!({ "status": "ok" })))
 }
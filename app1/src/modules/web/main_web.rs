@@ -16,9 +16,14 @@ use crate::modules::config::config::get_config;
 use crate::modules::proxy::proxy_http::ProxyMiddleware;
 use crate::modules::web::chat_api::upload_media as chat_upload_media;
 use crate::modules::web::database::Database;
+use crate::modules::web::migrations::get_migration_status;
+use crate::modules::web::auth_utils::{self, JwtKeys};
+use crate::modules::web::login_guard::LoginGuard;
+use crate::modules::web::oidc::OidcClient;
 use crate::modules::web::login_handler::{
-    get_user_info, get_user_theme_config_handler, health_check, login, refresh_token,
-    update_user_theme_config_handler, get_user_terminal_config_handler, update_user_terminal_config_handler,
+    get_user_info, get_user_theme_config_handler, health_check, login, login_totp, logout,
+    logout_all, refresh_token, update_user_theme_config_handler, get_user_terminal_config_handler,
+    update_user_terminal_config_handler,
 };
 use crate::modules::web::sftp_api::{
     create_dir as sftp_mkdir, create_session as sftp_create_session, delete_file as sftp_delete,
@@ -27,16 +32,22 @@ use crate::modules::web::sftp_api::{
     write_file as sftp_write,
 };
 use crate::modules::web::sqlite_api::{
-    add_column, batch_delete_rows, create_table, delete_row, drop_column, drop_table,
-    get_all_databases, get_table_data, get_tables_by_database, insert_row, rename_column,
-    rename_table, sql_query, update_row,
+    add_column, batch_delete_rows, create_database, create_index, create_table, create_trigger,
+    create_view, delete_row, drop_column, drop_index, drop_table, drop_trigger, drop_view,
+    dump_database, export_data, get_all_databases, get_table_data, get_tables_by_database,
+    import_data, insert_row, list_indexes, list_triggers, list_views, register_database,
+    rename_column, rename_table, restore_database, sql_query, unregister_database, update_row,
+    upload_database,
 };
+use crate::modules::web::sqlite_maintenance::{
+    SqliteMaintenance, get_maintenance_jobs, start_maintenance,
+};
+use crate::modules::web::sqlite_registry::SqliteRegistry;
 use crate::modules::web::ssh_servers_api::{
     create_group, create_server, delete_group, delete_server, list_groups, list_servers,
     update_group, update_server,
 };
 use crate::modules::web::ssh_monitor_api;
-use actix_web_actors::ws;
 // 导入日志宏
 // use crate::log_debug;
 // use crate::log_error;
@@ -52,11 +63,21 @@ use crate::modules::ssh::SshService; // 从ssh模块导入SshService
 use crate::modules::task::api::{start_task, stop_task, task_status};
 use crate::modules::task::service::TaskManager;
 use crate::modules::web::ChatServer; // 来自 actors.rs 的重导出
+use crate::modules::web::access_tokens_api;
+use crate::modules::web::audit_api;
+use crate::modules::web::openapi;
+use crate::modules::web::tls;
+use crate::modules::web::response;
 use crate::modules::web::apitest_api::{
     create_endpoint as apitest_create, delete_endpoint as apitest_delete,
     get_endpoint as apitest_get, list_endpoints as apitest_list, update_endpoint as apitest_update,
 };
-use crate::modules::web::auth_middleware::AuthMiddleware;
+use crate::modules::web::auth_middleware::{AuthMiddleware, RequirePermission, RequireSession};
+use crate::modules::web::oidc_api;
+use crate::modules::web::rbac;
+use crate::modules::web::totp_api;
+use crate::modules::web::users_api;
+use crate::modules::web::ws_auth::{self, WsUser};
 use crate::modules::web::sobel_ws::sobel_ws_route; // 导入Sobel WebSocket路由函数
 use crate::modules::web::ssh_websocket::ssh_route; // 导入SSH WebSocket路由函数
 use crate::modules::web::ssh_websocket_pty::ssh_pty_route; // 导入SSH PTY WebSocket路由函数
@@ -68,14 +89,16 @@ async fn chat_route(
     stream: web::Payload,
     srv: web::Data<Addr<ChatServer>>,
 ) -> Result<HttpResponse, Error> {
-    ws::start(
+    let user = WsUser::from_request(&req)?;
+    ws_auth::start(
         crate::modules::web::WsChatSession {
             id: 0,
             hb: Instant::now(),
             room: "main".to_owned(),
-            name: None,
+            name: user.username.clone(),
             addr: srv.get_ref().clone(),
         },
+        &user,
         &req,
         stream,
     )
@@ -90,11 +113,18 @@ async fn get_count(count: web::Data<AtomicUsize>) -> impl Responder {
 pub async fn setup_actix() -> Result<Server, std::io::Error> {
     // 读取配置文件
     let config = get_config().expect("Failed to load configuration");
+    // 加载 JWT 签名密钥
+    let jwt_keys = JwtKeys::from_config(&config.jwt).expect("Failed to load JWT keys");
+    log_info!("JWT 签名密钥: {}", jwt_keys.active_kid());
+    auth_utils::install_keys(jwt_keys);
 
     // 初始化数据库连接
     let db = Arc::new(
-        Database::new(&config.server.database_path).expect("Failed to initialize database"),
+        Database::connect(&config.server)
+            .await
+            .expect("Failed to initialize database"),
     );
+    log_info!("元数据库后端: {}", db.backend());
     // set up applications state
     // keep a count of the number of visitors
     let app_state = Arc::new(AtomicUsize::new(0));
@@ -125,11 +155,28 @@ pub async fn setup_actix() -> Result<Server, std::io::Error> {
     // 创建SFTP服务
     let sftp_service = std::sync::Arc::new(tokio::sync::Mutex::new(SftpService::new()));
     let sftp_service_data = web::Data::new(sftp_service.clone());
+    // SQLite 数据库登记表
+    // 元数据库本身排除在外
+    let sqlite_registry = web::Data::new(SqliteRegistry::from_config(
+        &config.sqlite,
+        &config.server.database_path,
+    ));
+    let sqlite_upload_limit = sqlite_registry.max_upload_bytes();
+    let sqlite_maintenance = web::Data::new(SqliteMaintenance::new(&config.sqlite.backup_dir));
+    // 登录限速与锁定（按 IP 的计数在各 worker 间共享）
+    let login_guard = web::Data::new(LoginGuard::new(config.login_protection.clone()));
+    // 单点登录（state 与兑换码在各 worker 间共享）
+    let oidc_client = web::Data::new(OidcClient::new(config.oidc.clone()));
     // 创建并配置Actix-Web服务器
     let server = HttpServer::new(move || {
         App::new()
             // 提高Json负载大小限制以支持媒体上传（默认较小）
-            .app_data(actix_web::web::JsonConfig::default().limit(50 * 1024 * 1024))
+            .app_data(
+                actix_web::web::JsonConfig::default()
+                    .limit(50 * 1024 * 1024)
+                    .error_handler(response::json_error),
+            )
+            .app_data(web::QueryConfig::default().error_handler(response::query_error))
             // 全局 CORS（允许前端开发端口访问）
             .wrap(
                 Cors::default()
@@ -148,6 +195,10 @@ pub async fn setup_actix() -> Result<Server, std::io::Error> {
             .app_data(web::Data::new(server.clone()))
             .app_data(ssh_service_data.clone())
             .app_data(sftp_service_data.clone())
+            .app_data(sqlite_registry.clone())
+            .app_data(sqlite_maintenance.clone())
+            .app_data(login_guard.clone())
+            .app_data(oidc_client.clone())
             // 任务管理器共享状态
             .app_data(web::Data::new(TaskManager::new()))
             // 注册代理中间件
@@ -155,21 +206,59 @@ pub async fn setup_actix() -> Result<Server, std::io::Error> {
             .wrap(ProxyMiddleware::new())
             // 配置API路由
             .route("/count", web::get().to(get_count))
-            .route("/ws", web::get().to(chat_route))
-            .route("/ws/ssh", web::get().to(ssh_route))
-            .route("/ws/ssh-pty", web::get().to(ssh_pty_route))
-            .route("/ws/sobel", web::get().to(sobel_ws_route))
-            .route("/ws/tianyi", web::get().to(tianyi_ws_route))
+            // WebSocket 路由（按功能要求相应权限）
+            .route(
+                "/ws",
+                web::get().to(chat_route).wrap(RequirePermission(rbac::CHAT_USE)),
+            )
+            .route(
+                "/ws/ssh",
+                web::get().to(ssh_route).wrap(RequirePermission(rbac::SSH_CONNECT)),
+            )
+            .route(
+                "/ws/ssh-pty",
+                web::get()
+                    .to(ssh_pty_route)
+                    .wrap(RequirePermission(rbac::SSH_CONNECT)),
+            )
+            .route(
+                "/ws/sobel",
+                web::get()
+                    .to(sobel_ws_route)
+                    .wrap(RequirePermission(rbac::TOOLS_USE)),
+            )
+            .route(
+                "/ws/tianyi",
+                web::get()
+                    .to(tianyi_ws_route)
+                    .wrap(RequirePermission(rbac::TOOLS_USE)),
+            )
             .route("/api/health", web::get().to(health_check))
+            // OpenAPI 文档与 Swagger UI
+            .service(openapi::docs_service())
+            .route(
+                "/api/system/migrations",
+                web::get()
+                    .to(get_migration_status)
+                    .wrap(RequirePermission(rbac::ADMIN)),
+            )
             .route("/api/login", web::post().to(login))
+            .route("/api/login/totp", web::post().to(login_totp))
+            .route("/api/oidc/providers", web::get().to(oidc_api::list_providers))
+            .route("/api/oidc/exchange", web::post().to(oidc_api::exchange))
+            .route("/api/oidc/{provider}/authorize", web::get().to(oidc_api::authorize))
+            .route("/api/oidc/{provider}/callback", web::get().to(oidc_api::callback))
             .service(
                 web::resource("/auth/getUserInfo")
                     .wrap(AuthMiddleware::new())
                     .route(web::get().to(get_user_info)),
             )
             .route("/auth/refreshToken", web::post().to(refresh_token))
+            .route("/auth/logout", web::post().to(logout))
             .service(
                 web::scope("/api/user")
+                    // 账号设置只接受登录会话，个人访问令牌无权访问
+                    .wrap(RequireSession)
                     .wrap(AuthMiddleware::new())
                     .route(
                         "/theme-config",
@@ -186,105 +275,396 @@ pub async fn setup_actix() -> Result<Server, std::io::Error> {
                     .route(
                         "/terminal-config",
                         web::post().to(update_user_terminal_config_handler),
+                    )
+                    // 修改自己的密码（必须先改密的账号也可访问）
+                    .route("/password", web::post().to(users_api::change_password))
+                    // 退出全部会话
+                    .route("/logout-all", web::post().to(logout_all))
+                    // 两步验证
+                    .route("/totp", web::get().to(totp_api::get_status))
+                    .route("/totp/setup", web::post().to(totp_api::setup))
+                    .route("/totp/enable", web::post().to(totp_api::enable))
+                    .route("/totp/disable", web::post().to(totp_api::disable))
+                    .route(
+                        "/totp/recovery-codes",
+                        web::post().to(totp_api::regenerate_recovery_codes),
+                    )
+                    // 个人访问令牌
+                    .route("/tokens", web::get().to(access_tokens_api::list_tokens))
+                    .route("/tokens", web::post().to(access_tokens_api::create_token))
+                    .route(
+                        "/tokens/{id}",
+                        web::delete().to(access_tokens_api::revoke_token),
+                    )
+                    .route(
+                        "/password-policy",
+                        web::get().to(users_api::get_password_policy),
                     ),
             )
-            // SQLite API routes
-            .route("/api/sqlite/databases", web::get().to(get_all_databases))
-            .route("/api/sqlite/tables", web::get().to(get_tables_by_database))
-            .route("/api/sqlite/table-data", web::get().to(get_table_data))
-            // 新增SQLite管理API路由
-            .route("/api/sqlite/table/create", web::post().to(create_table))
-            .route("/api/sqlite/table/delete", web::post().to(drop_table))
-            .route("/api/sqlite/table/rename", web::post().to(rename_table))
-            .route("/api/sqlite/column/rename", web::post().to(rename_column))
-            .route("/api/sqlite/column/add", web::post().to(add_column))
-            .route("/api/sqlite/column/drop", web::post().to(drop_column))
-            .route("/api/sqlite/row/insert", web::post().to(insert_row))
-            .route("/api/sqlite/row/update", web::post().to(update_row))
-            .route("/api/sqlite/row/delete", web::post().to(delete_row))
-            .route(
-                "/api/sqlite/row/batch-delete",
-                web::post().to(batch_delete_rows),
-            )
-            .route("/api/sqlite/query", web::post().to(sql_query))
-            // SQL Studio Connections API
-            .route(
-                "/api/sqlstudio/connection/test",
-                web::post().to(test_connection_handler),
-            )
-            .route(
-                "/api/sqlstudio/connection/create",
-                web::post().to(create_connection_handler),
+            // 用户管理
+            .service(
+                web::scope("/api/users")
+                    .wrap(RequirePermission(rbac::ADMIN))
+                    .route("", web::get().to(users_api::list_users))
+                    .route("", web::post().to(users_api::create_user))
+                    .route(
+                        "/login-failures",
+                        web::get().to(users_api::list_login_failures),
+                    )
+                    .route("/{username}", web::put().to(users_api::update_user))
+                    .route("/{username}", web::delete().to(users_api::delete_user))
+                    .route(
+                        "/{username}/password",
+                        web::put().to(users_api::reset_password),
+                    )
+                    .route("/{username}/unlock", web::put().to(users_api::unlock_user))
+                    .route(
+                        "/{username}/totp",
+                        web::delete().to(totp_api::reset_user_totp),
+                    )
+                    .route(
+                        "/{username}/tokens",
+                        web::delete().to(access_tokens_api::revoke_user_tokens),
+                    ),
             )
-            .route(
-                "/api/sqlstudio/connection/list",
-                web::get().to(list_connections_handler),
+            // 角色与权限管理
+            .service(
+                web::scope("/api/rbac")
+                    .wrap(RequirePermission(rbac::ADMIN))
+                    .route("/permissions", web::get().to(rbac::list_permissions))
+                    .route("/roles", web::get().to(rbac::list_roles))
+                    .route("/roles", web::post().to(rbac::save_role))
+                    .route("/roles/{name}", web::delete().to(rbac::delete_role))
+                    .route("/users/{username}/roles", web::get().to(rbac::get_user_roles))
+                    .route("/users/{username}/roles", web::put().to(rbac::set_user_roles)),
             )
-            .route(
-                "/api/sqlstudio/connection/update",
-                web::post().to(update_connection_handler),
+            // 审计日志查询与导出
+            .service(
+                web::scope("/api/audit")
+                    .wrap(RequirePermission(rbac::AUDIT_READ))
+                    .route("", web::get().to(audit_api::list_audit))
+                    .route("/export", web::get().to(audit_api::export_audit)),
             )
-            .route(
-                "/api/sqlstudio/connection/delete",
-                web::post().to(delete_connection_handler),
+            // SQLite API routes（浏览需要 sql:read，修改需要 sql:execute，库登记与维护需要 sql:manage）
+            .service(
+                web::scope("/api/sqlite")
+                    .wrap(RequirePermission(rbac::SQL_READ))
+                    .route("/databases", web::get().to(get_all_databases))
+                    .route(
+                        "/databases/create",
+                        web::post()
+                            .to(create_database)
+                            .wrap(RequirePermission(rbac::SQL_MANAGE)),
+                    )
+                    .route(
+                        "/databases/register",
+                        web::post()
+                            .to(register_database)
+                            .wrap(RequirePermission(rbac::SQL_MANAGE)),
+                    )
+                    .route(
+                        "/databases/unregister",
+                        web::post()
+                            .to(unregister_database)
+                            .wrap(RequirePermission(rbac::SQL_MANAGE)),
+                    )
+                    .service(
+                        web::resource("/databases/upload")
+                            .app_data(web::PayloadConfig::new(sqlite_upload_limit))
+                            .wrap(RequirePermission(rbac::SQL_MANAGE))
+                            .route(web::post().to(upload_database)),
+                    )
+                    .route("/tables", web::get().to(get_tables_by_database))
+                    .route("/table-data", web::get().to(get_table_data))
+                    // 新增SQLite管理API路由
+                    .route(
+                        "/table/create",
+                        web::post()
+                            .to(create_table)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/table/delete",
+                        web::post()
+                            .to(drop_table)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/table/rename",
+                        web::post()
+                            .to(rename_table)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/column/rename",
+                        web::post()
+                            .to(rename_column)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/column/add",
+                        web::post()
+                            .to(add_column)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/column/drop",
+                        web::post()
+                            .to(drop_column)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/row/insert",
+                        web::post()
+                            .to(insert_row)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/row/update",
+                        web::post()
+                            .to(update_row)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/row/delete",
+                        web::post()
+                            .to(delete_row)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/row/batch-delete",
+                        web::post()
+                            .to(batch_delete_rows)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/query",
+                        web::post()
+                            .to(sql_query)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route("/indexes", web::get().to(list_indexes))
+                    .route(
+                        "/index/create",
+                        web::post()
+                            .to(create_index)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/index/delete",
+                        web::post()
+                            .to(drop_index)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route("/views", web::get().to(list_views))
+                    .route(
+                        "/view/create",
+                        web::post()
+                            .to(create_view)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/view/delete",
+                        web::post()
+                            .to(drop_view)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route("/triggers", web::get().to(list_triggers))
+                    .route(
+                        "/trigger/create",
+                        web::post()
+                            .to(create_trigger)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/trigger/delete",
+                        web::post()
+                            .to(drop_trigger)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/import",
+                        web::post()
+                            .to(import_data)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route("/export", web::post().to(export_data))
+                    .route("/dump", web::get().to(dump_database))
+                    .route(
+                        "/restore",
+                        web::post()
+                            .to(restore_database)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    )
+                    .route(
+                        "/maintenance/start",
+                        web::post()
+                            .to(start_maintenance)
+                            .wrap(RequirePermission(rbac::SQL_MANAGE)),
+                    )
+                    .route("/maintenance/jobs", web::get().to(get_maintenance_jobs)),
             )
-            .route(
-                "/api/sqlstudio/connection/metadata",
-                web::post().to(get_metadata_handler),
+            // SQL Studio Connections API
+            .service(
+                web::scope("/api/sqlstudio/connection")
+                    .wrap(RequirePermission(rbac::SQL_READ))
+                    .route(
+                        "/test",
+                        web::post()
+                            .to(test_connection_handler)
+                            .wrap(RequirePermission(rbac::SQL_MANAGE)),
+                    )
+                    .route(
+                        "/create",
+                        web::post()
+                            .to(create_connection_handler)
+                            .wrap(RequirePermission(rbac::SQL_MANAGE)),
+                    )
+                    .route("/list", web::get().to(list_connections_handler))
+                    .route(
+                        "/update",
+                        web::post()
+                            .to(update_connection_handler)
+                            .wrap(RequirePermission(rbac::SQL_MANAGE)),
+                    )
+                    .route(
+                        "/delete",
+                        web::post()
+                            .to(delete_connection_handler)
+                            .wrap(RequirePermission(rbac::SQL_MANAGE)),
+                    )
+                    .route("/metadata", web::post().to(get_metadata_handler))
+                    .route("/table-data", web::post().to(get_table_data_handler))
+                    .route(
+                        "/execute",
+                        web::post()
+                            .to(execute_sql_handler)
+                            .wrap(RequirePermission(rbac::SQL_EXECUTE)),
+                    ),
             )
+            // Chat 媒体上传 API 路由
             .route(
-                "/api/sqlstudio/connection/table-data",
-                web::post().to(get_table_data_handler),
+                "/api/chat/upload",
+                web::post()
+                    .to(chat_upload_media)
+                    .wrap(RequirePermission(rbac::CHAT_USE)),
             )
-            .route(
-                "/api/sqlstudio/connection/execute",
-                web::post().to(execute_sql_handler),
+            // SFTP 文件操作 API 路由（远程SFTP会话；浏览需要 sftp:read，修改需要 sftp:write）
+            .service(
+                web::scope("/api/sftp")
+                    .wrap(RequirePermission(rbac::SFTP_READ))
+                    .route("/session", web::post().to(sftp_create_session))
+                    .route("/list", web::get().to(sftp_list))
+                    .route("/read", web::get().to(sftp_read))
+                    .route(
+                        "/write",
+                        web::post()
+                            .to(sftp_write)
+                            .wrap(RequirePermission(rbac::SFTP_WRITE)),
+                    )
+                    .route(
+                        "/delete",
+                        web::post()
+                            .to(sftp_delete)
+                            .wrap(RequirePermission(rbac::SFTP_WRITE)),
+                    )
+                    .route(
+                        "/rename",
+                        web::post()
+                            .to(sftp_rename)
+                            .wrap(RequirePermission(rbac::SFTP_WRITE)),
+                    )
+                    .service(
+                        web::resource("/upload")
+                            .app_data(
+                                web::JsonConfig::default()
+                                    .limit(100 * 1024 * 1024) // 100MB limit
+                                    .error_handler(response::json_error),
+                            )
+                            .wrap(RequirePermission(rbac::SFTP_WRITE))
+                            .route(web::post().to(sftp_upload)),
+                    )
+                    .route("/download", web::get().to(sftp_download))
+                    .route(
+                        "/mkdir",
+                        web::post()
+                            .to(sftp_mkdir)
+                            .wrap(RequirePermission(rbac::SFTP_WRITE)),
+                    )
+                    .route(
+                        "/chmod",
+                        web::post()
+                            .to(sftp_chmod)
+                            .wrap(RequirePermission(rbac::SFTP_WRITE)),
+                    ),
             )
-            // Chat 媒体上传 API 路由
-            .route("/api/chat/upload", web::post().to(chat_upload_media))
-            // SFTP 文件操作 API 路由（远程SFTP会话）
-            .route("/api/sftp/session", web::post().to(sftp_create_session))
-            .route("/api/sftp/list", web::get().to(sftp_list))
-            .route("/api/sftp/read", web::get().to(sftp_read))
-            .route("/api/sftp/write", web::post().to(sftp_write))
-            .route("/api/sftp/delete", web::post().to(sftp_delete))
-            .route("/api/sftp/rename", web::post().to(sftp_rename))
+            // SSH 服务器配置 CRUD 与实时监控（查看需要 ssh:connect，修改需要 ssh:manage）
             .service(
-                web::resource("/api/sftp/upload")
-                    .app_data(web::JsonConfig::default().limit(100 * 1024 * 1024)) // 100MB limit
-                    .route(web::post().to(sftp_upload)),
+                web::scope("/api/ssh")
+                    .wrap(RequirePermission(rbac::SSH_CONNECT))
+                    .route("/groups", web::get().to(list_groups))
+                    .route(
+                        "/groups",
+                        web::post()
+                            .to(create_group)
+                            .wrap(RequirePermission(rbac::SSH_MANAGE)),
+                    )
+                    .route(
+                        "/groups/{id}",
+                        web::put()
+                            .to(update_group)
+                            .wrap(RequirePermission(rbac::SSH_MANAGE)),
+                    )
+                    .route(
+                        "/groups/{id}",
+                        web::delete()
+                            .to(delete_group)
+                            .wrap(RequirePermission(rbac::SSH_MANAGE)),
+                    )
+                    .route("/servers", web::get().to(list_servers))
+                    .route(
+                        "/servers",
+                        web::post()
+                            .to(create_server)
+                            .wrap(RequirePermission(rbac::SSH_MANAGE)),
+                    )
+                    .route(
+                        "/servers/{id}",
+                        web::put()
+                            .to(update_server)
+                            .wrap(RequirePermission(rbac::SSH_MANAGE)),
+                    )
+                    .route(
+                        "/servers/{id}",
+                        web::delete()
+                            .to(delete_server)
+                            .wrap(RequirePermission(rbac::SSH_MANAGE)),
+                    )
+                    // SSH 实时监控 API
+                    .route("/monitor", web::post().to(ssh_monitor_api::get_monitor_stats))
+                    // 兼容预检请求（RequirePermission 对 OPTIONS 放行）
+                    .route("/monitor", web::route().guard(actix_web::guard::Options()).to(|| async { HttpResponse::NoContent().finish() })),
             )
-            .route("/api/sftp/download", web::get().to(sftp_download))
-            .route("/api/sftp/mkdir", web::post().to(sftp_mkdir))
-            .route("/api/sftp/chmod", web::post().to(sftp_chmod))
-            // SSH 服务器配置 CRUD API 路由
-            .route("/api/ssh/groups", web::get().to(list_groups))
-            .route("/api/ssh/groups", web::post().to(create_group))
-            .route("/api/ssh/groups/{id}", web::put().to(update_group))
-            .route("/api/ssh/groups/{id}", web::delete().to(delete_group))
-            .route("/api/ssh/servers", web::get().to(list_servers))
-            .route("/api/ssh/servers", web::post().to(create_server))
-            .route("/api/ssh/servers/{id}", web::put().to(update_server))
-            .route("/api/ssh/servers/{id}", web::delete().to(delete_server))
-            // SSH 实时监控 API
-            .route("/api/ssh/monitor", web::post().to(ssh_monitor_api::get_monitor_stats))
-            // 兼容预检请求
-            .route("/api/ssh/monitor", web::route().guard(actix_web::guard::Options()).to(|| async { HttpResponse::NoContent().finish() }))
             // 使用模板引擎渲染 index.html
             .route("/", web::get().to(serve_index))
             .route("/index.html", web::get().to(serve_index))
             // Task API routes
-            .route("/api/task/start", web::post().to(start_task))
-            .route("/api/task/stop", web::post().to(stop_task))
-            .route("/api/task/status", web::get().to(task_status))
+            .service(
+                web::scope("/api/task")
+                    .wrap(RequirePermission(rbac::TASK_MANAGE))
+                    .route("/start", web::post().to(start_task))
+                    .route("/stop", web::post().to(stop_task))
+                    .route("/status", web::get().to(task_status)),
+            )
             // API Test 定义 CRUD 路由
-            .route("/api/apitest/endpoints", web::get().to(apitest_list))
-            .route("/api/apitest/endpoints/{id}", web::get().to(apitest_get))
-            .route("/api/apitest/endpoints", web::post().to(apitest_create))
-            .route("/api/apitest/endpoints/{id}", web::put().to(apitest_update))
-            .route(
-                "/api/apitest/endpoints/{id}",
-                web::delete().to(apitest_delete),
+            .service(
+                web::scope("/api/apitest")
+                    .wrap(RequirePermission(rbac::APITEST_USE))
+                    .route("/endpoints", web::get().to(apitest_list))
+                    .route("/endpoints/{id}", web::get().to(apitest_get))
+                    .route("/endpoints", web::post().to(apitest_create))
+                    .route("/endpoints/{id}", web::put().to(apitest_update))
+                    .route("/endpoints/{id}", web::delete().to(apitest_delete)),
             )
             // 配置静态文件服务
             .service(
@@ -292,10 +672,35 @@ pub async fn setup_actix() -> Result<Server, std::io::Error> {
                     .show_files_listing(),
             )
     })
-    .workers(2)
-    .bind(format!("{}:{}", config.server.host, config.server.port))?
-    .run();
-    Ok(server)
+    .workers(2);
+    let addr = format!("{}:{}", config.server.host, config.server.port);
+    let tls_config = &config.server.tls;
+    let server = if tls_config.enable {
+        let (rustls_config, resolver) =
+            tls::server_config(tls_config).map_err(std::io::Error::other)?;
+        tls::spawn_reload(resolver, tls_config.reload_interval_secs);
+        if tls_config.redirect_http_port > 0 {
+            let redirect = tls::redirect_server(
+                &config.server.host,
+                tls_config.redirect_http_port,
+                config.server.port,
+            )?;
+            actix_web::rt::spawn(redirect);
+            log_info!(
+                "🔒 HTTP 端口 {} 跳转到 HTTPS",
+                tls_config.redirect_http_port
+            );
+        }
+        log_info!(
+            "🔒 HTTPS 已启用，最低版本 TLS {}，客户端证书: {}",
+            tls_config.min_version,
+            if tls_config.client_ca_path.is_some() { "必须" } else { "不要求" }
+        );
+        server.bind_rustls_0_23(addr, rustls_config)?
+    } else {
+        server.bind(addr)?
+    };
+    Ok(server.run())
 }
 
 /// 使用轻量级模板引擎渲染 index.html，将 <rsp:include page="..." /> 嵌入对应文件内容
@@ -0,0 +1,963 @@
+use std::sync::Arc;
+
+use actix_web::web;
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+use serde_json::json;
+use sqlx::postgres::PgPool;
+use sqlx::{Connection as _, Executor, PgConnection, Row};
+
+use super::database::Database;
+use super::models::{MigrationStatus, Response};
+use super::response::ApiResult;
+use crate::{log_info, log_warn};
+
+// rsts.db 元数据库的版本化迁移
+//
+// 所有模块的表结构都在这里按版本号顺序定义，启动时由 Database::new 调用 run_migrations。
+// 每个迁移在独立事务中执行，成功后写入 schema_version；已发布的迁移不要修改，新增表/列请追加新版本。
+// 早期版本由各模块自行 CREATE TABLE IF NOT EXISTS 建表，因此基线迁移保持幂等，以便接管已有数据库。
+// PostgreSQL 后端使用 PG_MIGRATIONS，版本号与名称必须与 MIGRATIONS 一一对应（有测试校验）。
+
+pub struct Migration {
+    /// 版本号（严格递增）
+    pub version: i64,
+    /// 迁移名称
+    pub name: &'static str,
+    /// 迁移内容（在事务内执行）
+    pub up: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_users",
+        up: create_users,
+    },
+    Migration {
+        version: 2,
+        name: "users_add_terminal_setting_config",
+        up: users_add_terminal_setting_config,
+    },
+    Migration {
+        version: 3,
+        name: "create_ws_session_and_message",
+        up: create_ws_tables,
+    },
+    Migration {
+        version: 4,
+        name: "create_sql_connections",
+        up: create_sql_connections,
+    },
+    Migration {
+        version: 5,
+        name: "create_api_endpoints",
+        up: create_api_endpoints,
+    },
+    Migration {
+        version: 6,
+        name: "create_ssh_groups_and_servers",
+        up: create_ssh_tables,
+    },
+    Migration {
+        version: 7,
+        name: "ssh_servers_add_group_and_remark",
+        up: ssh_servers_add_group_and_remark,
+    },
+    Migration {
+        version: 8,
+        name: "create_rbac",
+        up: create_rbac,
+    },
+    Migration {
+        version: 9,
+        name: "users_add_account_state",
+        up: users_add_account_state,
+    },
+    Migration {
+        version: 10,
+        name: "create_refresh_tokens",
+        up: create_refresh_tokens,
+    },
+    Migration {
+        version: 11,
+        name: "create_login_protection",
+        up: create_login_protection,
+    },
+    Migration {
+        version: 12,
+        name: "create_user_totp",
+        up: create_user_totp,
+    },
+    Migration {
+        version: 13,
+        name: "create_oidc_identities",
+        up: create_oidc_identities,
+    },
+    Migration {
+        version: 14,
+        name: "create_personal_access_tokens",
+        up: create_personal_access_tokens,
+    },
+    Migration {
+        version: 15,
+        name: "create_audit_log",
+        up: create_audit_log,
+    },
+];
+
+// 内置角色及权限（SQLite 与 PostgreSQL 通用）；角色名与前端路由的 roles 一致
+// 注意：INSERT ... SELECT 后接 ON CONFLICT 时 SQLite 需要 WHERE 子句消除歧义
+macro_rules! rbac_seed_sql {
+    () => {
+        "INSERT INTO roles (name, description) VALUES
+            ('R_SUPER', '超级管理员：拥有全部权限'),
+            ('R_ADMIN', '管理员：执行 SQL、管理连接、服务器与任务'),
+            ('R_USER', '普通用户：只读查询、SSH 连接与文件浏览')
+            ON CONFLICT (name) DO NOTHING;
+        INSERT INTO role_permissions (role_id, permission)
+            SELECT r.id, p.permission FROM roles r JOIN (
+                SELECT 'R_SUPER' AS role, 'admin' AS permission
+                UNION ALL SELECT 'R_ADMIN', 'sql:read'
+                UNION ALL SELECT 'R_ADMIN', 'sql:execute'
+                UNION ALL SELECT 'R_ADMIN', 'sql:manage'
+                UNION ALL SELECT 'R_ADMIN', 'ssh:connect'
+                UNION ALL SELECT 'R_ADMIN', 'ssh:manage'
+                UNION ALL SELECT 'R_ADMIN', 'sftp:read'
+                UNION ALL SELECT 'R_ADMIN', 'sftp:write'
+                UNION ALL SELECT 'R_ADMIN', 'task:manage'
+                UNION ALL SELECT 'R_ADMIN', 'apitest:use'
+                UNION ALL SELECT 'R_ADMIN', 'chat:use'
+                UNION ALL SELECT 'R_ADMIN', 'tools:use'
+                UNION ALL SELECT 'R_USER', 'sql:read'
+                UNION ALL SELECT 'R_USER', 'ssh:connect'
+                UNION ALL SELECT 'R_USER', 'sftp:read'
+                UNION ALL SELECT 'R_USER', 'apitest:use'
+                UNION ALL SELECT 'R_USER', 'chat:use'
+                UNION ALL SELECT 'R_USER', 'tools:use'
+            ) p ON p.role = r.name
+            WHERE true
+            ON CONFLICT DO NOTHING;"
+    };
+}
+
+// 默认角色分配：admin 拥有全部内置角色，其余用户为 R_USER
+macro_rules! assign_default_roles_sql {
+    () => {
+        "INSERT INTO user_roles (user_id, role_id)
+            SELECT u.id, r.id FROM users u JOIN roles r
+                ON u.username = 'admin' OR r.name = 'R_USER'
+            WHERE true
+            ON CONFLICT DO NOTHING;"
+    };
+}
+
+/// 给现有用户分配默认角色（新库在创建默认用户后执行）
+pub const ASSIGN_DEFAULT_ROLES: &str = assign_default_roles_sql!();
+
+// 默认账号首次登录后必须修改密码
+macro_rules! flag_default_users_sql {
+    () => {
+        "UPDATE users SET must_change_password = 1
+            WHERE username IN ('admin', 'user1') AND password_changed_at IS NULL;"
+    };
+}
+
+const FLAG_DEFAULT_USERS: &str = flag_default_users_sql!();
+
+fn create_users(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS users (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            username TEXT NOT NULL UNIQUE,
+            password TEXT NOT NULL,
+            email TEXT,
+            theme_config TEXT
+        );",
+    )
+}
+
+fn users_add_terminal_setting_config(tx: &Transaction) -> rusqlite::Result<()> {
+    add_column_if_missing(tx, "users", "terminal_setting_config", "TEXT")
+}
+
+fn create_ws_tables(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS ws_session (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL UNIQUE,
+            username TEXT,
+            room TEXT,
+            connected_at TEXT NOT NULL,
+            last_active TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS ws_message (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            username TEXT NOT NULL,
+            room TEXT,
+            content TEXT NOT NULL,
+            timestamp TEXT NOT NULL
+        );",
+    )
+}
+
+fn create_sql_connections(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sql_connections (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            db_type TEXT NOT NULL,
+            host TEXT NOT NULL,
+            port INTEGER NOT NULL,
+            username TEXT NOT NULL,
+            password TEXT,
+            database TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );",
+    )
+}
+
+fn create_api_endpoints(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS api_endpoints (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            parent_id INTEGER,
+            name TEXT NOT NULL,
+            method TEXT NOT NULL,
+            url TEXT NOT NULL,
+            headers TEXT,
+            params TEXT,
+            body_type TEXT,
+            body TEXT,
+            content_type TEXT,
+            order_index INTEGER DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY(parent_id) REFERENCES api_endpoints(id) ON DELETE SET NULL
+        );",
+    )
+}
+
+fn create_ssh_tables(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS ssh_groups (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            is_default INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE TABLE IF NOT EXISTS ssh_servers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            alias TEXT NOT NULL,
+            hostname TEXT NOT NULL,
+            port INTEGER NOT NULL DEFAULT 22,
+            username TEXT NOT NULL,
+            password TEXT
+        );",
+    )?;
+    // 默认分组（删除分组时服务器会回落到这里）
+    let exists: Option<i64> = tx
+        .query_row(
+            "SELECT id FROM ssh_groups WHERE is_default=1 LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if exists.is_none() {
+        tx.execute(
+            "INSERT INTO ssh_groups (name, is_default) VALUES (?1, 1)",
+            params!["默认分组"],
+        )?;
+    }
+    Ok(())
+}
+
+fn ssh_servers_add_group_and_remark(tx: &Transaction) -> rusqlite::Result<()> {
+    add_column_if_missing(tx, "ssh_servers", "group_id", "INTEGER")?;
+    add_column_if_missing(tx, "ssh_servers", "remark", "TEXT")
+}
+
+fn create_rbac(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS roles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            description TEXT
+        );
+        CREATE TABLE IF NOT EXISTS role_permissions (
+            role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+            permission TEXT NOT NULL,
+            PRIMARY KEY (role_id, permission)
+        );
+        CREATE TABLE IF NOT EXISTS user_roles (
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+            PRIMARY KEY (user_id, role_id)
+        );",
+    )?;
+    tx.execute_batch(rbac_seed_sql!())?;
+    tx.execute_batch(ASSIGN_DEFAULT_ROLES)
+}
+
+// 账号停用与强制改密；此前无法修改密码，默认账号必然仍在使用初始密码
+fn users_add_account_state(tx: &Transaction) -> rusqlite::Result<()> {
+    add_column_if_missing(tx, "users", "disabled", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(
+        tx,
+        "users",
+        "must_change_password",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(tx, "users", "password_changed_at", "TEXT")?;
+    tx.execute_batch(FLAG_DEFAULT_USERS)
+}
+
+// 刷新令牌；expires_at 为 Unix 时间戳（秒），family_id 为登录时第一个令牌的 jti
+fn create_refresh_tokens(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS refresh_tokens (
+            jti TEXT PRIMARY KEY,
+            family_id TEXT NOT NULL,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            issued_at TEXT NOT NULL,
+            expires_at INTEGER NOT NULL,
+            used_at TEXT,
+            revoked_at TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_refresh_tokens_family ON refresh_tokens(family_id);
+        CREATE INDEX IF NOT EXISTS idx_refresh_tokens_user ON refresh_tokens(user_id);",
+    )
+}
+
+// 登录锁定状态（按输入的用户名，不要求账号存在）与失败记录；时间戳为 Unix 秒
+fn create_login_protection(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS login_lockouts (
+            username TEXT PRIMARY KEY,
+            failures INTEGER NOT NULL,
+            window_start INTEGER NOT NULL,
+            locked_until INTEGER
+        );
+        CREATE TABLE IF NOT EXISTS login_failures (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            username TEXT NOT NULL,
+            ip TEXT,
+            reason TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_login_failures_created ON login_failures(created_at);
+        CREATE INDEX IF NOT EXISTS idx_login_failures_username ON login_failures(username);",
+    )
+}
+
+// 两步验证：TOTP 密钥（enabled=0 表示尚未完成绑定）与一次性恢复码（只存哈希）
+fn create_user_totp(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS user_totp (
+            user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+            secret TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 0,
+            last_used_step INTEGER,
+            created_at TEXT NOT NULL,
+            enabled_at TEXT
+        );
+        CREATE TABLE IF NOT EXISTS user_recovery_codes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            code_hash TEXT NOT NULL,
+            used_at TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_user_recovery_codes_user ON user_recovery_codes(user_id);",
+    )
+}
+
+// 单点登录身份：IdP 中的 (provider, subject) 对应一个本地用户
+fn create_oidc_identities(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS oidc_identities (
+            provider TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            email TEXT,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (provider, subject)
+        );
+        CREATE INDEX IF NOT EXISTS idx_oidc_identities_user ON oidc_identities(user_id);",
+    )
+}
+
+// 个人访问令牌：只存 SHA-256 哈希，prefix 为明文开头几位（便于用户辨认）；
+// scopes 为空格分隔的权限码；时间戳为 Unix 秒
+fn create_personal_access_tokens(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS personal_access_tokens (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            token_hash TEXT NOT NULL UNIQUE,
+            prefix TEXT NOT NULL,
+            scopes TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            last_used_at INTEGER,
+            revoked_at INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_personal_access_tokens_user ON personal_access_tokens(user_id);",
+    )
+}
+
+// 审计日志：特权操作与登录事件，只追加（触发器拒绝 UPDATE / DELETE）；
+// username 存文本而非外键，删除用户后记录仍保留；时间为 Unix 秒
+fn create_audit_log(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            created_at INTEGER NOT NULL,
+            username TEXT NOT NULL,
+            ip TEXT,
+            action TEXT NOT NULL,
+            target TEXT NOT NULL,
+            outcome TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            detail TEXT,
+            error TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_audit_log_created ON audit_log(created_at);
+        CREATE INDEX IF NOT EXISTS idx_audit_log_username ON audit_log(username);
+        CREATE INDEX IF NOT EXISTS idx_audit_log_action ON audit_log(action);
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;",
+    )
+}
+
+// 旧库可能已经由模块自行补过列，这里先检查再 ALTER
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    column_type: &str,
+) -> rusqlite::Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE \"{}\" ADD COLUMN \"{}\" {}",
+            table, column, column_type
+        ))?;
+    }
+    Ok(())
+}
+
+fn ensure_version_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        );",
+    )
+}
+
+// 当前已应用的最高版本（没有记录时为 0）
+pub fn current_version(conn: &Connection) -> rusqlite::Result<i64> {
+    ensure_version_table(conn)?;
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )
+}
+
+// 执行所有未应用的迁移，返回本次应用的版本号
+pub fn run_migrations(conn: &mut Connection) -> rusqlite::Result<Vec<i64>> {
+    let current = current_version(conn)?;
+    let latest = MIGRATIONS.last().map(|m| m.version).unwrap_or(0);
+    if current > latest {
+        log_warn!(
+            "数据库 schema 版本 {} 高于程序已知的最新版本 {}，可能由更新的版本创建",
+            current,
+            latest
+        );
+    }
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        (migration.up)(&tx)?;
+        tx.execute(
+            "INSERT INTO schema_version (version, name, applied_at) VALUES (?1, ?2, ?3)",
+            params![migration.version, migration.name, Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
+        log_info!("已应用数据库迁移 {} {}", migration.version, migration.name);
+        applied.push(migration.version);
+    }
+    Ok(applied)
+}
+
+// 列出全部迁移及其应用状态（包括数据库中存在但程序未定义的版本）
+pub fn migration_status(conn: &Connection) -> rusqlite::Result<Vec<MigrationStatus>> {
+    ensure_version_table(conn)?;
+    let mut stmt =
+        conn.prepare("SELECT version, name, applied_at FROM schema_version ORDER BY version")?;
+    let recorded = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(build_status(recorded))
+}
+
+// 合并程序内定义的迁移与 schema_version 中的记录
+fn build_status(recorded: Vec<(i64, String, String)>) -> Vec<MigrationStatus> {
+    let mut list: Vec<MigrationStatus> = MIGRATIONS
+        .iter()
+        .map(|m| {
+            let applied_at = recorded
+                .iter()
+                .find(|(v, _, _)| *v == m.version)
+                .map(|(_, _, at)| at.clone());
+            MigrationStatus {
+                version: m.version,
+                name: m.name.to_string(),
+                applied: applied_at.is_some(),
+                applied_at,
+                known: true,
+            }
+        })
+        .collect();
+    for (version, name, applied_at) in recorded {
+        if !MIGRATIONS.iter().any(|m| m.version == version) {
+            list.push(MigrationStatus {
+                version,
+                name,
+                applied: true,
+                applied_at: Some(applied_at),
+                known: false,
+            });
+        }
+    }
+    list.sort_by_key(|m| m.version);
+    list
+}
+
+pub struct PgMigration {
+    /// 版本号（与 MIGRATIONS 对应）
+    pub version: i64,
+    /// 迁移名称（与 MIGRATIONS 对应）
+    pub name: &'static str,
+    /// 迁移 SQL（可包含多条语句，在事务内执行）
+    pub sql: &'static str,
+}
+
+pub const PG_MIGRATIONS: &[PgMigration] = &[
+    PgMigration {
+        version: 1,
+        name: "create_users",
+        sql: "CREATE TABLE IF NOT EXISTS users (
+            id BIGSERIAL PRIMARY KEY,
+            username TEXT NOT NULL UNIQUE,
+            password TEXT NOT NULL,
+            email TEXT,
+            theme_config TEXT
+        );",
+    },
+    PgMigration {
+        version: 2,
+        name: "users_add_terminal_setting_config",
+        sql: "ALTER TABLE users ADD COLUMN IF NOT EXISTS terminal_setting_config TEXT;",
+    },
+    PgMigration {
+        version: 3,
+        name: "create_ws_session_and_message",
+        sql: "CREATE TABLE IF NOT EXISTS ws_session (
+            id BIGSERIAL PRIMARY KEY,
+            session_id TEXT NOT NULL UNIQUE,
+            username TEXT,
+            room TEXT,
+            connected_at TEXT NOT NULL,
+            last_active TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS ws_message (
+            id BIGSERIAL PRIMARY KEY,
+            session_id TEXT NOT NULL,
+            username TEXT NOT NULL,
+            room TEXT,
+            content TEXT NOT NULL,
+            timestamp TEXT NOT NULL
+        );",
+    },
+    PgMigration {
+        version: 4,
+        name: "create_sql_connections",
+        sql: "CREATE TABLE IF NOT EXISTS sql_connections (
+            id BIGSERIAL PRIMARY KEY,
+            name TEXT NOT NULL,
+            db_type TEXT NOT NULL,
+            host TEXT NOT NULL,
+            port INTEGER NOT NULL,
+            username TEXT NOT NULL,
+            password TEXT,
+            database TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );",
+    },
+    PgMigration {
+        version: 5,
+        name: "create_api_endpoints",
+        sql: "CREATE TABLE IF NOT EXISTS api_endpoints (
+            id BIGSERIAL PRIMARY KEY,
+            parent_id BIGINT,
+            name TEXT NOT NULL,
+            method TEXT NOT NULL,
+            url TEXT NOT NULL,
+            headers TEXT,
+            params TEXT,
+            body_type TEXT,
+            body TEXT,
+            content_type TEXT,
+            order_index INTEGER DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY(parent_id) REFERENCES api_endpoints(id) ON DELETE SET NULL DEFERRABLE
+        );",
+    },
+    PgMigration {
+        version: 6,
+        name: "create_ssh_groups_and_servers",
+        sql: "CREATE TABLE IF NOT EXISTS ssh_groups (
+            id BIGSERIAL PRIMARY KEY,
+            name TEXT NOT NULL,
+            is_default INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE TABLE IF NOT EXISTS ssh_servers (
+            id BIGSERIAL PRIMARY KEY,
+            alias TEXT NOT NULL,
+            hostname TEXT NOT NULL,
+            port INTEGER NOT NULL DEFAULT 22,
+            username TEXT NOT NULL,
+            password TEXT
+        );
+        INSERT INTO ssh_groups (name, is_default)
+            SELECT '默认分组', 1 WHERE NOT EXISTS (SELECT 1 FROM ssh_groups WHERE is_default = 1);",
+    },
+    PgMigration {
+        version: 7,
+        name: "ssh_servers_add_group_and_remark",
+        sql: "ALTER TABLE ssh_servers ADD COLUMN IF NOT EXISTS group_id BIGINT;
+        ALTER TABLE ssh_servers ADD COLUMN IF NOT EXISTS remark TEXT;",
+    },
+    PgMigration {
+        version: 8,
+        name: "create_rbac",
+        sql: concat!(
+            "CREATE TABLE IF NOT EXISTS roles (
+                id BIGSERIAL PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                description TEXT
+            );
+            CREATE TABLE IF NOT EXISTS role_permissions (
+                role_id BIGINT NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+                permission TEXT NOT NULL,
+                PRIMARY KEY (role_id, permission)
+            );
+            CREATE TABLE IF NOT EXISTS user_roles (
+                user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                role_id BIGINT NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+                PRIMARY KEY (user_id, role_id)
+            );",
+            rbac_seed_sql!(),
+            assign_default_roles_sql!()
+        ),
+    },
+    PgMigration {
+        version: 9,
+        name: "users_add_account_state",
+        sql: concat!(
+            "ALTER TABLE users ADD COLUMN IF NOT EXISTS disabled INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE users ADD COLUMN IF NOT EXISTS must_change_password INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE users ADD COLUMN IF NOT EXISTS password_changed_at TEXT;",
+            flag_default_users_sql!()
+        ),
+    },
+    PgMigration {
+        version: 10,
+        name: "create_refresh_tokens",
+        sql: "CREATE TABLE IF NOT EXISTS refresh_tokens (
+            jti TEXT PRIMARY KEY,
+            family_id TEXT NOT NULL,
+            user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            issued_at TEXT NOT NULL,
+            expires_at BIGINT NOT NULL,
+            used_at TEXT,
+            revoked_at TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_refresh_tokens_family ON refresh_tokens(family_id);
+        CREATE INDEX IF NOT EXISTS idx_refresh_tokens_user ON refresh_tokens(user_id);",
+    },
+    PgMigration {
+        version: 11,
+        name: "create_login_protection",
+        sql: "CREATE TABLE IF NOT EXISTS login_lockouts (
+            username TEXT PRIMARY KEY,
+            failures INTEGER NOT NULL,
+            window_start BIGINT NOT NULL,
+            locked_until BIGINT
+        );
+        CREATE TABLE IF NOT EXISTS login_failures (
+            id BIGSERIAL PRIMARY KEY,
+            username TEXT NOT NULL,
+            ip TEXT,
+            reason TEXT NOT NULL,
+            created_at BIGINT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_login_failures_created ON login_failures(created_at);
+        CREATE INDEX IF NOT EXISTS idx_login_failures_username ON login_failures(username);",
+    },
+    PgMigration {
+        version: 12,
+        name: "create_user_totp",
+        sql: "CREATE TABLE IF NOT EXISTS user_totp (
+            user_id BIGINT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+            secret TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 0,
+            last_used_step BIGINT,
+            created_at TEXT NOT NULL,
+            enabled_at TEXT
+        );
+        CREATE TABLE IF NOT EXISTS user_recovery_codes (
+            id BIGSERIAL PRIMARY KEY,
+            user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            code_hash TEXT NOT NULL,
+            used_at TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_user_recovery_codes_user ON user_recovery_codes(user_id);",
+    },
+    PgMigration {
+        version: 13,
+        name: "create_oidc_identities",
+        sql: "CREATE TABLE IF NOT EXISTS oidc_identities (
+            provider TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            email TEXT,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (provider, subject)
+        );
+        CREATE INDEX IF NOT EXISTS idx_oidc_identities_user ON oidc_identities(user_id);",
+    },
+    PgMigration {
+        version: 14,
+        name: "create_personal_access_tokens",
+        sql: "CREATE TABLE IF NOT EXISTS personal_access_tokens (
+            id BIGSERIAL PRIMARY KEY,
+            user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            token_hash TEXT NOT NULL UNIQUE,
+            prefix TEXT NOT NULL,
+            scopes TEXT NOT NULL,
+            created_at BIGINT NOT NULL,
+            expires_at BIGINT NOT NULL,
+            last_used_at BIGINT,
+            revoked_at BIGINT
+        );
+        CREATE INDEX IF NOT EXISTS idx_personal_access_tokens_user ON personal_access_tokens(user_id);",
+    },
+    PgMigration {
+        version: 15,
+        name: "create_audit_log",
+        sql: "CREATE TABLE IF NOT EXISTS audit_log (
+            id BIGSERIAL PRIMARY KEY,
+            created_at BIGINT NOT NULL,
+            username TEXT NOT NULL,
+            ip TEXT,
+            action TEXT NOT NULL,
+            target TEXT NOT NULL,
+            outcome TEXT NOT NULL,
+            duration_ms BIGINT NOT NULL,
+            detail TEXT,
+            error TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_audit_log_created ON audit_log(created_at);
+        CREATE INDEX IF NOT EXISTS idx_audit_log_username ON audit_log(username);
+        CREATE INDEX IF NOT EXISTS idx_audit_log_action ON audit_log(action);
+        CREATE OR REPLACE FUNCTION audit_log_append_only() RETURNS trigger AS $$
+        BEGIN
+            RAISE EXCEPTION 'audit_log is append-only';
+        END;
+        $$ LANGUAGE plpgsql;
+        DROP TRIGGER IF EXISTS audit_log_append_only ON audit_log;
+        CREATE TRIGGER audit_log_append_only BEFORE UPDATE OR DELETE ON audit_log
+            FOR EACH ROW EXECUTE FUNCTION audit_log_append_only();
+        DROP TRIGGER IF EXISTS audit_log_no_truncate ON audit_log;
+        CREATE TRIGGER audit_log_no_truncate BEFORE TRUNCATE ON audit_log
+            FOR EACH STATEMENT EXECUTE FUNCTION audit_log_append_only();",
+    },
+];
+
+/// 多实例同时启动时用于串行化迁移的 advisory lock 键
+const PG_MIGRATION_LOCK: i64 = 0x0072_7374_736d_6967;
+
+async fn pg_current_version(conn: &mut PgConnection) -> Result<i64, sqlx::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version BIGINT PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+    )
+    .await?;
+    let row = sqlx::query("SELECT COALESCE(MAX(version), 0)::BIGINT FROM schema_version")
+        .fetch_one(&mut *conn)
+        .await?;
+    row.try_get(0)
+}
+
+async fn pg_apply_pending(conn: &mut PgConnection) -> Result<Vec<i64>, sqlx::Error> {
+    let current = pg_current_version(conn).await?;
+    let latest = PG_MIGRATIONS.last().map(|m| m.version).unwrap_or(0);
+    if current > latest {
+        log_warn!(
+            "数据库 schema 版本 {} 高于程序已知的最新版本 {}，可能由更新的版本创建",
+            current,
+            latest
+        );
+    }
+
+    let mut applied = Vec::new();
+    for migration in PG_MIGRATIONS.iter().filter(|m| m.version > current) {
+        let mut tx = conn.begin().await?;
+        tx.execute(migration.sql).await?;
+        sqlx::query("INSERT INTO schema_version (version, name, applied_at) VALUES ($1, $2, $3)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        log_info!("已应用数据库迁移 {} {}", migration.version, migration.name);
+        applied.push(migration.version);
+    }
+    Ok(applied)
+}
+
+// PostgreSQL：执行所有未应用的迁移（持有 advisory lock，避免多个实例并发迁移）
+pub async fn run_pg_migrations(pool: &PgPool) -> Result<Vec<i64>, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(PG_MIGRATION_LOCK)
+        .execute(&mut *conn)
+        .await?;
+    let result = pg_apply_pending(&mut conn).await;
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(PG_MIGRATION_LOCK)
+        .execute(&mut *conn)
+        .await?;
+    result
+}
+
+// PostgreSQL：当前版本与各迁移的应用状态
+pub async fn pg_migration_status(
+    pool: &PgPool,
+) -> Result<(i64, Vec<MigrationStatus>), sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+    let current = pg_current_version(&mut conn).await?;
+    let rows = sqlx::query("SELECT version, name, applied_at FROM schema_version ORDER BY version")
+        .fetch_all(&mut *conn)
+        .await?;
+    let recorded = rows
+        .iter()
+        .map(|row| Ok((row.try_get(0)?, row.try_get(1)?, row.try_get(2)?)))
+        .collect::<Result<Vec<_>, sqlx::Error>>()?;
+    Ok((current, build_status(recorded)))
+}
+
+// 查询迁移状态
+#[utoipa::path(
+    get,
+    path = "/api/system/migrations",
+    tag = "system",
+    summary = "数据库迁移状态",
+    responses((status = 200, body = Response))
+)]
+pub async fn get_migration_status(db: web::Data<Arc<Database>>) -> ApiResult {
+    let (current, migrations) = db.migration_status().await?;
+    let latest = MIGRATIONS.last().map(|m| m.version).unwrap_or(0);
+    let pending = migrations.iter().filter(|m| !m.applied).count();
+    Ok(Response::ok(json!({
+        "current_version": current,
+        "latest_version": latest,
+        "pending": pending,
+        "migrations": migrations,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations_are_ordered_and_idempotent() {
+        assert!(MIGRATIONS.windows(2).all(|w| w[0].version < w[1].version));
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        let applied = run_migrations(&mut conn).unwrap();
+        assert_eq!(applied.len(), MIGRATIONS.len());
+        assert!(run_migrations(&mut conn).unwrap().is_empty());
+        assert_eq!(
+            current_version(&conn).unwrap(),
+            MIGRATIONS.last().unwrap().version
+        );
+        assert!(migration_status(&conn).unwrap().iter().all(|m| m.applied));
+    }
+
+    #[test]
+    fn pg_migrations_match_sqlite() {
+        let sqlite: Vec<_> = MIGRATIONS.iter().map(|m| (m.version, m.name)).collect();
+        let pg: Vec<_> = PG_MIGRATIONS.iter().map(|m| (m.version, m.name)).collect();
+        assert_eq!(sqlite, pg);
+    }
+
+    #[test]
+    fn adopts_legacy_schema() {
+        // 迁移子系统之前由各模块建表的旧库：已有 terminal_setting_config，ssh_servers 只有部分列
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY AUTOINCREMENT, username TEXT NOT NULL UNIQUE,
+                password TEXT NOT NULL, email TEXT, theme_config TEXT, terminal_setting_config TEXT);
+             CREATE TABLE ssh_servers (id INTEGER PRIMARY KEY AUTOINCREMENT, alias TEXT NOT NULL,
+                hostname TEXT NOT NULL, port INTEGER NOT NULL DEFAULT 22, username TEXT NOT NULL,
+                password TEXT, group_id INTEGER);",
+        )
+        .unwrap();
+        run_migrations(&mut conn).unwrap();
+        let remark: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM pragma_table_info('ssh_servers') WHERE name = 'remark')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(remark);
+        let groups: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM ssh_groups WHERE is_default=1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(groups, 1);
+    }
+}
@@ -1,22 +1,40 @@
 // 导出web模块中的组件
+pub mod access_tokens_api;
 pub mod actors;
 pub mod apitest_api;
+pub mod audit;
+pub mod audit_api;
 pub mod auth_middleware;
 pub mod auth_tests;
 pub mod auth_utils;
 pub mod chat_api;
 pub mod database;
 pub mod database_models;
+pub mod login_guard;
 pub mod login_handler;
 pub mod main_web;
+pub mod migrations;
 pub mod models;
+pub mod oidc;
+pub mod oidc_api;
+pub mod openapi;
+pub mod rbac;
+pub mod repository;
+pub mod response;
 pub mod sftp_api;
 pub mod sobel_ws;
 pub mod sqlite_api;
+pub mod sqlite_maintenance;
+pub mod sqlite_registry;
 pub mod ssh_servers_api;
 pub mod ssh_websocket;
 pub mod ssh_websocket_pty;
 pub mod ssh_monitor_api;
 pub mod template_engine;
+pub mod tls;
+pub mod totp;
+pub mod totp_api;
+pub mod users_api;
 pub mod websocket;
+pub mod ws_auth;
 pub use actors::*;
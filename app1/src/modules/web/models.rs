@@ -3,9 +3,10 @@ use crate::modules::ssh::ssh2_pty_client::Ssh2PtyClient;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use utoipa::{IntoParams, ToSchema};
 
 // API 测试模块：端点列表简要信息
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct ApiEndpointBrief {
     /// 端点唯一ID
     pub id: i64,
@@ -22,7 +23,7 @@ pub struct ApiEndpointBrief {
 }
 
 // API 测试模块：端点详细信息
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct ApiEndpointDetail {
     /// 端点唯一ID
     pub id: i64,
@@ -53,7 +54,7 @@ pub struct ApiEndpointDetail {
 }
 
 // API 测试模块：创建端点请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct CreateEndpointPayload {
     /// 父节点ID（可为空）
     pub parent_id: Option<i64>,
@@ -78,7 +79,7 @@ pub struct CreateEndpointPayload {
 }
 
 // API 测试模块：更新端点请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct UpdateEndpointPayload {
     /// 父节点ID（可为空）
     pub parent_id: Option<i64>,
@@ -102,26 +103,142 @@ pub struct UpdateEndpointPayload {
     pub order_index: Option<i32>,
 }
 
+// 数据库迁移：迁移版本及应用状态
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct MigrationStatus {
+    /// 版本号
+    pub version: i64,
+    /// 迁移名称
+    pub name: String,
+    /// 是否已应用
+    pub applied: bool,
+    /// 应用时间（RFC3339）
+    pub applied_at: Option<String>,
+    /// 是否为程序内定义的迁移（false 表示数据库由更新版本的程序迁移过）
+    pub known: bool,
+}
+
 // SQLite 模块：数据库文件信息
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct DatabaseInfo {
     /// 数据库文件名（含扩展名）
     pub name: String,
-    /// 文件系统路径
+    /// 文件系统路径（规范化后的绝对路径）
     pub path: String,
+    /// 来源：root（根目录）| config（配置登记）| registered（API 登记）
+    pub source: String,
+    /// 文件大小（字节）
+    pub size: u64,
+}
+
+// SQLite 模块：新建数据库请求体
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct CreateDatabasePayload {
+    /// 数据库文件名（扩展名为 db/sqlite/sqlite3）
+    pub db_name: String,
+}
+
+// SQLite 模块：登记已有数据库请求体
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct RegisterDatabasePayload {
+    /// 登记名称（扩展名为 db/sqlite/sqlite3）
+    pub db_name: String,
+    /// 服务器上的数据库文件路径
+    pub path: String,
+}
+
+// SQLite 模块：取消登记请求体
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct UnregisterDatabasePayload {
+    /// 登记名称
+    pub db_name: String,
+}
+
+// SQLite 模块：上传数据库查询参数（请求体为数据库文件内容）
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UploadDatabaseParams {
+    /// 保存的文件名
+    pub db_name: String,
+    /// 是否覆盖同名文件（可选，默认否）
+    pub overwrite: Option<bool>,
 }
 
 // SQLite 模块：表信息
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct TableInfo {
     /// 表名
     pub name: String,
     /// 列信息列表
     pub columns: Vec<ColumnInfo>,
+    /// 外键列表
+    pub foreign_keys: Vec<ForeignKeyInfo>,
+    /// 索引列表
+    pub indexes: Vec<IndexInfo>,
+}
+
+// SQLite 模块：外键信息（复合外键合并为一条）
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ForeignKeyInfo {
+    /// 外键序号
+    pub id: i64,
+    /// 本表列
+    pub columns: Vec<String>,
+    /// 引用的表
+    pub ref_table: String,
+    /// 引用的列（为空表示引用主键）
+    pub ref_columns: Vec<String>,
+    /// ON UPDATE 动作
+    pub on_update: String,
+    /// ON DELETE 动作
+    pub on_delete: String,
+}
+
+// SQLite 模块：索引信息
+#[derive(Serialize, Debug, ToSchema)]
+pub struct IndexInfo {
+    /// 索引名
+    pub name: String,
+    /// 所属表
+    pub table_name: String,
+    /// 是否唯一索引
+    pub unique: bool,
+    /// 来源：c（CREATE INDEX）| u（UNIQUE 约束）| pk（主键）
+    pub origin: String,
+    /// 索引列（表达式列为 "<expr>"）
+    pub columns: Vec<String>,
+    /// 是否部分索引
+    pub partial: bool,
+    /// 部分索引的 WHERE 条件
+    pub predicate: Option<String>,
+    /// 建索引语句（约束自动创建的索引为空）
+    pub sql: Option<String>,
+}
+
+// SQLite 模块：视图信息
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ViewInfo {
+    /// 视图名
+    pub name: String,
+    /// 视图列
+    pub columns: Vec<String>,
+    /// 建视图语句
+    pub sql: String,
+}
+
+// SQLite 模块：触发器信息
+#[derive(Serialize, Debug, ToSchema)]
+pub struct TriggerInfo {
+    /// 触发器名
+    pub name: String,
+    /// 所属表或视图
+    pub table_name: String,
+    /// 建触发器语句
+    pub sql: String,
 }
 
 // SQLite 模块：列信息
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct ColumnInfo {
     /// 列名
     pub name: String,
@@ -134,7 +251,8 @@ pub struct ColumnInfo {
 }
 
 // SQLite 模块：分页查询参数
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct QueryParams {
     /// 数据库名
     pub db_name: String,
@@ -147,7 +265,7 @@ pub struct QueryParams {
 }
 
 // SQLite 模块：分页查询结果
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct PaginationResult {
     /// 数据记录列表（JSON）
     pub data: Vec<serde_json::Value>,
@@ -162,7 +280,7 @@ pub struct PaginationResult {
 }
 
 // SQLite 模块：创建表列定义
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct CreateTableColumn {
     /// 列名
     pub name: String,
@@ -175,7 +293,7 @@ pub struct CreateTableColumn {
 }
 
 // SQLite 模块：创建表请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct CreateTablePayload {
     /// 数据库名
     pub db_name: String,
@@ -186,7 +304,7 @@ pub struct CreateTablePayload {
 }
 
 // SQLite 模块：删除表请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct DropTablePayload {
     /// 数据库名
     pub db_name: String,
@@ -195,7 +313,7 @@ pub struct DropTablePayload {
 }
 
 // SQLite 模块：重命名表请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct RenameTablePayload {
     /// 数据库名
     pub db_name: String,
@@ -206,7 +324,7 @@ pub struct RenameTablePayload {
 }
 
 // SQLite 模块：重命名列请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct RenameColumnPayload {
     /// 数据库名
     pub db_name: String,
@@ -219,7 +337,7 @@ pub struct RenameColumnPayload {
 }
 
 // SQLite 模块：新增列请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct AddColumnPayload {
     /// 数据库名
     pub db_name: String,
@@ -236,7 +354,7 @@ pub struct AddColumnPayload {
 }
 
 // SQLite 模块：删除列请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct DropColumnPayload {
     /// 数据库名
     pub db_name: String,
@@ -247,18 +365,19 @@ pub struct DropColumnPayload {
 }
 
 // SQLite 模块：插入行请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct RowInsertPayload {
     /// 数据库名
     pub db_name: String,
     /// 表名
     pub table_name: String,
     /// 插入的键值对
+    #[schema(value_type = Object)]
     pub values: serde_json::Map<String, serde_json::Value>,
 }
 
 // SQLite 模块：更新行请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct RowUpdatePayload {
     /// 数据库名
     pub db_name: String,
@@ -269,11 +388,12 @@ pub struct RowUpdatePayload {
     /// 主键值
     pub pk_value: serde_json::Value,
     /// 更新的键值对
+    #[schema(value_type = Object)]
     pub values: serde_json::Map<String, serde_json::Value>,
 }
 
 // SQLite 模块：删除单行请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct RowDeletePayload {
     /// 数据库名
     pub db_name: String,
@@ -286,7 +406,7 @@ pub struct RowDeletePayload {
 }
 
 // SQLite 模块：批量删除行请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct RowBatchDeletePayload {
     /// 数据库名
     pub db_name: String,
@@ -299,18 +419,218 @@ pub struct RowBatchDeletePayload {
 }
 
 // SQLite 模块：SQL 控制台请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct SqlQueryPayload {
     /// 数据库名
     pub db_name: String,
-    /// SQL 语句
+    /// SQL 语句，可包含以分号分隔的多条语句
     pub sql: String,
-    /// 可选参数列表
+    /// 可选参数：数组按位置绑定（多条语句依次使用），对象按名称绑定（键可省略 :/@/$ 前缀）
+    #[schema(value_type = Option<serde_json::Value>)]
+    pub params: Option<SqlParams>,
+    /// 只返回执行计划（EXPLAIN QUERY PLAN），不执行语句（可选）
+    pub explain: Option<bool>,
+}
+
+// SQLite 模块：SQL 参数
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum SqlParams {
+    /// 位置参数
+    Positional(Vec<serde_json::Value>),
+    /// 命名参数
+    Named(serde_json::Map<String, serde_json::Value>),
+}
+
+// SQLite 模块：结果列
+#[derive(Serialize, Debug, ToSchema)]
+pub struct SqlColumnInfo {
+    /// 列名
+    pub name: String,
+    /// 声明类型（表达式列为空）
+    pub decl_type: Option<String>,
+}
+
+// SQLite 模块：执行计划节点
+#[derive(Serialize, Debug, ToSchema)]
+pub struct QueryPlanNode {
+    /// 节点ID
+    pub id: i64,
+    /// 计划描述
+    pub detail: String,
+    /// 子节点
+    pub children: Vec<QueryPlanNode>,
+}
+
+// SQLite 模块：单条语句的执行结果
+#[derive(Serialize, Debug, ToSchema)]
+pub struct SqlStatementResult {
+    /// 语句文本
+    pub sql: String,
+    /// 结果列（含声明类型）
+    pub columns: Vec<SqlColumnInfo>,
+    /// 查询结果（查询语句）
+    pub data: Option<Vec<serde_json::Value>>,
+    /// 影响行数（非查询语句）
+    pub changed: Option<usize>,
+    /// 执行计划（explain 模式）
+    pub plan: Option<Vec<QueryPlanNode>>,
+}
+
+// SQLite 模块：创建索引请求体
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct CreateIndexPayload {
+    /// 数据库名
+    pub db_name: String,
+    /// 表名
+    pub table_name: String,
+    /// 索引名
+    pub index_name: String,
+    /// 索引列
+    pub columns: Vec<String>,
+    /// 是否唯一（可选）
+    pub unique: Option<bool>,
+    /// 部分索引条件（可选，不含 WHERE 关键字）
+    pub predicate: Option<String>,
+}
+
+// SQLite 模块：删除索引请求体
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct DropIndexPayload {
+    /// 数据库名
+    pub db_name: String,
+    /// 索引名
+    pub index_name: String,
+}
+
+// SQLite 模块：创建视图请求体
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct CreateViewPayload {
+    /// 数据库名
+    pub db_name: String,
+    /// 视图名
+    pub view_name: String,
+    /// SELECT 语句
+    pub select_sql: String,
+}
+
+// SQLite 模块：删除视图请求体
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct DropViewPayload {
+    /// 数据库名
+    pub db_name: String,
+    /// 视图名
+    pub view_name: String,
+}
+
+// SQLite 模块：创建触发器请求体
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct CreateTriggerPayload {
+    /// 数据库名
+    pub db_name: String,
+    /// 触发器名
+    pub trigger_name: String,
+    /// 表名（INSTEAD OF 时为视图名）
+    pub table_name: String,
+    /// BEFORE | AFTER | INSTEAD OF
+    pub timing: String,
+    /// INSERT | UPDATE | DELETE
+    pub event: String,
+    /// UPDATE OF 的列（可选）
+    pub update_columns: Option<Vec<String>>,
+    /// WHEN 条件（可选）
+    pub when: Option<String>,
+    /// BEGIN 与 END 之间的语句
+    pub body: String,
+}
+
+// SQLite 模块：导入请求体
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct SqliteImportPayload {
+    /// 数据库名
+    pub db_name: String,
+    /// 目标表（不存在时按数据推断类型新建）
+    pub table_name: String,
+    /// 数据格式：csv | json
+    pub format: crate::modules::demo::csv_to_sqlite::DataFormat,
+    /// 文件内容（CSV 文本或 JSON 对象数组）
+    pub content: String,
+    /// CSV 首行是否为表头（可选，默认是）
+    pub header: Option<bool>,
+    /// CSV 分隔符（可选，默认逗号）
+    pub delimiter: Option<String>,
+    /// 导入前清空目标表（可选）
+    pub truncate: Option<bool>,
+    /// 源列名到表列名的映射（可选，映射为空字符串表示跳过）
+    pub column_map: Option<std::collections::HashMap<String, String>>,
+}
+
+// SQLite 模块：导出请求体，table_name 与 sql 二选一
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct SqliteExportPayload {
+    /// 数据库名
+    pub db_name: String,
+    /// 导出整表
+    pub table_name: Option<String>,
+    /// 导出只读查询结果
+    pub sql: Option<String>,
+    /// 查询参数（可选）
     pub params: Option<Vec<serde_json::Value>>,
+    /// 数据格式：csv | json
+    pub format: crate::modules::demo::csv_to_sqlite::DataFormat,
+    /// CSV 分隔符（可选，默认逗号）
+    pub delimiter: Option<String>,
+}
+
+// SQLite 模块：SQL 脚本导出查询参数
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SqliteDumpParams {
+    /// 数据库名
+    pub db_name: String,
+}
+
+// SQLite 模块：执行 SQL 脚本恢复请求体
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct SqliteRestorePayload {
+    /// 数据库名
+    pub db_name: String,
+    /// SQL 脚本（如导出的 dump）
+    pub script: String,
+}
+
+// SQLite 模块：维护任务请求体，如 {"db_name":"a.db","operation":"journal_mode","mode":"wal"}
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct MaintenancePayload {
+    /// 数据库名
+    pub db_name: String,
+    /// 维护操作
+    #[serde(flatten)]
+    pub op: crate::modules::web::sqlite_maintenance::MaintenanceOp,
+}
+
+// SQLite 模块：维护任务查询参数
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MaintenanceJobQuery {
+    /// 任务ID（可选，省略则返回任务列表）
+    pub job_id: Option<String>,
+    /// 按数据库过滤（可选）
+    pub db_name: Option<String>,
+}
+
+// SQLite 模块：删除触发器请求体
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct DropTriggerPayload {
+    /// 数据库名
+    pub db_name: String,
+    /// 触发器名
+    pub trigger_name: String,
 }
 
 // SFTP 模块：查询路径请求参数
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PathQuery {
     /// 会话ID
     pub session_id: usize,
@@ -319,7 +639,7 @@ pub struct PathQuery {
 }
 
 // SFTP 模块：创建会话请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct CreateSessionPayload {
     /// 主机名
     pub hostname: String,
@@ -332,7 +652,7 @@ pub struct CreateSessionPayload {
 }
 
 // SFTP 模块：删除文件请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct DeletePayload {
     /// 会话ID
     pub session_id: usize,
@@ -341,7 +661,7 @@ pub struct DeletePayload {
 }
 
 // SFTP 模块：重命名请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct RenamePayload {
     /// 会话ID
     pub session_id: usize,
@@ -352,7 +672,7 @@ pub struct RenamePayload {
 }
 
 // SFTP 模块：写文件请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct WriteFilePayload {
     /// 会话ID
     pub session_id: usize,
@@ -363,7 +683,7 @@ pub struct WriteFilePayload {
 }
 
 // SFTP 模块：上传文件请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct UploadPayload {
     /// 会话ID
     pub session_id: usize,
@@ -376,7 +696,7 @@ pub struct UploadPayload {
 }
 
 // SFTP 模块：创建目录请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct MkdirPayload {
     /// 会话ID
     pub session_id: usize,
@@ -385,7 +705,7 @@ pub struct MkdirPayload {
 }
 
 // SFTP 模块：修改权限请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct ChmodPayload {
     /// 会话ID
     pub session_id: usize,
@@ -395,19 +715,19 @@ pub struct ChmodPayload {
     pub mode: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct SshGroup {
     pub id: i64,
     pub name: String,
     pub is_default: i32,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, ToSchema)]
 pub struct SshGroupInput {
     pub name: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct SshServer {
     pub id: i64,
     pub alias: String,
@@ -419,7 +739,7 @@ pub struct SshServer {
     pub remark: Option<String>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, ToSchema)]
 pub struct SshServerInput {
     pub alias: Option<String>,
     pub hostname: String,
@@ -430,8 +750,280 @@ pub struct SshServerInput {
     pub remark: Option<String>,
 }
 
+// 权限：角色及其权限
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct Role {
+    /// 角色名（与前端路由 roles 一致，如 R_SUPER）
+    pub name: String,
+    /// 描述
+    pub description: Option<String>,
+    /// 权限码列表
+    pub permissions: Vec<String>,
+}
+
+// 权限：新建/更新角色请求体
+#[derive(Deserialize, Debug, Clone, ToSchema)]
+pub struct RoleInput {
+    /// 角色名
+    pub name: String,
+    /// 描述
+    #[serde(default)]
+    pub description: Option<String>,
+    /// 权限码列表（整体替换）
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+// 权限：设置用户角色请求体
+#[derive(Deserialize, Debug, Clone, ToSchema)]
+pub struct UserRolesInput {
+    /// 角色名列表（整体替换）
+    pub roles: Vec<String>,
+}
+
+// 用户管理：账号信息（不含密码）
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct UserAccount {
+    /// 用户ID
+    pub id: i64,
+    /// 用户名
+    pub username: String,
+    /// 邮箱
+    pub email: Option<String>,
+    /// 是否已停用
+    pub disabled: bool,
+    /// 下次登录后必须修改密码
+    pub must_change_password: bool,
+    /// 最近一次修改密码的时间（RFC3339）
+    pub password_changed_at: Option<String>,
+    /// 角色名列表
+    pub roles: Vec<String>,
+}
+
+// 用户管理：账号状态（登录与权限校验使用）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountState {
+    /// 是否已停用
+    pub disabled: bool,
+    /// 必须先修改密码
+    pub must_change_password: bool,
+}
+
+// 用户管理：新建用户请求体
+#[derive(Deserialize, Debug, Clone, ToSchema)]
+pub struct UserCreateInput {
+    /// 用户名
+    pub username: String,
+    /// 初始密码（需满足密码策略）
+    pub password: String,
+    /// 邮箱
+    #[serde(default)]
+    pub email: Option<String>,
+    /// 角色名列表，缺省为 R_USER
+    #[serde(default)]
+    pub roles: Option<Vec<String>>,
+    /// 首次登录后必须修改密码，缺省为 true
+    #[serde(default)]
+    pub must_change_password: Option<bool>,
+}
+
+// 用户管理：修改用户请求体（未提供的字段保持不变）
+#[derive(Deserialize, Debug, Clone, Default, ToSchema)]
+pub struct UserUpdateInput {
+    /// 邮箱
+    #[serde(default)]
+    pub email: Option<String>,
+    /// 停用或启用账号
+    #[serde(default)]
+    pub disabled: Option<bool>,
+}
+
+// 用户管理：管理员重置密码请求体
+#[derive(Deserialize, Debug, Clone, ToSchema)]
+pub struct PasswordResetInput {
+    /// 新密码
+    pub password: String,
+    /// 下次登录后必须修改密码，缺省为 true
+    #[serde(default)]
+    pub must_change_password: Option<bool>,
+}
+
+// 用户：修改自己的密码请求体
+#[derive(Deserialize, Debug, Clone, ToSchema)]
+pub struct PasswordChangeInput {
+    /// 当前密码
+    pub old_password: String,
+    /// 新密码
+    pub new_password: String,
+}
+
+// 登录保护：登录失败记录
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct LoginFailure {
+    /// 记录ID
+    pub id: i64,
+    /// 登录时输入的用户名（不一定存在）
+    pub username: String,
+    /// 客户端地址
+    pub ip: Option<String>,
+    /// 失败原因：bad_credentials | bad_totp | locked | disabled
+    pub reason: String,
+    /// 发生时间（Unix 秒）
+    pub created_at: i64,
+}
+
+// 登录保护：失败记录查询参数
+#[derive(Deserialize, Debug, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LoginFailureQuery {
+    /// 只看某个用户名
+    #[serde(default)]
+    pub username: Option<String>,
+    /// 返回条数，缺省 100，最多 1000
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+// 两步验证：当前用户的绑定状态
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct TotpStatus {
+    /// 是否已启用（登录时要求验证码）
+    pub enabled: bool,
+    /// 是否有待确认的密钥
+    pub pending: bool,
+    /// 剩余可用的恢复码数量
+    pub recovery_codes_left: i64,
+}
+
+// 两步验证：生成密钥请求体
+#[derive(Deserialize, Debug, Clone, ToSchema)]
+pub struct TotpSetupInput {
+    /// 当前密码
+    pub password: String,
+}
+
+// 两步验证：生成密钥响应
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct TotpSetup {
+    /// base32 密钥（无法扫码时手动输入）
+    pub secret: String,
+    /// otpauth:// 地址，前端渲染为二维码
+    pub otpauth_uri: String,
+}
+
+// 两步验证：提交验证码
+#[derive(Deserialize, Debug, Clone, ToSchema)]
+pub struct TotpCodeInput {
+    /// 验证器生成的 6 位验证码
+    pub code: String,
+}
+
+// 两步验证：停用请求体
+#[derive(Deserialize, Debug, Clone, ToSchema)]
+pub struct TotpDisableInput {
+    /// 当前密码
+    pub password: String,
+    /// 验证码或恢复码
+    pub code: String,
+}
+
+// 个人访问令牌：列表项（不含令牌本身）
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct AccessTokenInfo {
+    pub id: i64,
+    /// 用途说明
+    pub name: String,
+    /// 令牌开头几位，便于辨认
+    pub prefix: String,
+    /// 授予的权限码（不超过创建者自身的权限）
+    pub scopes: Vec<String>,
+    /// 创建时间（Unix 秒）
+    pub created_at: i64,
+    /// 过期时间（Unix 秒）
+    pub expires_at: i64,
+    /// 最近一次使用时间（Unix 秒，按分钟更新）
+    pub last_used_at: Option<i64>,
+}
+
+// 个人访问令牌：创建请求体
+#[derive(Deserialize, Debug, Clone, ToSchema)]
+pub struct AccessTokenCreateInput {
+    /// 用途说明
+    pub name: String,
+    /// 授予的权限码
+    pub scopes: Vec<String>,
+    /// 有效期（天），缺省使用配置的 default_days
+    #[serde(default)]
+    pub expires_in_days: Option<u32>,
+}
+
+// 个人访问令牌：创建响应，token 只返回这一次
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct AccessTokenCreated {
+    pub token: String,
+    #[serde(flatten)]
+    pub info: AccessTokenInfo,
+}
+
+// 审计日志：一条记录
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// 记录时间（Unix 秒）
+    pub created_at: i64,
+    /// 操作人（登录事件为登录时输入的用户名）
+    pub username: String,
+    /// 客户端地址
+    pub ip: Option<String>,
+    /// 操作类型，如 sql.execute、sftp.delete、login
+    pub action: String,
+    /// 操作对象：连接、主机、文件路径等
+    pub target: String,
+    /// 结果：success | failure
+    pub outcome: String,
+    /// 耗时（毫秒）
+    pub duration_ms: i64,
+    /// 补充信息，如执行的 SQL
+    pub detail: Option<String>,
+    /// 失败原因
+    pub error: Option<String>,
+}
+
+// 审计日志：查询条件（均可省略）
+#[derive(Deserialize, Debug, Clone, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    /// 操作人
+    #[serde(default)]
+    pub username: Option<String>,
+    /// 操作类型；sftp 同时匹配 sftp.delete 等子类型
+    #[serde(default)]
+    pub action: Option<String>,
+    /// 结果：success | failure
+    #[serde(default)]
+    pub outcome: Option<String>,
+    /// 客户端地址
+    #[serde(default)]
+    pub ip: Option<String>,
+    /// 操作对象包含的文字
+    #[serde(default)]
+    pub target: Option<String>,
+    /// 起始时间（Unix 秒，含）
+    #[serde(default)]
+    pub from: Option<i64>,
+    /// 截止时间（Unix 秒，不含）
+    #[serde(default)]
+    pub to: Option<i64>,
+    /// 返回条数，缺省 100，最多 1000（导出时忽略）
+    #[serde(default)]
+    pub limit: Option<i64>,
+    /// 跳过的条数（导出时忽略）
+    #[serde(default)]
+    pub offset: Option<i64>,
+}
+
 // 聊天上传媒体：请求体
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct UploadChatMediaPayload {
     /// 文件名
     pub filename: String,
@@ -442,7 +1034,7 @@ pub struct UploadChatMediaPayload {
 }
 
 // 登录：请求体
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct LoginRequest {
     /// 用户名
     pub username: String,
@@ -450,15 +1042,50 @@ pub struct LoginRequest {
     pub password: String,
 }
 
-// 登录：响应体
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Response {
-    /// 是否成功
+// 登录：两步验证请求体
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoginTotpRequest {
+    /// 第一步登录返回的临时令牌
+    #[serde(rename = "mfaToken")]
+    pub mfa_token: String,
+    /// 验证码或恢复码
+    pub code: String,
+}
+
+// 单点登录：IdP 回调参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OidcCallbackQuery {
+    /// 授权码
+    #[serde(default)]
+    pub code: Option<String>,
+    /// 发起登录时生成的 state
+    #[serde(default)]
+    pub state: Option<String>,
+    /// IdP 拒绝授权时的错误码
+    #[serde(default)]
+    pub error: Option<String>,
+    /// 错误说明
+    #[serde(default)]
+    pub error_description: Option<String>,
+}
+
+// 单点登录：用回调得到的一次性兑换码换取令牌
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct OidcExchangeRequest {
+    /// 一次性兑换码
+    pub code: String,
+}
+
+// 统一响应信封：所有 JSON 接口的成功与失败都使用此结构（构造见 response.rs）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Response<T = serde_json::Value> {
+    /// 业务码："0000" 表示成功，其余见 auth_utils.rs
     pub code: String,
     /// 提示信息
     pub msg: String,
     /// 返回数据
-    pub data: Option<serde_json::Value>,
+    pub data: Option<T>,
 }
 
 // 登录：用户信息
@@ -484,6 +1111,8 @@ pub struct Message(pub String);
 pub struct Connect {
     /// 会话地址（用于发送消息）
     pub addr: actix::Recipient<Message>,
+    /// 登录用户名（来自访问令牌）
+    pub username: String,
 }
 
 // WebSocket：会话断开
@@ -506,16 +1135,6 @@ pub struct ClientMessage {
     pub room: String,
 }
 
-// WebSocket：设置用户名
-#[derive(actix::Message)]
-#[rtype(result = "()")]
-pub struct SetUsername {
-    /// 会话ID
-    pub id: u64,
-    /// 用户名
-    pub username: String,
-}
-
 // WebSocket：私聊消息
 #[derive(actix::Message)]
 #[rtype(result = "()")]
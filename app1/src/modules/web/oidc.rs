@@ -0,0 +1,468 @@
+// OpenID Connect 单点登录：授权码 + PKCE 流程，按 JWKS 校验 ID Token，把声明映射为本地用户与角色
+// 登录中的 state 与一次性兑换码只保存在进程内，多实例部署时回调需要落到发起登录的实例上
+use data_encoding::BASE64URL_NOPAD;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{AlgorithmFamily, DecodingKey, Validation, decode, decode_header};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::modules::config::config::{OidcConfig, OidcProviderConfig};
+
+/// 从跳转到 IdP 到回调的最长时间
+const PENDING_TTL: Duration = Duration::from_secs(600);
+/// 回调后前端兑换令牌的最长时间
+const EXCHANGE_TTL: Duration = Duration::from_secs(60);
+/// 发现文档缓存时间
+const METADATA_TTL: Duration = Duration::from_secs(3600);
+/// 校验 exp/iat 时允许的时钟偏差（秒）
+const CLOCK_LEEWAY_SECS: u64 = 60;
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum OidcError {
+    #[error("Unknown OIDC provider: {0}")]
+    UnknownProvider(String),
+    #[error("Login state is missing or expired")]
+    InvalidState,
+    #[error("Request to identity provider failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Invalid provider metadata: {0}")]
+    Metadata(String),
+    #[error("Token request rejected: {0}")]
+    TokenRejected(String),
+    #[error("Invalid ID token: {0}")]
+    InvalidIdToken(String),
+    #[error("ID token is missing claim: {0}")]
+    MissingClaim(String),
+}
+
+/// 发现文档中用到的字段
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+struct PendingLogin {
+    provider: String,
+    nonce: String,
+    code_verifier: String,
+    created: Instant,
+}
+
+/// 通过校验的 IdP 身份
+#[derive(Debug, Clone, PartialEq)]
+pub struct OidcIdentity {
+    pub provider: String,
+    /// IdP 中的唯一标识（sub）
+    pub subject: String,
+    pub username: String,
+    pub email: Option<String>,
+    /// 映射后的 rsts 角色
+    pub roles: Vec<String>,
+}
+
+pub struct OidcClient {
+    config: OidcConfig,
+    http: reqwest::Client,
+    metadata: Mutex<HashMap<String, (Instant, ProviderMetadata)>>,
+    jwks: Mutex<HashMap<String, JwkSet>>,
+    pending: Mutex<HashMap<String, PendingLogin>>,
+    exchanges: Mutex<HashMap<String, (Instant, String)>>,
+}
+
+// 32 字节随机数的 base64url 编码，用于 state、nonce、code_verifier 与兑换码
+pub fn random_token() -> String {
+    let bytes: [u8; 32] = rand::random();
+    BASE64URL_NOPAD.encode(&bytes)
+}
+
+// PKCE S256：BASE64URL(SHA256(code_verifier))
+pub fn code_challenge(code_verifier: &str) -> String {
+    BASE64URL_NOPAD.encode(&Sha256::digest(code_verifier.as_bytes()))
+}
+
+fn same_issuer(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+// 按 role_mapping 把 IdP 的组映射为 rsts 角色，没有命中时使用 default_roles
+pub fn map_roles(provider: &OidcProviderConfig, groups: &[String]) -> Vec<String> {
+    let roles: BTreeSet<String> = groups
+        .iter()
+        .filter_map(|group| provider.role_mapping.get(group))
+        .flatten()
+        .cloned()
+        .collect();
+    if roles.is_empty() {
+        provider.default_roles.clone()
+    } else {
+        roles.into_iter().collect()
+    }
+}
+
+// 从已校验的 ID Token 声明中取出身份
+pub fn identity_from_claims(
+    provider: &OidcProviderConfig,
+    claims: &Map<String, Value>,
+) -> Result<OidcIdentity, OidcError> {
+    let string_claim = |name: &str| {
+        claims
+            .get(name)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    let subject = string_claim("sub").ok_or_else(|| OidcError::MissingClaim("sub".into()))?;
+    let username = string_claim(&provider.username_claim)
+        .ok_or_else(|| OidcError::MissingClaim(provider.username_claim.clone()))?;
+    // 组声明可能是数组，也可能是单个字符串
+    let groups: Vec<String> = match claims.get(&provider.roles_claim) {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        Some(Value::String(group)) => vec![group.clone()],
+        _ => Vec::new(),
+    };
+    Ok(OidcIdentity {
+        provider: provider.name.clone(),
+        subject,
+        username,
+        email: string_claim("email"),
+        roles: map_roles(provider, &groups),
+    })
+}
+
+impl OidcClient {
+    pub fn new(config: OidcConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            config,
+            http,
+            metadata: Mutex::new(HashMap::new()),
+            jwks: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+            exchanges: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn providers(&self) -> &[OidcProviderConfig] {
+        &self.config.providers
+    }
+
+    pub fn provider(&self, name: &str) -> Result<&OidcProviderConfig, OidcError> {
+        self.config
+            .providers
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| OidcError::UnknownProvider(name.to_string()))
+    }
+
+    pub fn frontend_redirect(&self) -> &str {
+        &self.config.frontend_redirect
+    }
+
+    async fn metadata(&self, provider: &OidcProviderConfig) -> Result<ProviderMetadata, OidcError> {
+        if let Some((fetched, metadata)) = self.metadata.lock().unwrap().get(&provider.name)
+            && fetched.elapsed() < METADATA_TTL
+        {
+            return Ok(metadata.clone());
+        }
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            provider.issuer.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = self
+            .http
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        // 发现文档中的 issuer 必须与配置一致，防止被替换为其他发行方
+        if !same_issuer(&metadata.issuer, &provider.issuer) {
+            return Err(OidcError::Metadata(format!(
+                "issuer mismatch: {}",
+                metadata.issuer
+            )));
+        }
+        self.metadata
+            .lock()
+            .unwrap()
+            .insert(provider.name.clone(), (Instant::now(), metadata.clone()));
+        Ok(metadata)
+    }
+
+    // 生成跳转到 IdP 授权页的地址
+    pub async fn authorization_url(&self, name: &str) -> Result<String, OidcError> {
+        let provider = self.provider(name)?;
+        let metadata = self.metadata(provider).await?;
+        let (state, nonce, code_verifier) = (random_token(), random_token(), random_token());
+        let scope = provider.scopes.join(" ");
+        let challenge = code_challenge(&code_verifier);
+        let url = reqwest::Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", provider.client_id.as_str()),
+                ("redirect_uri", provider.redirect_uri.as_str()),
+                ("scope", scope.as_str()),
+                ("state", state.as_str()),
+                ("nonce", nonce.as_str()),
+                ("code_challenge", challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| OidcError::Metadata(format!("authorization_endpoint: {}", e)))?;
+
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, login| login.created.elapsed() < PENDING_TTL);
+        pending.insert(
+            state,
+            PendingLogin {
+                provider: provider.name.clone(),
+                nonce,
+                code_verifier,
+                created: Instant::now(),
+            },
+        );
+        Ok(url.into())
+    }
+
+    // 处理回调：核对 state，用授权码换取 ID Token 并校验，返回映射后的身份
+    pub async fn complete(
+        &self,
+        name: &str,
+        code: &str,
+        state: &str,
+    ) -> Result<OidcIdentity, OidcError> {
+        let login = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(state)
+            .filter(|login| login.created.elapsed() < PENDING_TTL && login.provider == name)
+            .ok_or(OidcError::InvalidState)?;
+        let provider = self.provider(name)?;
+        let metadata = self.metadata(provider).await?;
+        let id_token = self
+            .request_id_token(provider, &metadata, code, &login.code_verifier)
+            .await?;
+        let claims = self
+            .verify_id_token(provider, &metadata, &id_token, &login.nonce)
+            .await?;
+        identity_from_claims(provider, &claims)
+    }
+
+    async fn request_id_token(
+        &self,
+        provider: &OidcProviderConfig,
+        metadata: &ProviderMetadata,
+        code: &str,
+        code_verifier: &str,
+    ) -> Result<String, OidcError> {
+        let client_secret = provider.client_secret.clone().or_else(|| {
+            provider
+                .client_secret_env
+                .as_deref()
+                .and_then(|name| std::env::var(name).ok())
+        });
+        let mut form = vec![
+            ("grant_type", "authorization_code".to_string()),
+            ("code", code.to_string()),
+            ("redirect_uri", provider.redirect_uri.clone()),
+            ("client_id", provider.client_id.clone()),
+            ("code_verifier", code_verifier.to_string()),
+        ];
+        if let Some(secret) = client_secret {
+            form.push(("client_secret", secret));
+        }
+        let response: TokenResponse = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await?
+            .json()
+            .await?;
+        if let Some(error) = response.error {
+            let description = response.error_description.unwrap_or_default();
+            return Err(OidcError::TokenRejected(
+                format!("{} {}", error, description).trim().to_string(),
+            ));
+        }
+        response
+            .id_token
+            .ok_or_else(|| OidcError::TokenRejected("response has no id_token".into()))
+    }
+
+    async fn fetch_jwks(
+        &self,
+        provider: &OidcProviderConfig,
+        metadata: &ProviderMetadata,
+    ) -> Result<JwkSet, OidcError> {
+        let jwks: JwkSet = self
+            .http
+            .get(&metadata.jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        self.jwks
+            .lock()
+            .unwrap()
+            .insert(provider.name.clone(), jwks.clone());
+        Ok(jwks)
+    }
+
+    // 按 kid 选择公钥；缓存中找不到时重新拉取一次 JWKS（IdP 可能已轮换密钥）
+    async fn decoding_key(
+        &self,
+        provider: &OidcProviderConfig,
+        metadata: &ProviderMetadata,
+        kid: Option<&str>,
+    ) -> Result<DecodingKey, OidcError> {
+        let pick = |jwks: &JwkSet| match kid {
+            Some(kid) => jwks.find(kid).cloned(),
+            None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+            None => None,
+        };
+        let cached = self.jwks.lock().unwrap().get(&provider.name).and_then(pick);
+        let jwk = match cached {
+            Some(jwk) => jwk,
+            None => pick(&self.fetch_jwks(provider, metadata).await?)
+                .ok_or_else(|| OidcError::InvalidIdToken(format!("unknown kid: {:?}", kid)))?,
+        };
+        DecodingKey::from_jwk(&jwk).map_err(|e| OidcError::InvalidIdToken(e.to_string()))
+    }
+
+    async fn verify_id_token(
+        &self,
+        provider: &OidcProviderConfig,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<Map<String, Value>, OidcError> {
+        let invalid = |e: jsonwebtoken::errors::Error| OidcError::InvalidIdToken(e.to_string());
+        let header = decode_header(id_token).map_err(invalid)?;
+        // 只接受非对称签名，HS256 等算法会让持有 client_secret 的一方也能伪造令牌
+        if header.alg.family() == AlgorithmFamily::Hmac {
+            return Err(OidcError::InvalidIdToken(format!(
+                "unsupported algorithm: {:?}",
+                header.alg
+            )));
+        }
+        let key = self
+            .decoding_key(provider, metadata, header.kid.as_deref())
+            .await?;
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[metadata.issuer.as_str()]);
+        validation.set_audience(&[provider.client_id.as_str()]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        validation.leeway = CLOCK_LEEWAY_SECS;
+        let claims = decode::<Map<String, Value>>(id_token, &key, &validation)
+            .map_err(invalid)?
+            .claims;
+        if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+            return Err(OidcError::InvalidIdToken("nonce mismatch".into()));
+        }
+        Ok(claims)
+    }
+
+    // 回调完成后签发一次性兑换码，前端凭它换取 rsts 令牌（令牌不出现在跳转地址中）
+    pub fn issue_exchange_code(&self, username: &str) -> String {
+        let code = random_token();
+        let mut exchanges = self.exchanges.lock().unwrap();
+        exchanges.retain(|_, (created, _)| created.elapsed() < EXCHANGE_TTL);
+        exchanges.insert(code.clone(), (Instant::now(), username.to_string()));
+        code
+    }
+
+    // 兑换码只能使用一次，返回对应的用户名
+    pub fn redeem_exchange_code(&self, code: &str) -> Option<String> {
+        self.exchanges
+            .lock()
+            .unwrap()
+            .remove(code)
+            .filter(|(created, _)| created.elapsed() < EXCHANGE_TTL)
+            .map(|(_, username)| username)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn provider() -> OidcProviderConfig {
+        let mut provider = OidcProviderConfig {
+            name: "corp".into(),
+            ..OidcProviderConfig::default()
+        };
+        provider
+            .role_mapping
+            .insert("ops".into(), vec!["R_ADMIN".into()]);
+        provider
+            .role_mapping
+            .insert("root".into(), vec!["R_SUPER".into(), "R_ADMIN".into()]);
+        provider
+    }
+
+    #[test]
+    fn pkce_challenge() {
+        // RFC 7636 附录 B 的示例
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+        assert_eq!(random_token().len(), 43);
+    }
+
+    #[test]
+    fn claims_to_identity() {
+        let provider = provider();
+        let claims = json!({
+            "sub": "u-1",
+            "preferred_username": "alice",
+            "email": "alice@example.com",
+            "groups": ["ops", "root", "unmapped"],
+        });
+        let identity = identity_from_claims(&provider, claims.as_object().unwrap()).unwrap();
+        assert_eq!(identity.username, "alice");
+        assert_eq!(identity.roles, vec!["R_ADMIN", "R_SUPER"]);
+
+        // 没有映射命中时使用默认角色；组声明也可以是单个字符串
+        let claims = json!({ "sub": "u-2", "preferred_username": "bob", "groups": "staff" });
+        let identity = identity_from_claims(&provider, claims.as_object().unwrap()).unwrap();
+        assert_eq!(identity.roles, vec!["R_USER"]);
+        assert_eq!(identity.email, None);
+
+        let claims = json!({ "sub": "u-3" });
+        assert!(matches!(
+            identity_from_claims(&provider, claims.as_object().unwrap()),
+            Err(OidcError::MissingClaim(_))
+        ));
+    }
+}
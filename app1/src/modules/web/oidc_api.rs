@@ -0,0 +1,261 @@
+// 单点登录 API：列出 IdP、跳转授权、处理回调（关联或即时创建本地用户）、兑换 rsts 令牌
+// 回调只把一次性兑换码放进跳转地址，令牌由前端通过 POST 兑换，不会出现在浏览器历史中
+use actix_web::http::header;
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use serde_json::json;
+use std::sync::Arc;
+
+use super::database::Database;
+use super::login_handler::{LoginAudit, login_success};
+use super::models::{OidcCallbackQuery, OidcExchangeRequest, Response, UserCreateInput};
+use super::oidc::{OidcClient, OidcIdentity, random_token};
+use super::repository::RbacOutcome;
+use super::response::{ApiError, ApiResult};
+use super::totp::percent_encode;
+use super::users_api::valid_username;
+use crate::modules::config::config::OidcProviderConfig;
+
+fn redirect(location: String) -> HttpResponse {
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, location))
+        .finish()
+}
+
+// 回到前端登录页，附带兑换码或错误信息
+fn back_to_frontend(client: &OidcClient, key: &str, value: &str) -> HttpResponse {
+    let target = client.frontend_redirect();
+    let separator = if target.contains('?') { '&' } else { '?' };
+    redirect(format!(
+        "{}{}{}={}",
+        target,
+        separator,
+        key,
+        percent_encode(value)
+    ))
+}
+
+// GET /api/oidc/providers：登录页展示的单点登录入口
+#[utoipa::path(
+    get,
+    path = "/api/oidc/providers",
+    tag = "auth",
+    summary = "单点登录提供方列表",
+    responses((status = 200, body = Response)),
+    security(())
+)]
+pub async fn list_providers(client: web::Data<OidcClient>) -> ApiResult {
+    let providers: Vec<_> = client
+        .providers()
+        .iter()
+        .map(|p| {
+            let display_name = if p.display_name.is_empty() {
+                &p.name
+            } else {
+                &p.display_name
+            };
+            json!({ "name": p.name, "display_name": display_name })
+        })
+        .collect();
+    Ok(Response::ok(json!(providers)))
+}
+
+// GET /api/oidc/{provider}/authorize：跳转到 IdP 授权页
+#[utoipa::path(
+    get,
+    path = "/api/oidc/{provider}/authorize",
+    tag = "auth",
+    summary = "跳转到 IdP 授权页",
+    params(("provider" = String, Path, description = "单点登录提供方名称")),
+    responses((status = 302, description = "重定向")),
+    security(())
+)]
+pub async fn authorize(client: web::Data<OidcClient>, path: web::Path<String>) -> impl Responder {
+    let provider = path.into_inner();
+    match client.authorization_url(&provider).await {
+        Ok(url) => redirect(url),
+        Err(e) => {
+            log::warn!("oidc authorize failed for {}: {}", provider, e);
+            back_to_frontend(&client, "error", "单点登录暂不可用")
+        }
+    }
+}
+
+// 按 (provider, sub) 找到本地用户；首次登录时关联同名用户或即时创建
+async fn resolve_user(
+    db: &Database,
+    provider: &OidcProviderConfig,
+    identity: &OidcIdentity,
+) -> Result<String, String> {
+    let linked = db
+        .oidc_identities()
+        .find_user(&identity.provider, &identity.subject)
+        .await
+        .map_err(|e| format!("数据库错误: {}", e))?;
+    let username = match linked {
+        Some(username) => username,
+        None => {
+            if !valid_username(&identity.username) {
+                return Err(format!("用户名不合法: {}", identity.username));
+            }
+            let exists = db
+                .get_user_info(&identity.username)
+                .await
+                .map_err(|e| format!("数据库错误: {}", e))?
+                .is_some();
+            if exists && !provider.link_existing_users {
+                return Err(format!("本地已存在用户 {}，未允许关联", identity.username));
+            }
+            if !exists {
+                if !provider.auto_provision {
+                    return Err(format!("用户 {} 不存在", identity.username));
+                }
+                provision_user(db, identity).await?;
+            }
+            db.oidc_identities()
+                .link(
+                    &identity.provider,
+                    &identity.subject,
+                    &identity.username,
+                    identity.email.clone(),
+                )
+                .await
+                .map_err(|e| format!("数据库错误: {}", e))?;
+            log::info!(
+                "oidc identity {}/{} linked to user {}",
+                identity.provider,
+                identity.subject,
+                identity.username
+            );
+            identity.username.clone()
+        }
+    };
+    if provider.sync_roles {
+        match db
+            .rbac()
+            .set_user_roles(username.clone(), identity.roles.clone())
+            .await
+        {
+            Ok(RbacOutcome::Done) => {}
+            // 角色同步失败不阻止登录，保留原有角色
+            Ok(outcome) => log::warn!("oidc role sync for {} skipped: {:?}", username, outcome),
+            Err(e) => return Err(format!("数据库错误: {}", e)),
+        }
+    }
+    Ok(username)
+}
+
+// 即时创建本地用户：随机密码（只能通过单点登录），角色取映射结果
+async fn provision_user(db: &Database, identity: &OidcIdentity) -> Result<(), String> {
+    let input = UserCreateInput {
+        username: identity.username.clone(),
+        password: random_token(),
+        email: identity.email.clone(),
+        roles: Some(identity.roles.clone()),
+        must_change_password: Some(false),
+    };
+    match db.users().create_user(input).await {
+        Ok(RbacOutcome::Done) => {
+            log::info!(
+                "user {} provisioned from oidc provider {}",
+                identity.username,
+                identity.provider
+            );
+            Ok(())
+        }
+        Ok(RbacOutcome::UnknownRole(role)) => Err(format!("角色不存在: {}", role)),
+        Ok(outcome) => Err(format!("创建用户失败: {:?}", outcome)),
+        Err(e) => Err(format!("数据库错误: {}", e)),
+    }
+}
+
+// GET /api/oidc/{provider}/callback：IdP 授权后的回调地址
+#[utoipa::path(
+    get,
+    path = "/api/oidc/{provider}/callback",
+    tag = "auth",
+    summary = "IdP 授权回调",
+    params(("provider" = String, Path, description = "单点登录提供方名称"), OidcCallbackQuery),
+    responses((status = 302, description = "重定向")),
+    security(())
+)]
+pub async fn callback(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+    client: web::Data<OidcClient>,
+    path: web::Path<String>,
+    query: web::Query<OidcCallbackQuery>,
+) -> impl Responder {
+    let name = path.into_inner();
+    let query = query.into_inner();
+    if let Some(error) = query.error {
+        log::warn!(
+            "oidc provider {} returned error: {} {}",
+            name,
+            error,
+            query.error_description.unwrap_or_default()
+        );
+        return back_to_frontend(&client, "error", "身份提供方拒绝了登录");
+    }
+    let (Some(code), Some(state)) = (query.code, query.state) else {
+        return back_to_frontend(&client, "error", "缺少授权码");
+    };
+    let identity = match client.complete(&name, &code, &state).await {
+        Ok(identity) => identity,
+        Err(e) => {
+            log::warn!("oidc login via {} failed: {}", name, e);
+            return back_to_frontend(&client, "error", "单点登录失败，请重试");
+        }
+    };
+    let Ok(provider) = client.provider(&name) else {
+        return back_to_frontend(&client, "error", "单点登录失败，请重试");
+    };
+    match resolve_user(&db, provider, &identity).await {
+        Ok(username) => {
+            let exchange_code = client.issue_exchange_code(&username);
+            back_to_frontend(&client, "code", &exchange_code)
+        }
+        Err(msg) => {
+            log::warn!("oidc login via {} rejected: {}", name, msg);
+            LoginAudit::new(&req, "oidc", &identity.username)
+                .record(&db, Some(&msg))
+                .await;
+            back_to_frontend(&client, "error", &msg)
+        }
+    }
+}
+
+// POST /api/oidc/exchange：用一次性兑换码换取访问令牌与刷新令牌
+#[utoipa::path(
+    post,
+    path = "/api/oidc/exchange",
+    tag = "auth",
+    summary = "用一次性兑换码换取令牌",
+    responses((status = 200, body = Response)),
+    security(())
+)]
+pub async fn exchange(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+    client: web::Data<OidcClient>,
+    body: web::Json<OidcExchangeRequest>,
+) -> ApiResult {
+    let Some(username) = client.redeem_exchange_code(&body.code) else {
+        return Err(ApiError::InvalidCredentials(
+            "登录已过期，请重新登录".to_string(),
+        ));
+    };
+    let audit = LoginAudit::new(&req, "oidc", &username);
+    let Some((user_id, _, _)) = db.get_user_info(&username).await? else {
+        return Err(ApiError::UserNotFound);
+    };
+    let Some(state) = db.users().account_state(&username).await? else {
+        return Err(ApiError::UserNotFound);
+    };
+    if state.disabled {
+        log::warn!("oidc login rejected for disabled user: {}", username);
+        audit.record(&db, Some("disabled")).await;
+        return Err(ApiError::AccountDisabled);
+    }
+    // 身份已由 IdP 认证（含其多因素策略），不再要求本地两步验证
+    login_success(&db, user_id, &username, state.must_change_password, &audit).await
+}
@@ -0,0 +1,401 @@
+// OpenAPI 文档：由各处理函数上的 #[utoipa::path] 以及 models.rs、sqlstudio/models.rs 中的请求/响应类型生成
+//
+// GET /api/openapi.json 返回文档，/api/docs/ 为内嵌的 Swagger UI（静态资源编译进程序，不依赖外网）。
+// 新增路由时需在处理函数上添加 #[utoipa::path] 并登记到下面的 paths，否则 tests 中的路由对照测试会失败。
+use utoipa::openapi::response::ResponseBuilder;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{ContentBuilder, RefOr};
+use utoipa::{Modify, OpenApi, PartialSchema};
+use utoipa_swagger_ui::SwaggerUi;
+
+use super::models::Response;
+use super::{
+    access_tokens_api, apitest_api, audit_api, chat_api, login_handler, migrations, oidc_api, rbac,
+    sftp_api, sqlite_api, sqlite_maintenance, ssh_monitor_api, ssh_servers_api, totp_api,
+    users_api,
+};
+use crate::modules::sqlstudio;
+use crate::modules::task::api as task_api;
+
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "rsts API",
+        description = "所有 JSON 接口返回 { code, msg, data } 信封，code 为 \"0000\" 表示成功，其余业务码见 auth_utils.rs"
+    ),
+    paths(
+        login_handler::health_check,
+        login_handler::login,
+        login_handler::login_totp,
+        login_handler::get_user_info,
+        login_handler::refresh_token,
+        login_handler::logout,
+        login_handler::get_user_theme_config_handler,
+        login_handler::update_user_theme_config_handler,
+        login_handler::get_user_terminal_config_handler,
+        login_handler::update_user_terminal_config_handler,
+        login_handler::logout_all,
+        migrations::get_migration_status,
+        oidc_api::list_providers,
+        oidc_api::exchange,
+        oidc_api::authorize,
+        oidc_api::callback,
+        users_api::change_password,
+        users_api::get_password_policy,
+        users_api::list_users,
+        users_api::create_user,
+        users_api::list_login_failures,
+        users_api::update_user,
+        users_api::delete_user,
+        users_api::reset_password,
+        users_api::unlock_user,
+        totp_api::get_status,
+        totp_api::setup,
+        totp_api::enable,
+        totp_api::disable,
+        totp_api::regenerate_recovery_codes,
+        totp_api::reset_user_totp,
+        access_tokens_api::list_tokens,
+        access_tokens_api::create_token,
+        access_tokens_api::revoke_token,
+        access_tokens_api::revoke_user_tokens,
+        rbac::list_permissions,
+        rbac::list_roles,
+        rbac::save_role,
+        rbac::delete_role,
+        rbac::get_user_roles,
+        rbac::set_user_roles,
+        audit_api::list_audit,
+        audit_api::export_audit,
+        sqlite_api::get_all_databases,
+        sqlite_api::create_database,
+        sqlite_api::register_database,
+        sqlite_api::unregister_database,
+        sqlite_api::upload_database,
+        sqlite_api::get_tables_by_database,
+        sqlite_api::get_table_data,
+        sqlite_api::create_table,
+        sqlite_api::drop_table,
+        sqlite_api::rename_table,
+        sqlite_api::rename_column,
+        sqlite_api::add_column,
+        sqlite_api::drop_column,
+        sqlite_api::insert_row,
+        sqlite_api::update_row,
+        sqlite_api::delete_row,
+        sqlite_api::batch_delete_rows,
+        sqlite_api::sql_query,
+        sqlite_api::list_indexes,
+        sqlite_api::create_index,
+        sqlite_api::drop_index,
+        sqlite_api::list_views,
+        sqlite_api::create_view,
+        sqlite_api::drop_view,
+        sqlite_api::list_triggers,
+        sqlite_api::create_trigger,
+        sqlite_api::drop_trigger,
+        sqlite_api::import_data,
+        sqlite_api::export_data,
+        sqlite_api::dump_database,
+        sqlite_api::restore_database,
+        sqlite_maintenance::start_maintenance,
+        sqlite_maintenance::get_maintenance_jobs,
+        sqlstudio::test_connection_handler,
+        sqlstudio::create_connection_handler,
+        sqlstudio::list_connections_handler,
+        sqlstudio::update_connection_handler,
+        sqlstudio::delete_connection_handler,
+        sqlstudio::get_metadata_handler,
+        sqlstudio::get_table_data_handler,
+        sqlstudio::execute_sql_handler,
+        chat_api::upload_media,
+        sftp_api::create_session,
+        sftp_api::list_dir,
+        sftp_api::read_file,
+        sftp_api::write_file,
+        sftp_api::delete_file,
+        sftp_api::rename_file,
+        sftp_api::upload_file,
+        sftp_api::download_file,
+        sftp_api::create_dir,
+        sftp_api::set_permissions,
+        ssh_servers_api::list_groups,
+        ssh_servers_api::create_group,
+        ssh_servers_api::update_group,
+        ssh_servers_api::delete_group,
+        ssh_servers_api::list_servers,
+        ssh_servers_api::create_server,
+        ssh_servers_api::update_server,
+        ssh_servers_api::delete_server,
+        ssh_monitor_api::get_monitor_stats,
+        task_api::start_task,
+        task_api::stop_task,
+        task_api::task_status,
+        apitest_api::list_endpoints,
+        apitest_api::get_endpoint,
+        apitest_api::create_endpoint,
+        apitest_api::update_endpoint,
+        apitest_api::delete_endpoint,
+    ),
+    modifiers(&BearerAuth, &ErrorEnvelope),
+    security(("bearer" = [])),
+    tags(
+        (name = "system", description = "健康检查与系统状态"),
+        (name = "auth", description = "登录、单点登录与令牌"),
+        (name = "user", description = "当前用户的配置、密码、两步验证与访问令牌"),
+        (name = "users", description = "用户管理"),
+        (name = "rbac", description = "角色与权限"),
+        (name = "audit", description = "审计日志"),
+        (name = "sqlite", description = "SQLite 数据库管理"),
+        (name = "sqlstudio", description = "SQL Studio 外部数据库连接"),
+        (name = "chat", description = "聊天"),
+        (name = "sftp", description = "SFTP 文件操作"),
+        (name = "ssh", description = "SSH 服务器配置与监控"),
+        (name = "task", description = "压测任务"),
+        (name = "apitest", description = "接口测试定义"),
+    )
+)]
+pub struct ApiDoc;
+
+// 访问令牌或个人访问令牌，放在 Authorization: Bearer 头中
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+// 失败时统一返回错误信封，HTTP 状态码随错误类型变化（见 response.rs），不在每个接口上重复声明
+struct ErrorEnvelope;
+
+impl Modify for ErrorEnvelope {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let schema: RefOr<_> = Response::<serde_json::Value>::schema();
+        let response = ResponseBuilder::new()
+            .description("失败：code 为业务码，msg 为错误信息")
+            .content(
+                "application/json",
+                ContentBuilder::new().schema(Some(schema)).build(),
+            )
+            .build();
+        for item in openapi.paths.paths.values_mut() {
+            for op in [
+                &mut item.get,
+                &mut item.post,
+                &mut item.put,
+                &mut item.delete,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                op.responses
+                    .responses
+                    .entry("default".to_string())
+                    .or_insert_with(|| response.clone().into());
+            }
+        }
+    }
+}
+
+// 文档与 Swagger UI，在 setup_actix 中注册（无需登录）
+pub fn docs_service() -> SwaggerUi {
+    SwaggerUi::new("/api/docs/{_:.*}").url(OPENAPI_PATH, ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    // 当前注册的 REST 接口数；删减接口时同步调低，扫描结果少于此数说明解析漏掉了路由
+    const MIN_API_ROUTES: usize = 112;
+
+    // 去掉 main_web.rs 中的注释，保留字符串字面量
+    fn strip_comments(src: &str) -> String {
+        let mut out = String::with_capacity(src.len());
+        let mut chars = src.chars().peekable();
+        let mut in_str = false;
+        while let Some(c) = chars.next() {
+            if in_str {
+                out.push(c);
+                if c == '\\' {
+                    if let Some(n) = chars.next() {
+                        out.push(n);
+                    }
+                } else if c == '"' {
+                    in_str = false;
+                }
+            } else if c == '"' {
+                in_str = true;
+                out.push(c);
+            } else if c == '/' && chars.peek() == Some(&'/') {
+                for n in chars.by_ref() {
+                    if n == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    fn string_literal(s: &str) -> Option<&str> {
+        let s = s.trim_start().strip_prefix('"')?;
+        s.find('"').map(|end| &s[..end])
+    }
+
+    // 从 setup_actix 的源码中收集 (方法, 完整路径)：跟踪 web::scope / web::resource 的前缀，
+    // 记录 .route("路径", web::get()...) 与资源内的 .route(web::post()...)
+    fn registered_routes() -> BTreeSet<(String, String)> {
+        let src = strip_comments(include_str!("main_web.rs"));
+        let start = src.find("pub async fn setup_actix").unwrap();
+        let end = src[start..].find("async fn serve_index").unwrap() + start;
+        let src = &src[start..end];
+
+        let mut routes = BTreeSet::new();
+        let mut matched = 0usize;
+        // (前缀, 进入时的括号深度)
+        let mut scopes: Vec<(String, usize)> = Vec::new();
+        let mut depth = 0usize;
+        let prefix =
+            |scopes: &[(String, usize)]| scopes.last().map(|s| s.0.clone()).unwrap_or_default();
+        let mut i = 0;
+        while i < src.len() {
+            let rest = &src[i..];
+            if rest.starts_with("web::scope(") || rest.starts_with("web::resource(") {
+                let open = rest.find('(').unwrap();
+                let path = string_literal(&rest[open + 1..]).unwrap();
+                scopes.push((prefix(&scopes) + path, depth));
+            } else if let Some(args) = rest.strip_prefix(".route(") {
+                let (path, after) = match string_literal(args) {
+                    Some(p) => (p, &args[args.find(',').unwrap() + 1..]),
+                    None => ("", args),
+                };
+                let after = after.trim_start();
+                let method = ["get", "post", "put", "delete"]
+                    .into_iter()
+                    .find(|m| after.starts_with(&format!("web::{}()", m)));
+                match method {
+                    Some(method) => {
+                        routes.insert((method.to_uppercase(), prefix(&scopes) + path));
+                        matched += 1;
+                    }
+                    // 带守卫的 web::route()（如 OPTIONS 预检）不属于 REST 接口
+                    None => assert!(
+                        after.starts_with("web::route()"),
+                        "无法识别的路由写法: .route({}",
+                        args.chars().take(80).collect::<String>()
+                    ),
+                }
+            }
+            match rest.chars().next().unwrap() {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    while scopes.last().is_some_and(|s| depth < s.1) {
+                        scopes.pop();
+                    }
+                }
+                _ => {}
+            }
+            i += rest.chars().next().unwrap().len_utf8();
+        }
+        // 每个 web::get() 等都应归属到某条 .route(...)，否则说明扫描跳过了某种写法
+        let builders: usize = ["get", "post", "put", "delete"]
+            .iter()
+            .map(|m| src.matches(&format!("web::{}()", m)).count())
+            .sum();
+        assert_eq!(matched, builders, "路由扫描与源码中的方法数不一致");
+        // 只对照 REST 接口（WebSocket、首页与计数器不在文档中）
+        routes
+            .into_iter()
+            .filter(|(_, path)| path.starts_with("/api") || path.starts_with("/auth/"))
+            .collect()
+    }
+
+    fn documented_routes() -> BTreeSet<(String, String)> {
+        let doc = ApiDoc::openapi();
+        let mut routes = BTreeSet::new();
+        for (path, item) in &doc.paths.paths {
+            for (method, op) in [
+                ("GET", &item.get),
+                ("POST", &item.post),
+                ("PUT", &item.put),
+                ("DELETE", &item.delete),
+            ] {
+                if op.is_some() {
+                    routes.insert((method.to_string(), path.clone()));
+                }
+            }
+        }
+        routes
+    }
+
+    #[test]
+    fn every_route_is_documented() {
+        let registered = registered_routes();
+        // 解析本身出错时不能让测试空跑通过
+        assert!(
+            registered.len() >= MIN_API_ROUTES,
+            "只扫描到 {} 个接口，少于 {}",
+            registered.len(),
+            MIN_API_ROUTES
+        );
+        assert!(registered.contains(&("POST".into(), "/api/sqlite/databases/upload".into())));
+        assert!(registered.contains(&("DELETE".into(), "/api/users/{username}/tokens".into())));
+
+        let documented = documented_routes();
+        let missing: Vec<_> = registered.difference(&documented).collect();
+        assert!(missing.is_empty(), "路由缺少 OpenAPI 文档: {:?}", missing);
+        let stale: Vec<_> = documented.difference(&registered).collect();
+        assert!(stale.is_empty(), "文档中的路由未注册: {:?}", stale);
+    }
+
+    #[test]
+    fn document_describes_envelope_and_auth() {
+        let doc = ApiDoc::openapi();
+        let json = serde_json::to_value(&doc).unwrap();
+        assert!(json["components"]["securitySchemes"]["bearer"].is_object());
+
+        let login = &json["paths"]["/api/login"]["post"];
+        assert_eq!(
+            login["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/LoginRequest"
+        );
+        assert!(login["responses"]["default"].is_object());
+        // 登录接口无需令牌
+        assert_eq!(login["security"], serde_json::json!([{}]));
+        assert!(json["components"]["schemas"]["SqlConnection"].is_object());
+
+        // 查询参数来自 IntoParams，路径参数来自 params(...)
+        let names = |op: &serde_json::Value| -> Vec<String> {
+            op["parameters"]
+                .as_array()
+                .map(|ps| {
+                    ps.iter()
+                        .map(|p| p["name"].as_str().unwrap().to_string())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let tables = names(&json["paths"]["/api/sqlite/tables"]["get"]);
+        assert!(tables.contains(&"db_name".to_string()), "{:?}", tables);
+        let reset = names(&json["paths"]["/api/users/{username}/password"]["put"]);
+        assert_eq!(reset, vec!["username".to_string()]);
+    }
+}
@@ -0,0 +1,268 @@
+// 基于角色的访问控制：权限码定义与角色管理 API
+//
+// 权限通过角色授予用户（roles / role_permissions / user_roles 表，见迁移 create_rbac），
+// 路由由 auth_middleware::RequirePermission 按权限码拦截；admin 权限视为拥有全部权限。
+use actix_web::web;
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::database::Database;
+use super::models::{Response, Role, RoleInput, UserRolesInput};
+use super::repository::RbacOutcome;
+use super::response::{ApiError, ApiResult};
+
+pub const ADMIN: &str = "admin";
+pub const SQL_READ: &str = "sql:read";
+pub const SQL_EXECUTE: &str = "sql:execute";
+pub const SQL_MANAGE: &str = "sql:manage";
+pub const SSH_CONNECT: &str = "ssh:connect";
+pub const SSH_MANAGE: &str = "ssh:manage";
+pub const SFTP_READ: &str = "sftp:read";
+pub const SFTP_WRITE: &str = "sftp:write";
+pub const TASK_MANAGE: &str = "task:manage";
+pub const APITEST_USE: &str = "apitest:use";
+pub const CHAT_USE: &str = "chat:use";
+pub const TOOLS_USE: &str = "tools:use";
+pub const AUDIT_READ: &str = "audit:read";
+
+/// 全部权限码及说明
+pub const PERMISSIONS: &[(&str, &str)] = &[
+    (ADMIN, "系统管理（角色、迁移等），拥有全部权限"),
+    (SQL_READ, "浏览数据库、表结构与数据"),
+    (SQL_EXECUTE, "执行 SQL、修改表结构与数据、导入与恢复"),
+    (SQL_MANAGE, "管理 SQL Studio 连接与 SQLite 数据库登记、维护"),
+    (SSH_CONNECT, "连接 SSH 终端、查看服务器列表与监控"),
+    (SSH_MANAGE, "管理 SSH 服务器与分组"),
+    (SFTP_READ, "浏览与下载远程文件"),
+    (SFTP_WRITE, "上传、修改、删除远程文件"),
+    (TASK_MANAGE, "启停定时任务"),
+    (APITEST_USE, "使用 API 测试"),
+    (CHAT_USE, "使用聊天室"),
+    (TOOLS_USE, "使用图像算子与 AI 工具"),
+    (AUDIT_READ, "查询与导出审计日志"),
+];
+
+/// 当前请求用户拥有的权限（由 RequirePermission 存入 request extensions）
+#[derive(Debug, Clone, Default)]
+pub struct Permissions(Arc<HashSet<String>>);
+
+impl Permissions {
+    pub fn new(list: Vec<String>) -> Self {
+        Self(Arc::new(list.into_iter().collect()))
+    }
+
+    pub fn allows(&self, permission: &str) -> bool {
+        self.0.contains(ADMIN) || self.0.contains(permission)
+    }
+
+    // 个人访问令牌只保留授予的、且用户当前仍拥有的权限
+    pub fn restrict(&self, scopes: &[String]) -> Self {
+        Self::new(
+            scopes
+                .iter()
+                .filter(|scope| self.allows(scope))
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+/// 个人访问令牌授予的权限码（认证中间件存入 request extensions）
+#[derive(Debug, Clone)]
+pub struct TokenScopes(pub Vec<String>);
+
+// 角色与用户角色变更的结果
+pub(super) fn outcome_result(
+    outcome: RbacOutcome,
+    ok_msg: &str,
+    not_found_msg: &str,
+) -> ApiResult<()> {
+    match outcome {
+        RbacOutcome::Done => Ok(Response::message(ok_msg)),
+        RbacOutcome::NotFound => Err(ApiError::NotFound(not_found_msg.to_string())),
+        RbacOutcome::AlreadyExists => Err(ApiError::Conflict("用户名已存在".to_string())),
+        RbacOutcome::UnknownRole(role) => {
+            Err(ApiError::InvalidParams(format!("角色不存在: {}", role)))
+        }
+        RbacOutcome::NoAdminLeft => Err(ApiError::InvalidParams(
+            "操作后将没有任何用户拥有 admin 权限，已拒绝".to_string(),
+        )),
+    }
+}
+
+// GET /api/rbac/permissions
+#[utoipa::path(
+    get,
+    path = "/api/rbac/permissions",
+    tag = "rbac",
+    summary = "权限码列表",
+    responses((status = 200, body = Response))
+)]
+pub async fn list_permissions() -> ApiResult {
+    let data: Vec<_> = PERMISSIONS
+        .iter()
+        .map(|(code, description)| json!({ "code": code, "description": description }))
+        .collect();
+    Ok(Response::ok(json!(data)))
+}
+
+// GET /api/rbac/roles
+#[utoipa::path(
+    get,
+    path = "/api/rbac/roles",
+    tag = "rbac",
+    summary = "角色列表",
+    responses((status = 200, body = Response<Vec<Role>>))
+)]
+pub async fn list_roles(db: web::Data<Arc<Database>>) -> ApiResult<Vec<Role>> {
+    Ok(Response::ok(db.rbac().list_roles().await?))
+}
+
+// POST /api/rbac/roles：新建或更新角色
+#[utoipa::path(
+    post,
+    path = "/api/rbac/roles",
+    tag = "rbac",
+    summary = "新建或更新角色",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn save_role(
+    db: web::Data<Arc<Database>>,
+    body: web::Json<RoleInput>,
+) -> ApiResult<()> {
+    let mut input = body.into_inner();
+    input.name = input.name.trim().to_string();
+    if input.name.is_empty() {
+        return Err(ApiError::InvalidParams("角色名不能为空".to_string()));
+    }
+    if let Some(unknown) = input
+        .permissions
+        .iter()
+        .find(|p| !PERMISSIONS.iter().any(|(code, _)| code == p))
+    {
+        return Err(ApiError::InvalidParams(format!("未知权限: {}", unknown)));
+    }
+    let outcome = db.rbac().save_role(input).await?;
+    outcome_result(outcome, "角色已保存", "角色不存在")
+}
+
+// DELETE /api/rbac/roles/{name}
+#[utoipa::path(
+    delete,
+    path = "/api/rbac/roles/{name}",
+    tag = "rbac",
+    summary = "删除角色",
+    params(("name" = String, Path, description = "角色名")),
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn delete_role(
+    db: web::Data<Arc<Database>>,
+    path: web::Path<String>,
+) -> ApiResult<()> {
+    let outcome = db.rbac().delete_role(path.into_inner()).await?;
+    outcome_result(outcome, "角色已删除", "角色不存在")
+}
+
+// GET /api/rbac/users/{username}/roles
+#[utoipa::path(
+    get,
+    path = "/api/rbac/users/{username}/roles",
+    tag = "rbac",
+    summary = "用户的角色",
+    params(("username" = String, Path, description = "用户名")),
+    responses((status = 200, body = Response))
+)]
+pub async fn get_user_roles(db: web::Data<Arc<Database>>, path: web::Path<String>) -> ApiResult {
+    let username = path.into_inner();
+    let rbac = db.rbac();
+    let roles = rbac.user_roles(&username).await?;
+    let permissions = rbac.user_permissions(&username).await?;
+    Ok(Response::ok(
+        json!({ "username": username, "roles": roles, "permissions": permissions }),
+    ))
+}
+
+// PUT /api/rbac/users/{username}/roles：整体替换用户角色
+#[utoipa::path(
+    put,
+    path = "/api/rbac/users/{username}/roles",
+    tag = "rbac",
+    summary = "设置用户的角色",
+    params(("username" = String, Path, description = "用户名")),
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn set_user_roles(
+    db: web::Data<Arc<Database>>,
+    path: web::Path<String>,
+    body: web::Json<UserRolesInput>,
+) -> ApiResult<()> {
+    let outcome = db
+        .rbac()
+        .set_user_roles(path.into_inner(), body.into_inner().roles)
+        .await?;
+    outcome_result(outcome, "用户角色已更新", "用户不存在")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn builtin_roles_and_admin_guard() {
+        let db = Database::new(":memory:").unwrap();
+        let rbac = db.rbac();
+
+        // 迁移种子中的权限码都必须是已定义的
+        for role in rbac.list_roles().await.unwrap() {
+            assert!(
+                role.permissions
+                    .iter()
+                    .all(|p| PERMISSIONS.iter().any(|(code, _)| code == p)),
+                "{:?}",
+                role
+            );
+        }
+
+        let admin = Permissions::new(rbac.user_permissions("admin").await.unwrap());
+        assert!(admin.allows(SQL_EXECUTE));
+        let user = Permissions::new(rbac.user_permissions("user1").await.unwrap());
+        assert!(user.allows(SQL_READ));
+        assert!(!user.allows(SQL_EXECUTE));
+        assert_eq!(rbac.user_roles("user1").await.unwrap(), vec!["R_USER"]);
+
+        // 不允许移除最后一个管理员
+        assert_eq!(
+            rbac.set_user_roles("admin".into(), vec!["R_USER".into()])
+                .await
+                .unwrap(),
+            RbacOutcome::NoAdminLeft
+        );
+        assert_eq!(
+            rbac.delete_role("R_SUPER".into()).await.unwrap(),
+            RbacOutcome::NoAdminLeft
+        );
+        assert_eq!(
+            rbac.set_user_roles("user1".into(), vec!["R_NOPE".into()])
+                .await
+                .unwrap(),
+            RbacOutcome::UnknownRole("R_NOPE".into())
+        );
+
+        let input = RoleInput {
+            name: "R_DBA".into(),
+            description: None,
+            permissions: vec![SQL_READ.into(), SQL_EXECUTE.into()],
+        };
+        assert_eq!(rbac.save_role(input).await.unwrap(), RbacOutcome::Done);
+        assert_eq!(
+            rbac.set_user_roles("user1".into(), vec!["R_DBA".into()])
+                .await
+                .unwrap(),
+            RbacOutcome::Done
+        );
+        let user = Permissions::new(rbac.user_permissions("user1").await.unwrap());
+        assert!(user.allows(SQL_EXECUTE));
+        assert!(!user.allows(SSH_CONNECT));
+    }
+}
@@ -0,0 +1,295 @@
+//! 个人访问令牌（`personal_access_tokens` 表）
+//!
+//! 令牌是高熵随机串，只保存 SHA-256 哈希，按哈希直接查找。
+//! 吊销只标记 revoked_at，不删除记录；停用的账号持有的令牌一律不可用。
+use chrono::Utc;
+use rusqlite::{OptionalExtension, params};
+use sqlx::Connection as _;
+
+use super::pool::StoreError;
+use super::store::{Store, sqlite_sql};
+use crate::modules::web::models::AccessTokenInfo;
+
+/// 通过校验的令牌及其所属用户
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessTokenOwner {
+    pub id: i64,
+    pub user_id: i64,
+    pub username: String,
+    /// 授予的权限码
+    pub scopes: Vec<String>,
+    /// 过期时间（Unix 秒）
+    pub expires_at: i64,
+}
+
+/// 待登记的新令牌
+#[derive(Debug, Clone)]
+pub struct NewAccessToken {
+    pub user_id: i64,
+    pub name: String,
+    /// 令牌的 SHA-256 哈希
+    pub token_hash: String,
+    /// 令牌开头几位
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    /// 过期时间（Unix 秒）
+    pub expires_at: i64,
+}
+
+// 最近使用时间按分钟更新，避免每个请求都写库
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+const AUTHENTICATE_SQL: &str =
+    "SELECT t.id, t.user_id, u.username, t.scopes, t.expires_at, t.last_used_at
+    FROM personal_access_tokens t JOIN users u ON u.id = t.user_id
+    WHERE t.token_hash = $1 AND t.revoked_at IS NULL AND t.expires_at > $2 AND u.disabled = 0";
+const TOUCH_SQL: &str = "UPDATE personal_access_tokens SET last_used_at = $1 WHERE id = $2";
+const ACTIVE_COUNT_SQL: &str = "SELECT COUNT(*) FROM personal_access_tokens
+    WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > $2";
+const LOCK_USER_SQL: &str = "SELECT id FROM users WHERE id = $1 FOR UPDATE";
+const INSERT_SQL: &str = "INSERT INTO personal_access_tokens
+    (user_id, name, token_hash, prefix, scopes, created_at, expires_at)
+    VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id";
+const LIST_SQL: &str = "SELECT id, name, prefix, scopes, created_at, expires_at, last_used_at
+    FROM personal_access_tokens WHERE user_id = $1 AND revoked_at IS NULL ORDER BY id DESC";
+const REVOKE_SQL: &str = "UPDATE personal_access_tokens SET revoked_at = $1
+    WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL";
+const REVOKE_USER_SQL: &str = "UPDATE personal_access_tokens SET revoked_at = $1
+    WHERE revoked_at IS NULL AND user_id = (SELECT id FROM users WHERE username = $2)";
+
+fn split_scopes(scopes: &str) -> Vec<String> {
+    scopes.split_whitespace().map(str::to_string).collect()
+}
+
+type ListRow = (i64, String, String, String, i64, i64, Option<i64>);
+
+fn token_info(row: ListRow) -> AccessTokenInfo {
+    let (id, name, prefix, scopes, created_at, expires_at, last_used_at) = row;
+    AccessTokenInfo {
+        id,
+        name,
+        prefix,
+        scopes: split_scopes(&scopes),
+        created_at,
+        expires_at,
+        last_used_at,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AccessTokenRepository {
+    store: Store,
+}
+
+impl AccessTokenRepository {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    // 按哈希查找有效的令牌并记录使用时间；已吊销、已过期或账号停用时返回 None
+    pub async fn authenticate(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<AccessTokenOwner>, StoreError> {
+        let token_hash = token_hash.to_string();
+        let now = Utc::now().timestamp();
+        type AuthRow = (i64, i64, String, String, i64, Option<i64>);
+        let row: Option<AuthRow> = match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.query_row(
+                        &sqlite_sql(AUTHENTICATE_SQL),
+                        params![token_hash, now],
+                        |row| {
+                            Ok((
+                                row.get(0)?,
+                                row.get(1)?,
+                                row.get(2)?,
+                                row.get(3)?,
+                                row.get(4)?,
+                                row.get(5)?,
+                            ))
+                        },
+                    )
+                    .optional()
+                })
+                .await?
+            }
+            Store::Postgres(pg) => {
+                sqlx::query_as(AUTHENTICATE_SQL)
+                    .bind(token_hash)
+                    .bind(now)
+                    .fetch_optional(pg)
+                    .await?
+            }
+        };
+        let Some((id, user_id, username, scopes, expires_at, last_used_at)) = row else {
+            return Ok(None);
+        };
+        if last_used_at.is_none_or(|at| at <= now - TOUCH_INTERVAL_SECS) {
+            match &self.store {
+                Store::Sqlite(pool) => {
+                    pool.run(move |conn| conn.execute(&sqlite_sql(TOUCH_SQL), params![now, id]))
+                        .await?;
+                }
+                Store::Postgres(pg) => {
+                    sqlx::query(TOUCH_SQL)
+                        .bind(now)
+                        .bind(id)
+                        .execute(pg)
+                        .await?;
+                }
+            }
+        }
+        Ok(Some(AccessTokenOwner {
+            id,
+            user_id,
+            username,
+            scopes: split_scopes(&scopes),
+            expires_at,
+        }))
+    }
+
+    // 登记新令牌；用户的有效令牌已达上限时返回 None
+    pub async fn create(
+        &self,
+        token: NewAccessToken,
+        max_per_user: usize,
+    ) -> Result<Option<AccessTokenInfo>, StoreError> {
+        let NewAccessToken {
+            user_id,
+            name,
+            token_hash,
+            prefix,
+            scopes,
+            expires_at,
+        } = token;
+        let scopes = scopes.join(" ");
+        let now = Utc::now().timestamp();
+        let max = max_per_user as i64;
+        let id: Option<i64> = match &self.store {
+            Store::Sqlite(pool) => {
+                let (name, prefix, scopes) = (name.clone(), prefix.clone(), scopes.clone());
+                pool.run(move |conn| {
+                    let tx = conn.transaction()?;
+                    let active: i64 = tx.query_row(
+                        &sqlite_sql(ACTIVE_COUNT_SQL),
+                        params![user_id, now],
+                        |row| row.get(0),
+                    )?;
+                    if active >= max {
+                        return Ok(None);
+                    }
+                    let id = tx.query_row(
+                        &sqlite_sql(INSERT_SQL),
+                        params![user_id, name, token_hash, prefix, scopes, now, expires_at],
+                        |row| row.get(0),
+                    )?;
+                    tx.commit()?;
+                    Ok(Some(id))
+                })
+                .await?
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let mut tx = conn.begin().await?;
+                // 锁住用户行，串行化同一用户的并发创建，保证不超过上限
+                sqlx::query(LOCK_USER_SQL)
+                    .bind(user_id)
+                    .execute(&mut *tx)
+                    .await?;
+                let active: i64 = sqlx::query_scalar(ACTIVE_COUNT_SQL)
+                    .bind(user_id)
+                    .bind(now)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                if active >= max {
+                    return Ok(None);
+                }
+                let id: i64 = sqlx::query_scalar(INSERT_SQL)
+                    .bind(user_id)
+                    .bind(&name)
+                    .bind(token_hash)
+                    .bind(&prefix)
+                    .bind(&scopes)
+                    .bind(now)
+                    .bind(expires_at)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                tx.commit().await?;
+                Some(id)
+            }
+        };
+        Ok(id.map(|id| token_info((id, name, prefix, scopes, now, expires_at, None))))
+    }
+
+    // 用户未吊销的令牌（含已过期的）
+    pub async fn list(&self, user_id: i64) -> Result<Vec<AccessTokenInfo>, StoreError> {
+        let rows: Vec<ListRow> = match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let mut stmt = conn.prepare(&sqlite_sql(LIST_SQL))?;
+                    let rows = stmt.query_map(params![user_id], |row| {
+                        Ok((
+                            row.get(0)?,
+                            row.get(1)?,
+                            row.get(2)?,
+                            row.get(3)?,
+                            row.get(4)?,
+                            row.get(5)?,
+                            row.get(6)?,
+                        ))
+                    })?;
+                    rows.collect()
+                })
+                .await?
+            }
+            Store::Postgres(pg) => sqlx::query_as(LIST_SQL).bind(user_id).fetch_all(pg).await?,
+        };
+        Ok(rows.into_iter().map(token_info).collect())
+    }
+
+    // 吊销用户自己的一个令牌；不存在或已吊销时返回 false
+    pub async fn revoke(&self, user_id: i64, id: i64) -> Result<bool, StoreError> {
+        let now = Utc::now().timestamp();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    Ok(conn.execute(&sqlite_sql(REVOKE_SQL), params![now, id, user_id])? > 0)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query(REVOKE_SQL)
+                    .bind(now)
+                    .bind(id)
+                    .bind(user_id)
+                    .execute(pg)
+                    .await?;
+                Ok(result.rows_affected() > 0)
+            }
+        }
+    }
+
+    // 吊销用户的全部令牌，返回吊销的数量
+    pub async fn revoke_user(&self, username: &str) -> Result<u64, StoreError> {
+        let username = username.to_string();
+        let now = Utc::now().timestamp();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    Ok(conn.execute(&sqlite_sql(REVOKE_USER_SQL), params![now, username])? as u64)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query(REVOKE_USER_SQL)
+                    .bind(now)
+                    .bind(username)
+                    .execute(pg)
+                    .await?;
+                Ok(result.rows_affected())
+            }
+        }
+    }
+}
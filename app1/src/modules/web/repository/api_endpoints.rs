@@ -0,0 +1,250 @@
+//! API 测试端点（`api_endpoints` 表）
+use rusqlite::{OptionalExtension, params};
+use sqlx::Row;
+use sqlx::postgres::PgRow;
+
+use super::pool::StoreError;
+use super::store::Store;
+use crate::modules::web::models::{
+    ApiEndpointBrief, ApiEndpointDetail, CreateEndpointPayload, UpdateEndpointPayload,
+};
+
+#[derive(Debug, Clone)]
+pub struct ApiEndpointRepository {
+    store: Store,
+}
+
+fn pg_detail(row: &PgRow) -> Result<ApiEndpointDetail, sqlx::Error> {
+    Ok(ApiEndpointDetail {
+        id: row.try_get(0)?,
+        parent_id: row.try_get(1)?,
+        name: row.try_get(2)?,
+        method: row.try_get(3)?,
+        url: row.try_get(4)?,
+        headers: row.try_get(5)?,
+        params: row.try_get(6)?,
+        body_type: row.try_get(7)?,
+        body: row.try_get(8)?,
+        content_type: row.try_get(9)?,
+        order_index: row.try_get::<Option<i32>, _>(10)?.unwrap_or(0),
+        created_at: row.try_get(11)?,
+        updated_at: row.try_get(12)?,
+    })
+}
+
+impl ApiEndpointRepository {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    pub async fn list(&self) -> Result<Vec<ApiEndpointBrief>, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(|conn| {
+                    let mut stmt = conn.prepare(
+                        "SELECT id, parent_id, name, method, url, order_index FROM api_endpoints ORDER BY parent_id IS NOT NULL, parent_id ASC, order_index ASC, id ASC",
+                    )?;
+                    let rows = stmt.query_map([], |row| {
+                        Ok(ApiEndpointBrief {
+                            id: row.get(0)?,
+                            parent_id: row.get::<_, Option<i64>>(1)?,
+                            name: row.get(2)?,
+                            method: row.get(3)?,
+                            url: row.get(4)?,
+                            order_index: row.get(5)?,
+                        })
+                    })?;
+                    rows.collect()
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let rows = sqlx::query(
+                    "SELECT id, parent_id, name, method, url, order_index FROM api_endpoints ORDER BY parent_id IS NOT NULL, parent_id ASC, order_index ASC, id ASC",
+                )
+                .fetch_all(pg)
+                .await?;
+                let list = rows
+                    .iter()
+                    .map(|row| {
+                        Ok(ApiEndpointBrief {
+                            id: row.try_get(0)?,
+                            parent_id: row.try_get(1)?,
+                            name: row.try_get(2)?,
+                            method: row.try_get(3)?,
+                            url: row.try_get(4)?,
+                            order_index: row.try_get::<Option<i32>, _>(5)?.unwrap_or(0),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, sqlx::Error>>()?;
+                Ok(list)
+            }
+        }
+    }
+
+    pub async fn get(&self, id: i64) -> Result<Option<ApiEndpointDetail>, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.query_row(
+                        "SELECT id, parent_id, name, method, url, headers, params, body_type, body, content_type, order_index, created_at, updated_at FROM api_endpoints WHERE id=?1",
+                        params![id],
+                        |row| {
+                            Ok(ApiEndpointDetail {
+                                id: row.get(0)?,
+                                parent_id: row.get(1)?,
+                                name: row.get(2)?,
+                                method: row.get(3)?,
+                                url: row.get(4)?,
+                                headers: row.get(5).ok(),
+                                params: row.get(6).ok(),
+                                body_type: row.get(7).ok(),
+                                body: row.get(8).ok(),
+                                content_type: row.get(9).ok(),
+                                order_index: row.get(10)?,
+                                created_at: row.get(11)?,
+                                updated_at: row.get(12)?,
+                            })
+                        },
+                    )
+                    .optional()
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let row = sqlx::query(
+                    "SELECT id, parent_id, name, method, url, headers, params, body_type, body, content_type, order_index, created_at, updated_at FROM api_endpoints WHERE id=$1",
+                )
+                .bind(id)
+                .fetch_optional(pg)
+                .await?;
+                Ok(row.as_ref().map(pg_detail).transpose()?)
+            }
+        }
+    }
+
+    // 新建端点，返回新 ID
+    pub async fn create(&self, payload: CreateEndpointPayload) -> Result<i64, StoreError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.execute(
+                        "INSERT INTO api_endpoints (parent_id, name, method, url, headers, params, body_type, body, content_type, order_index, created_at, updated_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11)",
+                        params![
+                            payload.parent_id,
+                            payload.name,
+                            payload.method,
+                            payload.url,
+                            payload.headers,
+                            payload.params,
+                            payload.body_type,
+                            payload.body,
+                            payload.content_type,
+                            payload.order_index.unwrap_or(0),
+                            now
+                        ],
+                    )?;
+                    Ok(conn.last_insert_rowid())
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let row = sqlx::query(
+                    "INSERT INTO api_endpoints (parent_id, name, method, url, headers, params, body_type, body, content_type, order_index, created_at, updated_at)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $11) RETURNING id",
+                )
+                .bind(payload.parent_id)
+                .bind(payload.name)
+                .bind(payload.method)
+                .bind(payload.url)
+                .bind(payload.headers)
+                .bind(payload.params)
+                .bind(payload.body_type)
+                .bind(payload.body)
+                .bind(payload.content_type)
+                .bind(payload.order_index.unwrap_or(0))
+                .bind(now)
+                .fetch_one(pg)
+                .await?;
+                Ok(row.try_get(0)?)
+            }
+        }
+    }
+
+    // 更新端点，返回是否存在
+    pub async fn update(
+        &self,
+        id: i64,
+        payload: UpdateEndpointPayload,
+    ) -> Result<bool, StoreError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let rows = conn.execute(
+                        "UPDATE api_endpoints SET parent_id=?1, name=?2, method=?3, url=?4, headers=?5, params=?6, body_type=?7, body=?8, content_type=?9, order_index=?10, updated_at=?11 WHERE id=?12",
+                        params![
+                            payload.parent_id,
+                            payload.name,
+                            payload.method,
+                            payload.url,
+                            payload.headers,
+                            payload.params,
+                            payload.body_type,
+                            payload.body,
+                            payload.content_type,
+                            payload.order_index.unwrap_or(0),
+                            now,
+                            id
+                        ],
+                    )?;
+                    Ok(rows > 0)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query(
+                    "UPDATE api_endpoints SET parent_id=$1, name=$2, method=$3, url=$4, headers=$5, params=$6, body_type=$7, body=$8, content_type=$9, order_index=$10, updated_at=$11 WHERE id=$12",
+                )
+                .bind(payload.parent_id)
+                .bind(payload.name)
+                .bind(payload.method)
+                .bind(payload.url)
+                .bind(payload.headers)
+                .bind(payload.params)
+                .bind(payload.body_type)
+                .bind(payload.body)
+                .bind(payload.content_type)
+                .bind(payload.order_index.unwrap_or(0))
+                .bind(now)
+                .bind(id)
+                .execute(pg)
+                .await?;
+                Ok(result.rows_affected() > 0)
+            }
+        }
+    }
+
+    // 删除端点，返回是否存在
+    pub async fn delete(&self, id: i64) -> Result<bool, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let rows =
+                        conn.execute("DELETE FROM api_endpoints WHERE id=?1", params![id])?;
+                    Ok(rows > 0)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query("DELETE FROM api_endpoints WHERE id=$1")
+                    .bind(id)
+                    .execute(pg)
+                    .await?;
+                Ok(result.rows_affected() > 0)
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! 审计日志（`audit_log` 表）
+//!
+//! 只提供追加与查询；表上的触发器拒绝 UPDATE / DELETE，记录一经写入不可修改。
+use chrono::Utc;
+use rusqlite::params;
+use sqlx::Row;
+
+use super::pool::StoreError;
+use super::store::{Store, sqlite_sql};
+use crate::modules::web::models::{AuditEntry, AuditQuery};
+
+/// 待写入的审计记录；error 为 None 表示操作成功
+#[derive(Debug, Clone)]
+pub struct NewAuditEntry {
+    pub username: String,
+    pub ip: Option<String>,
+    pub action: String,
+    pub target: String,
+    pub duration_ms: i64,
+    pub detail: Option<String>,
+    pub error: Option<String>,
+}
+
+pub const OUTCOME_SUCCESS: &str = "success";
+pub const OUTCOME_FAILURE: &str = "failure";
+
+const APPEND_SQL: &str = "INSERT INTO audit_log
+    (created_at, username, ip, action, target, outcome, duration_ms, detail, error)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)";
+// action 同时匹配子类型（$3 为 "action.%"）；target 按包含匹配
+const LIST_SQL: &str = "SELECT id, created_at, username, ip, action, target, outcome,
+        duration_ms, detail, error
+    FROM audit_log
+    WHERE ($1 IS NULL OR username = $1)
+        AND ($2 IS NULL OR action = $2 OR action LIKE $3 ESCAPE '\\')
+        AND ($4 IS NULL OR outcome = $4)
+        AND ($5 IS NULL OR ip = $5)
+        AND ($6 IS NULL OR target LIKE $6 ESCAPE '\\')
+        AND ($7 IS NULL OR created_at >= $7)
+        AND ($8 IS NULL OR created_at < $8)
+    ORDER BY id DESC LIMIT $9 OFFSET $10";
+
+// LIKE 模式中的通配符按字面匹配
+fn like_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditRepository {
+    store: Store,
+}
+
+impl AuditRepository {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    // 追加一条记录
+    pub async fn append(&self, entry: NewAuditEntry) -> Result<(), StoreError> {
+        let NewAuditEntry {
+            username,
+            ip,
+            action,
+            target,
+            duration_ms,
+            detail,
+            error,
+        } = entry;
+        let now = Utc::now().timestamp();
+        let outcome = if error.is_none() {
+            OUTCOME_SUCCESS
+        } else {
+            OUTCOME_FAILURE
+        };
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.execute(
+                        &sqlite_sql(APPEND_SQL),
+                        params![
+                            now,
+                            username,
+                            ip,
+                            action,
+                            target,
+                            outcome,
+                            duration_ms,
+                            detail,
+                            error
+                        ],
+                    )?;
+                    Ok(())
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                sqlx::query(APPEND_SQL)
+                    .bind(now)
+                    .bind(username)
+                    .bind(ip)
+                    .bind(action)
+                    .bind(target)
+                    .bind(outcome)
+                    .bind(duration_ms)
+                    .bind(detail)
+                    .bind(error)
+                    .execute(pg)
+                    .await?;
+                Ok(())
+            }
+        }
+    }
+
+    // 按条件查询，新的在前；query 中的 limit / offset 由调用方换算后传入
+    pub async fn list(
+        &self,
+        query: AuditQuery,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<AuditEntry>, StoreError> {
+        let action_children = query
+            .action
+            .as_deref()
+            .map(|action| format!("{}.%", like_escape(action)));
+        let target = query
+            .target
+            .as_deref()
+            .map(|target| format!("%{}%", like_escape(target)));
+        let AuditQuery {
+            username,
+            action,
+            outcome,
+            ip,
+            from,
+            to,
+            ..
+        } = query;
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.prepare(&sqlite_sql(LIST_SQL))?
+                        .query_map(
+                            params![
+                                username,
+                                action,
+                                action_children,
+                                outcome,
+                                ip,
+                                target,
+                                from,
+                                to,
+                                limit,
+                                offset
+                            ],
+                            |row| {
+                                Ok(AuditEntry {
+                                    id: row.get(0)?,
+                                    created_at: row.get(1)?,
+                                    username: row.get(2)?,
+                                    ip: row.get(3)?,
+                                    action: row.get(4)?,
+                                    target: row.get(5)?,
+                                    outcome: row.get(6)?,
+                                    duration_ms: row.get(7)?,
+                                    detail: row.get(8)?,
+                                    error: row.get(9)?,
+                                })
+                            },
+                        )?
+                        .collect()
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let rows = sqlx::query(LIST_SQL)
+                    .bind(username)
+                    .bind(action)
+                    .bind(action_children)
+                    .bind(outcome)
+                    .bind(ip)
+                    .bind(target)
+                    .bind(from)
+                    .bind(to)
+                    .bind(limit)
+                    .bind(offset)
+                    .fetch_all(pg)
+                    .await?;
+                let entries = rows
+                    .iter()
+                    .map(|row| {
+                        Ok(AuditEntry {
+                            id: row.try_get(0)?,
+                            created_at: row.try_get(1)?,
+                            username: row.try_get(2)?,
+                            ip: row.try_get(3)?,
+                            action: row.try_get(4)?,
+                            target: row.try_get(5)?,
+                            outcome: row.try_get(6)?,
+                            duration_ms: row.try_get(7)?,
+                            detail: row.try_get(8)?,
+                            error: row.try_get(9)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, sqlx::Error>>()?;
+                Ok(entries)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::web::database::Database;
+
+    fn entry(username: &str, action: &str, target: &str, error: Option<&str>) -> NewAuditEntry {
+        NewAuditEntry {
+            username: username.to_string(),
+            ip: Some("10.0.0.1".to_string()),
+            action: action.to_string(),
+            target: target.to_string(),
+            duration_ms: 5,
+            detail: None,
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn append_only_and_filters() {
+        let db = Database::new(":memory:").unwrap();
+        let audit = db.audit();
+        audit
+            .append(entry("admin", "sftp.delete", "/srv/app_1/log", None))
+            .await
+            .unwrap();
+        audit
+            .append(entry("admin", "sftp.session", "root@10.0.0.2:22", Some("auth")))
+            .await
+            .unwrap();
+        audit
+            .append(entry("user1", "sql.execute", "prod (postgresql)", None))
+            .await
+            .unwrap();
+
+        let all = audit.list(AuditQuery::default(), 100, 0).await.unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].action, "sql.execute");
+
+        let sftp = AuditQuery {
+            action: Some("sftp".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(audit.list(sftp, 100, 0).await.unwrap().len(), 2);
+        let failures = AuditQuery {
+            outcome: Some(OUTCOME_FAILURE.to_string()),
+            ..Default::default()
+        };
+        let failures = audit.list(failures, 100, 0).await.unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].error.as_deref(), Some("auth"));
+        // 通配符按字面匹配
+        let literal = AuditQuery {
+            target: Some("app_1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(audit.list(literal, 100, 0).await.unwrap().len(), 1);
+        let wildcard = AuditQuery {
+            target: Some("%".to_string()),
+            ..Default::default()
+        };
+        assert!(audit.list(wildcard, 100, 0).await.unwrap().is_empty());
+        assert_eq!(
+            audit.list(AuditQuery::default(), 1, 1).await.unwrap()[0].action,
+            "sftp.session"
+        );
+
+        // 触发器拒绝修改与删除
+        let Store::Sqlite(pool) = &audit.store else {
+            unreachable!()
+        };
+        let conn = pool.get().unwrap();
+        assert!(conn.execute("UPDATE audit_log SET outcome = 'x'", []).is_err());
+        assert!(conn.execute("DELETE FROM audit_log", []).is_err());
+    }
+}
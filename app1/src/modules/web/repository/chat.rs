@@ -0,0 +1,350 @@
+//! 聊天会话与消息（`ws_session` / `ws_message` 表）
+//!
+//! ChatServer 是同步 Actor，写操作通过 [`ChatWriter`] 交给后台任务按顺序落库，不阻塞 Actor；
+//! 读操作使用 [`ChatRepository`] 的异步方法。
+use chrono::Utc;
+use rusqlite::{Connection, params};
+use sqlx::Row;
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+use super::pool::StoreError;
+use super::store::Store;
+use crate::log_error;
+
+pub type SessionRow = (String, Option<String>, Option<String>);
+pub type MessageRow = (String, String, Option<String>, String, String);
+
+pub fn save_session(
+    conn: &Connection,
+    session_id: &str,
+    username: Option<&str>,
+    room: Option<&str>,
+) -> rusqlite::Result<()> {
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT OR REPLACE INTO ws_session (session_id, username, room, connected_at, last_active) \
+         VALUES (?1, ?2, ?3, ?4, ?4)",
+        params![session_id, username, room, now],
+    )?;
+    Ok(())
+}
+
+pub fn load_sessions(conn: &Connection) -> rusqlite::Result<Vec<SessionRow>> {
+    let mut stmt = conn.prepare("SELECT session_id, username, room FROM ws_session")?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    rows.collect()
+}
+
+pub fn remove_session(conn: &Connection, session_id: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM ws_session WHERE session_id = ?1",
+        params![session_id],
+    )?;
+    Ok(())
+}
+
+pub fn save_message(
+    conn: &Connection,
+    session_id: &str,
+    username: &str,
+    room: Option<&str>,
+    content: &str,
+) -> rusqlite::Result<()> {
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO ws_message (session_id, username, room, content, timestamp) \
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![session_id, username, room, content, now],
+    )?;
+    Ok(())
+}
+
+// 加载消息列表（最早的消息在前）
+pub fn load_messages(
+    conn: &Connection,
+    room: Option<&str>,
+    limit: i32,
+) -> rusqlite::Result<Vec<MessageRow>> {
+    let map_row = |row: &rusqlite::Row| {
+        Ok((
+            row.get(0)?,
+            row.get(1)?,
+            row.get(2)?,
+            row.get(3)?,
+            row.get(4)?,
+        ))
+    };
+    let mut messages = match room {
+        Some(r) => {
+            let mut stmt = conn.prepare(
+                "SELECT session_id, username, room, content, timestamp FROM ws_message \
+                 WHERE room = ?1 ORDER BY timestamp DESC LIMIT ?2",
+            )?;
+            stmt.query_map(params![r, limit], map_row)?
+                .collect::<rusqlite::Result<Vec<MessageRow>>>()?
+        }
+        None => {
+            let mut stmt = conn.prepare(
+                "SELECT session_id, username, room, content, timestamp FROM ws_message \
+                 ORDER BY timestamp DESC LIMIT ?1",
+            )?;
+            stmt.query_map(params![limit], map_row)?
+                .collect::<rusqlite::Result<Vec<MessageRow>>>()?
+        }
+    };
+    messages.reverse();
+    Ok(messages)
+}
+
+#[derive(Debug, Clone)]
+pub struct ChatRepository {
+    store: Store,
+}
+
+impl ChatRepository {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    pub async fn load_sessions(&self) -> Result<Vec<SessionRow>, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => pool.run(|conn| load_sessions(conn)).await,
+            Store::Postgres(pg) => {
+                let rows = sqlx::query("SELECT session_id, username, room FROM ws_session")
+                    .fetch_all(pg)
+                    .await?;
+                let sessions = rows
+                    .iter()
+                    .map(|row| Ok((row.try_get(0)?, row.try_get(1)?, row.try_get(2)?)))
+                    .collect::<Result<Vec<_>, sqlx::Error>>()?;
+                Ok(sessions)
+            }
+        }
+    }
+
+    pub async fn load_messages(
+        &self,
+        room: Option<String>,
+        limit: i32,
+    ) -> Result<Vec<MessageRow>, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| load_messages(conn, room.as_deref(), limit))
+                    .await
+            }
+            Store::Postgres(pg) => {
+                let query = match &room {
+                    Some(r) => sqlx::query(
+                        "SELECT session_id, username, room, content, timestamp FROM ws_message \
+                         WHERE room = $1 ORDER BY timestamp DESC LIMIT $2",
+                    )
+                    .bind(r)
+                    .bind(i64::from(limit)),
+                    None => sqlx::query(
+                        "SELECT session_id, username, room, content, timestamp FROM ws_message \
+                         ORDER BY timestamp DESC LIMIT $1",
+                    )
+                    .bind(i64::from(limit)),
+                };
+                let rows = query.fetch_all(pg).await?;
+                let mut messages = rows
+                    .iter()
+                    .map(|row| {
+                        Ok((
+                            row.try_get(0)?,
+                            row.try_get(1)?,
+                            row.try_get(2)?,
+                            row.try_get(3)?,
+                            row.try_get(4)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<MessageRow>, sqlx::Error>>()?;
+                messages.reverse();
+                Ok(messages)
+            }
+        }
+    }
+
+    pub async fn save_session(
+        &self,
+        session_id: String,
+        username: Option<String>,
+        room: Option<String>,
+    ) -> Result<(), StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    save_session(conn, &session_id, username.as_deref(), room.as_deref())
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let now = Utc::now().to_rfc3339();
+                sqlx::query(
+                    "INSERT INTO ws_session (session_id, username, room, connected_at, last_active) \
+                     VALUES ($1, $2, $3, $4, $4) \
+                     ON CONFLICT (session_id) DO UPDATE SET username = EXCLUDED.username, \
+                     room = EXCLUDED.room, connected_at = EXCLUDED.connected_at, last_active = EXCLUDED.last_active",
+                )
+                .bind(session_id)
+                .bind(username)
+                .bind(room)
+                .bind(now)
+                .execute(pg)
+                .await?;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn remove_session(&self, session_id: String) -> Result<(), StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| remove_session(conn, &session_id))
+                    .await
+            }
+            Store::Postgres(pg) => {
+                sqlx::query("DELETE FROM ws_session WHERE session_id = $1")
+                    .bind(session_id)
+                    .execute(pg)
+                    .await?;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn save_message(
+        &self,
+        session_id: String,
+        username: String,
+        room: Option<String>,
+        content: String,
+    ) -> Result<(), StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    save_message(conn, &session_id, &username, room.as_deref(), &content)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let now = Utc::now().to_rfc3339();
+                sqlx::query(
+                    "INSERT INTO ws_message (session_id, username, room, content, timestamp) \
+                     VALUES ($1, $2, $3, $4, $5)",
+                )
+                .bind(session_id)
+                .bind(username)
+                .bind(room)
+                .bind(content)
+                .bind(now)
+                .execute(pg)
+                .await?;
+                Ok(())
+            }
+        }
+    }
+
+    async fn apply(&self, op: ChatWrite) -> Result<(), StoreError> {
+        match op {
+            ChatWrite::SaveSession {
+                session_id,
+                username,
+                room,
+            } => self.save_session(session_id, username, room).await,
+            ChatWrite::RemoveSession { session_id } => self.remove_session(session_id).await,
+            ChatWrite::SaveMessage {
+                session_id,
+                username,
+                room,
+                content,
+            } => self.save_message(session_id, username, room, content).await,
+        }
+    }
+
+    // 启动后台写任务（需在 tokio 运行时内调用）
+    pub fn writer(&self) -> ChatWriter {
+        let (tx, mut rx) = mpsc::unbounded_channel::<ChatWrite>();
+        let repo = self.clone();
+        tokio::spawn(async move {
+            while let Some(op) = rx.recv().await {
+                let kind = op.kind();
+                if let Err(err) = repo.apply(op).await {
+                    log_error!("聊天记录写入失败 ({}): {}", kind, err);
+                }
+            }
+        });
+        ChatWriter { tx }
+    }
+}
+
+#[derive(Debug)]
+enum ChatWrite {
+    SaveSession {
+        session_id: String,
+        username: Option<String>,
+        room: Option<String>,
+    },
+    RemoveSession {
+        session_id: String,
+    },
+    SaveMessage {
+        session_id: String,
+        username: String,
+        room: Option<String>,
+        content: String,
+    },
+}
+
+impl ChatWrite {
+    fn kind(&self) -> &'static str {
+        match self {
+            ChatWrite::SaveSession { .. } => "save session",
+            ChatWrite::RemoveSession { .. } => "remove session",
+            ChatWrite::SaveMessage { .. } => "save message",
+        }
+    }
+}
+
+/// 聊天记录写入句柄：提交后立即返回，由后台任务按提交顺序写入
+#[derive(Debug, Clone)]
+pub struct ChatWriter {
+    tx: UnboundedSender<ChatWrite>,
+}
+
+impl ChatWriter {
+    fn send(&self, op: ChatWrite) {
+        if self.tx.send(op).is_err() {
+            log_error!("聊天记录写入任务已退出");
+        }
+    }
+
+    pub fn save_session(&self, session_id: &str, username: Option<&str>, room: Option<&str>) {
+        self.send(ChatWrite::SaveSession {
+            session_id: session_id.to_string(),
+            username: username.map(str::to_string),
+            room: room.map(str::to_string),
+        });
+    }
+
+    pub fn remove_session(&self, session_id: &str) {
+        self.send(ChatWrite::RemoveSession {
+            session_id: session_id.to_string(),
+        });
+    }
+
+    pub fn save_message(
+        &self,
+        session_id: &str,
+        username: &str,
+        room: Option<&str>,
+        content: &str,
+    ) {
+        self.send(ChatWrite::SaveMessage {
+            session_id: session_id.to_string(),
+            username: username.to_string(),
+            room: room.map(str::to_string),
+            content: content.to_string(),
+        });
+    }
+}
@@ -0,0 +1,227 @@
+//! 登录保护：账号锁定状态（`login_lockouts`）与失败记录（`login_failures`）
+//!
+//! 锁定按登录时输入的用户名记录，不存在的账号同样会被锁定，避免通过响应差异探测用户名。
+use chrono::Utc;
+use rusqlite::{OptionalExtension, params};
+use sqlx::Row;
+
+use super::pool::StoreError;
+use super::store::{Store, sqlite_sql};
+use crate::modules::config::config::LoginProtectionConfig;
+use crate::modules::web::models::LoginFailure;
+
+// 窗口内累计失败次数，窗口过期后从 1 重新计数
+const COUNT_FAILURE_SQL: &str =
+    "INSERT INTO login_lockouts (username, failures, window_start) VALUES ($1, 1, $2)
+    ON CONFLICT (username) DO UPDATE SET
+        failures = CASE WHEN login_lockouts.window_start > $3
+            THEN login_lockouts.failures + 1 ELSE 1 END,
+        window_start = CASE WHEN login_lockouts.window_start > $3
+            THEN login_lockouts.window_start ELSE $2 END
+    RETURNING failures";
+// 锁定后清零计数，解锁后重新获得完整的尝试次数
+const LOCK_SQL: &str =
+    "UPDATE login_lockouts SET locked_until = $1, failures = 0, window_start = $2
+    WHERE username = $3";
+const LOCKED_UNTIL_SQL: &str = "SELECT locked_until FROM login_lockouts WHERE username = $1";
+const CLEAR_SQL: &str = "DELETE FROM login_lockouts WHERE username = $1";
+const LOG_FAILURE_SQL: &str =
+    "INSERT INTO login_failures (username, ip, reason, created_at) VALUES ($1, $2, $3, $4)";
+const PURGE_FAILURES_SQL: &str = "DELETE FROM login_failures WHERE created_at < $1";
+const LIST_FAILURES_SQL: &str = "SELECT id, username, ip, reason, created_at FROM login_failures
+    WHERE ($1 IS NULL OR username = $1) ORDER BY id DESC LIMIT $2";
+
+#[derive(Debug, Clone)]
+pub struct LoginAttemptRepository {
+    store: Store,
+}
+
+impl LoginAttemptRepository {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    // 账号处于锁定期时返回剩余秒数
+    pub async fn locked_for(&self, username: &str) -> Result<Option<i64>, StoreError> {
+        let username = username.to_string();
+        let locked_until: Option<Option<i64>> = match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.query_row(&sqlite_sql(LOCKED_UNTIL_SQL), params![username], |row| {
+                        row.get(0)
+                    })
+                    .optional()
+                })
+                .await?
+            }
+            Store::Postgres(pg) => {
+                sqlx::query_scalar(LOCKED_UNTIL_SQL)
+                    .bind(username)
+                    .fetch_optional(pg)
+                    .await?
+            }
+        };
+        let now = Utc::now().timestamp();
+        Ok(locked_until
+            .flatten()
+            .filter(|until| *until > now)
+            .map(|until| until - now))
+    }
+
+    // 累计一次密码错误；达到上限时锁定账号并返回锁定秒数
+    pub async fn count_failure(
+        &self,
+        username: &str,
+        policy: &LoginProtectionConfig,
+    ) -> Result<Option<i64>, StoreError> {
+        if policy.max_failures == 0 {
+            return Ok(None);
+        }
+        let username = username.to_string();
+        let now = Utc::now().timestamp();
+        let window_start = now - policy.failure_window_secs;
+        let locked_until = now + policy.lockout_secs;
+        let max_failures = policy.max_failures as i64;
+        let locked = match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let tx = conn.transaction()?;
+                    let failures: i64 = tx.query_row(
+                        &sqlite_sql(COUNT_FAILURE_SQL),
+                        params![username, now, window_start],
+                        |row| row.get(0),
+                    )?;
+                    let locked = failures >= max_failures;
+                    if locked {
+                        tx.execute(&sqlite_sql(LOCK_SQL), params![locked_until, now, username])?;
+                    }
+                    tx.commit()?;
+                    Ok(locked)
+                })
+                .await?
+            }
+            Store::Postgres(pg) => {
+                let failures: i32 = sqlx::query_scalar(COUNT_FAILURE_SQL)
+                    .bind(&username)
+                    .bind(now)
+                    .bind(window_start)
+                    .fetch_one(pg)
+                    .await?;
+                let locked = failures as i64 >= max_failures;
+                if locked {
+                    sqlx::query(LOCK_SQL)
+                        .bind(locked_until)
+                        .bind(now)
+                        .bind(&username)
+                        .execute(pg)
+                        .await?;
+                }
+                locked
+            }
+        };
+        Ok(locked.then_some(policy.lockout_secs))
+    }
+
+    // 登录成功或管理员解锁：清除失败计数与锁定，返回是否存在记录
+    pub async fn clear(&self, username: &str) -> Result<bool, StoreError> {
+        let username = username.to_string();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    Ok(conn.execute(&sqlite_sql(CLEAR_SQL), params![username])? > 0)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query(CLEAR_SQL).bind(username).execute(pg).await?;
+                Ok(result.rows_affected() > 0)
+            }
+        }
+    }
+
+    // 写入一条失败记录，并清理超过保留期的记录
+    pub async fn log_failure(
+        &self,
+        username: &str,
+        ip: Option<&str>,
+        reason: &'static str,
+        retention_days: i64,
+    ) -> Result<(), StoreError> {
+        let (username, ip) = (username.to_string(), ip.map(str::to_string));
+        let now = Utc::now().timestamp();
+        let cutoff = now - retention_days * 24 * 3600;
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.execute(
+                        &sqlite_sql(LOG_FAILURE_SQL),
+                        params![username, ip, reason, now],
+                    )?;
+                    conn.execute(&sqlite_sql(PURGE_FAILURES_SQL), params![cutoff])?;
+                    Ok(())
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                sqlx::query(LOG_FAILURE_SQL)
+                    .bind(username)
+                    .bind(ip)
+                    .bind(reason)
+                    .bind(now)
+                    .execute(pg)
+                    .await?;
+                sqlx::query(PURGE_FAILURES_SQL)
+                    .bind(cutoff)
+                    .execute(pg)
+                    .await?;
+                Ok(())
+            }
+        }
+    }
+
+    // 最近的失败记录，新的在前
+    pub async fn list_failures(
+        &self,
+        username: Option<String>,
+        limit: i64,
+    ) -> Result<Vec<LoginFailure>, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.prepare(&sqlite_sql(LIST_FAILURES_SQL))?
+                        .query_map(params![username, limit], |row| {
+                            Ok(LoginFailure {
+                                id: row.get(0)?,
+                                username: row.get(1)?,
+                                ip: row.get(2)?,
+                                reason: row.get(3)?,
+                                created_at: row.get(4)?,
+                            })
+                        })?
+                        .collect()
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let rows = sqlx::query(LIST_FAILURES_SQL)
+                    .bind(username)
+                    .bind(limit)
+                    .fetch_all(pg)
+                    .await?;
+                let failures = rows
+                    .iter()
+                    .map(|row| {
+                        Ok(LoginFailure {
+                            id: row.try_get(0)?,
+                            username: row.try_get(1)?,
+                            ip: row.try_get(2)?,
+                            reason: row.try_get(3)?,
+                            created_at: row.try_get(4)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, sqlx::Error>>()?;
+                Ok(failures)
+            }
+        }
+    }
+}
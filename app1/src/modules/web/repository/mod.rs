@@ -0,0 +1,33 @@
+//! 元数据库（rsts.db）仓储层：按领域封装读写，所有模块共用 Database 持有的连接池
+//! 每个仓储同时支持 SQLite 与 PostgreSQL 两种后端（见 store.rs）
+pub mod access_tokens;
+pub mod api_endpoints;
+pub mod audit;
+pub mod chat;
+pub mod login_attempts;
+pub mod oidc_identities;
+pub mod pool;
+pub mod rbac;
+pub mod refresh_tokens;
+pub mod sql_connections;
+pub mod ssh_servers;
+pub mod store;
+pub mod totp;
+pub mod transfer;
+pub mod users;
+
+pub use access_tokens::{AccessTokenOwner, AccessTokenRepository, NewAccessToken};
+pub use api_endpoints::ApiEndpointRepository;
+pub use audit::{AuditRepository, NewAuditEntry};
+pub use chat::{ChatRepository, ChatWriter};
+pub use login_attempts::LoginAttemptRepository;
+pub use oidc_identities::OidcIdentityRepository;
+pub use pool::{PooledConnection, SqlitePool, StoreError};
+pub use rbac::{RbacOutcome, RbacRepository};
+pub use refresh_tokens::{RefreshTokenRepository, RotateOutcome};
+pub use sql_connections::SqlConnectionRepository;
+pub use ssh_servers::{DeleteGroupOutcome, SshServerRepository};
+pub use store::Store;
+pub use totp::{TotpRepository, TotpState};
+pub use transfer::{TransferReport, sqlite_to_postgres};
+pub use users::UserRepository;
@@ -0,0 +1,89 @@
+//! 单点登录身份（`oidc_identities` 表）
+//!
+//! 用户按 IdP 的 (provider, subject) 识别，而不是按用户名：IdP 中的用户名可能变化或与本地账号重名。
+use chrono::Utc;
+use rusqlite::{OptionalExtension, params};
+
+use super::pool::StoreError;
+use super::store::{Store, sqlite_sql};
+
+const FIND_USER_SQL: &str = "SELECT u.username FROM oidc_identities i
+    JOIN users u ON u.id = i.user_id
+    WHERE i.provider = $1 AND i.subject = $2";
+const LINK_SQL: &str = "INSERT INTO oidc_identities (provider, subject, user_id, email, created_at)
+    SELECT $1, $2, id, $3, $4 FROM users WHERE username = $5
+    ON CONFLICT (provider, subject) DO NOTHING";
+
+#[derive(Debug, Clone)]
+pub struct OidcIdentityRepository {
+    store: Store,
+}
+
+impl OidcIdentityRepository {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    // 已关联的本地用户名
+    pub async fn find_user(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<String>, StoreError> {
+        let (provider, subject) = (provider.to_string(), subject.to_string());
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.query_row(
+                        &sqlite_sql(FIND_USER_SQL),
+                        params![provider, subject],
+                        |row| row.get(0),
+                    )
+                    .optional()
+                })
+                .await
+            }
+            Store::Postgres(pg) => Ok(sqlx::query_scalar(FIND_USER_SQL)
+                .bind(provider)
+                .bind(subject)
+                .fetch_optional(pg)
+                .await?),
+        }
+    }
+
+    // 把 IdP 身份关联到本地用户；用户不存在或身份已关联时返回 false
+    pub async fn link(
+        &self,
+        provider: &str,
+        subject: &str,
+        username: &str,
+        email: Option<String>,
+    ) -> Result<bool, StoreError> {
+        let (provider, subject) = (provider.to_string(), subject.to_string());
+        let username = username.to_string();
+        let now = Utc::now().to_rfc3339();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let count = conn.execute(
+                        &sqlite_sql(LINK_SQL),
+                        params![provider, subject, email, now, username],
+                    )?;
+                    Ok(count > 0)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query(LINK_SQL)
+                    .bind(provider)
+                    .bind(subject)
+                    .bind(email)
+                    .bind(now)
+                    .bind(username)
+                    .execute(pg)
+                    .await?;
+                Ok(result.rows_affected() > 0)
+            }
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! rsts.db 连接池
+//!
+//! 连接按需创建、用完归还，数量不超过 `max_size`；新连接统一开启 WAL、外键约束与忙等待。
+//! rusqlite 为阻塞调用，异步代码通过 [`SqlitePool::run`] 放到阻塞线程池执行。
+use rusqlite::{Connection, ffi};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// 获取连接的最长等待时间
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(10);
+/// 写锁冲突时的忙等待时间
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("PostgreSQL error: {0}")]
+    Postgres(#[from] sqlx::Error),
+    #[error("Blocking task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
+    #[error("Password hashing failed: {0}")]
+    Hash(#[from] bcrypt::BcryptError),
+    #[error("{0}")]
+    Precondition(String),
+}
+
+#[derive(Debug)]
+struct PoolState {
+    idle: Vec<Connection>,
+    total: usize,
+}
+
+#[derive(Debug)]
+pub struct SqlitePool {
+    path: String,
+    max_size: usize,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+impl SqlitePool {
+    pub fn new(path: &str, max_size: usize) -> Self {
+        // 内存库每个连接都是独立的数据库，只能共用一个连接
+        let max_size = if path == ":memory:" {
+            1
+        } else {
+            max_size.max(1)
+        };
+        Self {
+            path: path.to_string(),
+            max_size,
+            state: Mutex::new(PoolState {
+                idle: Vec::new(),
+                total: 0,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn open_connection(&self) -> rusqlite::Result<Connection> {
+        let conn = Connection::open(&self.path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        // journal_mode 会返回结果行，不能用 execute
+        conn.query_row("PRAGMA journal_mode = WAL", [], |row| {
+            row.get::<_, String>(0)
+        })?;
+        conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA synchronous = NORMAL;")?;
+        Ok(conn)
+    }
+
+    // 取出一个连接（阻塞），池满时等待其他连接归还
+    pub fn get(&self) -> rusqlite::Result<PooledConnection<'_>> {
+        let deadline = Instant::now() + ACQUIRE_TIMEOUT;
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(conn) = state.idle.pop() {
+                return Ok(PooledConnection {
+                    pool: self,
+                    conn: Some(conn),
+                });
+            }
+            if state.total < self.max_size {
+                state.total += 1;
+                drop(state);
+                return match self.open_connection() {
+                    Ok(conn) => Ok(PooledConnection {
+                        pool: self,
+                        conn: Some(conn),
+                    }),
+                    Err(e) => {
+                        self.state.lock().unwrap().total -= 1;
+                        self.available.notify_one();
+                        Err(e)
+                    }
+                };
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(rusqlite::Error::SqliteFailure(
+                    ffi::Error::new(ffi::SQLITE_BUSY),
+                    Some("timed out waiting for a pooled connection".to_string()),
+                ));
+            }
+            state = self
+                .available
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
+
+    // 在阻塞线程池中取连接并执行 f
+    pub async fn run<T, F>(self: &Arc<Self>, f: F) -> Result<T, StoreError>
+    where
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            f(&mut conn)
+        })
+        .await?;
+        Ok(result?)
+    }
+
+    fn release(&self, conn: Connection) {
+        self.state.lock().unwrap().idle.push(conn);
+        self.available.notify_one();
+    }
+}
+
+/// 从池中借出的连接，离开作用域时自动归还
+pub struct PooledConnection<'a> {
+    pool: &'a SqlitePool,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.release(conn);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn pool_reuses_connections_in_wal_mode() {
+        let dir = std::env::temp_dir().join(format!("rsts_pool_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("pool.db");
+        let pool = Arc::new(SqlitePool::new(path.to_str().unwrap(), 2));
+
+        let mode: String = pool
+            .run(|conn| conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)))
+            .await
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        {
+            let a = pool.get().unwrap();
+            let _b = pool.get().unwrap();
+            a.execute_batch("CREATE TABLE t (v INTEGER)").unwrap();
+        }
+        assert_eq!(pool.state.lock().unwrap().total, 2);
+        pool.run(|conn| conn.execute("INSERT INTO t VALUES (1)", []))
+            .await
+            .unwrap();
+        assert_eq!(pool.state.lock().unwrap().total, 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
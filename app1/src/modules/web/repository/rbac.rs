@@ -0,0 +1,363 @@
+//! 角色与权限（`roles` / `role_permissions` / `user_roles` 表）
+use rusqlite::{OptionalExtension, Transaction, params};
+use sqlx::{Connection as _, PgConnection, Row};
+use std::collections::BTreeMap;
+
+use super::pool::StoreError;
+use super::store::Store;
+use crate::modules::web::models::{Role, RoleInput};
+
+const USER_PERMISSIONS_SQL: &str = "SELECT DISTINCT rp.permission FROM users u
+    JOIN user_roles ur ON ur.user_id = u.id
+    JOIN role_permissions rp ON rp.role_id = ur.role_id
+    WHERE u.username = $1 ORDER BY rp.permission";
+const USER_ROLES_SQL: &str = "SELECT r.name FROM users u
+    JOIN user_roles ur ON ur.user_id = u.id
+    JOIN roles r ON r.id = ur.role_id
+    WHERE u.username = $1 ORDER BY r.id";
+// 停用的账号不算管理员
+const ADMIN_COUNT_SQL: &str = "SELECT COUNT(*) FROM user_roles ur
+    JOIN role_permissions rp ON rp.role_id = ur.role_id
+    JOIN users u ON u.id = ur.user_id
+    WHERE rp.permission = 'admin' AND u.disabled = 0";
+
+/// 修改用户、角色或分配角色的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RbacOutcome {
+    Done,
+    /// 用户或角色不存在
+    NotFound,
+    /// 用户名已存在
+    AlreadyExists,
+    /// 引用了不存在的角色
+    UnknownRole(String),
+    /// 修改后将没有任何用户拥有 admin 权限
+    NoAdminLeft,
+}
+
+#[derive(Debug, Clone)]
+pub struct RbacRepository {
+    store: Store,
+}
+
+// rusqlite 使用 ?N 占位符，共用 SQL 时替换 $N
+fn sqlite_sql(sql: &str) -> String {
+    sql.replace('$', "?")
+}
+
+pub(super) fn sqlite_has_admin(tx: &Transaction) -> rusqlite::Result<bool> {
+    let count: i64 = tx.query_row(ADMIN_COUNT_SQL, [], |row| row.get(0))?;
+    Ok(count > 0)
+}
+
+pub(super) async fn pg_has_admin(conn: &mut PgConnection) -> Result<bool, sqlx::Error> {
+    let count: i64 = sqlx::query_scalar(ADMIN_COUNT_SQL)
+        .fetch_one(&mut *conn)
+        .await?;
+    Ok(count > 0)
+}
+
+// 替换用户的角色，返回第一个不存在的角色名
+pub(super) fn sqlite_assign_roles(
+    tx: &Transaction,
+    user_id: i64,
+    roles: &[String],
+) -> rusqlite::Result<Option<String>> {
+    tx.execute(
+        "DELETE FROM user_roles WHERE user_id = ?1",
+        params![user_id],
+    )?;
+    for role in roles {
+        let role_id: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM roles WHERE name = ?1",
+                params![role],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(role_id) = role_id else {
+            return Ok(Some(role.clone()));
+        };
+        tx.execute(
+            "INSERT OR IGNORE INTO user_roles (user_id, role_id) VALUES (?1, ?2)",
+            params![user_id, role_id],
+        )?;
+    }
+    Ok(None)
+}
+
+pub(super) async fn pg_assign_roles(
+    conn: &mut PgConnection,
+    user_id: i64,
+    roles: &[String],
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query("DELETE FROM user_roles WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    for role in roles {
+        let role_id: Option<i64> = sqlx::query_scalar("SELECT id FROM roles WHERE name = $1")
+            .bind(role)
+            .fetch_optional(&mut *conn)
+            .await?;
+        let Some(role_id) = role_id else {
+            return Ok(Some(role.clone()));
+        };
+        sqlx::query(
+            "INSERT INTO user_roles (user_id, role_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        )
+        .bind(user_id)
+        .bind(role_id)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(None)
+}
+
+fn group_roles(
+    roles: Vec<(i64, String, Option<String>)>,
+    permissions: Vec<(i64, String)>,
+) -> Vec<Role> {
+    let mut by_role: BTreeMap<i64, Vec<String>> = BTreeMap::new();
+    for (role_id, permission) in permissions {
+        by_role.entry(role_id).or_default().push(permission);
+    }
+    roles
+        .into_iter()
+        .map(|(id, name, description)| Role {
+            name,
+            description,
+            permissions: by_role.remove(&id).unwrap_or_default(),
+        })
+        .collect()
+}
+
+impl RbacRepository {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    async fn strings_for_user(
+        &self,
+        sql: &'static str,
+        username: &str,
+    ) -> Result<Vec<String>, StoreError> {
+        let username = username.to_string();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let mut stmt = conn.prepare(&sqlite_sql(sql))?;
+                    let rows = stmt.query_map(params![username], |row| row.get(0))?;
+                    rows.collect()
+                })
+                .await
+            }
+            Store::Postgres(pg) => Ok(sqlx::query_scalar(sql).bind(username).fetch_all(pg).await?),
+        }
+    }
+
+    // 用户通过角色获得的全部权限
+    pub async fn user_permissions(&self, username: &str) -> Result<Vec<String>, StoreError> {
+        self.strings_for_user(USER_PERMISSIONS_SQL, username).await
+    }
+
+    pub async fn user_roles(&self, username: &str) -> Result<Vec<String>, StoreError> {
+        self.strings_for_user(USER_ROLES_SQL, username).await
+    }
+
+    pub async fn list_roles(&self) -> Result<Vec<Role>, StoreError> {
+        let roles_sql = "SELECT id, name, description FROM roles ORDER BY id";
+        let perms_sql = "SELECT role_id, permission FROM role_permissions ORDER BY permission";
+        let (roles, permissions) = match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let roles = conn
+                        .prepare(roles_sql)?
+                        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                        .collect::<rusqlite::Result<Vec<_>>>()?;
+                    let permissions = conn
+                        .prepare(perms_sql)?
+                        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                        .collect::<rusqlite::Result<Vec<_>>>()?;
+                    Ok((roles, permissions))
+                })
+                .await?
+            }
+            Store::Postgres(pg) => {
+                let roles = sqlx::query(roles_sql)
+                    .fetch_all(pg)
+                    .await?
+                    .iter()
+                    .map(|row| Ok((row.try_get(0)?, row.try_get(1)?, row.try_get(2)?)))
+                    .collect::<Result<Vec<_>, sqlx::Error>>()?;
+                let permissions = sqlx::query(perms_sql)
+                    .fetch_all(pg)
+                    .await?
+                    .iter()
+                    .map(|row| Ok((row.try_get(0)?, row.try_get(1)?)))
+                    .collect::<Result<Vec<_>, sqlx::Error>>()?;
+                (roles, permissions)
+            }
+        };
+        Ok(group_roles(roles, permissions))
+    }
+
+    // 新建或更新角色（按名称），权限列表整体替换
+    pub async fn save_role(&self, input: RoleInput) -> Result<RbacOutcome, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let tx = conn.transaction()?;
+                    tx.execute(
+                        "INSERT INTO roles (name, description) VALUES (?1, ?2)
+                         ON CONFLICT (name) DO UPDATE SET description = excluded.description",
+                        params![input.name, input.description],
+                    )?;
+                    let role_id: i64 = tx.query_row(
+                        "SELECT id FROM roles WHERE name = ?1",
+                        params![input.name],
+                        |row| row.get(0),
+                    )?;
+                    tx.execute(
+                        "DELETE FROM role_permissions WHERE role_id = ?1",
+                        params![role_id],
+                    )?;
+                    for permission in &input.permissions {
+                        tx.execute(
+                            "INSERT OR IGNORE INTO role_permissions (role_id, permission) VALUES (?1, ?2)",
+                            params![role_id, permission],
+                        )?;
+                    }
+                    if !sqlite_has_admin(&tx)? {
+                        return Ok(RbacOutcome::NoAdminLeft);
+                    }
+                    tx.commit()?;
+                    Ok(RbacOutcome::Done)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let mut tx = conn.begin().await?;
+                let role_id: i64 = sqlx::query_scalar(
+                    "INSERT INTO roles (name, description) VALUES ($1, $2)
+                     ON CONFLICT (name) DO UPDATE SET description = EXCLUDED.description
+                     RETURNING id",
+                )
+                .bind(&input.name)
+                .bind(&input.description)
+                .fetch_one(&mut *tx)
+                .await?;
+                sqlx::query("DELETE FROM role_permissions WHERE role_id = $1")
+                    .bind(role_id)
+                    .execute(&mut *tx)
+                    .await?;
+                for permission in &input.permissions {
+                    sqlx::query(
+                        "INSERT INTO role_permissions (role_id, permission) VALUES ($1, $2)
+                         ON CONFLICT DO NOTHING",
+                    )
+                    .bind(role_id)
+                    .bind(permission)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                if !pg_has_admin(&mut tx).await? {
+                    return Ok(RbacOutcome::NoAdminLeft);
+                }
+                tx.commit().await?;
+                Ok(RbacOutcome::Done)
+            }
+        }
+    }
+
+    pub async fn delete_role(&self, name: String) -> Result<RbacOutcome, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let tx = conn.transaction()?;
+                    let rows = tx.execute("DELETE FROM roles WHERE name = ?1", params![name])?;
+                    if rows == 0 {
+                        return Ok(RbacOutcome::NotFound);
+                    }
+                    if !sqlite_has_admin(&tx)? {
+                        return Ok(RbacOutcome::NoAdminLeft);
+                    }
+                    tx.commit()?;
+                    Ok(RbacOutcome::Done)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let mut tx = conn.begin().await?;
+                let result = sqlx::query("DELETE FROM roles WHERE name = $1")
+                    .bind(&name)
+                    .execute(&mut *tx)
+                    .await?;
+                if result.rows_affected() == 0 {
+                    return Ok(RbacOutcome::NotFound);
+                }
+                if !pg_has_admin(&mut tx).await? {
+                    return Ok(RbacOutcome::NoAdminLeft);
+                }
+                tx.commit().await?;
+                Ok(RbacOutcome::Done)
+            }
+        }
+    }
+
+    // 替换用户的角色列表
+    pub async fn set_user_roles(
+        &self,
+        username: String,
+        roles: Vec<String>,
+    ) -> Result<RbacOutcome, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let tx = conn.transaction()?;
+                    let user_id: Option<i64> = tx
+                        .query_row(
+                            "SELECT id FROM users WHERE username = ?1",
+                            params![username],
+                            |row| row.get(0),
+                        )
+                        .optional()?;
+                    let Some(user_id) = user_id else {
+                        return Ok(RbacOutcome::NotFound);
+                    };
+                    if let Some(role) = sqlite_assign_roles(&tx, user_id, &roles)? {
+                        return Ok(RbacOutcome::UnknownRole(role));
+                    }
+                    if !sqlite_has_admin(&tx)? {
+                        return Ok(RbacOutcome::NoAdminLeft);
+                    }
+                    tx.commit()?;
+                    Ok(RbacOutcome::Done)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let mut tx = conn.begin().await?;
+                let user_id: Option<i64> =
+                    sqlx::query_scalar("SELECT id FROM users WHERE username = $1")
+                        .bind(&username)
+                        .fetch_optional(&mut *tx)
+                        .await?;
+                let Some(user_id) = user_id else {
+                    return Ok(RbacOutcome::NotFound);
+                };
+                if let Some(role) = pg_assign_roles(&mut tx, user_id, &roles).await? {
+                    return Ok(RbacOutcome::UnknownRole(role));
+                }
+                if !pg_has_admin(&mut tx).await? {
+                    return Ok(RbacOutcome::NoAdminLeft);
+                }
+                tx.commit().await?;
+                Ok(RbacOutcome::Done)
+            }
+        }
+    }
+}
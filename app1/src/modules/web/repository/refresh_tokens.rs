@@ -0,0 +1,215 @@
+//! 刷新令牌（`refresh_tokens` 表）
+//!
+//! 每次登录开启一个令牌族（family），刷新时旧令牌标记为已使用并在同一族内签发新令牌。
+//! 已使用的令牌再次出现说明令牌被盗用或重放，整族吊销，持有者必须重新登录。
+use chrono::Utc;
+use rusqlite::{OptionalExtension, Transaction, params};
+use sqlx::{Connection as _, Postgres};
+
+use super::pool::StoreError;
+use super::store::{Store, sqlite_sql};
+
+/// 刷新令牌轮换结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum RotateOutcome {
+    /// 旧令牌已作废，新令牌已登记
+    Rotated,
+    /// 旧令牌已被使用过，整族已吊销
+    Reused,
+    /// 令牌已被吊销（退出登录、重置密码等）
+    Revoked,
+    /// 令牌不存在
+    Unknown,
+}
+
+const CONSUME_SQL: &str = "UPDATE refresh_tokens SET used_at = $1
+    WHERE jti = $2 AND used_at IS NULL AND revoked_at IS NULL";
+const CHAIN_SQL: &str =
+    "INSERT INTO refresh_tokens (jti, family_id, user_id, issued_at, expires_at)
+    SELECT $1, family_id, user_id, $2, $3 FROM refresh_tokens WHERE jti = $4";
+const STATE_SQL: &str =
+    "SELECT family_id, revoked_at IS NOT NULL FROM refresh_tokens WHERE jti = $1";
+const REVOKE_FAMILY_SQL: &str = "UPDATE refresh_tokens SET revoked_at = $1
+    WHERE family_id = $2 AND revoked_at IS NULL";
+const REVOKE_FAMILY_OF_SQL: &str = "UPDATE refresh_tokens SET revoked_at = $1
+    WHERE revoked_at IS NULL
+      AND family_id = (SELECT family_id FROM refresh_tokens WHERE jti = $2)";
+// 已使用过的令牌无需吊销：再次出现时会按重放处理
+const REVOKE_USER_SQL: &str = "UPDATE refresh_tokens SET revoked_at = $1
+    WHERE revoked_at IS NULL AND used_at IS NULL
+      AND user_id = (SELECT id FROM users WHERE username = $2)";
+
+fn sqlite_rotate(
+    tx: &Transaction,
+    jti: &str,
+    next_jti: &str,
+    expires_at: i64,
+    now: &str,
+) -> rusqlite::Result<RotateOutcome> {
+    if tx.execute(&sqlite_sql(CONSUME_SQL), params![now, jti])? == 1 {
+        tx.execute(
+            &sqlite_sql(CHAIN_SQL),
+            params![next_jti, now, expires_at, jti],
+        )?;
+        return Ok(RotateOutcome::Rotated);
+    }
+    let state: Option<(String, bool)> = tx
+        .query_row(&sqlite_sql(STATE_SQL), params![jti], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .optional()?;
+    match state {
+        None => Ok(RotateOutcome::Unknown),
+        Some((_, true)) => Ok(RotateOutcome::Revoked),
+        Some((family, false)) => {
+            tx.execute(&sqlite_sql(REVOKE_FAMILY_SQL), params![now, family])?;
+            Ok(RotateOutcome::Reused)
+        }
+    }
+}
+
+async fn pg_rotate(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    jti: &str,
+    next_jti: &str,
+    expires_at: i64,
+    now: &str,
+) -> Result<RotateOutcome, sqlx::Error> {
+    let consumed = sqlx::query(CONSUME_SQL)
+        .bind(now)
+        .bind(jti)
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+    if consumed == 1 {
+        sqlx::query(CHAIN_SQL)
+            .bind(next_jti)
+            .bind(now)
+            .bind(expires_at)
+            .bind(jti)
+            .execute(&mut **tx)
+            .await?;
+        return Ok(RotateOutcome::Rotated);
+    }
+    let state: Option<(String, bool)> = sqlx::query_as(STATE_SQL)
+        .bind(jti)
+        .fetch_optional(&mut **tx)
+        .await?;
+    match state {
+        None => Ok(RotateOutcome::Unknown),
+        Some((_, true)) => Ok(RotateOutcome::Revoked),
+        Some((family, false)) => {
+            sqlx::query(REVOKE_FAMILY_SQL)
+                .bind(now)
+                .bind(family)
+                .execute(&mut **tx)
+                .await?;
+            Ok(RotateOutcome::Reused)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RefreshTokenRepository {
+    store: Store,
+}
+
+impl RefreshTokenRepository {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    // 登录时登记新令牌族的第一个令牌，顺带清理该用户已过期的令牌
+    pub async fn issue(&self, jti: &str, user_id: i64, expires_at: i64) -> Result<(), StoreError> {
+        let (jti, now) = (jti.to_string(), Utc::now());
+        let (issued_at, now_ts) = (now.to_rfc3339(), now.timestamp());
+        let purge = "DELETE FROM refresh_tokens WHERE user_id = $1 AND expires_at < $2";
+        let insert = "INSERT INTO refresh_tokens (jti, family_id, user_id, issued_at, expires_at)
+            VALUES ($1, $1, $2, $3, $4)";
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.execute(&sqlite_sql(purge), params![user_id, now_ts])?;
+                    conn.execute(
+                        &sqlite_sql(insert),
+                        params![jti, user_id, issued_at, expires_at],
+                    )?;
+                    Ok(())
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                sqlx::query(purge)
+                    .bind(user_id)
+                    .bind(now_ts)
+                    .execute(pg)
+                    .await?;
+                sqlx::query(insert)
+                    .bind(jti)
+                    .bind(user_id)
+                    .bind(issued_at)
+                    .bind(expires_at)
+                    .execute(pg)
+                    .await?;
+                Ok(())
+            }
+        }
+    }
+
+    // 刷新：作废 jti 并在同一族内登记 next_jti；jti 已被使用过时吊销整族
+    pub async fn rotate(
+        &self,
+        jti: &str,
+        next_jti: &str,
+        expires_at: i64,
+    ) -> Result<RotateOutcome, StoreError> {
+        let (jti, next_jti) = (jti.to_string(), next_jti.to_string());
+        let now = Utc::now().to_rfc3339();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let tx = conn.transaction()?;
+                    let outcome = sqlite_rotate(&tx, &jti, &next_jti, expires_at, &now)?;
+                    tx.commit()?;
+                    Ok(outcome)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let mut tx = conn.begin().await?;
+                let outcome = pg_rotate(&mut tx, &jti, &next_jti, expires_at, &now).await?;
+                tx.commit().await?;
+                Ok(outcome)
+            }
+        }
+    }
+
+    // 退出登录：吊销 jti 所在的令牌族，返回吊销的令牌数
+    pub async fn revoke_family(&self, jti: &str) -> Result<u64, StoreError> {
+        self.revoke(REVOKE_FAMILY_OF_SQL, jti).await
+    }
+
+    // 退出全部会话：吊销用户的所有刷新令牌，返回吊销的会话数
+    pub async fn revoke_user(&self, username: &str) -> Result<u64, StoreError> {
+        self.revoke(REVOKE_USER_SQL, username).await
+    }
+
+    async fn revoke(&self, sql: &'static str, key: &str) -> Result<u64, StoreError> {
+        let key = key.to_string();
+        let now = Utc::now().to_rfc3339();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let count = conn.execute(&sqlite_sql(sql), params![now, key])?;
+                    Ok(count as u64)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query(sql).bind(now).bind(key).execute(pg).await?;
+                Ok(result.rows_affected())
+            }
+        }
+    }
+}
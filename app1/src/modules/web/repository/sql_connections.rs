@@ -0,0 +1,232 @@
+//! SQLStudio 连接配置（`sql_connections` 表）
+use chrono::Utc;
+use rusqlite::{OptionalExtension, params};
+use sqlx::Row;
+use sqlx::postgres::PgRow;
+
+use super::pool::StoreError;
+use super::store::Store;
+use crate::modules::sqlstudio::models::{
+    CreateConnectionRequest, SqlConnection, UpdateConnectionRequest,
+};
+
+#[derive(Debug, Clone)]
+pub struct SqlConnectionRepository {
+    store: Store,
+}
+
+// PostgreSQL 中端口存为 INTEGER
+fn pg_connection(row: &PgRow, with_password: bool) -> Result<SqlConnection, sqlx::Error> {
+    Ok(SqlConnection {
+        id: Some(row.try_get("id")?),
+        name: row.try_get("name")?,
+        db_type: row.try_get("db_type")?,
+        host: row.try_get("host")?,
+        port: row.try_get::<i32, _>("port")? as u16,
+        username: row.try_get("username")?,
+        password: if with_password {
+            row.try_get("password")?
+        } else {
+            None
+        },
+        database: row.try_get("database")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+impl SqlConnectionRepository {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    // 读取完整连接配置（含密码），用于实际连接目标数据库
+    pub async fn get(&self, id: i64) -> Result<Option<SqlConnection>, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.query_row(
+                        "SELECT id, name, db_type, host, port, username, password, database, created_at, updated_at FROM sql_connections WHERE id = ?1",
+                        params![id],
+                        |row| {
+                            Ok(SqlConnection {
+                                id: Some(row.get(0)?),
+                                name: row.get(1)?,
+                                db_type: row.get(2)?,
+                                host: row.get(3)?,
+                                port: row.get(4)?,
+                                username: row.get(5)?,
+                                password: row.get(6)?,
+                                database: row.get(7)?,
+                                created_at: row.get(8)?,
+                                updated_at: row.get(9)?,
+                            })
+                        },
+                    )
+                    .optional()
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let row = sqlx::query(
+                    "SELECT id, name, db_type, host, port, username, password, database, created_at, updated_at FROM sql_connections WHERE id = $1",
+                )
+                .bind(id)
+                .fetch_optional(pg)
+                .await?;
+                Ok(row.map(|row| pg_connection(&row, true)).transpose()?)
+            }
+        }
+    }
+
+    // 列出连接配置（不返回密码）
+    pub async fn list(&self) -> Result<Vec<SqlConnection>, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(|conn| {
+                    let mut stmt = conn.prepare(
+                        "SELECT id, name, db_type, host, port, username, database, created_at, updated_at FROM sql_connections",
+                    )?;
+                    let rows = stmt.query_map([], |row| {
+                        Ok(SqlConnection {
+                            id: Some(row.get(0)?),
+                            name: row.get(1)?,
+                            db_type: row.get(2)?,
+                            host: row.get(3)?,
+                            port: row.get(4)?,
+                            username: row.get(5)?,
+                            password: None,
+                            database: row.get(6)?,
+                            created_at: row.get(7)?,
+                            updated_at: row.get(8)?,
+                        })
+                    })?;
+                    rows.collect()
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let rows = sqlx::query(
+                    "SELECT id, name, db_type, host, port, username, database, created_at, updated_at FROM sql_connections ORDER BY id",
+                )
+                .fetch_all(pg)
+                .await?;
+                let list = rows
+                    .iter()
+                    .map(|row| pg_connection(row, false))
+                    .collect::<Result<Vec<_>, sqlx::Error>>()?;
+                Ok(list)
+            }
+        }
+    }
+
+    pub async fn create(&self, req: CreateConnectionRequest) -> Result<i64, StoreError> {
+        let now = Utc::now().to_rfc3339();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.execute(
+                        "INSERT INTO sql_connections (name, db_type, host, port, username, password, database, created_at, updated_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
+                        params![
+                            req.name,
+                            req.db_type,
+                            req.host,
+                            req.port,
+                            req.username,
+                            req.password,
+                            req.database,
+                            now
+                        ],
+                    )?;
+                    Ok(conn.last_insert_rowid())
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let row = sqlx::query(
+                    "INSERT INTO sql_connections (name, db_type, host, port, username, password, database, created_at, updated_at)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8) RETURNING id",
+                )
+                .bind(req.name)
+                .bind(req.db_type)
+                .bind(req.host)
+                .bind(i32::from(req.port))
+                .bind(req.username)
+                .bind(req.password)
+                .bind(req.database)
+                .bind(now)
+                .fetch_one(pg)
+                .await?;
+                Ok(row.try_get(0)?)
+            }
+        }
+    }
+
+    // 更新连接配置，password 为 None 时保留原密码；返回是否存在
+    pub async fn update(&self, req: UpdateConnectionRequest) -> Result<bool, StoreError> {
+        let now = Utc::now().to_rfc3339();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let rows = conn.execute(
+                        "UPDATE sql_connections SET name=?1, db_type=?2, host=?3, port=?4, username=?5,
+                         password=COALESCE(?6, password), database=?7, updated_at=?8 WHERE id=?9",
+                        params![
+                            req.name,
+                            req.db_type,
+                            req.host,
+                            req.port,
+                            req.username,
+                            req.password,
+                            req.database,
+                            now,
+                            req.id
+                        ],
+                    )?;
+                    Ok(rows > 0)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query(
+                    "UPDATE sql_connections SET name=$1, db_type=$2, host=$3, port=$4, username=$5,
+                     password=COALESCE($6, password), database=$7, updated_at=$8 WHERE id=$9",
+                )
+                .bind(req.name)
+                .bind(req.db_type)
+                .bind(req.host)
+                .bind(i32::from(req.port))
+                .bind(req.username)
+                .bind(req.password)
+                .bind(req.database)
+                .bind(now)
+                .bind(req.id)
+                .execute(pg)
+                .await?;
+                Ok(result.rows_affected() > 0)
+            }
+        }
+    }
+
+    // 删除连接配置，返回是否存在
+    pub async fn delete(&self, id: i64) -> Result<bool, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let rows =
+                        conn.execute("DELETE FROM sql_connections WHERE id=?1", params![id])?;
+                    Ok(rows > 0)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query("DELETE FROM sql_connections WHERE id=$1")
+                    .bind(id)
+                    .execute(pg)
+                    .await?;
+                Ok(result.rows_affected() > 0)
+            }
+        }
+    }
+}
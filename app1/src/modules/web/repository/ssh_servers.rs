@@ -0,0 +1,455 @@
+//! SSH 分组与服务器（`ssh_groups` / `ssh_servers` 表）
+use rusqlite::{Connection, OptionalExtension, params};
+use sqlx::postgres::PgRow;
+use sqlx::{Connection as _, PgConnection, Row};
+
+use super::pool::StoreError;
+use super::store::Store;
+use crate::modules::web::models::{SshGroup, SshServer, SshServerInput};
+
+const SERVER_COLUMNS: &str = "SELECT id, alias, hostname, port, username, password, COALESCE(group_id, (SELECT id FROM ssh_groups WHERE is_default=1 LIMIT 1), 0), remark FROM ssh_servers";
+const PG_SERVER_COLUMNS: &str = "SELECT id, alias, hostname, port, username, password, COALESCE(group_id, (SELECT id FROM ssh_groups WHERE is_default=1 ORDER BY id LIMIT 1), 0), remark FROM ssh_servers";
+
+/// 删除分组的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteGroupOutcome {
+    Deleted,
+    NotFound,
+    IsDefault,
+}
+
+#[derive(Debug, Clone)]
+pub struct SshServerRepository {
+    store: Store,
+}
+
+fn map_server(row: &rusqlite::Row) -> rusqlite::Result<SshServer> {
+    Ok(SshServer {
+        id: row.get(0)?,
+        alias: row.get(1)?,
+        hostname: row.get(2)?,
+        port: row.get(3)?,
+        username: row.get(4)?,
+        password: row.get(5).ok(),
+        group_id: row.get(6)?,
+        remark: row.get(7).ok(),
+    })
+}
+
+fn pg_server(row: &PgRow) -> Result<SshServer, sqlx::Error> {
+    Ok(SshServer {
+        id: row.try_get(0)?,
+        alias: row.try_get(1)?,
+        hostname: row.try_get(2)?,
+        port: row.try_get(3)?,
+        username: row.try_get(4)?,
+        password: row.try_get(5)?,
+        group_id: row.try_get(6)?,
+        remark: row.try_get(7)?,
+    })
+}
+
+fn pg_group(row: &PgRow) -> Result<SshGroup, sqlx::Error> {
+    Ok(SshGroup {
+        id: row.try_get(0)?,
+        name: row.try_get(1)?,
+        is_default: row.try_get(2)?,
+    })
+}
+
+async fn pg_ensure_default_group(conn: &mut PgConnection) -> Result<i64, sqlx::Error> {
+    let id: Option<i64> =
+        sqlx::query_scalar("SELECT id FROM ssh_groups WHERE is_default=1 ORDER BY id LIMIT 1")
+            .fetch_optional(&mut *conn)
+            .await?;
+    if let Some(id) = id {
+        return Ok(id);
+    }
+    sqlx::query_scalar("INSERT INTO ssh_groups (name, is_default) VALUES ($1, 1) RETURNING id")
+        .bind("默认分组")
+        .fetch_one(&mut *conn)
+        .await
+}
+
+// 默认分组 ID，不存在时补建
+pub fn ensure_default_group(conn: &Connection) -> rusqlite::Result<i64> {
+    let id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM ssh_groups WHERE is_default=1 LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(id) = id {
+        return Ok(id);
+    }
+    conn.execute(
+        "INSERT INTO ssh_groups (name, is_default) VALUES (?1, 1)",
+        params!["默认分组"],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// 按 ID 或别名查找服务器（命令行工具直接持有连接时使用）
+pub fn find_server(conn: &Connection, key: &str) -> rusqlite::Result<Option<SshServer>> {
+    match key.parse::<i64>() {
+        Ok(id) => conn
+            .query_row(
+                &format!("{} WHERE id = ?1", SERVER_COLUMNS),
+                params![id],
+                map_server,
+            )
+            .optional(),
+        Err(_) => conn
+            .query_row(
+                &format!("{} WHERE alias = ?1 ORDER BY id LIMIT 1", SERVER_COLUMNS),
+                params![key],
+                map_server,
+            )
+            .optional(),
+    }
+}
+
+impl SshServerRepository {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    pub async fn list_groups(&self) -> Result<Vec<SshGroup>, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(|conn| {
+                    ensure_default_group(conn)?;
+                    let mut stmt = conn.prepare(
+                        "SELECT id, name, is_default FROM ssh_groups ORDER BY is_default DESC, id ASC",
+                    )?;
+                    let rows = stmt.query_map([], |row| {
+                        Ok(SshGroup {
+                            id: row.get(0)?,
+                            name: row.get(1)?,
+                            is_default: row.get(2)?,
+                        })
+                    })?;
+                    rows.collect()
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                pg_ensure_default_group(&mut conn).await?;
+                let rows = sqlx::query(
+                    "SELECT id, name, is_default FROM ssh_groups ORDER BY is_default DESC, id ASC",
+                )
+                .fetch_all(&mut *conn)
+                .await?;
+                let groups = rows
+                    .iter()
+                    .map(pg_group)
+                    .collect::<Result<Vec<_>, sqlx::Error>>()?;
+                Ok(groups)
+            }
+        }
+    }
+
+    pub async fn create_group(&self, name: String) -> Result<SshGroup, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.execute(
+                        "INSERT INTO ssh_groups (name, is_default) VALUES (?1, 0)",
+                        params![name],
+                    )?;
+                    Ok(SshGroup {
+                        id: conn.last_insert_rowid(),
+                        name,
+                        is_default: 0,
+                    })
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let id: i64 = sqlx::query_scalar(
+                    "INSERT INTO ssh_groups (name, is_default) VALUES ($1, 0) RETURNING id",
+                )
+                .bind(&name)
+                .fetch_one(pg)
+                .await?;
+                Ok(SshGroup {
+                    id,
+                    name,
+                    is_default: 0,
+                })
+            }
+        }
+    }
+
+    // 重命名分组，分组不存在时返回 None
+    pub async fn update_group(
+        &self,
+        id: i64,
+        name: String,
+    ) -> Result<Option<SshGroup>, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let rows = conn.execute(
+                        "UPDATE ssh_groups SET name=?1 WHERE id=?2",
+                        params![name, id],
+                    )?;
+                    if rows == 0 {
+                        return Ok(None);
+                    }
+                    let is_default: i32 = conn.query_row(
+                        "SELECT is_default FROM ssh_groups WHERE id=?1",
+                        params![id],
+                        |row| row.get(0),
+                    )?;
+                    Ok(Some(SshGroup {
+                        id,
+                        name,
+                        is_default,
+                    }))
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let row = sqlx::query(
+                    "UPDATE ssh_groups SET name=$1 WHERE id=$2 RETURNING id, name, is_default",
+                )
+                .bind(name)
+                .bind(id)
+                .fetch_optional(pg)
+                .await?;
+                Ok(row.as_ref().map(pg_group).transpose()?)
+            }
+        }
+    }
+
+    // 删除分组，组内服务器移到默认分组（同一事务内完成）
+    pub async fn delete_group(&self, id: i64) -> Result<DeleteGroupOutcome, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let tx = conn.transaction()?;
+                    let is_default: Option<i32> = tx
+                        .query_row(
+                            "SELECT is_default FROM ssh_groups WHERE id=?1",
+                            params![id],
+                            |row| row.get(0),
+                        )
+                        .optional()?;
+                    match is_default {
+                        None => return Ok(DeleteGroupOutcome::NotFound),
+                        Some(1) => return Ok(DeleteGroupOutcome::IsDefault),
+                        Some(_) => {}
+                    }
+                    let default_id = ensure_default_group(&tx)?;
+                    tx.execute(
+                        "UPDATE ssh_servers SET group_id=?1 WHERE group_id=?2",
+                        params![default_id, id],
+                    )?;
+                    tx.execute("DELETE FROM ssh_groups WHERE id=?1", params![id])?;
+                    tx.commit()?;
+                    Ok(DeleteGroupOutcome::Deleted)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let mut tx = conn.begin().await?;
+                let is_default: Option<i32> =
+                    sqlx::query_scalar("SELECT is_default FROM ssh_groups WHERE id=$1 FOR UPDATE")
+                        .bind(id)
+                        .fetch_optional(&mut *tx)
+                        .await?;
+                match is_default {
+                    None => return Ok(DeleteGroupOutcome::NotFound),
+                    Some(1) => return Ok(DeleteGroupOutcome::IsDefault),
+                    Some(_) => {}
+                }
+                let default_id = pg_ensure_default_group(&mut tx).await?;
+                sqlx::query("UPDATE ssh_servers SET group_id=$1 WHERE group_id=$2")
+                    .bind(default_id)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query("DELETE FROM ssh_groups WHERE id=$1")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                tx.commit().await?;
+                Ok(DeleteGroupOutcome::Deleted)
+            }
+        }
+    }
+
+    pub async fn list_servers(&self) -> Result<Vec<SshServer>, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(|conn| {
+                    ensure_default_group(conn)?;
+                    let mut stmt =
+                        conn.prepare(&format!("{} ORDER BY alias ASC, id ASC", SERVER_COLUMNS))?;
+                    let rows = stmt.query_map([], map_server)?;
+                    rows.collect()
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                pg_ensure_default_group(&mut conn).await?;
+                let sql = format!("{} ORDER BY alias ASC, id ASC", PG_SERVER_COLUMNS);
+                let rows = sqlx::query(&sql).fetch_all(&mut *conn).await?;
+                let servers = rows
+                    .iter()
+                    .map(pg_server)
+                    .collect::<Result<Vec<_>, sqlx::Error>>()?;
+                Ok(servers)
+            }
+        }
+    }
+
+    pub async fn find_server(&self, key: String) -> Result<Option<SshServer>, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => pool.run(move |conn| find_server(conn, &key)).await,
+            Store::Postgres(pg) => {
+                let row = match key.parse::<i64>() {
+                    Ok(id) => {
+                        let sql = format!("{} WHERE id = $1", PG_SERVER_COLUMNS);
+                        sqlx::query(&sql).bind(id).fetch_optional(pg).await?
+                    }
+                    Err(_) => {
+                        let sql =
+                            format!("{} WHERE alias = $1 ORDER BY id LIMIT 1", PG_SERVER_COLUMNS);
+                        sqlx::query(&sql).bind(&key).fetch_optional(pg).await?
+                    }
+                };
+                Ok(row.as_ref().map(pg_server).transpose()?)
+            }
+        }
+    }
+
+    pub async fn create_server(&self, input: SshServerInput) -> Result<SshServer, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let group_id = match input.group_id {
+                        Some(id) => id,
+                        None => ensure_default_group(conn)?,
+                    };
+                    let server = server_from_input(0, input, group_id);
+                    conn.execute(
+                        "INSERT INTO ssh_servers (alias, hostname, port, username, password, group_id, remark) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                        params![server.alias, server.hostname, server.port, server.username, server.password, server.group_id, server.remark],
+                    )?;
+                    Ok(SshServer {
+                        id: conn.last_insert_rowid(),
+                        ..server
+                    })
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let group_id = match input.group_id {
+                    Some(id) => id,
+                    None => pg_ensure_default_group(&mut conn).await?,
+                };
+                let server = server_from_input(0, input, group_id);
+                let id: i64 = sqlx::query_scalar(
+                    "INSERT INTO ssh_servers (alias, hostname, port, username, password, group_id, remark) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+                )
+                .bind(&server.alias)
+                .bind(&server.hostname)
+                .bind(server.port)
+                .bind(&server.username)
+                .bind(&server.password)
+                .bind(server.group_id)
+                .bind(&server.remark)
+                .fetch_one(&mut *conn)
+                .await?;
+                Ok(SshServer { id, ..server })
+            }
+        }
+    }
+
+    // 更新服务器，服务器不存在时返回 None
+    pub async fn update_server(
+        &self,
+        id: i64,
+        input: SshServerInput,
+    ) -> Result<Option<SshServer>, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let group_id = match input.group_id {
+                        Some(id) => id,
+                        None => ensure_default_group(conn)?,
+                    };
+                    let server = server_from_input(id, input, group_id);
+                    let rows = conn.execute(
+                        "UPDATE ssh_servers SET alias=?1, hostname=?2, port=?3, username=?4, password=?5, group_id=?6, remark=?7 WHERE id=?8",
+                        params![server.alias, server.hostname, server.port, server.username, server.password, server.group_id, server.remark, id],
+                    )?;
+                    Ok((rows > 0).then_some(server))
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let group_id = match input.group_id {
+                    Some(id) => id,
+                    None => pg_ensure_default_group(&mut conn).await?,
+                };
+                let server = server_from_input(id, input, group_id);
+                let result = sqlx::query(
+                    "UPDATE ssh_servers SET alias=$1, hostname=$2, port=$3, username=$4, password=$5, group_id=$6, remark=$7 WHERE id=$8",
+                )
+                .bind(&server.alias)
+                .bind(&server.hostname)
+                .bind(server.port)
+                .bind(&server.username)
+                .bind(&server.password)
+                .bind(server.group_id)
+                .bind(&server.remark)
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+                Ok((result.rows_affected() > 0).then_some(server))
+            }
+        }
+    }
+
+    // 删除服务器，返回是否存在
+    pub async fn delete_server(&self, id: i64) -> Result<bool, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let rows = conn.execute("DELETE FROM ssh_servers WHERE id=?1", params![id])?;
+                    Ok(rows > 0)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query("DELETE FROM ssh_servers WHERE id=$1")
+                    .bind(id)
+                    .execute(pg)
+                    .await?;
+                Ok(result.rows_affected() > 0)
+            }
+        }
+    }
+}
+
+fn server_from_input(id: i64, input: SshServerInput, group_id: i64) -> SshServer {
+    SshServer {
+        id,
+        alias: input.alias.unwrap_or_default(),
+        hostname: input.hostname,
+        port: input.port.unwrap_or(22),
+        username: input.username,
+        password: input.password,
+        group_id,
+        remark: input.remark,
+    }
+}
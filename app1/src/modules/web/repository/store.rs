@@ -0,0 +1,39 @@
+//! 元数据库后端
+//!
+//! 默认使用本地 SQLite 文件（[`SqlitePool`]）；作为多人共享服务部署时可切换到 PostgreSQL（sqlx 连接池）。
+//! 各领域仓储按后端分别执行对应方言的 SQL，对外接口一致。
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::pool::{SqlitePool, StoreError};
+
+#[derive(Debug, Clone)]
+pub enum Store {
+    Sqlite(Arc<SqlitePool>),
+    Postgres(PgPool),
+}
+
+impl Store {
+    // 连接 PostgreSQL（不执行迁移）
+    pub async fn connect_postgres(url: &str, max_size: usize) -> Result<PgPool, StoreError> {
+        let pool = PgPoolOptions::new()
+            .max_connections(max_size.max(1) as u32)
+            .acquire_timeout(Duration::from_secs(10))
+            .connect(url)
+            .await?;
+        Ok(pool)
+    }
+
+    pub fn backend(&self) -> &'static str {
+        match self {
+            Store::Sqlite(_) => "sqlite",
+            Store::Postgres(_) => "postgres",
+        }
+    }
+}
+
+// 把 PostgreSQL 的 $n 占位符改写为 SQLite 的 ?n，两种后端共用一份 SQL
+pub(super) fn sqlite_sql(sql: &str) -> String {
+    sql.replace('$', "?")
+}
@@ -0,0 +1,309 @@
+//! 两步验证：TOTP 密钥（`user_totp`）与一次性恢复码（`user_recovery_codes`）
+//!
+//! 绑定分两步：先登记未启用的密钥，用户用验证器生成的验证码确认后才启用。
+//! `last_used_step` 记录最近一次通过校验的时间步，同一验证码不能重复使用。
+use chrono::Utc;
+use rusqlite::{OptionalExtension, Transaction, params};
+use sqlx::{Connection as _, Postgres};
+
+use super::pool::StoreError;
+use super::store::{Store, sqlite_sql};
+
+/// 用户的 TOTP 绑定状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct TotpState {
+    /// base32 编码的密钥
+    pub secret: String,
+    /// 是否已完成绑定（登录时要求验证码）
+    pub enabled: bool,
+    /// 最近一次通过校验的时间步
+    pub last_used_step: Option<i64>,
+}
+
+const STATE_SQL: &str = "SELECT secret, enabled, last_used_step FROM user_totp WHERE user_id = $1";
+// 已启用的绑定不会被覆盖，需先停用
+const SET_PENDING_SQL: &str = "INSERT INTO user_totp (user_id, secret, enabled, created_at)
+    VALUES ($1, $2, 0, $3)
+    ON CONFLICT (user_id) DO UPDATE SET
+        secret = excluded.secret, created_at = excluded.created_at, last_used_step = NULL
+    WHERE user_totp.enabled = 0";
+const ENABLE_SQL: &str = "UPDATE user_totp SET enabled = 1, enabled_at = $1, last_used_step = $2
+    WHERE user_id = $3 AND enabled = 0";
+const USE_STEP_SQL: &str = "UPDATE user_totp SET last_used_step = $1
+    WHERE user_id = $2 AND enabled = 1 AND (last_used_step IS NULL OR last_used_step < $1)";
+const USE_RECOVERY_CODE_SQL: &str = "UPDATE user_recovery_codes SET used_at = $1
+    WHERE user_id = $2 AND code_hash = $3 AND used_at IS NULL";
+const CODES_LEFT_SQL: &str =
+    "SELECT COUNT(*) FROM user_recovery_codes WHERE user_id = $1 AND used_at IS NULL";
+const DELETE_CODES_SQL: &str = "DELETE FROM user_recovery_codes WHERE user_id = $1";
+const INSERT_CODE_SQL: &str =
+    "INSERT INTO user_recovery_codes (user_id, code_hash) VALUES ($1, $2)";
+const DELETE_TOTP_SQL: &str = "DELETE FROM user_totp WHERE user_id = $1";
+
+fn sqlite_replace_codes(
+    tx: &Transaction,
+    user_id: i64,
+    code_hashes: &[String],
+) -> rusqlite::Result<()> {
+    tx.execute(&sqlite_sql(DELETE_CODES_SQL), params![user_id])?;
+    let mut insert = tx.prepare(&sqlite_sql(INSERT_CODE_SQL))?;
+    for hash in code_hashes {
+        insert.execute(params![user_id, hash])?;
+    }
+    Ok(())
+}
+
+async fn pg_replace_codes(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    user_id: i64,
+    code_hashes: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(DELETE_CODES_SQL)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+    for hash in code_hashes {
+        sqlx::query(INSERT_CODE_SQL)
+            .bind(user_id)
+            .bind(hash)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct TotpRepository {
+    store: Store,
+}
+
+impl TotpRepository {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    pub async fn state(&self, user_id: i64) -> Result<Option<TotpState>, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.query_row(&sqlite_sql(STATE_SQL), params![user_id], |row| {
+                        Ok(TotpState {
+                            secret: row.get(0)?,
+                            enabled: row.get(1)?,
+                            last_used_step: row.get(2)?,
+                        })
+                    })
+                    .optional()
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let row: Option<(String, i32, Option<i64>)> = sqlx::query_as(STATE_SQL)
+                    .bind(user_id)
+                    .fetch_optional(pg)
+                    .await?;
+                Ok(row.map(|(secret, enabled, last_used_step)| TotpState {
+                    secret,
+                    enabled: enabled != 0,
+                    last_used_step,
+                }))
+            }
+        }
+    }
+
+    // 登记（或替换）尚未启用的密钥；已启用时返回 false
+    pub async fn set_pending(&self, user_id: i64, secret: &str) -> Result<bool, StoreError> {
+        let secret = secret.to_string();
+        let now = Utc::now().to_rfc3339();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let count =
+                        conn.execute(&sqlite_sql(SET_PENDING_SQL), params![user_id, secret, now])?;
+                    Ok(count > 0)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query(SET_PENDING_SQL)
+                    .bind(user_id)
+                    .bind(secret)
+                    .bind(now)
+                    .execute(pg)
+                    .await?;
+                Ok(result.rows_affected() > 0)
+            }
+        }
+    }
+
+    // 确认绑定：启用密钥并保存恢复码哈希；没有待确认的密钥时返回 false
+    pub async fn enable(
+        &self,
+        user_id: i64,
+        step: i64,
+        code_hashes: Vec<String>,
+    ) -> Result<bool, StoreError> {
+        let now = Utc::now().to_rfc3339();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let tx = conn.transaction()?;
+                    let enabled =
+                        tx.execute(&sqlite_sql(ENABLE_SQL), params![now, step, user_id])? == 1;
+                    if enabled {
+                        sqlite_replace_codes(&tx, user_id, &code_hashes)?;
+                    }
+                    tx.commit()?;
+                    Ok(enabled)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let mut tx = conn.begin().await?;
+                let enabled = sqlx::query(ENABLE_SQL)
+                    .bind(now)
+                    .bind(step)
+                    .bind(user_id)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected()
+                    == 1;
+                if enabled {
+                    pg_replace_codes(&mut tx, user_id, &code_hashes).await?;
+                }
+                tx.commit().await?;
+                Ok(enabled)
+            }
+        }
+    }
+
+    // 记录通过校验的时间步；不晚于上次使用的时间步（重放）时返回 false
+    pub async fn use_step(&self, user_id: i64, step: i64) -> Result<bool, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    Ok(conn.execute(&sqlite_sql(USE_STEP_SQL), params![step, user_id])? == 1)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query(USE_STEP_SQL)
+                    .bind(step)
+                    .bind(user_id)
+                    .execute(pg)
+                    .await?;
+                Ok(result.rows_affected() == 1)
+            }
+        }
+    }
+
+    // 核销一个恢复码；不存在或已使用时返回 false
+    pub async fn use_recovery_code(
+        &self,
+        user_id: i64,
+        code_hash: &str,
+    ) -> Result<bool, StoreError> {
+        let code_hash = code_hash.to_string();
+        let now = Utc::now().to_rfc3339();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let count = conn.execute(
+                        &sqlite_sql(USE_RECOVERY_CODE_SQL),
+                        params![now, user_id, code_hash],
+                    )?;
+                    Ok(count > 0)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query(USE_RECOVERY_CODE_SQL)
+                    .bind(now)
+                    .bind(user_id)
+                    .bind(code_hash)
+                    .execute(pg)
+                    .await?;
+                Ok(result.rows_affected() > 0)
+            }
+        }
+    }
+
+    // 重新生成恢复码，旧的恢复码全部作废
+    pub async fn replace_recovery_codes(
+        &self,
+        user_id: i64,
+        code_hashes: Vec<String>,
+    ) -> Result<(), StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let tx = conn.transaction()?;
+                    sqlite_replace_codes(&tx, user_id, &code_hashes)?;
+                    tx.commit()
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let mut tx = conn.begin().await?;
+                pg_replace_codes(&mut tx, user_id, &code_hashes).await?;
+                tx.commit().await?;
+                Ok(())
+            }
+        }
+    }
+
+    // 剩余可用的恢复码数量
+    pub async fn recovery_codes_left(&self, user_id: i64) -> Result<i64, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.query_row(&sqlite_sql(CODES_LEFT_SQL), params![user_id], |row| {
+                        row.get(0)
+                    })
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let count: i64 = sqlx::query_scalar(CODES_LEFT_SQL)
+                    .bind(user_id)
+                    .fetch_one(pg)
+                    .await?;
+                Ok(count)
+            }
+        }
+    }
+
+    // 解除绑定并删除恢复码；原本未绑定时返回 false
+    pub async fn disable(&self, user_id: i64) -> Result<bool, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let tx = conn.transaction()?;
+                    tx.execute(&sqlite_sql(DELETE_CODES_SQL), params![user_id])?;
+                    let removed = tx.execute(&sqlite_sql(DELETE_TOTP_SQL), params![user_id])? > 0;
+                    tx.commit()?;
+                    Ok(removed)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let mut tx = conn.begin().await?;
+                sqlx::query(DELETE_CODES_SQL)
+                    .bind(user_id)
+                    .execute(&mut *tx)
+                    .await?;
+                let removed = sqlx::query(DELETE_TOTP_SQL)
+                    .bind(user_id)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected()
+                    > 0;
+                tx.commit().await?;
+                Ok(removed)
+            }
+        }
+    }
+}
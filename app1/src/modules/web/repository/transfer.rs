@@ -0,0 +1,431 @@
+//! 元数据库一次性迁移：SQLite（rsts.db）→ PostgreSQL
+//!
+//! 源库先执行 SQLite 迁移补齐表结构，目标库执行 PostgreSQL 迁移建表；
+//! 数据在一个事务内写入并保留原 ID，写完后重置各表自增序列。目标库已有数据时默认拒绝执行。
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use sqlx::{Acquire, Executor};
+use std::path::Path;
+
+use super::pool::StoreError;
+use super::store::Store;
+use crate::modules::web::migrations::{run_migrations, run_pg_migrations};
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    /// BIGINT / BIGSERIAL
+    BigInt,
+    /// INTEGER
+    Int,
+    Text,
+}
+
+use Kind::{BigInt, Int, Text};
+
+/// 只允许追加的表：不参与 overwrite 清空，目标库中已有记录时拒绝迁移
+const APPEND_ONLY_TABLE: &str = "audit_log";
+
+/// 迁移的表及列类型，按外键依赖排序
+const TABLES: &[(&str, &[(&str, Kind)])] = &[
+    (
+        "users",
+        &[
+            ("id", BigInt),
+            ("username", Text),
+            ("password", Text),
+            ("email", Text),
+            ("theme_config", Text),
+            ("terminal_setting_config", Text),
+            ("disabled", Int),
+            ("must_change_password", Int),
+            ("password_changed_at", Text),
+        ],
+    ),
+    (
+        "roles",
+        &[("id", BigInt), ("name", Text), ("description", Text)],
+    ),
+    (
+        "role_permissions",
+        &[("role_id", BigInt), ("permission", Text)],
+    ),
+    ("user_roles", &[("user_id", BigInt), ("role_id", BigInt)]),
+    (
+        "user_totp",
+        &[
+            ("user_id", BigInt),
+            ("secret", Text),
+            ("enabled", Int),
+            ("last_used_step", BigInt),
+            ("created_at", Text),
+            ("enabled_at", Text),
+        ],
+    ),
+    (
+        "user_recovery_codes",
+        &[
+            ("id", BigInt),
+            ("user_id", BigInt),
+            ("code_hash", Text),
+            ("used_at", Text),
+        ],
+    ),
+    (
+        "oidc_identities",
+        &[
+            ("provider", Text),
+            ("subject", Text),
+            ("user_id", BigInt),
+            ("email", Text),
+            ("created_at", Text),
+        ],
+    ),
+    (
+        "personal_access_tokens",
+        &[
+            ("id", BigInt),
+            ("user_id", BigInt),
+            ("name", Text),
+            ("token_hash", Text),
+            ("prefix", Text),
+            ("scopes", Text),
+            ("created_at", BigInt),
+            ("expires_at", BigInt),
+            ("last_used_at", BigInt),
+            ("revoked_at", BigInt),
+        ],
+    ),
+    (
+        "ws_session",
+        &[
+            ("id", BigInt),
+            ("session_id", Text),
+            ("username", Text),
+            ("room", Text),
+            ("connected_at", Text),
+            ("last_active", Text),
+        ],
+    ),
+    (
+        "ws_message",
+        &[
+            ("id", BigInt),
+            ("session_id", Text),
+            ("username", Text),
+            ("room", Text),
+            ("content", Text),
+            ("timestamp", Text),
+        ],
+    ),
+    (
+        "sql_connections",
+        &[
+            ("id", BigInt),
+            ("name", Text),
+            ("db_type", Text),
+            ("host", Text),
+            ("port", Int),
+            ("username", Text),
+            ("password", Text),
+            ("database", Text),
+            ("created_at", Text),
+            ("updated_at", Text),
+        ],
+    ),
+    (
+        "api_endpoints",
+        &[
+            ("id", BigInt),
+            ("parent_id", BigInt),
+            ("name", Text),
+            ("method", Text),
+            ("url", Text),
+            ("headers", Text),
+            ("params", Text),
+            ("body_type", Text),
+            ("body", Text),
+            ("content_type", Text),
+            ("order_index", Int),
+            ("created_at", Text),
+            ("updated_at", Text),
+        ],
+    ),
+    (
+        "ssh_groups",
+        &[("id", BigInt), ("name", Text), ("is_default", Int)],
+    ),
+    (
+        "ssh_servers",
+        &[
+            ("id", BigInt),
+            ("alias", Text),
+            ("hostname", Text),
+            ("port", Int),
+            ("username", Text),
+            ("password", Text),
+            ("group_id", BigInt),
+            ("remark", Text),
+        ],
+    ),
+    (
+        "audit_log",
+        &[
+            ("id", BigInt),
+            ("created_at", BigInt),
+            ("username", Text),
+            ("ip", Text),
+            ("action", Text),
+            ("target", Text),
+            ("outcome", Text),
+            ("duration_ms", BigInt),
+            ("detail", Text),
+            ("error", Text),
+        ],
+    ),
+];
+
+#[derive(Debug, Clone)]
+enum Cell {
+    Int(Option<i64>),
+    Text(Option<String>),
+}
+
+type TableRows = (
+    &'static str,
+    &'static [(&'static str, Kind)],
+    Vec<Vec<Cell>>,
+);
+
+/// 迁移结果：各表写入的行数
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferReport {
+    pub source: String,
+    pub tables: Vec<TableCount>,
+    pub total_rows: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableCount {
+    pub table: String,
+    pub rows: usize,
+}
+
+// SQLite 列类型宽松，按目标列类型转换
+fn read_cell(value: ValueRef<'_>, kind: Kind, column: &str) -> Result<Cell, StoreError> {
+    let invalid = || StoreError::Precondition(format!("列 {} 的值无法转换为整数", column));
+    Ok(match kind {
+        BigInt | Int => Cell::Int(match value {
+            ValueRef::Null => None,
+            ValueRef::Integer(i) => Some(i),
+            ValueRef::Real(f) => Some(f as i64),
+            ValueRef::Text(t) => Some(
+                std::str::from_utf8(t)
+                    .ok()
+                    .and_then(|s| s.trim().parse().ok())
+                    .ok_or_else(invalid)?,
+            ),
+            ValueRef::Blob(_) => return Err(invalid()),
+        }),
+        Text => Cell::Text(match value {
+            ValueRef::Null => None,
+            ValueRef::Integer(i) => Some(i.to_string()),
+            ValueRef::Real(f) => Some(f.to_string()),
+            ValueRef::Text(t) | ValueRef::Blob(t) => Some(String::from_utf8_lossy(t).into_owned()),
+        }),
+    })
+}
+
+fn read_sqlite(conn: &Connection) -> Result<Vec<TableRows>, StoreError> {
+    let mut tables = Vec::new();
+    for (table, columns) in TABLES {
+        let names: Vec<&str> = columns.iter().map(|(name, _)| *name).collect();
+        let sql = format!("SELECT {} FROM {} ORDER BY 1", names.join(", "), table);
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query([])?;
+        let mut data = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut cells = Vec::with_capacity(columns.len());
+            for (i, (name, kind)) in columns.iter().enumerate() {
+                cells.push(read_cell(row.get_ref(i)?, *kind, name)?);
+            }
+            data.push(cells);
+        }
+        tables.push((*table, *columns, data));
+    }
+    Ok(tables)
+}
+
+// 把 rsts.db 中的全部元数据复制到 PostgreSQL；overwrite 为 true 时清空目标库已有数据
+// （审计日志只允许追加，目标库已有审计记录时即使 overwrite 也拒绝）
+pub async fn sqlite_to_postgres(
+    sqlite_path: &str,
+    pg_url: &str,
+    overwrite: bool,
+) -> Result<TransferReport, StoreError> {
+    if !Path::new(sqlite_path).exists() {
+        return Err(StoreError::Precondition(format!(
+            "SQLite 数据库不存在: {}",
+            sqlite_path
+        )));
+    }
+
+    let path = sqlite_path.to_string();
+    let tables = tokio::task::spawn_blocking(move || {
+        let mut conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+        run_migrations(&mut conn)?;
+        read_sqlite(&conn)
+    })
+    .await??;
+
+    let pg = Store::connect_postgres(pg_url, 1).await?;
+    run_pg_migrations(&pg).await?;
+
+    let mut conn = pg.acquire().await?;
+    let mut tx = conn.begin().await?;
+
+    // 目标库为空才允许写入（迁移自动创建的默认分组与内置角色不算数据）
+    for (table, _, _) in &tables {
+        if overwrite && *table != APPEND_ONLY_TABLE {
+            continue;
+        }
+        let sql = match *table {
+            "roles" | "role_permissions" => continue,
+            "ssh_groups" => "SELECT COUNT(*) FROM ssh_groups WHERE is_default = 0".to_string(),
+            _ => format!("SELECT COUNT(*) FROM {}", table),
+        };
+        let count: i64 = sqlx::query_scalar(&sql).fetch_one(&mut *tx).await?;
+        if count > 0 && *table == APPEND_ONLY_TABLE {
+            return Err(StoreError::Precondition(format!(
+                "目标库审计日志已有 {} 行记录，审计日志只允许追加，不能覆盖",
+                count
+            )));
+        }
+        if count > 0 {
+            return Err(StoreError::Precondition(format!(
+                "目标库表 {} 已有 {} 行数据，如需覆盖请使用 overwrite",
+                table, count
+            )));
+        }
+    }
+
+    tx.execute("SET CONSTRAINTS ALL DEFERRED").await?;
+    // 审计日志已确认为空，不参与清空（其 TRUNCATE 触发器会拒绝）
+    let names: Vec<&str> = tables
+        .iter()
+        .map(|(table, _, _)| *table)
+        .filter(|table| *table != APPEND_ONLY_TABLE)
+        .collect();
+    tx.execute(format!("TRUNCATE {} RESTART IDENTITY CASCADE", names.join(", ")).as_str())
+        .await?;
+
+    let mut counts = Vec::new();
+    let mut total_rows = 0;
+    for (table, columns, rows) in &tables {
+        let names: Vec<&str> = columns.iter().map(|(name, _)| *name).collect();
+        let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("${}", i)).collect();
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table,
+            names.join(", "),
+            placeholders.join(", ")
+        );
+        for row in rows {
+            let mut query = sqlx::query(&sql);
+            for (cell, (_, kind)) in row.iter().zip(columns.iter()) {
+                query = match (cell, kind) {
+                    (Cell::Int(v), Int) => query.bind(v.map(|v| v as i32)),
+                    (Cell::Int(v), _) => query.bind(*v),
+                    (Cell::Text(v), _) => query.bind(v.clone()),
+                };
+            }
+            query.execute(&mut *tx).await?;
+        }
+        // 保留了原 ID，序列需要跳到当前最大值之后（关联表没有 id 列）
+        if columns[0].0 == "id" {
+            let sql = format!(
+                "SELECT setval(pg_get_serial_sequence('{0}', 'id'), COALESCE((SELECT MAX(id) FROM {0}), 0) + 1, false)",
+                table
+            );
+            tx.execute(sql.as_str()).await?;
+        }
+        total_rows += rows.len();
+        counts.push(TableCount {
+            table: table.to_string(),
+            rows: rows.len(),
+        });
+    }
+
+    tx.commit().await?;
+    drop(conn);
+    pg.close().await;
+
+    Ok(TransferReport {
+        source: sqlite_path.to_string(),
+        tables: counts,
+        total_rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::web::database::Database;
+    use crate::modules::web::models::SshServerInput;
+
+    // 需要可用的 PostgreSQL：RSTS_TEST_PG_URL=postgres://... cargo test
+    #[tokio::test]
+    async fn copies_sqlite_into_postgres() {
+        let Ok(url) = std::env::var("RSTS_TEST_PG_URL") else {
+            return;
+        };
+        let dir = std::env::temp_dir().join(format!("rsts_transfer_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("rsts.db");
+        let path = path.to_str().unwrap().to_string();
+
+        let source = Database::new(&path).unwrap();
+        let server = source
+            .ssh_servers()
+            .create_server(SshServerInput {
+                alias: Some("web".to_string()),
+                hostname: "10.0.0.1".to_string(),
+                port: Some(2222),
+                username: "root".to_string(),
+                password: None,
+                group_id: None,
+                remark: None,
+            })
+            .await
+            .unwrap();
+        drop(source);
+
+        let report = sqlite_to_postgres(&path, &url, true).await.unwrap();
+        let users = report.tables.iter().find(|t| t.table == "users").unwrap();
+        assert_eq!(users.rows, 2);
+
+        let target = Database::with_postgres(&url, 2).await.unwrap();
+        let copied = target
+            .ssh_servers()
+            .find_server("web".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(copied.id, server.id);
+        assert_eq!(copied.port, 2222);
+        assert!(target.validate_user("admin", "password123").await.unwrap());
+        let created = target
+            .ssh_servers()
+            .create_group("ops".to_string())
+            .await
+            .unwrap();
+        assert!(created.id > 1);
+
+        // 目标库已有数据时拒绝覆盖
+        assert!(matches!(
+            sqlite_to_postgres(&path, &url, false).await,
+            Err(StoreError::Precondition(_))
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,566 @@
+//! 用户账号与个人配置（`users` 表）
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension, params};
+use sqlx::postgres::PgPool;
+use sqlx::{Connection as _, Row};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::pool::StoreError;
+use super::rbac::{
+    RbacOutcome, pg_assign_roles, pg_has_admin, sqlite_assign_roles, sqlite_has_admin,
+};
+use super::store::Store;
+use crate::modules::web::migrations::ASSIGN_DEFAULT_ROLES;
+use crate::modules::web::models::{AccountState, UserAccount, UserCreateInput, UserUpdateInput};
+
+/// 首次启动时创建的默认账号（用户名、密码、邮箱）
+const DEFAULT_USERS: &[(&str, &str, &str)] = &[
+    ("admin", "password123", "admin@example.com"),
+    ("user1", "user123", "user1@example.com"),
+];
+
+// 使用bcrypt算法加密存储默认用户密码
+fn default_users() -> Vec<(&'static str, String, &'static str)> {
+    DEFAULT_USERS
+        .iter()
+        .map(|(username, pwd, email)| {
+            let hash = bcrypt::hash(pwd, bcrypt::DEFAULT_COST).unwrap_or_else(|_| pwd.to_string());
+            (*username, hash, *email)
+        })
+        .collect()
+}
+
+// 使用bcrypt加密密码，较慢，放到阻塞线程池执行
+async fn hash_password(password: &str) -> Result<String, StoreError> {
+    let password = password.to_string();
+    let hash =
+        tokio::task::spawn_blocking(move || bcrypt::hash(password, bcrypt::DEFAULT_COST)).await??;
+    Ok(hash)
+}
+
+// 支持明文和bcrypt哈希
+fn verify_password(password: &str, stored: &str) -> bool {
+    // 如果是bcrypt哈希（以$2开头），使用bcrypt验证；否则回退为明文比较
+    if stored.starts_with("$2") {
+        bcrypt::verify(password, stored).unwrap_or(false)
+    } else {
+        stored == password
+    }
+}
+
+fn dummy_hash() -> &'static str {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    DUMMY_HASH.get_or_init(|| bcrypt::hash("rsts-dummy", bcrypt::DEFAULT_COST).unwrap_or_default())
+}
+
+// SQLite：没有任何用户时创建默认用户
+pub fn seed_default_users(conn: &Connection) -> rusqlite::Result<()> {
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM users", [], |row| row.get(0))?;
+    if count > 0 {
+        return Ok(());
+    }
+    for (username, hash, email) in default_users() {
+        conn.execute(
+            "INSERT INTO users (username, password, email, must_change_password) VALUES (?1, ?2, ?3, 1)",
+            params![username, hash, email],
+        )?;
+    }
+    conn.execute_batch(ASSIGN_DEFAULT_ROLES)
+}
+
+// PostgreSQL：没有任何用户时创建默认用户
+pub async fn pg_seed_default_users(pg: &PgPool) -> Result<(), StoreError> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(pg)
+        .await?;
+    if count > 0 {
+        return Ok(());
+    }
+    let users = tokio::task::spawn_blocking(default_users).await?;
+    for (username, hash, email) in users {
+        sqlx::query(
+            "INSERT INTO users (username, password, email, must_change_password) VALUES ($1, $2, $3, 1)
+             ON CONFLICT (username) DO NOTHING",
+        )
+        .bind(username)
+        .bind(hash)
+        .bind(email)
+        .execute(pg)
+        .await?;
+    }
+    sqlx::query(ASSIGN_DEFAULT_ROLES).execute(pg).await?;
+    Ok(())
+}
+
+const ACCOUNTS_SQL: &str =
+    "SELECT id, username, email, disabled, must_change_password, password_changed_at
+    FROM users ORDER BY id";
+const ACCOUNT_ROLES_SQL: &str = "SELECT ur.user_id, r.name FROM user_roles ur
+    JOIN roles r ON r.id = ur.role_id ORDER BY r.id";
+
+type AccountRow = (i64, String, Option<String>, bool, bool, Option<String>);
+
+fn group_accounts(rows: Vec<AccountRow>, roles: Vec<(i64, String)>) -> Vec<UserAccount> {
+    let mut by_user: HashMap<i64, Vec<String>> = HashMap::new();
+    for (user_id, role) in roles {
+        by_user.entry(user_id).or_default().push(role);
+    }
+    rows.into_iter()
+        .map(
+            |(id, username, email, disabled, must_change_password, password_changed_at)| {
+                UserAccount {
+                    id,
+                    username,
+                    email,
+                    disabled,
+                    must_change_password,
+                    password_changed_at,
+                    roles: by_user.remove(&id).unwrap_or_default(),
+                }
+            },
+        )
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct UserRepository {
+    store: Store,
+}
+
+impl UserRepository {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    // 读取存储的密码（哈希）
+    async fn stored_password(&self, username: &str) -> Result<Option<String>, StoreError> {
+        let username = username.to_string();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.query_row(
+                        "SELECT password FROM users WHERE username = ?1",
+                        params![username],
+                        |row| row.get(0),
+                    )
+                    .optional()
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let password = sqlx::query_scalar("SELECT password FROM users WHERE username = $1")
+                    .bind(username)
+                    .fetch_optional(pg)
+                    .await?;
+                Ok(password)
+            }
+        }
+    }
+
+    // 验证用户凭据，bcrypt 校验较慢，放到阻塞线程池执行
+    // 用户不存在时同样校验一次假哈希，避免通过响应时间探测用户名
+    pub async fn validate_user(&self, username: &str, password: &str) -> Result<bool, StoreError> {
+        let stored = self.stored_password(username).await?;
+        let password = password.to_string();
+        let valid = tokio::task::spawn_blocking(move || match stored {
+            Some(stored) => verify_password(&password, &stored),
+            None => {
+                let _ = verify_password(&password, dummy_hash());
+                false
+            }
+        })
+        .await?;
+        Ok(valid)
+    }
+
+    // 用户 ID、用户名、邮箱
+    pub async fn get_user_info(
+        &self,
+        username: &str,
+    ) -> Result<Option<(i32, String, String)>, StoreError> {
+        let username = username.to_string();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.query_row(
+                        "SELECT id, username, email FROM users WHERE username = ?1",
+                        params![username],
+                        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                    )
+                    .optional()
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let row = sqlx::query("SELECT id, username, email FROM users WHERE username = $1")
+                    .bind(username)
+                    .fetch_optional(pg)
+                    .await?;
+                match row {
+                    Some(row) => {
+                        let id: i64 = row.try_get(0)?;
+                        let email: Option<String> = row.try_get(2)?;
+                        Ok(Some((
+                            id as i32,
+                            row.try_get(1)?,
+                            email.unwrap_or_default(),
+                        )))
+                    }
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+
+    // 读取用户的某个配置列（theme_config / terminal_setting_config）
+    async fn get_config(
+        &self,
+        column: &'static str,
+        username: &str,
+    ) -> Result<Option<String>, StoreError> {
+        let username = username.to_string();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let config = conn
+                        .query_row(
+                            &format!("SELECT {} FROM users WHERE username = ?1", column),
+                            params![username],
+                            |row| row.get::<_, Option<String>>(0),
+                        )
+                        .optional()?;
+                    Ok(config.flatten())
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let sql = format!("SELECT {} FROM users WHERE username = $1", column);
+                let config: Option<Option<String>> = sqlx::query_scalar(&sql)
+                    .bind(username)
+                    .fetch_optional(pg)
+                    .await?;
+                Ok(config.flatten())
+            }
+        }
+    }
+
+    async fn set_config(
+        &self,
+        column: &'static str,
+        username: &str,
+        config: &str,
+    ) -> Result<(), StoreError> {
+        let username = username.to_string();
+        let config = config.to_string();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.execute(
+                        &format!("UPDATE users SET {} = ?1 WHERE username = ?2", column),
+                        params![config, username],
+                    )?;
+                    Ok(())
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let sql = format!("UPDATE users SET {} = $1 WHERE username = $2", column);
+                sqlx::query(&sql)
+                    .bind(config)
+                    .bind(username)
+                    .execute(pg)
+                    .await?;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn get_theme_config(&self, username: &str) -> Result<Option<String>, StoreError> {
+        self.get_config("theme_config", username).await
+    }
+
+    pub async fn update_theme_config(
+        &self,
+        username: &str,
+        config: &str,
+    ) -> Result<(), StoreError> {
+        self.set_config("theme_config", username, config).await
+    }
+
+    pub async fn get_terminal_config(&self, username: &str) -> Result<Option<String>, StoreError> {
+        self.get_config("terminal_setting_config", username).await
+    }
+
+    pub async fn update_terminal_config(
+        &self,
+        username: &str,
+        config: &str,
+    ) -> Result<(), StoreError> {
+        self.set_config("terminal_setting_config", username, config)
+            .await
+    }
+
+    // 账号是否停用、是否必须修改密码；用户不存在时返回 None
+    pub async fn account_state(&self, username: &str) -> Result<Option<AccountState>, StoreError> {
+        let username = username.to_string();
+        let sql = "SELECT disabled, must_change_password FROM users WHERE username = $1";
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    conn.query_row(&sql.replace('$', "?"), params![username], |row| {
+                        Ok(AccountState {
+                            disabled: row.get(0)?,
+                            must_change_password: row.get(1)?,
+                        })
+                    })
+                    .optional()
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let row: Option<(i32, i32)> = sqlx::query_as(sql)
+                    .bind(username)
+                    .fetch_optional(pg)
+                    .await?;
+                Ok(row.map(|(disabled, must_change)| AccountState {
+                    disabled: disabled != 0,
+                    must_change_password: must_change != 0,
+                }))
+            }
+        }
+    }
+
+    // 全部账号及其角色
+    pub async fn list_users(&self) -> Result<Vec<UserAccount>, StoreError> {
+        let (rows, roles) = match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(|conn| {
+                    let rows = conn
+                        .prepare(ACCOUNTS_SQL)?
+                        .query_map([], |row| {
+                            Ok((
+                                row.get(0)?,
+                                row.get(1)?,
+                                row.get(2)?,
+                                row.get(3)?,
+                                row.get(4)?,
+                                row.get(5)?,
+                            ))
+                        })?
+                        .collect::<rusqlite::Result<Vec<AccountRow>>>()?;
+                    let roles = conn
+                        .prepare(ACCOUNT_ROLES_SQL)?
+                        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                        .collect::<rusqlite::Result<Vec<_>>>()?;
+                    Ok((rows, roles))
+                })
+                .await?
+            }
+            Store::Postgres(pg) => {
+                let rows = sqlx::query(ACCOUNTS_SQL)
+                    .fetch_all(pg)
+                    .await?
+                    .iter()
+                    .map(|row| {
+                        Ok((
+                            row.try_get(0)?,
+                            row.try_get(1)?,
+                            row.try_get(2)?,
+                            row.try_get::<i32, _>(3)? != 0,
+                            row.try_get::<i32, _>(4)? != 0,
+                            row.try_get(5)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<AccountRow>, sqlx::Error>>()?;
+                let roles = sqlx::query_as(ACCOUNT_ROLES_SQL).fetch_all(pg).await?;
+                (rows, roles)
+            }
+        };
+        Ok(group_accounts(rows, roles))
+    }
+
+    // 新建用户并分配角色（缺省为 R_USER）；密码需事先通过密码策略校验
+    pub async fn create_user(&self, input: UserCreateInput) -> Result<RbacOutcome, StoreError> {
+        let hash = hash_password(&input.password).await?;
+        let roles = input.roles.unwrap_or_else(|| vec!["R_USER".to_string()]);
+        let must_change = input.must_change_password.unwrap_or(true);
+        let now = Utc::now().to_rfc3339();
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let tx = conn.transaction()?;
+                    let exists: bool = tx.query_row(
+                        "SELECT EXISTS(SELECT 1 FROM users WHERE username = ?1)",
+                        params![input.username],
+                        |row| row.get(0),
+                    )?;
+                    if exists {
+                        return Ok(RbacOutcome::AlreadyExists);
+                    }
+                    tx.execute(
+                        "INSERT INTO users (username, password, email, must_change_password, password_changed_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![input.username, hash, input.email, must_change, now],
+                    )?;
+                    let user_id = tx.last_insert_rowid();
+                    if let Some(role) = sqlite_assign_roles(&tx, user_id, &roles)? {
+                        return Ok(RbacOutcome::UnknownRole(role));
+                    }
+                    tx.commit()?;
+                    Ok(RbacOutcome::Done)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let mut tx = conn.begin().await?;
+                let user_id: Option<i64> = sqlx::query_scalar(
+                    "INSERT INTO users (username, password, email, must_change_password, password_changed_at)
+                     VALUES ($1, $2, $3, $4, $5)
+                     ON CONFLICT (username) DO NOTHING RETURNING id",
+                )
+                .bind(&input.username)
+                .bind(hash)
+                .bind(&input.email)
+                .bind(must_change as i32)
+                .bind(now)
+                .fetch_optional(&mut *tx)
+                .await?;
+                let Some(user_id) = user_id else {
+                    return Ok(RbacOutcome::AlreadyExists);
+                };
+                if let Some(role) = pg_assign_roles(&mut tx, user_id, &roles).await? {
+                    return Ok(RbacOutcome::UnknownRole(role));
+                }
+                tx.commit().await?;
+                Ok(RbacOutcome::Done)
+            }
+        }
+    }
+
+    // 修改邮箱或停用/启用账号；不允许停用最后一个管理员
+    pub async fn update_user(
+        &self,
+        username: String,
+        input: UserUpdateInput,
+    ) -> Result<RbacOutcome, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let tx = conn.transaction()?;
+                    let rows = tx.execute(
+                        "UPDATE users SET email = COALESCE(?1, email), disabled = COALESCE(?2, disabled)
+                         WHERE username = ?3",
+                        params![input.email, input.disabled, username],
+                    )?;
+                    if rows == 0 {
+                        return Ok(RbacOutcome::NotFound);
+                    }
+                    if !sqlite_has_admin(&tx)? {
+                        return Ok(RbacOutcome::NoAdminLeft);
+                    }
+                    tx.commit()?;
+                    Ok(RbacOutcome::Done)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let mut tx = conn.begin().await?;
+                let result = sqlx::query(
+                    "UPDATE users SET email = COALESCE($1, email), disabled = COALESCE($2, disabled)
+                     WHERE username = $3",
+                )
+                .bind(&input.email)
+                .bind(input.disabled.map(i32::from))
+                .bind(&username)
+                .execute(&mut *tx)
+                .await?;
+                if result.rows_affected() == 0 {
+                    return Ok(RbacOutcome::NotFound);
+                }
+                if !pg_has_admin(&mut tx).await? {
+                    return Ok(RbacOutcome::NoAdminLeft);
+                }
+                tx.commit().await?;
+                Ok(RbacOutcome::Done)
+            }
+        }
+    }
+
+    // 删除用户（角色关联级联删除）；不允许删除最后一个管理员
+    pub async fn delete_user(&self, username: String) -> Result<RbacOutcome, StoreError> {
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let tx = conn.transaction()?;
+                    let rows =
+                        tx.execute("DELETE FROM users WHERE username = ?1", params![username])?;
+                    if rows == 0 {
+                        return Ok(RbacOutcome::NotFound);
+                    }
+                    if !sqlite_has_admin(&tx)? {
+                        return Ok(RbacOutcome::NoAdminLeft);
+                    }
+                    tx.commit()?;
+                    Ok(RbacOutcome::Done)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let mut conn = pg.acquire().await?;
+                let mut tx = conn.begin().await?;
+                let result = sqlx::query("DELETE FROM users WHERE username = $1")
+                    .bind(&username)
+                    .execute(&mut *tx)
+                    .await?;
+                if result.rows_affected() == 0 {
+                    return Ok(RbacOutcome::NotFound);
+                }
+                if !pg_has_admin(&mut tx).await? {
+                    return Ok(RbacOutcome::NoAdminLeft);
+                }
+                tx.commit().await?;
+                Ok(RbacOutcome::Done)
+            }
+        }
+    }
+
+    // 设置新密码（bcrypt），返回用户是否存在；must_change 为 true 时下次登录须再修改
+    pub async fn set_password(
+        &self,
+        username: &str,
+        password: &str,
+        must_change: bool,
+    ) -> Result<bool, StoreError> {
+        let hash = hash_password(password).await?;
+        let username = username.to_string();
+        let now = Utc::now().to_rfc3339();
+        let sql =
+            "UPDATE users SET password = $1, must_change_password = $2, password_changed_at = $3
+            WHERE username = $4";
+        match &self.store {
+            Store::Sqlite(pool) => {
+                pool.run(move |conn| {
+                    let rows = conn.execute(
+                        &sql.replace('$', "?"),
+                        params![hash, must_change, now, username],
+                    )?;
+                    Ok(rows > 0)
+                })
+                .await
+            }
+            Store::Postgres(pg) => {
+                let result = sqlx::query(sql)
+                    .bind(hash)
+                    .bind(must_change as i32)
+                    .bind(now)
+                    .bind(username)
+                    .execute(pg)
+                    .await?;
+                Ok(result.rows_affected() > 0)
+            }
+        }
+    }
+}
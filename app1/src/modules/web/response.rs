@@ -0,0 +1,271 @@
+// 统一响应：所有 JSON 接口都返回 Response { code, msg, data } 信封
+//
+// 处理函数返回 ApiResult<T>：成功时为 Response<T>（HTTP 200，code 为 "0000"），
+// 失败时为 ApiError，按错误类型给出 HTTP 状态码与业务码（定义在 auth_utils.rs，取值保持稳定）。
+// 元数据库与内部错误的详情只写日志，不返回给客户端。
+use actix_web::error::{JsonPayloadError, QueryPayloadError};
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse, Responder, ResponseError, body::BoxBody};
+use serde::Serialize;
+use serde_json::json;
+use thiserror::Error;
+
+use super::auth_utils::{
+    CODE_ACCOUNT_DISABLED, CODE_CONFLICT, CODE_DATABASE_ERROR, CODE_FORBIDDEN,
+    CODE_INTERNAL_ERROR, CODE_INVALID_CREDENTIALS, CODE_INVALID_PARAMS, CODE_LOGIN_THROTTLED,
+    CODE_NOT_FOUND, CODE_PASSWORD_CHANGE_REQUIRED, CODE_PAYLOAD_TOO_LARGE,
+    CODE_REFRESH_TOKEN_INVALID, CODE_REMOTE_ERROR, CODE_SQL_ERROR, CODE_SUCCESS,
+    CODE_TOKEN_EXPIRED, CODE_TOKEN_INVALID, CODE_USER_NOT_FOUND,
+};
+use super::models::Response;
+use super::repository::StoreError;
+use super::sqlite_registry::RegistryError;
+
+pub type ApiResult<T = serde_json::Value> = Result<Response<T>, ApiError>;
+
+impl<T> Response<T> {
+    // 成功并返回数据
+    pub fn ok(data: T) -> Self {
+        Self::with_msg("ok", data)
+    }
+
+    // 成功并返回数据与提示信息
+    pub fn with_msg(msg: impl Into<String>, data: T) -> Self {
+        Self {
+            code: CODE_SUCCESS.to_string(),
+            msg: msg.into(),
+            data: Some(data),
+        }
+    }
+
+    // 成功，只返回提示信息
+    pub fn message(msg: impl Into<String>) -> Self {
+        Self {
+            code: CODE_SUCCESS.to_string(),
+            msg: msg.into(),
+            data: None,
+        }
+    }
+}
+
+impl<T: Serialize> Responder for Response<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse {
+        HttpResponse::Ok().json(self)
+    }
+}
+
+/// 接口错误，每个变体对应固定的 HTTP 状态码与业务码
+#[derive(Debug, Error)]
+pub enum ApiError {
+    /// 请求参数不合法
+    #[error("{0}")]
+    InvalidParams(String),
+    /// 新密码不满足密码策略，data 为不满足的规则
+    #[error("密码不符合策略：{}", .0.join("；"))]
+    PasswordPolicy(Vec<String>),
+    /// 用户名、密码或验证码错误
+    #[error("{0}")]
+    InvalidCredentials(String),
+    /// 访问令牌缺失或无效（前端退出登录）
+    #[error("{0}")]
+    TokenInvalid(String),
+    /// 访问令牌已过期（前端刷新令牌后重试）
+    #[error("{0}")]
+    TokenExpired(String),
+    /// 刷新令牌无效、已过期或已被使用
+    #[error("{0}")]
+    RefreshTokenInvalid(String),
+    /// 已登录但缺少权限
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("账号已停用")]
+    AccountDisabled,
+    /// 必须先修改密码
+    #[error("{0}")]
+    PasswordChangeRequired(String),
+    /// 登录过于频繁或账号临时锁定，data.retryAfter 为需要等待的秒数
+    #[error("{msg}")]
+    LoginThrottled { msg: String, retry_after: u64 },
+    #[error("用户不存在")]
+    UserNotFound,
+    /// 资源不存在
+    #[error("{0}")]
+    NotFound(String),
+    /// 资源已存在或状态冲突
+    #[error("{0}")]
+    Conflict(String),
+    /// 请求体超过上限
+    #[error("{0}")]
+    PayloadTooLarge(String),
+    /// SQL 语句执行失败（语法错误、约束冲突等）
+    #[error("{0}")]
+    Sql(String),
+    /// 远程主机或外部数据库连接、操作失败（SSH、SFTP、SQL Studio 连接）
+    #[error("{0}")]
+    Remote(String),
+    /// 元数据库读写失败
+    #[error("数据库错误")]
+    Store(#[from] StoreError),
+    /// 其他服务器内部错误
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidParams(_) | Self::PasswordPolicy(_) => CODE_INVALID_PARAMS,
+            Self::InvalidCredentials(_) => CODE_INVALID_CREDENTIALS,
+            Self::TokenInvalid(_) => CODE_TOKEN_INVALID,
+            Self::TokenExpired(_) => CODE_TOKEN_EXPIRED,
+            Self::RefreshTokenInvalid(_) => CODE_REFRESH_TOKEN_INVALID,
+            Self::Forbidden(_) => CODE_FORBIDDEN,
+            Self::AccountDisabled => CODE_ACCOUNT_DISABLED,
+            Self::PasswordChangeRequired(_) => CODE_PASSWORD_CHANGE_REQUIRED,
+            Self::LoginThrottled { .. } => CODE_LOGIN_THROTTLED,
+            Self::UserNotFound => CODE_USER_NOT_FOUND,
+            Self::NotFound(_) => CODE_NOT_FOUND,
+            Self::Conflict(_) => CODE_CONFLICT,
+            Self::PayloadTooLarge(_) => CODE_PAYLOAD_TOO_LARGE,
+            Self::Sql(_) => CODE_SQL_ERROR,
+            Self::Remote(_) => CODE_REMOTE_ERROR,
+            Self::Store(_) => CODE_DATABASE_ERROR,
+            Self::Internal(_) => CODE_INTERNAL_ERROR,
+        }
+    }
+
+    fn data(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::PasswordPolicy(problems) => Some(json!(problems)),
+            Self::LoginThrottled { retry_after, .. } => Some(json!({ "retryAfter": retry_after })),
+            _ => None,
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::InvalidParams(_) | Self::PasswordPolicy(_) => StatusCode::BAD_REQUEST,
+            Self::InvalidCredentials(_)
+            | Self::TokenInvalid(_)
+            | Self::TokenExpired(_)
+            | Self::RefreshTokenInvalid(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) | Self::AccountDisabled | Self::PasswordChangeRequired(_) => {
+                StatusCode::FORBIDDEN
+            }
+            Self::LoginThrottled { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::UserNotFound | Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Sql(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Remote(_) => StatusCode::BAD_GATEWAY,
+            Self::Store(_) | Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            Self::Store(e) => log::error!("db error: {}", e),
+            Self::Internal(msg) => log::error!("internal error: {}", msg),
+            _ => {}
+        }
+        HttpResponse::build(self.status_code()).json(Response {
+            code: self.code().to_string(),
+            msg: self.to_string(),
+            data: self.data(),
+        })
+    }
+}
+
+impl From<RegistryError> for ApiError {
+    fn from(e: RegistryError) -> Self {
+        match e {
+            RegistryError::NotFound(_) => Self::NotFound(e.to_string()),
+            RegistryError::InvalidName(_) | RegistryError::NotSqlite(_) => {
+                Self::InvalidParams(e.to_string())
+            }
+            RegistryError::Forbidden(_) => Self::Forbidden(e.to_string()),
+            RegistryError::AlreadyExists(_) => Self::Conflict(e.to_string()),
+            RegistryError::TooLarge(_) => Self::PayloadTooLarge(e.to_string()),
+            RegistryError::Io(_) | RegistryError::Registry(_) => Self::Internal(e.to_string()),
+            RegistryError::Sqlite(_) => Self::Sql(e.to_string()),
+        }
+    }
+}
+
+// SQLite 数据库（SQLite 管理接口操作的用户数据库）上的语句执行失败
+impl From<rusqlite::Error> for ApiError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::Sql(e.to_string())
+    }
+}
+
+// JsonConfig 的错误处理：请求体解析失败同样返回信封
+pub fn json_error(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    match err {
+        JsonPayloadError::Overflow { .. } | JsonPayloadError::OverflowKnownLength { .. } => {
+            ApiError::PayloadTooLarge(err.to_string()).into()
+        }
+        _ => ApiError::InvalidParams(err.to_string()).into(),
+    }
+}
+
+// QueryConfig 的错误处理
+pub fn query_error(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::InvalidParams(err.to_string()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+
+    async fn body(err: ApiError) -> (StatusCode, Response) {
+        let resp = err.error_response();
+        let status = resp.status();
+        let bytes = to_bytes(resp.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn errors_use_envelope() {
+        let (status, resp) = body(ApiError::NotFound("Connection not found".into())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(resp.code, CODE_NOT_FOUND);
+        assert_eq!(resp.msg, "Connection not found");
+        assert!(resp.data.is_none());
+
+        let throttled = ApiError::LoginThrottled {
+            msg: "稍后再试".into(),
+            retry_after: 30,
+        };
+        let (status, resp) = body(throttled).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(resp.code, CODE_LOGIN_THROTTLED);
+        assert_eq!(resp.data, Some(json!({ "retryAfter": 30 })));
+
+        // 元数据库错误不向客户端暴露详情
+        let store = ApiError::from(StoreError::Precondition("secret detail".into()));
+        let (status, resp) = body(store).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(resp.code, CODE_DATABASE_ERROR);
+        assert_eq!(resp.msg, "数据库错误");
+    }
+
+    #[test]
+    fn registry_errors_map_to_codes() {
+        let cases = [
+            (RegistryError::NotFound("a".into()), CODE_NOT_FOUND),
+            (RegistryError::InvalidName("../a".into()), CODE_INVALID_PARAMS),
+            (RegistryError::Forbidden("/etc".into()), CODE_FORBIDDEN),
+            (RegistryError::AlreadyExists("a".into()), CODE_CONFLICT),
+            (RegistryError::TooLarge(1), CODE_PAYLOAD_TOO_LARGE),
+        ];
+        for (err, code) in cases {
+            assert_eq!(ApiError::from(err).code(), code);
+        }
+    }
+}
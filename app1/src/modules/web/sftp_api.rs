@@ -1,303 +1,334 @@
-use actix_web::{HttpResponse, Responder, web};
+use actix_web::{HttpRequest, HttpResponse, web};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use super::audit::{self, AuditActor, AuditEvent};
+use super::database::Database;
 use super::models::{
     ChmodPayload, CreateSessionPayload, DeletePayload, MkdirPayload, PathQuery, RenamePayload,
-    UploadPayload, WriteFilePayload,
+    Response, UploadPayload, WriteFilePayload,
 };
+use super::response::{ApiError, ApiResult};
 use crate::modules::sftp::service::{SftpCredentials, SftpService};
 
 // 数据模型已迁移至 models.rs
+// 建立会话、下载与修改文件的操作写入审计日志，detail 记录会话 ID 便于关联到主机
 
+// 远程主机上的 SFTP 操作失败
+fn remote(e: anyhow::Error) -> ApiError {
+    ApiError::Remote(e.to_string())
+}
+
+fn session_detail(session_id: usize) -> String {
+    format!("session {}", session_id)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sftp/session",
+    tag = "sftp",
+    summary = "建立 SFTP 会话",
+    responses((status = 200, body = Response))
+)]
 pub async fn create_session(
+    req: HttpRequest,
     service: web::Data<Arc<Mutex<SftpService>>>,
+    db: web::Data<Arc<Database>>,
     payload: web::Json<CreateSessionPayload>,
-) -> impl Responder {
+) -> ApiResult {
     let creds = SftpCredentials {
         hostname: payload.hostname.clone(),
         port: payload.port,
         username: payload.username.clone(),
         password: payload.password.clone(),
     };
+    let target = format!("{}@{}:{}", creds.username, creds.hostname, creds.port);
+    let event = AuditEvent::start(audit::SFTP_SESSION_OPEN, target);
     let service = service.get_ref().clone();
-    match service.lock().await.create_session(creds).await {
-        Ok(id) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "0000",
-            "msg": "success",
-            "data": {"session_id": id}
-        })),
-        Err(e) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "5000",
-            "msg": e.to_string(),
-            "data": null
-        })),
-    }
+    let result = service.lock().await.create_session(creds).await;
+    let event = match &result {
+        Ok(id) => event.with_detail(session_detail(*id)),
+        Err(_) => event,
+    };
+    event
+        .finish(&db, &AuditActor::from_request(&req), &result)
+        .await;
+    let id = result.map_err(remote)?;
+    Ok(Response::ok(serde_json::json!({ "session_id": id })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/sftp/list",
+    tag = "sftp",
+    summary = "列出目录",
+    params(PathQuery),
+    responses((status = 200, body = Response))
+)]
 pub async fn list_dir(
     service: web::Data<Arc<Mutex<SftpService>>>,
     q: web::Query<PathQuery>,
-) -> impl Responder {
+) -> ApiResult {
     let path = q.path.clone().unwrap_or("/".to_string());
     let service = service.get_ref().clone();
-    let guard = match service.lock().await.get_session(q.session_id).await {
-        Ok(g) => g,
-        Err(e) => {
-            return HttpResponse::Ok().json(serde_json::json!({
-                "code": "5000",
-                "msg": e.to_string(),
-                "data": null
-            }));
-        }
-    };
-    match guard.list(&path).await {
-        Ok(entries) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "0000",
-            "msg": "success",
-            "data": entries
-        })),
-        Err(e) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "5000",
-            "msg": e.to_string(),
-            "data": null
-        })),
-    }
+    let guard = service
+        .lock()
+        .await
+        .get_session(q.session_id)
+        .await
+        .map_err(remote)?;
+    let entries = guard.list(&path).await.map_err(remote)?;
+    Ok(Response::ok(serde_json::json!(entries)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/sftp/read",
+    tag = "sftp",
+    summary = "读取文本文件",
+    params(PathQuery),
+    responses((status = 200, body = Response))
+)]
 pub async fn read_file(
     service: web::Data<Arc<Mutex<SftpService>>>,
     q: web::Query<PathQuery>,
-) -> impl Responder {
+) -> ApiResult {
     let path = q.path.clone().unwrap_or("/".to_string());
     let service = service.get_ref().clone();
-    let guard = match service.lock().await.get_session(q.session_id).await {
-        Ok(g) => g,
-        Err(e) => {
-            return HttpResponse::Ok().json(serde_json::json!({
-                "code": "5000",
-                "msg": e.to_string(),
-                "data": null
-            }));
-        }
-    };
-    match guard.read_text(&path).await {
-        Ok(text) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "0000",
-            "msg": "success",
-            "data": {"content": text}
-        })),
-        Err(e) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "5000",
-            "msg": e.to_string(),
-            "data": null
-        })),
-    }
+    let guard = service
+        .lock()
+        .await
+        .get_session(q.session_id)
+        .await
+        .map_err(remote)?;
+    let text = guard.read_text(&path).await.map_err(remote)?;
+    Ok(Response::ok(serde_json::json!({ "content": text })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/sftp/write",
+    tag = "sftp",
+    summary = "写入文本文件",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
 pub async fn write_file(
+    req: HttpRequest,
     service: web::Data<Arc<Mutex<SftpService>>>,
+    db: web::Data<Arc<Database>>,
     payload: web::Json<WriteFilePayload>,
-) -> impl Responder {
+) -> ApiResult<()> {
+    let event = AuditEvent::start(audit::SFTP_WRITE, payload.path.clone())
+        .with_detail(session_detail(payload.session_id));
     let service = service.get_ref().clone();
-    let guard = match service.lock().await.get_session(payload.session_id).await {
-        Ok(g) => g,
-        Err(e) => {
-            return HttpResponse::Ok().json(serde_json::json!({
-                "code": "5000",
-                "msg": e.to_string(),
-                "data": null
-            }));
-        }
+    let session = service.lock().await.get_session(payload.session_id).await;
+    let result = match session {
+        Ok(guard) => guard.write_text(&payload.path, &payload.content).await,
+        Err(e) => Err(e),
     };
-    match guard.write_text(&payload.path, &payload.content).await {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "0000",
-            "msg": "success",
-            "data": null
-        })),
-        Err(e) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "5000",
-            "msg": e.to_string(),
-            "data": null
-        })),
-    }
+    event
+        .finish(&db, &AuditActor::from_request(&req), &result)
+        .await;
+    result.map_err(remote)?;
+    Ok(Response::message("OK"))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/sftp/delete",
+    tag = "sftp",
+    summary = "删除文件或目录",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
 pub async fn delete_file(
+    req: HttpRequest,
     service: web::Data<Arc<Mutex<SftpService>>>,
+    db: web::Data<Arc<Database>>,
     payload: web::Json<DeletePayload>,
-) -> impl Responder {
+) -> ApiResult<()> {
+    let event = AuditEvent::start(audit::SFTP_DELETE, payload.path.clone())
+        .with_detail(session_detail(payload.session_id));
     let service = service.get_ref().clone();
-    let guard = match service.lock().await.get_session(payload.session_id).await {
-        Ok(g) => g,
-        Err(e) => {
-            return HttpResponse::Ok().json(serde_json::json!({
-                "code": "5000",
-                "msg": e.to_string(),
-                "data": null
-            }));
-        }
+    let session = service.lock().await.get_session(payload.session_id).await;
+    let result = match session {
+        Ok(guard) => guard.delete(&payload.path).await,
+        Err(e) => Err(e),
     };
-    match guard.delete(&payload.path).await {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "0000",
-            "msg": "success",
-            "data": "OK"
-        })),
-        Err(e) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "5000",
-            "msg": e.to_string(),
-            "data": null
-        })),
-    }
+    event
+        .finish(&db, &AuditActor::from_request(&req), &result)
+        .await;
+    result.map_err(remote)?;
+    Ok(Response::message("OK"))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/sftp/rename",
+    tag = "sftp",
+    summary = "重命名",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
 pub async fn rename_file(
+    req: HttpRequest,
     service: web::Data<Arc<Mutex<SftpService>>>,
+    db: web::Data<Arc<Database>>,
     payload: web::Json<RenamePayload>,
-) -> impl Responder {
+) -> ApiResult<()> {
+    let event = AuditEvent::start(audit::SFTP_RENAME, payload.path.clone()).with_detail(format!(
+        "{} -> {}",
+        session_detail(payload.session_id),
+        payload.new_name
+    ));
     let service = service.get_ref().clone();
-    let guard = match service.lock().await.get_session(payload.session_id).await {
-        Ok(g) => g,
-        Err(e) => {
-            return HttpResponse::Ok().json(serde_json::json!({
-                "code": "5000",
-                "msg": e.to_string(),
-                "data": null
-            }));
-        }
+    let session = service.lock().await.get_session(payload.session_id).await;
+    let result = match session {
+        Ok(guard) => guard.rename(&payload.path, &payload.new_name).await,
+        Err(e) => Err(e),
     };
-    match guard.rename(&payload.path, &payload.new_name).await {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "0000",
-            "msg": "success",
-            "data": "OK"
-        })),
-        Err(e) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "5000",
-            "msg": e.to_string(),
-            "data": null
-        })),
-    }
+    event
+        .finish(&db, &AuditActor::from_request(&req), &result)
+        .await;
+    result.map_err(remote)?;
+    Ok(Response::message("OK"))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/sftp/upload",
+    tag = "sftp",
+    summary = "上传文件",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
 pub async fn upload_file(
+    req: HttpRequest,
     service: web::Data<Arc<Mutex<SftpService>>>,
+    db: web::Data<Arc<Database>>,
     payload: web::Json<UploadPayload>,
-) -> impl Responder {
+) -> ApiResult<()> {
+    let event = AuditEvent::start(
+        audit::SFTP_UPLOAD,
+        format!(
+            "{}/{}",
+            payload.path.trim_end_matches('/'),
+            payload.filename
+        ),
+    )
+    .with_detail(session_detail(payload.session_id));
     let service = service.get_ref().clone();
-    let guard = match service.lock().await.get_session(payload.session_id).await {
-        Ok(g) => g,
-        Err(e) => {
-            return HttpResponse::Ok().json(serde_json::json!({
-                "code": "5000",
-                "msg": e.to_string(),
-                "data": null
-            }));
+    let session = service.lock().await.get_session(payload.session_id).await;
+    let result = match session {
+        Ok(guard) => {
+            guard
+                .upload_base64(&payload.path, &payload.filename, &payload.content_base64)
+                .await
         }
+        Err(e) => Err(e),
     };
-
-    match guard
-        .upload_base64(&payload.path, &payload.filename, &payload.content_base64)
-        .await
-    {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "0000",
-            "msg": "success",
-            "data": "OK"
-        })),
-        Err(e) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "5000",
-            "msg": e.to_string(),
-            "data": null
-        })),
-    }
+    event
+        .finish(&db, &AuditActor::from_request(&req), &result)
+        .await;
+    result.map_err(remote)?;
+    Ok(Response::message("OK"))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/sftp/download",
+    tag = "sftp",
+    summary = "下载文件",
+    params(PathQuery),
+    responses((status = 200, description = "文件内容", content_type = "application/octet-stream", body = String))
+)]
 pub async fn download_file(
+    req: HttpRequest,
     service: web::Data<Arc<Mutex<SftpService>>>,
+    db: web::Data<Arc<Database>>,
     q: web::Query<PathQuery>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let path = q.path.clone().unwrap_or("/".to_string());
+    let event = AuditEvent::start(audit::SFTP_DOWNLOAD, path.clone())
+        .with_detail(session_detail(q.session_id));
+    let actor = AuditActor::from_request(&req);
     let service = service.get_ref().clone();
     let guard = match service.lock().await.get_session(q.session_id).await {
         Ok(g) => g,
-        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+        Err(e) => {
+            event.record(&db, &actor, Some(e.to_string())).await;
+            return Err(remote(e));
+        }
     };
 
-    match guard.download(&path).await {
-        Ok(bytes) => {
-            let fname = std::path::Path::new(&path)
-                .file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("download");
-            HttpResponse::Ok()
-                .append_header(("Content-Type", "application/octet-stream"))
-                .append_header((
-                    "Content-Disposition",
-                    format!("attachment; filename=\"{}\"", fname),
-                ))
-                .body(bytes)
-        }
-        Err(e) => HttpResponse::NotFound().body(e.to_string()),
-    }
+    let result = guard.download(&path).await;
+    event.finish(&db, &actor, &result).await;
+    let bytes = result.map_err(|e| ApiError::NotFound(e.to_string()))?;
+    let fname = std::path::Path::new(&path)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("download");
+    Ok(HttpResponse::Ok()
+        .append_header(("Content-Type", "application/octet-stream"))
+        .append_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", fname),
+        ))
+        .body(bytes))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/sftp/mkdir",
+    tag = "sftp",
+    summary = "创建目录",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
 pub async fn create_dir(
+    req: HttpRequest,
     service: web::Data<Arc<Mutex<SftpService>>>,
+    db: web::Data<Arc<Database>>,
     payload: web::Json<MkdirPayload>,
-) -> impl Responder {
+) -> ApiResult<()> {
+    let event = AuditEvent::start(audit::SFTP_MKDIR, payload.path.clone())
+        .with_detail(session_detail(payload.session_id));
     let service = service.get_ref().clone();
-    let guard = match service.lock().await.get_session(payload.session_id).await {
-        Ok(g) => g,
-        Err(e) => {
-            return HttpResponse::Ok().json(serde_json::json!({
-                "code": "5000",
-                "msg": e.to_string(),
-                "data": null
-            }));
-        }
+    let session = service.lock().await.get_session(payload.session_id).await;
+    let result = match session {
+        Ok(guard) => guard.mkdir(&payload.path).await,
+        Err(e) => Err(e),
     };
-    match guard.mkdir(&payload.path).await {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "0000",
-            "msg": "success",
-            "data": "OK"
-        })),
-        Err(e) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "5000",
-            "msg": e.to_string(),
-            "data": null
-        })),
-    }
+    event
+        .finish(&db, &AuditActor::from_request(&req), &result)
+        .await;
+    result.map_err(remote)?;
+    Ok(Response::message("OK"))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/sftp/chmod",
+    tag = "sftp",
+    summary = "修改权限",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
 pub async fn set_permissions(
+    req: HttpRequest,
     service: web::Data<Arc<Mutex<SftpService>>>,
+    db: web::Data<Arc<Database>>,
     payload: web::Json<ChmodPayload>,
-) -> impl Responder {
+) -> ApiResult<()> {
+    let event = AuditEvent::start(audit::SFTP_CHMOD, payload.path.clone()).with_detail(format!(
+        "{} mode {:o}",
+        session_detail(payload.session_id),
+        payload.mode
+    ));
     let service = service.get_ref().clone();
-    let guard = match service.lock().await.get_session(payload.session_id).await {
-        Ok(g) => g,
-        Err(e) => {
-            return HttpResponse::Ok().json(serde_json::json!({
-                "code": "5000",
-                "msg": e.to_string(),
-                "data": null
-            }));
-        }
+    let session = service.lock().await.get_session(payload.session_id).await;
+    let result = match session {
+        Ok(guard) => guard.chmod(&payload.path, payload.mode).await,
+        Err(e) => Err(e),
     };
-    match guard.chmod(&payload.path, payload.mode).await {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "0000",
-            "msg": "success",
-            "data": "OK"
-        })),
-        Err(e) => HttpResponse::Ok().json(serde_json::json!({
-            "code": "5000",
-            "msg": e.to_string(),
-            "data": null
-        })),
-    }
+    event
+        .finish(&db, &AuditActor::from_request(&req), &result)
+        .await;
+    result.map_err(remote)?;
+    Ok(Response::message("OK"))
 }
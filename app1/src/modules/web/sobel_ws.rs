@@ -2,6 +2,8 @@ use crate::modules::operators::sobel::SobelOperator;
 use actix::prelude::*;
 use actix_web::{Error, HttpRequest, HttpResponse, web};
 use actix_web_actors::ws;
+
+use super::ws_auth::{self, TokenExpired, WsUser};
 use std::time::Instant;
 
 /// WebSocket actor for Sobel operator
@@ -23,6 +25,15 @@ impl Actor for SobelWsSession {
     }
 }
 
+/// 访问令牌过期时关闭连接
+impl Handler<TokenExpired> for SobelWsSession {
+    type Result = ();
+
+    fn handle(&mut self, _: TokenExpired, ctx: &mut Self::Context) {
+        ws_auth::close_expired(ctx);
+    }
+}
+
 /// Handler for WebSocket messages
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for SobelWsSession {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
@@ -68,5 +79,6 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for SobelWsSession {
 
 /// Route handler
 pub async fn sobel_ws_route(req: HttpRequest, stream: web::Payload) -> Result<HttpResponse, Error> {
-    ws::start(SobelWsSession::new(), &req, stream)
+    let user = WsUser::from_request(&req)?;
+    ws_auth::start(SobelWsSession::new(), &user, &req, stream)
 }
@@ -1,95 +1,182 @@
 use super::models::{
-    AddColumnPayload, ColumnInfo, CreateTablePayload, DatabaseInfo, DropColumnPayload,
-    DropTablePayload, PaginationResult, QueryParams, RenameColumnPayload, RenameTablePayload,
-    RowBatchDeletePayload, RowDeletePayload, RowInsertPayload, RowUpdatePayload, SqlQueryPayload,
-    TableInfo,
+    AddColumnPayload, ColumnInfo, CreateDatabasePayload, CreateIndexPayload, CreateTablePayload,
+    CreateTriggerPayload, CreateViewPayload, DropColumnPayload, DropIndexPayload, DropTablePayload,
+    DropTriggerPayload, DropViewPayload, ForeignKeyInfo, IndexInfo, PaginationResult, QueryParams,
+    QueryPlanNode, RegisterDatabasePayload, RenameColumnPayload, RenameTablePayload,
+    RowBatchDeletePayload, RowDeletePayload, RowInsertPayload, RowUpdatePayload, SqlColumnInfo,
+    SqlParams, SqlQueryPayload, SqlStatementResult, SqliteDumpParams, SqliteExportPayload,
+    SqliteImportPayload, SqliteRestorePayload, TableInfo, TriggerInfo, UnregisterDatabasePayload,
+    UploadDatabaseParams, ViewInfo,
 };
-use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use super::models::{DatabaseInfo, Response};
+use super::response::{ApiError, ApiResult};
+use super::sqlite_registry::{SqliteRegistry, open_db, quote_ident};
+use crate::modules::demo::csv_to_sqlite::{
+    DataFormat, SqliteImportOptions, SqliteImportReport, dump_sql, export_query, import_csv,
+    import_json, restore_sql,
+};
+use actix_web::{HttpRequest, HttpResponse, web};
 use log::error as log_error;
 use rusqlite::types::Value as SqlValue;
 use rusqlite::{Connection, Result as RusqliteResult};
-use std::fs;
 use std::path::Path;
 
 // 数据模型已迁移至 models.rs
 
-// 查询db目录下所有数据库文件
-pub async fn get_all_databases() -> impl Responder {
-    let db_dir = Path::new("db");
-    let mut databases = Vec::new();
-
-    if let Ok(entries) = fs::read_dir(db_dir) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                if entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
-                    let path = entry.path();
-                    if let Some(ext) = path.extension() {
-                        if ext == "db" {
-                            if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                                databases.push(DatabaseInfo {
-                                    name: file_name.to_string(),
-                                    path: path.to_str().unwrap_or("").to_string(),
-                                });
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
+// 列类型只允许类型名加可选的长度/精度，如 VARCHAR(255)、DECIMAL(10,2)
+fn is_valid_type_name(data_type: &str) -> bool {
+    let data_type = data_type.trim();
+    let (base, args) = match data_type.find('(') {
+        Some(i) => (&data_type[..i], Some(&data_type[i + 1..])),
+        None => (data_type, None),
+    };
+    let base_ok = base.starts_with(|c: char| c.is_ascii_alphabetic())
+        && base
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ' ');
+    let args_ok = match args {
+        None => true,
+        Some(a) => a.strip_suffix(')').is_some_and(|inner| {
+            let parts: Vec<&str> = inner.split(',').collect();
+            parts.len() <= 2
+                && parts.iter().all(|p| {
+                    let p = p.trim();
+                    !p.is_empty() && p.chars().all(|c| c.is_ascii_digit())
+                })
+        }),
+    };
+    base_ok && args_ok
+}
+
+// 查询所有可访问的数据库（根目录与登记的文件）
+#[utoipa::path(
+    get,
+    path = "/api/sqlite/databases",
+    tag = "sqlite",
+    summary = "数据库列表",
+    responses((status = 200, body = Response<Vec<DatabaseInfo>>))
+)]
+pub async fn get_all_databases(registry: web::Data<SqliteRegistry>) -> ApiResult<Vec<DatabaseInfo>> {
+    Ok(Response::ok(registry.list()))
+}
+
+// 在第一个根目录下新建数据库
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/databases/create",
+    tag = "sqlite",
+    summary = "新建数据库",
+    responses((status = 200, body = Response<DatabaseInfo>))
+)]
+pub async fn create_database(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<CreateDatabasePayload>,
+) -> ApiResult<DatabaseInfo> {
+    Ok(Response::ok(registry.create(&payload.db_name)?))
+}
 
-    HttpResponse::Ok().json(databases)
+// 登记服务器上已有的数据库文件
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/databases/register",
+    tag = "sqlite",
+    summary = "登记已有数据库",
+    responses((status = 200, body = Response<DatabaseInfo>))
+)]
+pub async fn register_database(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<RegisterDatabasePayload>,
+) -> ApiResult<DatabaseInfo> {
+    Ok(Response::ok(
+        registry.register(&payload.db_name, &payload.path)?,
+    ))
+}
+
+// 取消登记（不删除文件）
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/databases/unregister",
+    tag = "sqlite",
+    summary = "取消登记",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn unregister_database(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<UnregisterDatabasePayload>,
+) -> ApiResult<()> {
+    registry.unregister(&payload.db_name)?;
+    Ok(Response::message("ok"))
+}
+
+// 上传数据库文件，请求体为文件内容
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/databases/upload",
+    tag = "sqlite",
+    summary = "上传数据库文件",
+    params(UploadDatabaseParams),
+    request_body(content = String, content_type = "application/octet-stream", description = "数据库文件内容"),
+    responses((status = 200, body = Response<DatabaseInfo>))
+)]
+pub async fn upload_database(
+    registry: web::Data<SqliteRegistry>,
+    params: web::Query<UploadDatabaseParams>,
+    body: web::Bytes,
+) -> ApiResult<DatabaseInfo> {
+    let overwrite = params.overwrite.unwrap_or(false);
+    Ok(Response::ok(
+        registry.save_upload(&params.db_name, &body, overwrite)?,
+    ))
 }
 
 // 根据数据库名称查询表信息
+#[utoipa::path(
+    get,
+    path = "/api/sqlite/tables",
+    tag = "sqlite",
+    summary = "表结构列表",
+    params(QueryParams),
+    responses((status = 200, body = Response<Vec<TableInfo>>))
+)]
 pub async fn get_tables_by_database(
     _req: HttpRequest,
+    registry: web::Data<SqliteRegistry>,
     params: web::Query<QueryParams>,
-) -> impl Responder {
-    let db_name = &params.db_name;
-    let db_path = format!("db/{}", db_name);
-
-    if !Path::new(&db_path).exists() {
-        return HttpResponse::NotFound().body(format!("Database {} not found", db_name));
-    }
-
-    match get_tables_info(&db_path) {
-        Ok(tables) => HttpResponse::Ok().json(tables),
-        Err(e) => {
-            log_error!("Failed to get tables: {}", e);
-            HttpResponse::InternalServerError().body(format!("Error: {}", e))
-        }
-    }
+) -> ApiResult<Vec<TableInfo>> {
+    let db_path = registry.resolve(&params.db_name)?;
+    let tables = get_tables_info(&db_path).inspect_err(|e| log_error!("Failed to get tables: {}", e))?;
+    Ok(Response::ok(tables))
 }
 
 // 查询表数据（支持分页）
-pub async fn get_table_data(_req: HttpRequest, params: web::Query<QueryParams>) -> impl Responder {
-    let db_name = &params.db_name;
-    let table_name = match &params.table_name {
-        Some(name) => name,
-        None => return HttpResponse::BadRequest().body("Table name is required"),
+#[utoipa::path(
+    get,
+    path = "/api/sqlite/table-data",
+    tag = "sqlite",
+    summary = "分页查询表数据",
+    params(QueryParams),
+    responses((status = 200, body = Response<PaginationResult>))
+)]
+pub async fn get_table_data(
+    _req: HttpRequest,
+    registry: web::Data<SqliteRegistry>,
+    params: web::Query<QueryParams>,
+) -> ApiResult<PaginationResult> {
+    let Some(table_name) = &params.table_name else {
+        return Err(ApiError::InvalidParams("Table name is required".into()));
     };
 
     let page = params.page.unwrap_or(1);
-    let page_size = params.page_size.unwrap_or(10);
-    let db_path = format!("db/{}", db_name);
-
-    if !Path::new(&db_path).exists() {
-        return HttpResponse::NotFound().body(format!("Database {} not found", db_name));
-    }
-
-    match get_table_content(&db_path, table_name, page, page_size) {
-        Ok(result) => HttpResponse::Ok().json(result),
-        Err(e) => {
-            log_error!("Failed to get table data: {}", e);
-            HttpResponse::InternalServerError().body(format!("Error: {}", e))
-        }
-    }
+    let page_size = params.page_size.unwrap_or(10).max(1);
+    let db_path = registry.resolve(&params.db_name)?;
+    let result = get_table_content(&db_path, table_name, page, page_size)
+        .inspect_err(|e| log_error!("Failed to get table data: {}", e))?;
+    Ok(Response::ok(result))
 }
 
 // 内部函数：获取数据库中的表信息
-fn get_tables_info(db_path: &str) -> RusqliteResult<Vec<TableInfo>> {
-    let conn = Connection::open(db_path)?;
+fn get_tables_info(db_path: &Path) -> RusqliteResult<Vec<TableInfo>> {
+    let conn = open_db(db_path)?;
     let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name")?;
 
     let table_iter = stmt.query_map([], |row| row.get::<_, String>(0))?;
@@ -98,10 +185,14 @@ fn get_tables_info(db_path: &str) -> RusqliteResult<Vec<TableInfo>> {
     for table_name in table_iter.filter_map(Result::ok) {
         let table_name = table_name;
         let columns = get_columns_info(&conn, &table_name)?;
+        let foreign_keys = get_foreign_keys(&conn, &table_name)?;
+        let indexes = get_table_indexes(&conn, &table_name)?;
 
         tables.push(TableInfo {
             name: table_name.to_string(),
             columns,
+            foreign_keys,
+            indexes,
         });
     }
 
@@ -110,15 +201,15 @@ fn get_tables_info(db_path: &str) -> RusqliteResult<Vec<TableInfo>> {
 
 // 内部函数：获取表的列信息
 fn get_columns_info(conn: &Connection, table_name: &str) -> RusqliteResult<Vec<ColumnInfo>> {
-    let sql = format!("PRAGMA table_info({})", table_name);
-    let mut stmt = conn.prepare(&sql)?;
+    // 表值函数可绑定参数，无需拼接表名
+    let mut stmt = conn.prepare("SELECT name, type, \"notnull\", pk FROM pragma_table_info(?1)")?;
 
-    let column_iter = stmt.query_map([], |row| {
+    let column_iter = stmt.query_map([table_name], |row| {
         Ok(ColumnInfo {
-            name: row.get(1)?,
-            data_type: row.get(2)?,
-            not_null: row.get(3)?,
-            primary_key: row.get(5)?,
+            name: row.get(0)?,
+            data_type: row.get(1)?,
+            not_null: row.get(2)?,
+            primary_key: row.get(3)?,
         })
     })?;
 
@@ -132,12 +223,12 @@ fn get_columns_info(conn: &Connection, table_name: &str) -> RusqliteResult<Vec<C
 
 // 内部函数：获取表内容（支持分页）
 fn get_table_content(
-    db_path: &str,
+    db_path: &Path,
     table_name: &str,
     page: u32,
     page_size: u32,
 ) -> RusqliteResult<PaginationResult> {
-    let conn = Connection::open(db_path)?;
+    let conn = open_db(db_path)?;
 
     // 检查表是否存在
     let exists: bool = conn.query_row(
@@ -151,11 +242,11 @@ fn get_table_content(
     }
 
     // 获取总记录数
-    let total: u64 =
-        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table_name), [], |row| {
-            let count: i64 = row.get(0)?;
-            Ok(count as u64)
-        })?;
+    let table = quote_ident(table_name);
+    let total: u64 = conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+        let count: i64 = row.get(0)?;
+        Ok(count as u64)
+    })?;
 
     // 计算总页数
     let total_pages = if total % page_size as u64 == 0 {
@@ -165,11 +256,11 @@ fn get_table_content(
     };
 
     // 计算偏移量
-    let offset = (page.saturating_sub(1) * page_size) as u64;
+    let offset = page.saturating_sub(1) as u64 * page_size as u64;
 
     // 获取列名
-    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table_name))?;
-    let column_iter = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+    let column_iter = stmt.query_map([table_name], |row| row.get::<_, String>(0))?;
     let columns: Vec<String> = column_iter
         .map(|r| r.unwrap_or("unknown".to_string()))
         .collect();
@@ -177,7 +268,7 @@ fn get_table_content(
     // 查询分页数据
     let sql = format!(
         "SELECT * FROM {} LIMIT {} OFFSET {}",
-        table_name, page_size, offset
+        table, page_size, offset
     );
     let mut stmt = conn.prepare(&sql)?;
     let mut rows = stmt.query([])?;
@@ -234,24 +325,30 @@ fn get_table_content(
 }
 
 // -------- 表与列操作实现 --------
-pub async fn create_table(payload: web::Json<CreateTablePayload>) -> impl Responder {
-    let db_path = format!("db/{}", payload.db_name);
-    if !Path::new(&db_path).exists() {
-        if let Some(parent) = Path::new(&db_path).parent() {
-            if let Err(e) = fs::create_dir_all(parent) {
-                return HttpResponse::InternalServerError()
-                    .body(format!("Create db dir error: {}", e));
-            }
-        }
-    }
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/table/create",
+    tag = "sqlite",
+    summary = "新建表",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn create_table(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<CreateTablePayload>,
+) -> ApiResult<()> {
+    // 数据库须先通过新建/上传/登记接口创建
+    let db_path = registry.resolve(&payload.db_name)?;
 
     // 构建CREATE TABLE语句
     if payload.columns.is_empty() {
-        return HttpResponse::BadRequest().body("columns cannot be empty");
+        return Err(ApiError::InvalidParams("columns cannot be empty".into()));
     }
     let mut cols_sql: Vec<String> = Vec::new();
     for c in &payload.columns {
-        let mut part = format!("{} {}", c.name, c.data_type);
+        if !is_valid_type_name(&c.data_type) {
+            return Err(ApiError::InvalidParams(format!("Invalid data type: {}", c.data_type)));
+        }
+        let mut part = format!("{} {}", quote_ident(&c.name), c.data_type.trim());
         if c.not_null.unwrap_or(false) {
             part.push_str(" NOT NULL");
         }
@@ -262,66 +359,94 @@ pub async fn create_table(payload: web::Json<CreateTablePayload>) -> impl Respon
     }
     let sql = format!(
         "CREATE TABLE IF NOT EXISTS {} ({})",
-        payload.table_name,
+        quote_ident(&payload.table_name),
         cols_sql.join(", ")
     );
 
-    match Connection::open(&db_path).and_then(|conn| conn.execute(&sql, []).map(|_| ())) {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status":"ok"})),
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
-    }
+    open_db(&db_path)?.execute(&sql, [])?;
+    Ok(Response::message("ok"))
 }
 
-pub async fn drop_table(payload: web::Json<DropTablePayload>) -> impl Responder {
-    let db_path = format!("db/{}", payload.db_name);
-    if !Path::new(&db_path).exists() {
-        return HttpResponse::NotFound().body(format!("Database {} not found", payload.db_name));
-    }
-    let sql = format!("DROP TABLE IF EXISTS {}", payload.table_name);
-    match Connection::open(&db_path).and_then(|conn| conn.execute(&sql, []).map(|_| ())) {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status":"ok"})),
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
-    }
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/table/delete",
+    tag = "sqlite",
+    summary = "删除表",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn drop_table(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<DropTablePayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
+    let sql = format!("DROP TABLE IF EXISTS {}", quote_ident(&payload.table_name));
+    open_db(&db_path)?.execute(&sql, [])?;
+    Ok(Response::message("ok"))
 }
 
-pub async fn rename_table(payload: web::Json<RenameTablePayload>) -> impl Responder {
-    let db_path = format!("db/{}", payload.db_name);
-    if !Path::new(&db_path).exists() {
-        return HttpResponse::NotFound().body(format!("Database {} not found", payload.db_name));
-    }
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/table/rename",
+    tag = "sqlite",
+    summary = "重命名表",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn rename_table(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<RenameTablePayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
     let sql = format!(
         "ALTER TABLE {} RENAME TO {}",
-        payload.table_name, payload.new_name
+        quote_ident(&payload.table_name),
+        quote_ident(&payload.new_name)
     );
-    match Connection::open(&db_path).and_then(|conn| conn.execute(&sql, []).map(|_| ())) {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status":"ok"})),
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
-    }
+    open_db(&db_path)?.execute(&sql, [])?;
+    Ok(Response::message("ok"))
 }
 
-pub async fn rename_column(payload: web::Json<RenameColumnPayload>) -> impl Responder {
-    let db_path = format!("db/{}", payload.db_name);
-    if !Path::new(&db_path).exists() {
-        return HttpResponse::NotFound().body(format!("Database {} not found", payload.db_name));
-    }
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/column/rename",
+    tag = "sqlite",
+    summary = "重命名列",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn rename_column(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<RenameColumnPayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
     let sql = format!(
         "ALTER TABLE {} RENAME COLUMN {} TO {}",
-        payload.table_name, payload.old_name, payload.new_name
+        quote_ident(&payload.table_name),
+        quote_ident(&payload.old_name),
+        quote_ident(&payload.new_name)
     );
-    match Connection::open(&db_path).and_then(|conn| conn.execute(&sql, []).map(|_| ())) {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status":"ok"})),
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
-    }
+    open_db(&db_path)?.execute(&sql, [])?;
+    Ok(Response::message("ok"))
 }
 
-pub async fn add_column(payload: web::Json<AddColumnPayload>) -> impl Responder {
-    let db_path = format!("db/{}", payload.db_name);
-    if !Path::new(&db_path).exists() {
-        return HttpResponse::NotFound().body(format!("Database {} not found", payload.db_name));
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/column/add",
+    tag = "sqlite",
+    summary = "新增列",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn add_column(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<AddColumnPayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
+    if !is_valid_type_name(&payload.data_type) {
+        return Err(ApiError::InvalidParams(format!("Invalid data type: {}", payload.data_type)));
     }
     let mut sql = format!(
         "ALTER TABLE {} ADD COLUMN {} {}",
-        payload.table_name, payload.name, payload.data_type
+        quote_ident(&payload.table_name),
+        quote_ident(&payload.name),
+        payload.data_type.trim()
     );
     if payload.not_null.unwrap_or(false) {
         sql.push_str(" NOT NULL");
@@ -329,41 +454,52 @@ pub async fn add_column(payload: web::Json<AddColumnPayload>) -> impl Responder
     if let Some(def) = &payload.default {
         sql.push_str(&format!(" DEFAULT {}", json_value_to_sql_literal(def)));
     }
-    match Connection::open(&db_path).and_then(|conn| conn.execute(&sql, []).map(|_| ())) {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status":"ok"})),
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
-    }
+    open_db(&db_path)?.execute(&sql, [])?;
+    Ok(Response::message("ok"))
 }
 
-pub async fn drop_column(payload: web::Json<DropColumnPayload>) -> impl Responder {
-    let db_path = format!("db/{}", payload.db_name);
-    if !Path::new(&db_path).exists() {
-        return HttpResponse::NotFound().body(format!("Database {} not found", payload.db_name));
-    }
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/column/drop",
+    tag = "sqlite",
+    summary = "删除列",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn drop_column(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<DropColumnPayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
     let sql = format!(
         "ALTER TABLE {} DROP COLUMN {}",
-        payload.table_name, payload.column_name
+        quote_ident(&payload.table_name),
+        quote_ident(&payload.column_name)
     );
-    match Connection::open(&db_path).and_then(|conn| conn.execute(&sql, []).map(|_| ())) {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status":"ok"})),
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
-    }
+    open_db(&db_path)?.execute(&sql, [])?;
+    Ok(Response::message("ok"))
 }
 
 // -------- 行操作与SQL控制台实现 --------
-pub async fn insert_row(payload: web::Json<RowInsertPayload>) -> impl Responder {
-    let db_path = format!("db/{}", payload.db_name);
-    if !Path::new(&db_path).exists() {
-        return HttpResponse::NotFound().body(format!("Database {} not found", payload.db_name));
-    }
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/row/insert",
+    tag = "sqlite",
+    summary = "插入行",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn insert_row(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<RowInsertPayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
     if payload.values.is_empty() {
-        return HttpResponse::BadRequest().body("values cannot be empty");
+        return Err(ApiError::InvalidParams("values cannot be empty".into()));
     }
-    let cols: Vec<String> = payload.values.keys().cloned().collect();
+    let cols: Vec<String> = payload.values.keys().map(|k| quote_ident(k)).collect();
     let placeholders: Vec<String> = (1..=cols.len()).map(|i| format!("?{}", i)).collect();
     let sql = format!(
         "INSERT INTO {} ({}) VALUES ({})",
-        payload.table_name,
+        quote_ident(&payload.table_name),
         cols.join(","),
         placeholders.join(",")
     );
@@ -373,33 +509,35 @@ pub async fn insert_row(payload: web::Json<RowInsertPayload>) -> impl Responder
         params_vec.push(json_value_to_sql_value(v));
     }
 
-    match Connection::open(&db_path).and_then(|conn| {
-        conn.execute(&sql, rusqlite::params_from_iter(params_vec))
-            .map(|_| ())
-    }) {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status":"ok"})),
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
-    }
+    open_db(&db_path)?.execute(&sql, rusqlite::params_from_iter(params_vec))?;
+    Ok(Response::message("ok"))
 }
 
-pub async fn update_row(payload: web::Json<RowUpdatePayload>) -> impl Responder {
-    let db_path = format!("db/{}", payload.db_name);
-    if !Path::new(&db_path).exists() {
-        return HttpResponse::NotFound().body(format!("Database {} not found", payload.db_name));
-    }
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/row/update",
+    tag = "sqlite",
+    summary = "更新行",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn update_row(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<RowUpdatePayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
     if payload.values.is_empty() {
-        return HttpResponse::BadRequest().body("values cannot be empty");
+        return Err(ApiError::InvalidParams("values cannot be empty".into()));
     }
     let sets: Vec<String> = payload
         .values
         .keys()
-        .map(|k| format!("{} = ?", k))
+        .map(|k| format!("{} = ?", quote_ident(k)))
         .collect();
     let sql = format!(
         "UPDATE {} SET {} WHERE {} = ?",
-        payload.table_name,
+        quote_ident(&payload.table_name),
         sets.join(", "),
-        payload.pk_column
+        quote_ident(&payload.pk_column)
     );
 
     let mut params_vec: Vec<SqlValue> = payload
@@ -409,48 +547,54 @@ pub async fn update_row(payload: web::Json<RowUpdatePayload>) -> impl Responder
         .collect();
     params_vec.push(json_value_to_sql_value(&payload.pk_value));
 
-    match Connection::open(&db_path).and_then(|conn| {
-        conn.execute(&sql, rusqlite::params_from_iter(params_vec))
-            .map(|_| ())
-    }) {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status":"ok"})),
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
-    }
+    open_db(&db_path)?.execute(&sql, rusqlite::params_from_iter(params_vec))?;
+    Ok(Response::message("ok"))
 }
 
-pub async fn delete_row(payload: web::Json<RowDeletePayload>) -> impl Responder {
-    let db_path = format!("db/{}", payload.db_name);
-    if !Path::new(&db_path).exists() {
-        return HttpResponse::NotFound().body(format!("Database {} not found", payload.db_name));
-    }
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/row/delete",
+    tag = "sqlite",
+    summary = "删除行",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn delete_row(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<RowDeletePayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
     let sql = format!(
         "DELETE FROM {} WHERE {} = ?",
-        payload.table_name, payload.pk_column
+        quote_ident(&payload.table_name),
+        quote_ident(&payload.pk_column)
     );
     let pk_val = json_value_to_sql_value(&payload.pk_value);
-    match Connection::open(&db_path)
-        .and_then(|conn| conn.execute(&sql, rusqlite::params![pk_val]).map(|_| ()))
-    {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status":"ok"})),
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
-    }
+    open_db(&db_path)?.execute(&sql, rusqlite::params![pk_val])?;
+    Ok(Response::message("ok"))
 }
 
-pub async fn batch_delete_rows(payload: web::Json<RowBatchDeletePayload>) -> impl Responder {
-    let db_path = format!("db/{}", payload.db_name);
-    if !Path::new(&db_path).exists() {
-        return HttpResponse::NotFound().body(format!("Database {} not found", payload.db_name));
-    }
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/row/batch-delete",
+    tag = "sqlite",
+    summary = "批量删除行",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn batch_delete_rows(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<RowBatchDeletePayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
     if payload.pk_values.is_empty() {
-        return HttpResponse::BadRequest().body("pk_values cannot be empty");
+        return Err(ApiError::InvalidParams("pk_values cannot be empty".into()));
     }
     let placeholders: Vec<String> = (1..=payload.pk_values.len())
         .map(|i| format!("?{}", i))
         .collect();
     let sql = format!(
         "DELETE FROM {} WHERE {} IN ({})",
-        payload.table_name,
-        payload.pk_column,
+        quote_ident(&payload.table_name),
+        quote_ident(&payload.pk_column),
         placeholders.join(",")
     );
     let params_vec: Vec<SqlValue> = payload
@@ -458,81 +602,791 @@ pub async fn batch_delete_rows(payload: web::Json<RowBatchDeletePayload>) -> imp
         .iter()
         .map(|v| json_value_to_sql_value(v))
         .collect();
-    match Connection::open(&db_path).and_then(|conn| {
-        conn.execute(&sql, rusqlite::params_from_iter(params_vec))
-            .map(|_| ())
-    }) {
-        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"status":"ok"})),
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    open_db(&db_path)?.execute(&sql, rusqlite::params_from_iter(params_vec))?;
+    Ok(Response::message("ok"))
+}
+
+// 按 sqlite3 命令行的方式切分多条语句：遇到分号且 sqlite3_complete 认为语句完整时截断，
+// 可正确处理字符串、注释与触发器中的分号
+fn split_sql_statements(sql: &str) -> Vec<&str> {
+    let is_complete = |text: &str| match std::ffi::CString::new(text) {
+        Ok(c) => unsafe { rusqlite::ffi::sqlite3_complete(c.as_ptr()) != 0 },
+        Err(_) => true,
+    };
+    let mut statements = Vec::new();
+    let mut start = 0;
+    for (i, _) in sql.match_indices(';') {
+        let candidate = &sql[start..=i];
+        if is_complete(candidate) {
+            let stmt = candidate.trim().trim_end_matches(';').trim();
+            if !is_blank_sql(stmt) {
+                statements.push(stmt);
+            }
+            start = i + 1;
+        }
     }
+    let rest = sql[start..].trim();
+    if !is_blank_sql(rest) {
+        statements.push(rest);
+    }
+    statements
 }
 
-pub async fn sql_query(payload: web::Json<SqlQueryPayload>) -> impl Responder {
-    let db_path = format!("db/{}", payload.db_name);
-    if !Path::new(&db_path).exists() {
-        return HttpResponse::NotFound().body(format!("Database {} not found", payload.db_name));
+// 只含空白与注释的片段（prepare 得到空语句，执行会报错）
+fn is_blank_sql(mut sql: &str) -> bool {
+    loop {
+        sql = sql.trim_start();
+        if let Some(rest) = sql.strip_prefix("--") {
+            sql = rest.split_once('\n').map_or("", |(_, r)| r);
+        } else if let Some(rest) = sql.strip_prefix("/*") {
+            sql = rest.split_once("*/").map_or("", |(_, r)| r);
+        } else {
+            return sql.is_empty();
+        }
     }
-    let conn = match Connection::open(&db_path) {
-        Ok(c) => c,
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Error: {}", e)),
-    };
-    let sql_trimmed = payload.sql.trim().to_uppercase();
-    let params_vec: Vec<SqlValue> = payload
-        .params
-        .clone()
-        .unwrap_or_default()
-        .into_iter()
-        .map(|v| json_value_to_sql_value(&v))
+}
+
+// 绑定参数：数组按位置依次消耗（多条语句共用一个游标），对象按名称绑定
+fn bind_sql_params(
+    stmt: &mut rusqlite::Statement<'_>,
+    params: Option<&SqlParams>,
+    cursor: &mut usize,
+) -> Result<(), String> {
+    let count = stmt.parameter_count();
+    for i in 1..=count {
+        let value = match params {
+            None => return Err(format!("Missing value for parameter {}", i)),
+            Some(SqlParams::Positional(values)) => {
+                let v = values
+                    .get(*cursor)
+                    .ok_or_else(|| format!("Missing value for parameter {}", *cursor + 1))?;
+                *cursor += 1;
+                v
+            }
+            Some(SqlParams::Named(map)) => {
+                let name = stmt.parameter_name(i).ok_or_else(|| {
+                    "Positional parameter '?' requires params to be an array".to_string()
+                })?;
+                map.get(name)
+                    .or_else(|| map.get(&name[1..]))
+                    .ok_or_else(|| format!("Missing value for parameter {}", name))?
+            }
+        };
+        stmt.raw_bind_parameter(i, json_value_to_sql_value(value))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn value_ref_to_json(v: rusqlite::types::ValueRef<'_>) -> serde_json::Value {
+    use rusqlite::types::ValueRef;
+    match v {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(i) => serde_json::Value::from(i),
+        ValueRef::Real(f) => serde_json::Number::from_f64(f)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        ValueRef::Text(t) => serde_json::Value::String(String::from_utf8_lossy(t).to_string()),
+        ValueRef::Blob(b) => serde_json::Value::String(format!("<{} bytes>", b.len())),
+    }
+}
+
+// EXPLAIN QUERY PLAN 的结果按 parent 组装成树
+fn query_plan_tree(
+    conn: &Connection,
+    sql: &str,
+    params: Option<&SqlParams>,
+    cursor: &mut usize,
+) -> Result<Vec<QueryPlanNode>, String> {
+    let mut stmt = conn
+        .prepare(&format!("EXPLAIN QUERY PLAN {}", sql))
+        .map_err(|e| e.to_string())?;
+    bind_sql_params(&mut stmt, params, cursor)?;
+    let mut rows = stmt.raw_query();
+    let mut flat: Vec<(i64, i64, String)> = Vec::new();
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let id: i64 = row.get(0).map_err(|e| e.to_string())?;
+        let parent: i64 = row.get(1).map_err(|e| e.to_string())?;
+        let detail: String = row.get(3).map_err(|e| e.to_string())?;
+        flat.push((id, parent, detail));
+    }
+    fn children(flat: &[(i64, i64, String)], parent: i64) -> Vec<QueryPlanNode> {
+        flat.iter()
+            .filter(|(id, p, _)| *p == parent && *id != parent)
+            .map(|(id, _, detail)| QueryPlanNode {
+                id: *id,
+                detail: detail.clone(),
+                children: children(flat, *id),
+            })
+            .collect()
+    }
+    Ok(children(&flat, 0))
+}
+
+// 执行一条语句：有结果列的按查询返回数据与声明类型，否则返回影响行数
+fn run_sql_statement(
+    conn: &Connection,
+    sql: &str,
+    params: Option<&SqlParams>,
+    cursor: &mut usize,
+) -> Result<SqlStatementResult, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    bind_sql_params(&mut stmt, params, cursor)?;
+    let columns: Vec<SqlColumnInfo> = stmt
+        .columns()
+        .iter()
+        .map(|c| SqlColumnInfo {
+            name: c.name().to_string(),
+            decl_type: c.decl_type().map(|t| t.to_string()),
+        })
         .collect();
+    if columns.is_empty() {
+        let changed = stmt.raw_execute().map_err(|e| e.to_string())?;
+        return Ok(SqlStatementResult {
+            sql: sql.to_string(),
+            columns,
+            data: None,
+            changed: Some(changed),
+            plan: None,
+        });
+    }
+    let mut data = Vec::new();
+    let mut rows = stmt.raw_query();
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let mut record = serde_json::Map::new();
+        for (i, col) in columns.iter().enumerate() {
+            let v = row.get_ref(i).map_err(|e| e.to_string())?;
+            record.insert(col.name.clone(), value_ref_to_json(v));
+        }
+        data.push(serde_json::Value::Object(record));
+    }
+    Ok(SqlStatementResult {
+        sql: sql.to_string(),
+        columns,
+        data: Some(data),
+        changed: None,
+        plan: None,
+    })
+}
 
-    if sql_trimmed.starts_with("SELECT") {
-        let mut stmt = match conn.prepare(&payload.sql) {
-            Ok(s) => s,
-            Err(e) => return HttpResponse::BadRequest().body(format!("SQL prepare error: {}", e)),
+// SQL 控制台：支持多条语句、位置/命名参数与执行计划；
+// 顶层的 data/changed 与最后一条语句一致，兼容单条语句的旧用法
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/query",
+    tag = "sqlite",
+    summary = "执行 SQL",
+    responses((status = 200, body = Response))
+)]
+pub async fn sql_query(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<SqlQueryPayload>,
+) -> ApiResult {
+    let db_path = registry.resolve(&payload.db_name)?;
+    let conn = open_db(&db_path)?;
+    let statements = split_sql_statements(&payload.sql);
+    if statements.is_empty() {
+        return Err(ApiError::InvalidParams("sql cannot be empty".into()));
+    }
+    let params = payload.params.as_ref();
+    let explain = payload.explain.unwrap_or(false);
+    let mut cursor = 0usize;
+    let mut results = Vec::new();
+    for (i, sql) in statements.iter().enumerate() {
+        let result = if explain {
+            query_plan_tree(&conn, sql, params, &mut cursor).map(|plan| SqlStatementResult {
+                sql: sql.to_string(),
+                columns: Vec::new(),
+                data: None,
+                changed: None,
+                plan: Some(plan),
+            })
+        } else {
+            run_sql_statement(&conn, sql, params, &mut cursor)
         };
-        // 先获取列名，避免与rows的可变借用冲突
-        let col_names: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
-        let mut rows = match stmt.query(rusqlite::params_from_iter(params_vec.clone())) {
-            Ok(r) => r,
-            Err(e) => return HttpResponse::BadRequest().body(format!("SQL query error: {}", e)),
+        match result {
+            Ok(r) => results.push(r),
+            Err(e) => {
+                return Err(ApiError::Sql(format!(
+                    "SQL error at statement {} ({} executed): {}",
+                    i + 1,
+                    if explain { 0 } else { i },
+                    e
+                )));
+            }
+        }
+    }
+    if let Some(SqlParams::Positional(values)) = params
+        && cursor < values.len()
+    {
+        return Err(ApiError::InvalidParams(format!(
+            "Too many parameters: {} given, {} used",
+            values.len(),
+            cursor
+        )));
+    }
+    let last = results.last();
+    Ok(Response::ok(serde_json::json!({
+        "data": last.and_then(|r| r.data.as_ref()),
+        "changed": last.and_then(|r| r.changed),
+        "columns": last.map(|r| &r.columns),
+        "results": results,
+    })))
+}
+
+// -------- 索引、视图与触发器 --------
+// 内部函数：获取表的外键，复合外键按 id 合并
+fn get_foreign_keys(conn: &Connection, table_name: &str) -> RusqliteResult<Vec<ForeignKeyInfo>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, \"table\", \"from\", \"to\", on_update, on_delete \
+         FROM pragma_foreign_key_list(?1) ORDER BY id, seq",
+    )?;
+    let mut rows = stmt.query([table_name])?;
+    let mut keys: Vec<ForeignKeyInfo> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let id: i64 = row.get(0)?;
+        let from: String = row.get(2)?;
+        let to: Option<String> = row.get(3)?;
+        match keys.last_mut() {
+            Some(last) if last.id == id => {
+                last.columns.push(from);
+                last.ref_columns.extend(to);
+            }
+            _ => keys.push(ForeignKeyInfo {
+                id,
+                columns: vec![from],
+                ref_table: row.get(1)?,
+                ref_columns: to.into_iter().collect(),
+                on_update: row.get(4)?,
+                on_delete: row.get(5)?,
+            }),
+        }
+    }
+    Ok(keys)
+}
+
+// 内部函数：获取表的索引（含约束自动创建的索引）
+fn get_table_indexes(conn: &Connection, table_name: &str) -> RusqliteResult<Vec<IndexInfo>> {
+    let mut stmt = conn.prepare(
+        "SELECT il.name, il.\"unique\", il.origin, il.partial, m.sql \
+         FROM pragma_index_list(?1) AS il \
+         LEFT JOIN sqlite_master AS m ON m.type = 'index' AND m.name = il.name \
+         ORDER BY il.name",
+    )?;
+    let listed = stmt
+        .query_map([table_name], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, bool>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, bool>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })?
+        .collect::<RusqliteResult<Vec<_>>>()?;
+
+    let mut col_stmt =
+        conn.prepare("SELECT name FROM pragma_index_xinfo(?1) WHERE key = 1 ORDER BY seqno")?;
+    let mut indexes = Vec::new();
+    for (name, unique, origin, partial, sql) in listed {
+        let columns = col_stmt
+            .query_map([&name], |row| row.get::<_, Option<String>>(0))?
+            .map(|c| c.map(|c| c.unwrap_or_else(|| "<expr>".to_string())))
+            .collect::<RusqliteResult<Vec<_>>>()?;
+        let predicate = if partial {
+            sql.as_deref().and_then(partial_index_predicate)
+        } else {
+            None
         };
-        let mut data = Vec::new();
-        while let Some(row) = match rows.next() {
-            Ok(r) => r,
-            Err(e) => return HttpResponse::BadRequest().body(format!("Row fetch error: {}", e)),
-        } {
-            let mut record = serde_json::Map::new();
-            for (i, col) in col_names.iter().enumerate() {
-                let v = row.get_ref(i);
-                let json_v = match v {
-                    Ok(rusqlite::types::ValueRef::Null) => serde_json::Value::Null,
-                    Ok(rusqlite::types::ValueRef::Integer(i)) => {
-                        serde_json::Value::Number(serde_json::Number::from(i))
-                    }
-                    Ok(rusqlite::types::ValueRef::Real(f)) => serde_json::Number::from_f64(f)
-                        .map(serde_json::Value::Number)
-                        .unwrap_or(serde_json::Value::Null),
-                    Ok(rusqlite::types::ValueRef::Text(t)) => {
-                        serde_json::Value::String(String::from_utf8_lossy(t).to_string())
-                    }
-                    Ok(rusqlite::types::ValueRef::Blob(b)) => {
-                        serde_json::Value::String(format!("<{} bytes>", b.len()))
+        indexes.push(IndexInfo {
+            name,
+            table_name: table_name.to_string(),
+            unique,
+            origin,
+            columns,
+            partial,
+            predicate,
+            sql,
+        });
+    }
+    Ok(indexes)
+}
+
+// 从 CREATE INDEX 语句中取出列清单之后的 WHERE 条件
+fn partial_index_predicate(sql: &str) -> Option<String> {
+    let start = sql.find('(')?;
+    let mut depth = 0usize;
+    let mut in_quote: Option<char> = None;
+    for (i, c) in sql[start..].char_indices() {
+        match (in_quote, c) {
+            (Some(q), c) if c == q => in_quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`' | '[') => in_quote = Some(if c == '[' { ']' } else { c }),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    let tail = sql[start + i + 1..].trim_start();
+                    let keyword = tail.get(..5)?;
+                    if keyword.eq_ignore_ascii_case("WHERE") {
+                        return Some(tail[5..].trim().to_string());
                     }
-                    Err(_) => serde_json::Value::Null,
-                };
-                record.insert(col.clone(), json_v);
+                    return None;
+                }
             }
-            data.push(serde_json::Value::Object(record));
+            _ => {}
         }
-        HttpResponse::Ok().json(serde_json::json!({"status":"ok","data":data}))
-    } else {
-        match conn.execute(&payload.sql, rusqlite::params_from_iter(params_vec)) {
-            Ok(changed) => {
-                HttpResponse::Ok().json(serde_json::json!({"status":"ok","changed":changed}))
+    }
+    None
+}
+
+// 内部函数：按表过滤的索引列表，未指定表时返回全部
+fn list_indexes_info(db_path: &Path, table_name: Option<&str>) -> RusqliteResult<Vec<IndexInfo>> {
+    let conn = open_db(db_path)?;
+    let tables: Vec<String> = match table_name {
+        Some(t) => vec![t.to_string()],
+        None => {
+            let mut stmt = conn.prepare(
+                "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+            )?;
+            stmt.query_map([], |row| row.get::<_, String>(0))?
+                .collect::<RusqliteResult<Vec<_>>>()?
+        }
+    };
+    let mut indexes = Vec::new();
+    for table in tables {
+        indexes.extend(get_table_indexes(&conn, &table)?);
+    }
+    Ok(indexes)
+}
+
+fn list_views_info(db_path: &Path) -> RusqliteResult<Vec<ViewInfo>> {
+    let conn = open_db(db_path)?;
+    let mut stmt =
+        conn.prepare("SELECT name, sql FROM sqlite_master WHERE type='view' ORDER BY name")?;
+    let views = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<RusqliteResult<Vec<_>>>()?;
+    let mut col_stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+    let mut result = Vec::new();
+    for (name, sql) in views {
+        let columns = col_stmt
+            .query_map([&name], |row| row.get::<_, String>(0))?
+            .collect::<RusqliteResult<Vec<_>>>()?;
+        result.push(ViewInfo { name, columns, sql });
+    }
+    Ok(result)
+}
+
+fn list_triggers_info(
+    db_path: &Path,
+    table_name: Option<&str>,
+) -> RusqliteResult<Vec<TriggerInfo>> {
+    let conn = open_db(db_path)?;
+    let mut stmt = conn.prepare(
+        "SELECT name, tbl_name, sql FROM sqlite_master \
+         WHERE type='trigger' AND (?1 IS NULL OR tbl_name = ?1) ORDER BY name",
+    )?;
+    stmt.query_map([table_name], |row| {
+        Ok(TriggerInfo {
+            name: row.get(0)?,
+            table_name: row.get(1)?,
+            sql: row.get(2)?,
+        })
+    })?
+    .collect()
+}
+
+// 执行单条 DDL 语句；rusqlite 的 execute 会拒绝多条语句
+fn execute_ddl(db_path: &Path, sql: &str) -> ApiResult<()> {
+    open_db(db_path)?.execute(sql, [])?;
+    Ok(Response::message("ok"))
+}
+
+// 查询索引（table_name 可选）
+#[utoipa::path(
+    get,
+    path = "/api/sqlite/indexes",
+    tag = "sqlite",
+    summary = "索引列表",
+    params(QueryParams),
+    responses((status = 200, body = Response<Vec<IndexInfo>>))
+)]
+pub async fn list_indexes(
+    registry: web::Data<SqliteRegistry>,
+    params: web::Query<QueryParams>,
+) -> ApiResult<Vec<IndexInfo>> {
+    let db_path = registry.resolve(&params.db_name)?;
+    let indexes = list_indexes_info(&db_path, params.table_name.as_deref())
+        .inspect_err(|e| log_error!("Failed to list indexes: {}", e))?;
+    Ok(Response::ok(indexes))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/index/create",
+    tag = "sqlite",
+    summary = "新建索引",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn create_index(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<CreateIndexPayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
+    if payload.columns.is_empty() {
+        return Err(ApiError::InvalidParams("columns cannot be empty".into()));
+    }
+    let columns: Vec<String> = payload.columns.iter().map(|c| quote_ident(c)).collect();
+    let mut sql = format!(
+        "CREATE {}INDEX {} ON {} ({})",
+        if payload.unique.unwrap_or(false) {
+            "UNIQUE "
+        } else {
+            ""
+        },
+        quote_ident(&payload.index_name),
+        quote_ident(&payload.table_name),
+        columns.join(", ")
+    );
+    if let Some(predicate) = payload
+        .predicate
+        .as_deref()
+        .filter(|p| !p.trim().is_empty())
+    {
+        sql.push_str(&format!(" WHERE {}", predicate.trim()));
+    }
+    execute_ddl(&db_path, &sql)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/index/delete",
+    tag = "sqlite",
+    summary = "删除索引",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn drop_index(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<DropIndexPayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
+    let sql = format!("DROP INDEX IF EXISTS {}", quote_ident(&payload.index_name));
+    execute_ddl(&db_path, &sql)
+}
+
+// 查询视图
+#[utoipa::path(
+    get,
+    path = "/api/sqlite/views",
+    tag = "sqlite",
+    summary = "视图列表",
+    params(QueryParams),
+    responses((status = 200, body = Response<Vec<ViewInfo>>))
+)]
+pub async fn list_views(
+    registry: web::Data<SqliteRegistry>,
+    params: web::Query<QueryParams>,
+) -> ApiResult<Vec<ViewInfo>> {
+    let db_path = registry.resolve(&params.db_name)?;
+    let views = list_views_info(&db_path)
+        .inspect_err(|e| log_error!("Failed to list views: {}", e))?;
+    Ok(Response::ok(views))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/view/create",
+    tag = "sqlite",
+    summary = "新建视图",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn create_view(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<CreateViewPayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
+    let select_sql = payload.select_sql.trim().trim_end_matches(';');
+    let head = select_sql.to_uppercase();
+    if !(head.starts_with("SELECT") || head.starts_with("WITH")) {
+        return Err(ApiError::InvalidParams("select_sql must be a SELECT statement".into()));
+    }
+    let sql = format!(
+        "CREATE VIEW {} AS {}",
+        quote_ident(&payload.view_name),
+        select_sql
+    );
+    execute_ddl(&db_path, &sql)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/view/delete",
+    tag = "sqlite",
+    summary = "删除视图",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn drop_view(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<DropViewPayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
+    let sql = format!("DROP VIEW IF EXISTS {}", quote_ident(&payload.view_name));
+    execute_ddl(&db_path, &sql)
+}
+
+// 查询触发器（table_name 可选）
+#[utoipa::path(
+    get,
+    path = "/api/sqlite/triggers",
+    tag = "sqlite",
+    summary = "触发器列表",
+    params(QueryParams),
+    responses((status = 200, body = Response<Vec<TriggerInfo>>))
+)]
+pub async fn list_triggers(
+    registry: web::Data<SqliteRegistry>,
+    params: web::Query<QueryParams>,
+) -> ApiResult<Vec<TriggerInfo>> {
+    let db_path = registry.resolve(&params.db_name)?;
+    let triggers = list_triggers_info(&db_path, params.table_name.as_deref())
+        .inspect_err(|e| log_error!("Failed to list triggers: {}", e))?;
+    Ok(Response::ok(triggers))
+}
+
+// 构建 CREATE TRIGGER 语句，时机与事件只接受固定关键字
+fn build_trigger_sql(payload: &CreateTriggerPayload) -> Result<String, String> {
+    let timing = match payload.timing.trim().to_uppercase().as_str() {
+        "BEFORE" => "BEFORE",
+        "AFTER" => "AFTER",
+        "INSTEAD OF" => "INSTEAD OF",
+        other => return Err(format!("Invalid trigger timing: {}", other)),
+    };
+    let event = match payload.event.trim().to_uppercase().as_str() {
+        "INSERT" => "INSERT".to_string(),
+        "DELETE" => "DELETE".to_string(),
+        "UPDATE" => match payload.update_columns.as_ref().filter(|c| !c.is_empty()) {
+            Some(cols) => {
+                let cols: Vec<String> = cols.iter().map(|c| quote_ident(c)).collect();
+                format!("UPDATE OF {}", cols.join(", "))
             }
-            Err(e) => HttpResponse::BadRequest().body(format!("SQL exec error: {}", e)),
+            None => "UPDATE".to_string(),
+        },
+        other => return Err(format!("Invalid trigger event: {}", other)),
+    };
+    let body = payload.body.trim();
+    if body.is_empty() {
+        return Err("body cannot be empty".to_string());
+    }
+    let mut sql = format!(
+        "CREATE TRIGGER {} {} {} ON {} FOR EACH ROW",
+        quote_ident(&payload.trigger_name),
+        timing,
+        event,
+        quote_ident(&payload.table_name)
+    );
+    if let Some(when) = payload.when.as_deref().filter(|w| !w.trim().is_empty()) {
+        sql.push_str(&format!(" WHEN {}", when.trim()));
+    }
+    sql.push_str(&format!(
+        " BEGIN {}{} END",
+        body,
+        if body.ends_with(';') { "" } else { ";" }
+    ));
+    Ok(sql)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/trigger/create",
+    tag = "sqlite",
+    summary = "新建触发器",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn create_trigger(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<CreateTriggerPayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
+    let sql = build_trigger_sql(&payload).map_err(ApiError::InvalidParams)?;
+    execute_ddl(&db_path, &sql)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/trigger/delete",
+    tag = "sqlite",
+    summary = "删除触发器",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn drop_trigger(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<DropTriggerPayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
+    let sql = format!(
+        "DROP TRIGGER IF EXISTS {}",
+        quote_ident(&payload.trigger_name)
+    );
+    execute_ddl(&db_path, &sql)
+}
+
+// -------- 导入导出 --------
+// 解析单字符分隔符，支持 "\t"
+fn parse_delimiter(delimiter: Option<&str>) -> Result<u8, String> {
+    match delimiter {
+        None | Some("") => Ok(b','),
+        Some("\\t") => Ok(b'\t'),
+        Some(d) if d.len() == 1 && d.is_ascii() => Ok(d.as_bytes()[0]),
+        Some(d) => Err(format!("Invalid delimiter: {}", d)),
+    }
+}
+
+// 导入 CSV/JSON 到表
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/import",
+    tag = "sqlite",
+    summary = "导入 CSV/JSON",
+    responses((status = 200, body = Response<SqliteImportReport>))
+)]
+pub async fn import_data(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<SqliteImportPayload>,
+) -> ApiResult<SqliteImportReport> {
+    let db_path = registry.resolve(&payload.db_name)?;
+    let delimiter =
+        parse_delimiter(payload.delimiter.as_deref()).map_err(ApiError::InvalidParams)?;
+    let opts = SqliteImportOptions {
+        table: payload.table_name.clone(),
+        header: payload.header.unwrap_or(true),
+        delimiter,
+        truncate: payload.truncate.unwrap_or(false),
+        column_map: payload.column_map.clone().unwrap_or_default(),
+    };
+    let mut conn = open_db(&db_path)?;
+    let result = match payload.format {
+        DataFormat::Csv => import_csv(&mut conn, payload.content.as_bytes(), &opts),
+        DataFormat::Json => match serde_json::from_str(&payload.content) {
+            Ok(data) => import_json(&mut conn, &data, &opts),
+            Err(e) => return Err(ApiError::InvalidParams(format!("Invalid JSON: {}", e))),
+        },
+    };
+    let report = result.map_err(|e| ApiError::Sql(format!("Import error: {:#}", e)))?;
+    Ok(Response::ok(report))
+}
+
+// 导出表或只读查询为 CSV/JSON 文件
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/export",
+    tag = "sqlite",
+    summary = "导出 CSV/JSON",
+    responses((status = 200, description = "文件内容", content_type = "application/octet-stream", body = String))
+)]
+pub async fn export_data(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<SqliteExportPayload>,
+) -> Result<HttpResponse, ApiError> {
+    let db_path = registry.resolve(&payload.db_name)?;
+    let delimiter =
+        parse_delimiter(payload.delimiter.as_deref()).map_err(ApiError::InvalidParams)?;
+    let (sql, file_stem) = match (&payload.table_name, &payload.sql) {
+        (Some(table), None) => (
+            format!("SELECT * FROM {}", quote_ident(table)),
+            table.clone(),
+        ),
+        (None, Some(sql)) => (sql.clone(), "query".to_string()),
+        _ => {
+            return Err(ApiError::InvalidParams(
+                "Either table_name or sql is required".into(),
+            ));
         }
+    };
+    let params: Vec<SqlValue> = payload
+        .params
+        .iter()
+        .flatten()
+        .map(json_value_to_sql_value)
+        .collect();
+    let mut body = Vec::new();
+    let result = open_db(&db_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|conn| export_query(&conn, &sql, &params, payload.format, delimiter, &mut body));
+    if let Err(e) = result {
+        return Err(ApiError::Sql(format!("Export error: {:#}", e)));
     }
+    let (content_type, ext) = match payload.format {
+        DataFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        DataFormat::Json => ("application/json", "json"),
+    };
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header((
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"{}.{}\"",
+                file_stem.replace('"', ""),
+                ext
+            ),
+        ))
+        .body(body))
+}
+
+// 导出整个数据库的 SQL 脚本
+#[utoipa::path(
+    get,
+    path = "/api/sqlite/dump",
+    tag = "sqlite",
+    summary = "导出 SQL 脚本",
+    params(SqliteDumpParams),
+    responses((status = 200, description = "文件内容", content_type = "application/sql", body = String))
+)]
+pub async fn dump_database(
+    registry: web::Data<SqliteRegistry>,
+    params: web::Query<SqliteDumpParams>,
+) -> Result<HttpResponse, ApiError> {
+    let db_path = registry.resolve(&params.db_name)?;
+    let mut body = Vec::new();
+    let result = open_db(&db_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|conn| dump_sql(&conn, &mut body));
+    if let Err(e) = result {
+        return Err(ApiError::Internal(format!("Failed to dump database: {:#}", e)));
+    }
+    let stem = db_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("dump");
+    Ok(HttpResponse::Ok()
+        .content_type("application/sql; charset=utf-8")
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}.sql\"", stem),
+        ))
+        .body(body))
+}
+
+// 执行 SQL 脚本恢复数据（通常先新建空库再恢复）
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/restore",
+    tag = "sqlite",
+    summary = "执行 SQL 脚本恢复",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn restore_database(
+    registry: web::Data<SqliteRegistry>,
+    payload: web::Json<SqliteRestorePayload>,
+) -> ApiResult<()> {
+    let db_path = registry.resolve(&payload.db_name)?;
+    let result = open_db(&db_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|conn| restore_sql(&conn, &payload.script));
+    result.map_err(|e| ApiError::Sql(format!("Restore error: {:#}", e)))?;
+    Ok(Response::message("ok"))
 }
 
 // -------- 帮助函数：JSON值到SQL字面量/参数 --------
@@ -569,3 +1423,100 @@ fn json_value_to_sql_value(v: &serde_json::Value) -> SqlValue {
         serde_json::Value::Array(_) | serde_json::Value::Object(_) => SqlValue::Null,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_index_predicate() {
+        assert_eq!(
+            partial_index_predicate("CREATE INDEX i ON t (a, lower(b)) WHERE a > 0 AND b != ')'"),
+            Some("a > 0 AND b != ')'".to_string())
+        );
+        assert_eq!(
+            partial_index_predicate("CREATE INDEX \"x(\" ON t (a)"),
+            None
+        );
+        assert!(is_valid_type_name("DECIMAL(10, 2)"));
+        assert!(!is_valid_type_name("TEXT); DROP TABLE t; --"));
+    }
+
+    #[test]
+    fn test_split_and_run_statements() {
+        let sql = "CREATE TRIGGER tr AFTER INSERT ON t BEGIN UPDATE t SET v = 'a;b'; END;\n\
+                   -- 注释; \n SELECT 1; SELECT ';'; /* 结尾注释 */";
+        let parts = split_sql_statements(sql);
+        assert_eq!(parts.len(), 3);
+        assert!(parts[0].ends_with("END"));
+        assert_eq!(parts[2], "SELECT ';'");
+
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (id INTEGER PRIMARY KEY, v VARCHAR(10))")
+            .unwrap();
+        let params: SqlParams = serde_json::from_str(r#"{"v": "x", ":id": 7}"#).unwrap();
+        let mut cursor = 0;
+        let r = run_sql_statement(
+            &conn,
+            "INSERT INTO t (id, v) VALUES (:id, @v)",
+            Some(&params),
+            &mut cursor,
+        )
+        .unwrap();
+        assert_eq!(r.changed, Some(1));
+
+        let params: SqlParams = serde_json::from_str("[7]").unwrap();
+        let r = run_sql_statement(
+            &conn,
+            "SELECT v, id + 1 AS n FROM t WHERE id = ?",
+            Some(&params),
+            &mut cursor,
+        )
+        .unwrap();
+        assert_eq!(r.columns[0].decl_type.as_deref(), Some("VARCHAR(10)"));
+        assert_eq!(r.columns[1].decl_type, None);
+        assert_eq!(r.data.unwrap()[0]["n"], 8);
+        assert!(run_sql_statement(&conn, "SELECT ?", Some(&params), &mut cursor).is_err());
+
+        let mut cursor = 0;
+        let plan = query_plan_tree(
+            &conn,
+            "SELECT * FROM t WHERE id IN (SELECT id FROM t WHERE v = 'x')",
+            None,
+            &mut cursor,
+        )
+        .unwrap();
+        assert!(!plan.is_empty());
+        assert!(plan.iter().any(|n| !n.children.is_empty()));
+    }
+
+    #[test]
+    fn test_table_metadata() {
+        let path = std::env::temp_dir().join(format!("rsts_meta_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE p (id INTEGER PRIMARY KEY, code TEXT UNIQUE);
+             CREATE TABLE c (id INTEGER, a INTEGER, b TEXT,
+                 FOREIGN KEY (a, b) REFERENCES p (id, code) ON DELETE CASCADE);
+             CREATE INDEX c_partial ON c (b) WHERE a IS NOT NULL;",
+        )
+        .unwrap();
+        drop(conn);
+
+        let tables = get_tables_info(&path).unwrap();
+        let c = tables.iter().find(|t| t.name == "c").unwrap();
+        assert_eq!(c.foreign_keys.len(), 1);
+        assert_eq!(c.foreign_keys[0].columns, vec!["a", "b"]);
+        assert_eq!(c.foreign_keys[0].on_delete, "CASCADE");
+        let idx = &c.indexes[0];
+        assert!(idx.partial && !idx.unique);
+        assert_eq!(idx.predicate.as_deref(), Some("a IS NOT NULL"));
+        let p = list_indexes_info(&path, Some("p")).unwrap();
+        assert!(
+            p.iter()
+                .any(|i| i.unique && i.origin == "u" && i.sql.is_none())
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,488 @@
+//! SQLite 维护任务：在线备份、VACUUM/ANALYZE、完整性与外键检查、日志模式切换、空间统计
+//!
+//! 维护操作可能耗时较长，统一作为后台任务在阻塞线程池中执行；
+//! 接口立即返回任务 ID，通过任务查询接口获取状态与结果。同一数据库同时只允许一个任务运行。
+use actix_web::web;
+use anyhow::{Result, anyhow, bail};
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use std::fs;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use super::models::{MaintenanceJobQuery, MaintenancePayload, Response};
+use super::response::{ApiError, ApiResult};
+use super::sqlite_registry::{SqliteRegistry, open_db, open_db_with_flags};
+
+/// 保留的已结束任务数量
+const MAX_FINISHED_JOBS: usize = 100;
+/// 备份每步复制的页数
+const BACKUP_PAGES_PER_STEP: i32 = 256;
+
+/// 维护操作
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum MaintenanceOp {
+    /// 使用备份 API 复制到带时间戳的文件
+    Backup,
+    Vacuum,
+    Analyze,
+    /// `PRAGMA integrity_check`，quick 为 true 时使用 quick_check
+    IntegrityCheck {
+        #[serde(default)]
+        quick: bool,
+        /// 最多返回的错误条数
+        max_errors: Option<u32>,
+    },
+    ForeignKeyCheck,
+    /// 切换日志模式：delete | truncate | persist | memory | wal | off
+    JournalMode {
+        mode: String,
+    },
+    /// 文件大小、页统计与各表占用
+    Stats,
+}
+
+impl MaintenanceOp {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Backup => "backup",
+            Self::Vacuum => "vacuum",
+            Self::Analyze => "analyze",
+            Self::IntegrityCheck { .. } => "integrity_check",
+            Self::ForeignKeyCheck => "foreign_key_check",
+            Self::JournalMode { .. } => "journal_mode",
+            Self::Stats => "stats",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// 任务状态
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceJob {
+    pub id: String,
+    pub db_name: String,
+    pub operation: &'static str,
+    pub status: JobStatus,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+    pub elapsed_ms: Option<u64>,
+    /// 进度（目前仅备份上报：已复制页数/总页数）
+    pub progress: Option<Value>,
+    pub result: Option<Value>,
+    pub error: Option<String>,
+}
+
+/// 维护任务管理器，克隆后共享任务列表
+#[derive(Clone)]
+pub struct SqliteMaintenance {
+    jobs: Arc<Mutex<VecDeque<MaintenanceJob>>>,
+    backup_dir: PathBuf,
+}
+
+impl SqliteMaintenance {
+    pub fn new(backup_dir: &str) -> Self {
+        Self {
+            jobs: Arc::new(Mutex::new(VecDeque::new())),
+            backup_dir: PathBuf::from(backup_dir),
+        }
+    }
+
+    /// 启动后台任务；同一数据库已有任务运行时返回 None
+    pub fn start(&self, db_name: &str, db_path: PathBuf, op: MaintenanceOp) -> Option<String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            if jobs
+                .iter()
+                .any(|j| j.db_name == db_name && j.status == JobStatus::Running)
+            {
+                return None;
+            }
+            jobs.push_back(MaintenanceJob {
+                id: id.clone(),
+                db_name: db_name.to_string(),
+                operation: op.name(),
+                status: JobStatus::Running,
+                created_at: chrono::Utc::now().to_rfc3339(),
+                finished_at: None,
+                elapsed_ms: None,
+                progress: None,
+                result: None,
+                error: None,
+            });
+            self.trim(&mut jobs);
+        }
+
+        let manager = self.clone();
+        let job_id = id.clone();
+        tokio::task::spawn_blocking(move || {
+            let started = Instant::now();
+            // panic 也要结束任务，否则该数据库一直处于运行中，无法再启动新任务
+            let result = catch_unwind(AssertUnwindSafe(|| manager.run(&job_id, &db_path, &op)))
+                .unwrap_or_else(|_| Err(anyhow!("maintenance task panicked")));
+            manager.update(&job_id, |job| {
+                job.finished_at = Some(chrono::Utc::now().to_rfc3339());
+                job.elapsed_ms = Some(started.elapsed().as_millis() as u64);
+                match result {
+                    Ok(value) => {
+                        job.status = JobStatus::Succeeded;
+                        job.result = Some(value);
+                    }
+                    Err(e) => {
+                        log::error!(
+                            "SQLite {} on {} failed: {:#}",
+                            job.operation,
+                            job.db_name,
+                            e
+                        );
+                        job.status = JobStatus::Failed;
+                        job.error = Some(format!("{:#}", e));
+                    }
+                }
+            });
+        });
+        Some(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<MaintenanceJob> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|j| j.id == id)
+            .cloned()
+    }
+
+    /// 最近的任务在前，可按数据库过滤
+    pub fn list(&self, db_name: Option<&str>) -> Vec<MaintenanceJob> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|j| db_name.is_none_or(|name| j.db_name == name))
+            .cloned()
+            .collect()
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut MaintenanceJob)) {
+        if let Some(job) = self.jobs.lock().unwrap().iter_mut().find(|j| j.id == id) {
+            f(job);
+        }
+    }
+
+    // 只淘汰已结束的任务
+    fn trim(&self, jobs: &mut VecDeque<MaintenanceJob>) {
+        let mut finished = jobs
+            .iter()
+            .filter(|j| j.status != JobStatus::Running)
+            .count();
+        while finished > MAX_FINISHED_JOBS {
+            let Some(pos) = jobs.iter().position(|j| j.status != JobStatus::Running) else {
+                break;
+            };
+            jobs.remove(pos);
+            finished -= 1;
+        }
+    }
+
+    fn run(&self, job_id: &str, db_path: &Path, op: &MaintenanceOp) -> Result<Value> {
+        match op {
+            MaintenanceOp::Backup => self.backup(job_id, db_path),
+            MaintenanceOp::Vacuum => {
+                let before = file_size(db_path);
+                open_db(db_path)?.execute_batch("VACUUM")?;
+                Ok(json!({ "size_before": before, "size_after": file_size(db_path) }))
+            }
+            MaintenanceOp::Analyze => {
+                open_db(db_path)?.execute_batch("ANALYZE; PRAGMA optimize;")?;
+                Ok(json!({ "analyzed": true }))
+            }
+            MaintenanceOp::IntegrityCheck { quick, max_errors } => {
+                integrity_check(db_path, *quick, max_errors.unwrap_or(100))
+            }
+            MaintenanceOp::ForeignKeyCheck => foreign_key_check(db_path),
+            MaintenanceOp::JournalMode { mode } => set_journal_mode(db_path, mode),
+            MaintenanceOp::Stats => database_stats(db_path),
+        }
+    }
+
+    /// 在线备份：分步复制，每步之间释放锁，不阻塞其他连接的读写
+    fn backup(&self, job_id: &str, db_path: &Path) -> Result<Value> {
+        let stem = db_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("backup");
+        let ext = db_path.extension().and_then(|s| s.to_str()).unwrap_or("db");
+        let file_name = format!(
+            "{}_{}.{}",
+            stem,
+            chrono::Local::now().format("%Y%m%d_%H%M%S"),
+            ext
+        );
+        fs::create_dir_all(&self.backup_dir)?;
+        let target = self.backup_dir.join(file_name);
+
+        let src = open_db_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut dst = Connection::open(&target)?;
+        {
+            let backup = Backup::new(&src, &mut dst)?;
+            loop {
+                let step = backup.step(BACKUP_PAGES_PER_STEP)?;
+                let p = backup.progress();
+                self.update(job_id, |job| {
+                    job.progress = Some(json!({
+                        "copied_pages": p.pagecount - p.remaining,
+                        "page_count": p.pagecount,
+                    }));
+                });
+                match step {
+                    StepResult::Done => break,
+                    StepResult::More => {}
+                    // 源库正忙，稍后重试
+                    StepResult::Busy | StepResult::Locked => {
+                        std::thread::sleep(Duration::from_millis(50))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        drop(dst);
+        let target = target.canonicalize()?;
+        Ok(json!({
+            "backup_path": target.to_string_lossy(),
+            "size": file_size(&target),
+        }))
+    }
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn integrity_check(db_path: &Path, quick: bool, max_errors: u32) -> Result<Value> {
+    let conn = open_db_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let pragma = if quick {
+        "quick_check"
+    } else {
+        "integrity_check"
+    };
+    let mut stmt = conn.prepare(&format!("PRAGMA {}({})", pragma, max_errors.max(1)))?;
+    let messages = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let ok = messages.len() == 1 && messages[0] == "ok";
+    Ok(json!({ "ok": ok, "messages": if ok { Vec::new() } else { messages } }))
+}
+
+fn foreign_key_check(db_path: &Path) -> Result<Value> {
+    let conn = open_db_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+    let violations = stmt
+        .query_map([], |row| {
+            Ok(json!({
+                "table": row.get::<_, String>(0)?,
+                "rowid": row.get::<_, Option<i64>>(1)?,
+                "parent": row.get::<_, String>(2)?,
+                "fk_id": row.get::<_, i64>(3)?,
+            }))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(json!({ "ok": violations.is_empty(), "violations": violations }))
+}
+
+fn set_journal_mode(db_path: &Path, mode: &str) -> Result<Value> {
+    let mode = mode.trim().to_lowercase();
+    if !["delete", "truncate", "persist", "memory", "wal", "off"].contains(&mode.as_str()) {
+        bail!("invalid journal mode: {}", mode);
+    }
+    let conn = open_db(db_path)?;
+    let previous: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))?;
+    // 模式名已校验，可直接拼接
+    let current: String =
+        conn.query_row(&format!("PRAGMA journal_mode = {}", mode), [], |row| {
+            row.get(0)
+        })?;
+    // 切换失败时（如有其他连接在使用）SQLite 返回原模式而不是报错
+    if current != mode {
+        bail!("journal mode is still {}, database may be in use", current);
+    }
+    Ok(json!({ "previous": previous, "current": current }))
+}
+
+fn database_stats(db_path: &Path) -> Result<Value> {
+    let conn = open_db_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let pragma = |name: &str| -> Result<i64> {
+        Ok(conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get(0))?)
+    };
+    let page_size = pragma("page_size")?;
+    let page_count = pragma("page_count")?;
+    let freelist_count = pragma("freelist_count")?;
+    let journal_mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))?;
+    let wal_path = PathBuf::from(format!("{}-wal", db_path.display()));
+
+    // 各表与索引占用（dbstat 虚拟表）
+    let mut objects = Vec::new();
+    let mut stmt = conn.prepare(
+        "SELECT name, COUNT(*) AS pages, SUM(pgsize) AS bytes FROM dbstat \
+         GROUP BY name ORDER BY bytes DESC",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        objects.push(json!({
+            "name": row.get::<_, String>(0)?,
+            "pages": row.get::<_, i64>(1)?,
+            "bytes": row.get::<_, i64>(2)?,
+        }));
+    }
+
+    Ok(json!({
+        "file_size": file_size(db_path),
+        "wal_size": file_size(&wal_path),
+        "page_size": page_size,
+        "page_count": page_count,
+        "freelist_count": freelist_count,
+        "free_bytes": freelist_count * page_size,
+        "journal_mode": journal_mode,
+        "objects": objects,
+    }))
+}
+
+// -------- 接口 --------
+// 启动维护任务，返回任务 ID
+#[utoipa::path(
+    post,
+    path = "/api/sqlite/maintenance/start",
+    tag = "sqlite",
+    summary = "启动维护任务",
+    responses((status = 200, body = Response))
+)]
+pub async fn start_maintenance(
+    registry: web::Data<SqliteRegistry>,
+    maintenance: web::Data<SqliteMaintenance>,
+    payload: web::Json<MaintenancePayload>,
+) -> ApiResult {
+    let db_path = registry.resolve(&payload.db_name)?;
+    match maintenance.start(&payload.db_name, db_path, payload.op.clone()) {
+        Some(id) => Ok(Response::ok(json!({ "job_id": id }))),
+        None => Err(ApiError::Conflict(format!(
+            "A maintenance job is already running on {}",
+            payload.db_name
+        ))),
+    }
+}
+
+// 查询任务：指定 job_id 时返回单个任务，否则返回列表（可按 db_name 过滤）
+#[utoipa::path(
+    get,
+    path = "/api/sqlite/maintenance/jobs",
+    tag = "sqlite",
+    summary = "维护任务状态",
+    params(MaintenanceJobQuery),
+    responses((status = 200, body = Response))
+)]
+pub async fn get_maintenance_jobs(
+    maintenance: web::Data<SqliteMaintenance>,
+    params: web::Query<MaintenanceJobQuery>,
+) -> ApiResult {
+    match &params.job_id {
+        Some(id) => match maintenance.get(id) {
+            Some(job) => Ok(Response::ok(json!(job))),
+            None => Err(ApiError::NotFound(format!("Job {} not found", id))),
+        },
+        None => Ok(Response::ok(json!(
+            maintenance.list(params.db_name.as_deref())
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait(manager: &SqliteMaintenance, id: &str) -> MaintenanceJob {
+        for _ in 0..200 {
+            let job = manager.get(id).unwrap();
+            if job.status != JobStatus::Running {
+                return job;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        panic!("job {} did not finish", id);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_backup_and_checks() {
+        let dir = std::env::temp_dir().join(format!("rsts_maint_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("m.db");
+        Connection::open(&db)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE t (id INTEGER PRIMARY KEY, v TEXT);
+                 INSERT INTO t (v) VALUES ('a'), ('b');",
+            )
+            .unwrap();
+        let manager = SqliteMaintenance::new(dir.join("backups").to_str().unwrap());
+
+        let id = manager
+            .start("m.db", db.clone(), MaintenanceOp::Backup)
+            .unwrap();
+        let job = wait(&manager, &id);
+        assert_eq!(job.status, JobStatus::Succeeded, "{:?}", job.error);
+        let backup_path = job.result.unwrap()["backup_path"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let count: i64 = Connection::open(&backup_path)
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM t", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let op = MaintenanceOp::IntegrityCheck {
+            quick: false,
+            max_errors: None,
+        };
+        let job = wait(&manager, &manager.start("m.db", db.clone(), op).unwrap());
+        assert_eq!(job.result.unwrap()["ok"], true);
+
+        let op = MaintenanceOp::JournalMode { mode: "wal".into() };
+        let job = wait(&manager, &manager.start("m.db", db.clone(), op).unwrap());
+        assert_eq!(job.result.unwrap()["current"], "wal");
+
+        let job = wait(
+            &manager,
+            &manager
+                .start("m.db", db.clone(), MaintenanceOp::Stats)
+                .unwrap(),
+        );
+        let stats = job.result.unwrap();
+        assert_eq!(stats["journal_mode"], "wal");
+        assert!(
+            stats["objects"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|o| o["name"] == "t")
+        );
+        assert_eq!(manager.list(Some("m.db")).len(), 4);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,592 @@
+//! SQLite 数据库登记表
+//!
+//! 可访问的数据库来自两处：配置的根目录（`sqlite.roots`）下的数据库文件，
+//! 以及登记的单个文件（`sqlite.files` 与通过 API 登记、保存在 `sqlite.registry_file` 中的条目）。
+//! 通过 API 登记的文件须位于根目录或 `sqlite.register_roots` 之下。
+//! 请求中的 `db_name` 只作为名称查找，不直接拼接路径；解析结果一律规范化后校验仍在根目录内。
+//! 连接统一经 `open_db` 打开并禁止 ATTACH（含 VACUUM INTO），SQL 语句无法借此读写根目录之外的文件。
+//! 元数据库（`server.database_path`，含 -wal/-shm/-journal 文件）与登记清单本身不可见、不可登记。
+use rusqlite::hooks::{AuthAction, AuthContext, Authorization};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use thiserror::Error;
+
+use super::models::DatabaseInfo;
+use crate::modules::config::config::SqliteConfig;
+
+/// 允许的数据库文件扩展名
+const DB_EXTENSIONS: [&str; 3] = ["db", "sqlite", "sqlite3"];
+/// SQLite 文件头
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    #[error("Database {0} not found")]
+    NotFound(String),
+    #[error("Invalid database name: {0}")]
+    InvalidName(String),
+    #[error("Path is outside of allowed roots: {0}")]
+    Forbidden(String),
+    #[error("Database {0} already exists")]
+    AlreadyExists(String),
+    #[error("Not a SQLite database: {0}")]
+    NotSqlite(String),
+    #[error("Upload exceeds limit of {0} bytes")]
+    TooLarge(usize),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("Registry file error: {0}")]
+    Registry(#[from] serde_json::Error),
+}
+
+/// 通过 API 登记的数据库
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RegisteredDb {
+    name: String,
+    path: String,
+}
+
+pub struct SqliteRegistry {
+    /// 规范化后的根目录
+    roots: Vec<PathBuf>,
+    /// 规范化后的可登记目录
+    register_roots: Vec<PathBuf>,
+    /// 配置文件中登记的文件（不持久化到登记清单）
+    static_files: BTreeMap<String, PathBuf>,
+    /// 通过 API 登记的文件
+    registered: RwLock<BTreeMap<String, PathBuf>>,
+    registry_file: PathBuf,
+    /// 不允许通过登记表访问的文件（规范化路径）
+    protected: Vec<PathBuf>,
+    max_upload_bytes: usize,
+}
+
+impl SqliteRegistry {
+    /// 按配置初始化：创建并规范化根目录，加载登记清单。无效条目记录日志后跳过。
+    /// `metadata_db` 为元数据库路径（server.database_path），始终排除在外
+    pub fn from_config(cfg: &SqliteConfig, metadata_db: &str) -> Self {
+        let mut roots = Vec::new();
+        for root in &cfg.roots {
+            let canonical = fs::create_dir_all(root).and_then(|_| Path::new(root).canonicalize());
+            match canonical {
+                Ok(p) => roots.push(p),
+                Err(e) => log::error!("SQLite root {} unavailable: {}", root, e),
+            }
+        }
+        let mut register_roots = Vec::new();
+        for root in &cfg.register_roots {
+            match Path::new(root).canonicalize() {
+                Ok(p) => register_roots.push(p),
+                Err(e) => log::error!("SQLite register root {} unavailable: {}", root, e),
+            }
+        }
+
+        let registry_file = PathBuf::from(&cfg.registry_file);
+        let mut protected = Vec::new();
+        for path in [Path::new(metadata_db), registry_file.as_path()] {
+            let canonical = canonical_lenient(path);
+            for suffix in ["-wal", "-shm", "-journal"] {
+                let mut companion = canonical.clone().into_os_string();
+                companion.push(suffix);
+                protected.push(PathBuf::from(companion));
+            }
+            protected.push(canonical);
+        }
+
+        let mut static_files = BTreeMap::new();
+        for file in &cfg.files {
+            match canonical_db_file(Path::new(file)) {
+                Ok((_, path)) if protected.contains(&path) => {
+                    log::error!("SQLite file {} skipped: metadata store", file)
+                }
+                Ok((name, path)) => {
+                    static_files.insert(name, path);
+                }
+                Err(e) => log::error!("SQLite file {} skipped: {}", file, e),
+            }
+        }
+
+        let mut registered = BTreeMap::new();
+        match load_registry_file(&registry_file) {
+            Ok(entries) => {
+                for entry in entries {
+                    match Path::new(&entry.path).canonicalize() {
+                        Ok(path) if protected.contains(&path) => {
+                            log::error!(
+                                "Registered database {} skipped: metadata store",
+                                entry.name
+                            )
+                        }
+                        Ok(path)
+                            if !roots
+                                .iter()
+                                .chain(&register_roots)
+                                .any(|root| path.starts_with(root)) =>
+                        {
+                            log::error!(
+                                "Registered database {} skipped: outside of register roots",
+                                entry.name
+                            )
+                        }
+                        Ok(path) => {
+                            registered.insert(entry.name, path);
+                        }
+                        Err(e) => log::error!("Registered database {} skipped: {}", entry.name, e),
+                    }
+                }
+            }
+            Err(e) => log::error!("Load {} failed: {}", registry_file.display(), e),
+        }
+
+        Self {
+            roots,
+            register_roots,
+            static_files,
+            registered: RwLock::new(registered),
+            registry_file,
+            protected,
+            max_upload_bytes: cfg.max_upload_bytes,
+        }
+    }
+
+    pub fn max_upload_bytes(&self) -> usize {
+        self.max_upload_bytes
+    }
+
+    /// 列出所有可访问的数据库，登记的名称优先于根目录中的同名文件
+    pub fn list(&self) -> Vec<DatabaseInfo> {
+        let mut found: BTreeMap<String, DatabaseInfo> = BTreeMap::new();
+        for root in &self.roots {
+            let Ok(entries) = fs::read_dir(root) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let is_file = entry.file_type().map(|ft| ft.is_file()).unwrap_or(false);
+                let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                if is_file
+                    && validate_name(name).is_ok()
+                    && !found.contains_key(name)
+                    && !self.is_protected(&path)
+                {
+                    found.insert(name.to_string(), database_info(name, &path, "root"));
+                }
+            }
+        }
+        for (name, path) in &self.static_files {
+            found.insert(name.clone(), database_info(name, path, "config"));
+        }
+        for (name, path) in self.registered.read().unwrap().iter() {
+            found.insert(name.clone(), database_info(name, path, "registered"));
+        }
+        found.into_values().collect()
+    }
+
+    /// 将请求中的数据库名称解析为规范化路径，数据库必须已存在
+    pub fn resolve(&self, db_name: &str) -> Result<PathBuf, RegistryError> {
+        if let Some(path) = self.registered_path(db_name) {
+            let canonical = path
+                .canonicalize()
+                .map_err(|_| RegistryError::NotFound(db_name.to_string()))?;
+            if self.is_protected(&canonical) {
+                return Err(RegistryError::Forbidden(db_name.to_string()));
+            }
+            return Ok(canonical);
+        }
+        validate_name(db_name)?;
+        for root in &self.roots {
+            let candidate = root.join(db_name);
+            if !candidate.is_file() {
+                continue;
+            }
+            let canonical = candidate.canonicalize()?;
+            // 符号链接等可能指向根目录之外
+            if !canonical.starts_with(root) || self.is_protected(&canonical) {
+                return Err(RegistryError::Forbidden(db_name.to_string()));
+            }
+            return Ok(canonical);
+        }
+        Err(RegistryError::NotFound(db_name.to_string()))
+    }
+
+    /// 在第一个根目录下新建空数据库
+    pub fn create(&self, db_name: &str) -> Result<DatabaseInfo, RegistryError> {
+        let path = self.new_file_path(db_name)?;
+        let conn = open_db(&path)?;
+        // 写入文件头，避免留下 0 字节文件
+        conn.execute_batch("PRAGMA user_version = 0; VACUUM;")?;
+        drop(conn);
+        Ok(database_info(db_name, &path.canonicalize()?, "root"))
+    }
+
+    /// 保存上传的数据库文件到第一个根目录，`overwrite` 为 false 时不覆盖已有文件
+    pub fn save_upload(
+        &self,
+        db_name: &str,
+        data: &[u8],
+        overwrite: bool,
+    ) -> Result<DatabaseInfo, RegistryError> {
+        if data.len() > self.max_upload_bytes {
+            return Err(RegistryError::TooLarge(self.max_upload_bytes));
+        }
+        if !data.starts_with(SQLITE_HEADER) {
+            return Err(RegistryError::NotSqlite(db_name.to_string()));
+        }
+        let path = match self.new_file_path(db_name) {
+            Ok(p) => p,
+            Err(RegistryError::AlreadyExists(_)) if overwrite => {
+                if self.registered_path(db_name).is_some() {
+                    // 登记文件不在根目录中，不允许通过上传覆盖
+                    return Err(RegistryError::AlreadyExists(db_name.to_string()));
+                }
+                self.resolve(db_name)?
+            }
+            Err(e) => return Err(e),
+        };
+        // 先写临时文件再替换，避免写到一半的文件被打开
+        let tmp = path.with_extension("uploading");
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, &path)?;
+        Ok(database_info(db_name, &path.canonicalize()?, "root"))
+    }
+
+    /// 登记服务器上已有的数据库文件，须位于根目录或可登记目录之下
+    pub fn register(&self, db_name: &str, path: &str) -> Result<DatabaseInfo, RegistryError> {
+        validate_name(db_name)?;
+        if self.exists(db_name) {
+            return Err(RegistryError::AlreadyExists(db_name.to_string()));
+        }
+        let (_, canonical) = canonical_db_file(Path::new(path))?;
+        if self.is_protected(&canonical) || !self.is_registrable(&canonical) {
+            return Err(RegistryError::Forbidden(path.to_string()));
+        }
+        let mut registered = self.registered.write().unwrap();
+        registered.insert(db_name.to_string(), canonical.clone());
+        if let Err(e) = save_registry_file(&self.registry_file, &registered) {
+            registered.remove(db_name);
+            return Err(e);
+        }
+        Ok(database_info(db_name, &canonical, "registered"))
+    }
+
+    /// 取消登记（不删除文件）
+    pub fn unregister(&self, db_name: &str) -> Result<(), RegistryError> {
+        let mut registered = self.registered.write().unwrap();
+        let Some(path) = registered.remove(db_name) else {
+            return Err(RegistryError::NotFound(db_name.to_string()));
+        };
+        if let Err(e) = save_registry_file(&self.registry_file, &registered) {
+            registered.insert(db_name.to_string(), path);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn is_protected(&self, path: &Path) -> bool {
+        let canonical = canonical_lenient(path);
+        self.protected.contains(&canonical)
+    }
+
+    fn is_registrable(&self, canonical: &Path) -> bool {
+        self.roots
+            .iter()
+            .chain(&self.register_roots)
+            .any(|root| canonical.starts_with(root))
+    }
+
+    fn registered_path(&self, db_name: &str) -> Option<PathBuf> {
+        self.registered
+            .read()
+            .unwrap()
+            .get(db_name)
+            .or_else(|| self.static_files.get(db_name))
+            .cloned()
+    }
+
+    fn exists(&self, db_name: &str) -> bool {
+        self.registered_path(db_name).is_some()
+            || self.roots.iter().any(|root| root.join(db_name).exists())
+    }
+
+    /// 新文件在第一个根目录下的路径；名称已被占用时返回 AlreadyExists
+    fn new_file_path(&self, db_name: &str) -> Result<PathBuf, RegistryError> {
+        validate_name(db_name)?;
+        let root = self
+            .roots
+            .first()
+            .ok_or_else(|| RegistryError::Forbidden("no sqlite root configured".to_string()))?;
+        if self.exists(db_name) {
+            return Err(RegistryError::AlreadyExists(db_name.to_string()));
+        }
+        Ok(root.join(db_name))
+    }
+}
+
+/// 打开登记表中的数据库
+pub fn open_db(path: impl AsRef<Path>) -> rusqlite::Result<Connection> {
+    open_db_with_flags(path, OpenFlags::default())
+}
+
+pub fn open_db_with_flags(
+    path: impl AsRef<Path>,
+    flags: OpenFlags,
+) -> rusqlite::Result<Connection> {
+    let conn = Connection::open_with_flags(path, flags)?;
+    // 普通 VACUUM 内部会 ATTACH 空文件名的临时库，予以放行
+    conn.authorizer(Some(|ctx: AuthContext<'_>| match ctx.action {
+        AuthAction::Attach { filename } if !filename.is_empty() => Authorization::Deny,
+        _ => Authorization::Allow,
+    }));
+    Ok(conn)
+}
+
+/// 数据库名称只能是根目录下的单个文件名：不含路径分隔符，不以 `.` 开头，扩展名须为 db/sqlite/sqlite3
+pub fn validate_name(name: &str) -> Result<(), RegistryError> {
+    let invalid = || RegistryError::InvalidName(name.to_string());
+    if name.is_empty() || name.len() > 128 || name.starts_with('.') {
+        return Err(invalid());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(invalid());
+    }
+    let ext = Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .ok_or_else(invalid)?;
+    if !DB_EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)) {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+/// 规范化路径；文件尚不存在时规范化其所在目录
+fn canonical_lenient(path: &Path) -> PathBuf {
+    if let Ok(canonical) = path.canonicalize() {
+        return canonical;
+    }
+    let parent = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    match (parent.canonicalize(), path.file_name()) {
+        (Ok(dir), Some(name)) => dir.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// 规范化已有文件路径并确认是 SQLite 数据库，返回（文件名, 规范化路径）
+fn canonical_db_file(path: &Path) -> Result<(String, PathBuf), RegistryError> {
+    let display = path.display().to_string();
+    let canonical = path
+        .canonicalize()
+        .map_err(|_| RegistryError::NotFound(display.clone()))?;
+    if !canonical.is_file() {
+        return Err(RegistryError::NotFound(display));
+    }
+    let mut header = [0u8; 16];
+    let mut file = fs::File::open(&canonical)?;
+    if file.read_exact(&mut header).is_err() || &header != SQLITE_HEADER {
+        return Err(RegistryError::NotSqlite(display));
+    }
+    let name = canonical
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .to_string();
+    Ok((name, canonical))
+}
+
+fn database_info(name: &str, path: &Path, source: &str) -> DatabaseInfo {
+    DatabaseInfo {
+        name: name.to_string(),
+        path: path.to_string_lossy().to_string(),
+        source: source.to_string(),
+        size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+    }
+}
+
+fn load_registry_file(path: &Path) -> Result<Vec<RegisteredDb>, RegistryError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+fn save_registry_file(
+    path: &Path,
+    registered: &BTreeMap<String, PathBuf>,
+) -> Result<(), RegistryError> {
+    let entries: Vec<RegisteredDb> = registered
+        .iter()
+        .map(|(name, path)| RegisteredDb {
+            name: name.clone(),
+            path: path.to_string_lossy().to_string(),
+        })
+        .collect();
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(&entries)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// 按 SQLite 规则给标识符加双引号，内部的双引号写两次
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata_db(dir: &Path) -> String {
+        dir.join("root")
+            .join("rsts.db")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn temp_config(tag: &str) -> (PathBuf, SqliteConfig) {
+        let dir = std::env::temp_dir().join(format!("rsts_sqlite_{}_{}", tag, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let cfg = SqliteConfig {
+            roots: vec![dir.join("root").to_string_lossy().to_string()],
+            files: Vec::new(),
+            register_roots: vec![dir.join("ext").to_string_lossy().to_string()],
+            registry_file: dir.join("registry.json").to_string_lossy().to_string(),
+            max_upload_bytes: 1024 * 1024,
+            backup_dir: dir.join("backups").to_string_lossy().to_string(),
+        };
+        (dir, cfg)
+    }
+
+    #[test]
+    fn test_validate_name_and_quote() {
+        assert!(validate_name("app.db").is_ok());
+        assert!(validate_name("data_1.sqlite3").is_ok());
+        for bad in [
+            "../x.db",
+            "a/b.db",
+            "..\\x.db",
+            ".hidden.db",
+            "x.txt",
+            "x",
+            "",
+        ] {
+            assert!(validate_name(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(quote_ident("a\"b"), "\"a\"\"b\"");
+    }
+
+    #[test]
+    fn test_create_register_resolve() {
+        let (dir, cfg) = temp_config("reg");
+        let registry = SqliteRegistry::from_config(&cfg, &metadata_db(&dir));
+        let info = registry.create("one.db").unwrap();
+        assert!(info.size > 0);
+        assert!(registry.create("one.db").is_err());
+        let resolved = registry.resolve("one.db").unwrap();
+        assert!(resolved.starts_with(&registry.roots[0]));
+        assert!(matches!(
+            registry.resolve("../registry.json"),
+            Err(RegistryError::InvalidName(_))
+        ));
+
+        // 根目录之外的文件需登记后才能访问，且只能登记可登记目录下的文件；登记清单持久化
+        let elsewhere = dir.join("elsewhere.db");
+        fs::copy(&resolved, &elsewhere).unwrap();
+        assert!(matches!(
+            registry.register("ext.db", elsewhere.to_str().unwrap()),
+            Err(RegistryError::Forbidden(_))
+        ));
+        fs::create_dir_all(dir.join("ext")).unwrap();
+        let registry = SqliteRegistry::from_config(&cfg, &metadata_db(&dir));
+        let outside = dir.join("ext").join("outside.db");
+        fs::copy(&resolved, &outside).unwrap();
+        assert!(matches!(
+            registry.resolve("outside.db"),
+            Err(RegistryError::NotFound(_))
+        ));
+        registry
+            .register("ext.db", outside.to_str().unwrap())
+            .unwrap();
+        let reloaded = SqliteRegistry::from_config(&cfg, &metadata_db(&dir));
+        assert_eq!(
+            reloaded.resolve("ext.db").unwrap(),
+            outside.canonicalize().unwrap()
+        );
+        assert_eq!(reloaded.list().len(), 2);
+        reloaded.unregister("ext.db").unwrap();
+        assert!(reloaded.resolve("ext.db").is_err());
+
+        assert!(matches!(
+            registry.save_upload("up.db", b"not sqlite", false),
+            Err(RegistryError::NotSqlite(_))
+        ));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_metadata_store_is_hidden() {
+        let (dir, cfg) = temp_config("meta");
+        let registry = SqliteRegistry::from_config(&cfg, &metadata_db(&dir));
+        // 元数据库位于根目录中：不列出、不可解析、不可登记
+        let meta = Connection::open(metadata_db(&dir)).unwrap();
+        meta.execute_batch("PRAGMA journal_mode = WAL; CREATE TABLE users(id INTEGER);")
+            .unwrap();
+        registry.create("app.db").unwrap();
+        let names: Vec<String> = registry.list().into_iter().map(|d| d.name).collect();
+        assert_eq!(names, vec!["app.db".to_string()]);
+        assert!(matches!(
+            registry.resolve("rsts.db"),
+            Err(RegistryError::Forbidden(_))
+        ));
+        assert!(matches!(
+            registry.register("meta.db", &metadata_db(&dir)),
+            Err(RegistryError::Forbidden(_))
+        ));
+        assert!(
+            registry
+                .save_upload("rsts.db", SQLITE_HEADER, true)
+                .is_err()
+        );
+        drop(meta);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_open_db_denies_attach() {
+        let (dir, cfg) = temp_config("attach");
+        let registry = SqliteRegistry::from_config(&cfg, &metadata_db(&dir));
+        registry.create("app.db").unwrap();
+        let conn = open_db(registry.resolve("app.db").unwrap()).unwrap();
+        // ATTACH 与 VACUUM INTO 都不能打开或创建根目录之外的文件
+        let outside = dir.join("outside.db");
+        let attach = format!("ATTACH DATABASE '{}' AS x", outside.display());
+        assert!(conn.execute_batch(&attach).is_err());
+        assert!(crate::modules::demo::csv_to_sqlite::restore_sql(&conn, &attach).is_err());
+        assert!(
+            conn.execute_batch(&format!("VACUUM INTO '{}'", outside.display()))
+                .is_err()
+        );
+        assert!(!outside.exists());
+        conn.execute_batch("CREATE TABLE t (id INTEGER); VACUUM;")
+            .unwrap();
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
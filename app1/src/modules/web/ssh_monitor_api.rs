@@ -1,4 +1,7 @@
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::web;
+
+use super::models::Response;
+use super::response::{ApiError, ApiResult};
 
 #[test]
 fn test_parse_cpu_usage() {
@@ -9,9 +12,10 @@ fn test_parse_cpu_usage() {
     assert!(usage > 0);
 }
 
-use ssh2::{MethodType, Session as Ssh2Session};
+use ssh2::Session as Ssh2Session;
 use std::io::Read;
-use std::net::TcpStream;
+
+use crate::modules::ssh::exec::ssh2_connect;
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
@@ -59,8 +63,9 @@ fn get_cached_session(host: &str, port: u16, username: &str, password: &str) ->
 }
 
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct MonitorRequest {
     pub hostname: String,
     pub port: Option<u16>,
@@ -68,27 +73,27 @@ pub struct MonitorRequest {
     pub password: String,
 }
 
-#[derive(Serialize, Default)]
+#[derive(Serialize, Default, ToSchema)]
 pub struct MemoryStat {
     pub used: u64,
     pub total: u64,
     pub usage: u8,
 }
 
-#[derive(Serialize, Default)]
+#[derive(Serialize, Default, ToSchema)]
 pub struct NetworkStat {
     pub rx_rate: u64, // bytes per second
     pub tx_rate: u64, // bytes per second
 }
 
-#[derive(Serialize, Default)]
+#[derive(Serialize, Default, ToSchema)]
 pub struct InterfaceStat {
     pub name: String,
     pub rx_rate: u64,
     pub tx_rate: u64,
 }
 
-#[derive(Serialize, Default)]
+#[derive(Serialize, Default, ToSchema)]
 pub struct SystemStats {
     pub cpu: u8,
     pub memory: MemoryStat,
@@ -179,37 +184,6 @@ fn parse_net_dev_simple(output: &str) -> (u64, u64) {
     (total_rx, total_tx)
 }
 
-fn ssh2_connect(host: &str, port: u16, username: &str, password: &str) -> anyhow::Result<Ssh2Session> {
-    let addr = format!("{}:{}", host, port);
-    let tcp = TcpStream::connect(addr)?;
-    tcp.set_read_timeout(Some(Duration::from_secs(30)))?;
-    tcp.set_write_timeout(Some(Duration::from_secs(30)))?;
-    let mut sess = Ssh2Session::new()?;
-    sess.set_tcp_stream(tcp);
-    // 扩大兼容算法集合
-    let _ = sess.method_pref(
-        MethodType::Kex,
-        "curve25519-sha256,curve25519-sha256@libssh.org,ecdh-sha2-nistp256,ecdh-sha2-nistp384,ecdh-sha2-nistp521,diffie-hellman-group-exchange-sha256,diffie-hellman-group16-sha512,diffie-hellman-group18-sha512,diffie-hellman-group14-sha256",
-    );
-    let _ = sess.method_pref(
-        MethodType::HostKey,
-        "ecdsa-sha2-nistp256,ecdsa-sha2-nistp384,ecdsa-sha2-nistp521,ssh-ed25519,rsa-sha2-512,rsa-sha2-256,ssh-rsa",
-    );
-    let crypt = "aes128-ctr,aes192-ctr,aes256-ctr,aes128-gcm@openssh.com,aes256-gcm@openssh.com,chacha20-poly1305@openssh.com";
-    let _ = sess.method_pref(MethodType::CryptCs, crypt);
-    let _ = sess.method_pref(MethodType::CryptSc, crypt);
-    let mac = "hmac-sha2-256,hmac-sha2-512,hmac-sha2-256-etm@openssh.com,hmac-sha2-512-etm@openssh.com";
-    let _ = sess.method_pref(MethodType::MacCs, mac);
-    let _ = sess.method_pref(MethodType::MacSc, mac);
-    sess.handshake()?;
-    sess.set_blocking(true);
-    sess.userauth_password(username, password)?;
-    if !sess.authenticated() {
-        anyhow::bail!("ssh auth failed");
-    }
-    Ok(sess)
-}
-
 fn ssh2_exec(sess: &Ssh2Session, cmd: &str) -> anyhow::Result<String> {
     let mut ch = sess.channel_session()?;
     ch.exec(cmd)?;
@@ -317,7 +291,14 @@ fn parse_cpu_usage(stat1: &str, stat2: &str) -> u8 {
     usage.round().clamp(0.0, 100.0) as u8
 }
 
-pub async fn get_monitor_stats(payload: web::Json<MonitorRequest>) -> impl Responder {
+#[utoipa::path(
+    post,
+    path = "/api/ssh/monitor",
+    tag = "ssh",
+    summary = "远程主机资源监控",
+    responses((status = 200, body = Response<SystemStats>))
+)]
+pub async fn get_monitor_stats(payload: web::Json<MonitorRequest>) -> ApiResult<SystemStats> {
     let host = payload.hostname.trim();
     let port = payload.port.unwrap_or(22);
     let user = payload.username.trim();
@@ -326,15 +307,8 @@ pub async fn get_monitor_stats(payload: web::Json<MonitorRequest>) -> impl Respo
     let sess = match get_cached_session(host, port, user, pass) {
         Ok(s) => s,
         Err(e) => {
-            // 返回 200 OK 但带有错误信息的 JSON，而不是 400 Bad Request
-            // 这样前端 JSON 解析不会报错，或者我们应该在前端处理 400
-            // 但为了简单起见，这里记录错误并返回空数据结构或特定错误字段
-            // 不过前端是 expect 200 OK。
-            // 让我们保持 400，但前端需要捕获。
-            // 这里的 400 是因为连接失败。
-            return HttpResponse::BadRequest().json(serde_json::json!({
-                "error": format!("ssh connect failed: {}", e),
-            }));
+            // 连接失败返回 502，前端按统一的错误响应提示
+            return Err(ApiError::Remote(format!("ssh connect failed: {}", e)));
         }
     };
 
@@ -399,7 +373,7 @@ pub async fn get_monitor_stats(payload: web::Json<MonitorRequest>) -> impl Respo
         network,
         interfaces,
     };
-    HttpResponse::Ok().json(result)
+    Ok(Response::ok(result))
 }
 
 #[cfg(test)]
@@ -8,18 +8,22 @@ use tokio::sync::Mutex;
 
 // 使用真实的SSH模块
 use super::actors::WsSshSession;
+use super::audit::{self, AuditEvent, Auditor};
 use super::models::{ConnectSsh, ExecuteCommand, SendWsMessage, SetSshClientId};
+use super::ws_auth::{self, TokenExpired, WsUser};
 use crate::modules::ssh::{SshCredentials, SshService};
 
 // Actor 类型已集中到 actors.rs
 
 impl WsSshSession {
-    pub fn new(ssh_service: Arc<Mutex<SshService>>) -> Self {
+    pub fn new(ssh_service: Arc<Mutex<SshService>>, auditor: Option<Auditor>) -> Self {
         Self {
             hb: Instant::now(),
             ssh_service,
             ssh_client_id: None,
             connected_to_ssh: Arc::new(AtomicBool::new(false)),
+            auditor,
+            session_audit: None,
         }
     }
 
@@ -61,6 +65,12 @@ impl WsSshSession {
             self.connected_to_ssh
                 .store(false, std::sync::atomic::Ordering::Relaxed);
 
+            // 记录会话关闭（耗时即会话时长）
+            if let (Some(auditor), Some(event)) = (self.auditor.clone(), self.session_audit.take())
+            {
+                actix::spawn(async move { auditor.record(&event, None).await });
+            }
+
             // 发送断开连接消息
             let disconnect_msg =
                 serde_json::json!({"type": "disconnected", "message": "SSH连接已断开"});
@@ -242,6 +252,13 @@ impl Handler<ConnectSsh> for WsSshSession {
 
     fn handle(&mut self, msg: ConnectSsh, ctx: &mut Self::Context) {
         let credentials = msg.credentials;
+        let target = format!(
+            "{}@{}:{}",
+            credentials.username, credentials.hostname, credentials.port
+        );
+        let open_event = AuditEvent::start(audit::SSH_SESSION_OPEN, target.clone());
+        self.session_audit = Some(AuditEvent::start(audit::SSH_SESSION_CLOSE, target));
+        let auditor = self.auditor.clone();
 
         // 创建一个新的任务来处理异步连接
         let ssh_service = Arc::clone(&self.ssh_service);
@@ -256,6 +273,9 @@ impl Handler<ConnectSsh> for WsSshSession {
                 .await
             {
                 Ok(client_id) => {
+                    if let Some(auditor) = &auditor {
+                        auditor.record(&open_event, None).await;
+                    }
                     // 更新会话状态
                     addr.do_send(SetSshClientId { client_id });
 
@@ -266,6 +286,11 @@ impl Handler<ConnectSsh> for WsSshSession {
                     });
                 }
                 Err(err) => {
+                    if let Some(auditor) = &auditor {
+                        auditor
+                            .record(&open_event, Some(format!("{:?}", err)))
+                            .await;
+                    }
                     // 发送连接失败消息
                     let msg = serde_json::json!({"type": "error", "message": format!("SSH连接失败: {:?}", err)});
                     addr.do_send(SendWsMessage {
@@ -344,6 +369,16 @@ impl Handler<ExecuteCommand> for WsSshSession {
     }
 }
 
+/// 访问令牌过期时关闭连接
+impl Handler<TokenExpired> for WsSshSession {
+    type Result = ();
+
+    fn handle(&mut self, _: TokenExpired, ctx: &mut Self::Context) {
+        log_info!("访问令牌已过期，关闭SSH WebSocket连接");
+        ws_auth::close_expired(ctx);
+    }
+}
+
 /// 处理发送WebSocket消息
 impl Handler<SendWsMessage> for WsSshSession {
     type Result = ();
@@ -378,10 +413,14 @@ pub async fn ssh_route(
     log_info!("Connection头: {:?}", connection_header);
     log_info!("Upgrade头: {:?}", upgrade_header);
 
+    let user = WsUser::from_request(&req)?;
+    log_info!("SSH WebSocket用户: {}", user.username);
+
     // 创建SSH服务实例
     let ssh_service = Arc::new(Mutex::new(SshService::new()));
 
-    match ws::start(WsSshSession::new(ssh_service), &req, stream) {
+    let session = WsSshSession::new(ssh_service, Auditor::from_request(&req));
+    match ws_auth::start(session, &user, &req, stream) {
         Ok(response) => {
             log_info!("SSH WebSocket连接成功建立");
             Ok(response)
@@ -1,5 +1,7 @@
 use super::actors::WsSshPtySession;
+use super::audit::{self, AuditEvent, Auditor};
 use super::models::{AnyPtyClient, SendWsMessage, SetSshClient, SshCredentials};
+use super::ws_auth::{self, TokenExpired, WsUser};
 use crate::modules::ssh::russh_client::RusshClient;
 use crate::modules::ssh::ssh2_pty_client::Ssh2PtyClient;
 use actix::{Actor, ActorContext, AsyncContext, StreamHandler};
@@ -13,7 +15,7 @@ use tokio::sync::{Mutex, mpsc};
 // Actor 类型已集中到 actors.rs
 
 impl WsSshPtySession {
-    pub fn new() -> Self {
+    pub fn new(auditor: Option<Auditor>) -> Self {
         Self {
             hb: Instant::now(),
             ssh_client: None,
@@ -22,6 +24,8 @@ impl WsSshPtySession {
             last_resize_at: None,
             resize_deferred: false,
             pending_resize: None,
+            auditor,
+            session_audit: None,
         }
     }
 
@@ -52,6 +56,11 @@ impl WsSshPtySession {
                     error!("关闭SSH连接失败: {}", e);
                 }
             });
+            // 记录会话关闭（耗时即会话时长）
+            if let (Some(auditor), Some(event)) = (self.auditor.clone(), self.session_audit.take())
+            {
+                actix::spawn(async move { auditor.record(&event, None).await });
+            }
         }
 
         self.connected = false;
@@ -87,6 +96,14 @@ impl WsSshPtySession {
             credentials.username, credentials.hostname, credentials.port
         );
 
+        let target = format!(
+            "{}@{}:{}",
+            credentials.username, credentials.hostname, credentials.port
+        );
+        let open_event = AuditEvent::start(audit::SSH_SESSION_OPEN, target.clone());
+        self.session_audit = Some(AuditEvent::start(audit::SSH_SESSION_CLOSE, target));
+        let auditor = self.auditor.clone();
+
         let addr = ctx.address();
         let creds = credentials.clone();
         let mut cols = pty_cols;
@@ -128,12 +145,14 @@ impl WsSshPtySession {
                 // SSH2连接成功
                 let (tx, mut rx) = mpsc::unbounded_channel::<Vec<u8>>();
                 if let Err(e) = client_ssh2.start_pty_io(tx).await {
+                    record_open(auditor.as_ref(), &open_event, Some(e.to_string())).await;
                     let error_msg = serde_json::json!({"type": "error", "message": format!("启动PTY IO失败: {}", e)});
                     addr.do_send(SendWsMessage {
                         text: error_msg.to_string(),
                     });
                     return;
                 }
+                record_open(auditor.as_ref(), &open_event, None).await;
                 let client_arc = Arc::new(Mutex::new(client_ssh2));
                 addr.do_send(SetSshClient {
                     client: AnyPtyClient::Ssh2(client_arc.clone()),
@@ -164,6 +183,8 @@ impl WsSshPtySession {
                 );
 
                 if let Err(e_russh) = client_russh.connect().await {
+                    let error = format!("SSH2: {}, Russh: {}", ssh2_error, e_russh);
+                    record_open(auditor.as_ref(), &open_event, Some(error)).await;
                     let error_msg = serde_json::json!({
                         "type": "error",
                         "message": format!("SSH连接失败 (SSH2: {}, Russh: {})", ssh2_error, e_russh)
@@ -175,6 +196,7 @@ impl WsSshPtySession {
                 }
 
                 if let Err(e) = client_russh.create_pty_session(cols, rows).await {
+                    record_open(auditor.as_ref(), &open_event, Some(e.to_string())).await;
                     let error_msg = serde_json::json!({"type": "error", "message": format!("Russh创建PTY失败: {}", e)});
                     addr.do_send(SendWsMessage {
                         text: error_msg.to_string(),
@@ -184,6 +206,7 @@ impl WsSshPtySession {
 
                 let (tx, mut rx) = mpsc::unbounded_channel::<Vec<u8>>();
                 if let Err(e) = client_russh.start_pty_io(tx).await {
+                    record_open(auditor.as_ref(), &open_event, Some(e.to_string())).await;
                     let error_msg = serde_json::json!({"type": "error", "message": format!("Russh启动PTY IO失败: {}", e)});
                     addr.do_send(SendWsMessage {
                         text: error_msg.to_string(),
@@ -191,6 +214,7 @@ impl WsSshPtySession {
                     return;
                 }
 
+                record_open(auditor.as_ref(), &open_event, None).await;
                 let client_arc = Arc::new(Mutex::new(client_russh));
                 addr.do_send(SetSshClient {
                     client: AnyPtyClient::Russh(client_arc.clone()),
@@ -346,6 +370,16 @@ impl Actor for WsSshPtySession {
     }
 }
 
+/// 访问令牌过期时关闭连接
+impl actix::Handler<TokenExpired> for WsSshPtySession {
+    type Result = ();
+
+    fn handle(&mut self, _: TokenExpired, ctx: &mut Self::Context) {
+        info!("访问令牌已过期，关闭SSH PTY连接");
+        ws_auth::close_expired(ctx);
+    }
+}
+
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSshPtySession {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
         match msg {
@@ -445,6 +479,13 @@ impl actix::Handler<SendWsMessage> for WsSshPtySession {
     }
 }
 
+// 记录 SSH 会话建立的结果（应用未注册数据库时不记录）
+async fn record_open(auditor: Option<&Auditor>, event: &AuditEvent, error: Option<String>) {
+    if let Some(auditor) = auditor {
+        auditor.record(event, error).await;
+    }
+}
+
 // 常量定义
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
@@ -454,8 +495,14 @@ pub async fn ssh_pty_route(
     req: actix_web::HttpRequest,
     stream: actix_web::web::Payload,
 ) -> Result<actix_web::HttpResponse, actix_web::Error> {
-    let resp = ws::start(WsSshPtySession::new(), &req, stream);
-    info!("新的SSH PTY WebSocket连接: {:?}", req.peer_addr());
+    let user = WsUser::from_request(&req)?;
+    let session = WsSshPtySession::new(Auditor::from_request(&req));
+    let resp = ws_auth::start(session, &user, &req, stream);
+    info!(
+        "新的SSH PTY WebSocket连接: {:?}, 用户: {}",
+        req.peer_addr(),
+        user.username
+    );
     resp
 }
 
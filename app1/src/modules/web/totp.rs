@@ -0,0 +1,152 @@
+// 两步验证：TOTP（RFC 6238，HMAC-SHA1、6 位、30 秒）与一次性恢复码
+use data_encoding::{BASE32_NOPAD, HEXLOWER};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+pub const DIGITS: u32 = 6;
+pub const PERIOD: u64 = 30;
+// 160 位密钥（RFC 4226 推荐长度）
+const SECRET_BYTES: usize = 20;
+// 恢复码字符集，去掉了容易混淆的 0/o、1/l/i
+const RECOVERY_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+// 生成 base32 编码的随机密钥
+pub fn generate_secret() -> String {
+    let secret: [u8; SECRET_BYTES] = rand::random();
+    BASE32_NOPAD.encode(&secret)
+}
+
+// 身份验证器应用扫码使用的 otpauth:// 地址
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &str) -> String {
+    let issuer = percent_encode(issuer);
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        issuer,
+        percent_encode(account),
+        secret,
+        issuer,
+        DIGITS,
+        PERIOD
+    )
+}
+
+pub(super) fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// RFC 4226 HOTP
+fn hotp(key: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let code = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    code % 10u32.pow(DIGITS)
+}
+
+// 校验验证码，通过时返回匹配的时间步（用于防止同一验证码被重复使用）
+pub fn verify_code(secret: &str, code: &str, unix_time: u64, skew: u64) -> Option<u64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let expected: u32 = code.parse().ok()?;
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let current = unix_time / PERIOD;
+    (current.saturating_sub(skew)..=current + skew).find(|step| hotp(&key, *step) == expected)
+}
+
+// 指定时刻的验证码（验证器应用显示的值）
+pub fn code_at(secret: &str, unix_time: u64) -> Option<String> {
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    Some(format!(
+        "{:0width$}",
+        hotp(&key, unix_time / PERIOD),
+        width = DIGITS as usize
+    ))
+}
+
+// 是否形如 TOTP 验证码（否则按恢复码处理）
+pub fn looks_like_totp(code: &str) -> bool {
+    let code = code.trim();
+    code.len() == DIGITS as usize && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+// 生成一组恢复码，格式 xxxxx-xxxxx
+pub fn generate_recovery_codes(count: usize) -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    (0..count)
+        .map(|_| {
+            let chars: String = (0..10)
+                .map(|_| RECOVERY_ALPHABET[rng.gen_range(0..RECOVERY_ALPHABET.len())] as char)
+                .collect();
+            format!("{}-{}", &chars[..5], &chars[5..])
+        })
+        .collect()
+}
+
+// 恢复码只保存哈希；忽略大小写、空格与连字符
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    HEXLOWER.encode(&Sha256::digest(normalized.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc6238_vectors() {
+        // RFC 6238 附录 B 的 SHA1 测试密钥，取 8 位结果的后 6 位
+        let secret = BASE32_NOPAD.encode(b"12345678901234567890");
+        for (time, code) in [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+        ] {
+            assert_eq!(verify_code(&secret, code, time, 0), Some(time / PERIOD));
+        }
+        // 允许前后一个周期的偏差
+        assert_eq!(verify_code(&secret, "287082", 59 + PERIOD, 1), Some(1));
+        assert_eq!(verify_code(&secret, "287082", 59 + 2 * PERIOD, 1), None);
+        assert_eq!(verify_code(&secret, "28708", 59, 1), None);
+        assert_eq!(code_at(&secret, 1234567890).as_deref(), Some("005924"));
+    }
+
+    #[test]
+    fn secrets_and_recovery_codes() {
+        let secret = generate_secret();
+        assert_eq!(BASE32_NOPAD.decode(secret.as_bytes()).unwrap().len(), 20);
+        let uri = otpauth_uri("rsts", "alice@corp", &secret);
+        assert!(uri.starts_with("otpauth://totp/rsts:alice%40corp?secret="));
+        assert!(uri.ends_with("&issuer=rsts&algorithm=SHA1&digits=6&period=30"));
+
+        let codes = generate_recovery_codes(10);
+        assert_eq!(codes.len(), 10);
+        assert!(codes.iter().all(|c| c.len() == 11 && !looks_like_totp(c)));
+        assert_eq!(
+            hash_recovery_code(&codes[0]),
+            hash_recovery_code(&codes[0].to_uppercase().replace('-', " "))
+        );
+    }
+}
@@ -0,0 +1,239 @@
+// 两步验证 API：当前用户绑定/停用 TOTP、重新生成恢复码；管理员可重置用户的绑定
+use actix_web::{HttpMessage, HttpRequest, web};
+use chrono::Utc;
+use serde_json::json;
+use std::sync::Arc;
+
+use super::auth_utils::Claims;
+use super::database::Database;
+use super::models::{
+    Response, TotpCodeInput, TotpDisableInput, TotpSetup, TotpSetupInput, TotpStatus,
+};
+use super::repository::{StoreError, TotpState};
+use super::response::{ApiError, ApiResult};
+use super::totp;
+use crate::modules::config::config::Config;
+
+// 校验第二因素：6 位数字按 TOTP 校验（同一时间步只能用一次），否则按恢复码核销
+pub async fn verify_second_factor(
+    db: &Database,
+    user_id: i64,
+    state: &TotpState,
+    code: &str,
+    skew: u64,
+) -> Result<bool, StoreError> {
+    if totp::looks_like_totp(code) {
+        let now = Utc::now().timestamp() as u64;
+        match totp::verify_code(&state.secret, code, now, skew) {
+            Some(step) => db.totp().use_step(user_id, step as i64).await,
+            None => Ok(false),
+        }
+    } else {
+        db.totp()
+            .use_recovery_code(user_id, &totp::hash_recovery_code(code))
+            .await
+    }
+}
+
+// 当前登录用户的 (id, 用户名)
+fn current_user(req: &HttpRequest) -> Result<(i64, String), ApiError> {
+    let extensions = req.extensions();
+    extensions
+        .get::<Claims>()
+        .and_then(|claims| Some((claims.sub.parse().ok()?, claims.username.clone())))
+        .ok_or_else(|| ApiError::TokenInvalid("未登录".to_string()))
+}
+
+fn wrong_password() -> ApiError {
+    ApiError::InvalidCredentials("当前密码错误".to_string())
+}
+
+fn wrong_code() -> ApiError {
+    ApiError::InvalidCredentials("验证码错误".to_string())
+}
+
+fn not_enabled() -> ApiError {
+    ApiError::Conflict("未启用两步验证".to_string())
+}
+
+// 生成恢复码，返回 (明文, 哈希)
+fn new_recovery_codes(count: usize) -> (Vec<String>, Vec<String>) {
+    let codes = totp::generate_recovery_codes(count);
+    let hashes = codes.iter().map(|c| totp::hash_recovery_code(c)).collect();
+    (codes, hashes)
+}
+
+// GET /api/user/totp
+#[utoipa::path(
+    get,
+    path = "/api/user/totp",
+    tag = "user",
+    summary = "两步验证状态",
+    responses((status = 200, body = Response<TotpStatus>))
+)]
+pub async fn get_status(req: HttpRequest, db: web::Data<Arc<Database>>) -> ApiResult<TotpStatus> {
+    let (user_id, _) = current_user(&req)?;
+    let state = db.totp().state(user_id).await?;
+    let recovery_codes_left = db.totp().recovery_codes_left(user_id).await?;
+    let status = TotpStatus {
+        enabled: state.as_ref().is_some_and(|s| s.enabled),
+        pending: state.as_ref().is_some_and(|s| !s.enabled),
+        recovery_codes_left,
+    };
+    Ok(Response::ok(status))
+}
+
+// POST /api/user/totp/setup：生成新密钥，需用验证码确认后才启用
+#[utoipa::path(
+    post,
+    path = "/api/user/totp/setup",
+    tag = "user",
+    summary = "生成两步验证密钥",
+    responses((status = 200, body = Response<TotpSetup>))
+)]
+pub async fn setup(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+    config: web::Data<Config>,
+    body: web::Json<TotpSetupInput>,
+) -> ApiResult<TotpSetup> {
+    let (user_id, username) = current_user(&req)?;
+    if !db.validate_user(&username, &body.password).await? {
+        return Err(wrong_password());
+    }
+    let secret = totp::generate_secret();
+    if !db.totp().set_pending(user_id, &secret).await? {
+        return Err(ApiError::Conflict("两步验证已启用，请先停用".to_string()));
+    }
+    let otpauth_uri = totp::otpauth_uri(&config.totp.issuer, &username, &secret);
+    let setup = TotpSetup {
+        secret,
+        otpauth_uri,
+    };
+    Ok(Response::with_msg("请使用身份验证器扫码", setup))
+}
+
+// POST /api/user/totp/enable：确认验证码并启用，返回一次性恢复码（只显示这一次）
+#[utoipa::path(
+    post,
+    path = "/api/user/totp/enable",
+    tag = "user",
+    summary = "启用两步验证",
+    responses((status = 200, body = Response))
+)]
+pub async fn enable(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+    config: web::Data<Config>,
+    body: web::Json<TotpCodeInput>,
+) -> ApiResult {
+    let (user_id, username) = current_user(&req)?;
+    let not_pending = || ApiError::Conflict("请先生成密钥".to_string());
+    let secret = match db.totp().state(user_id).await? {
+        Some(state) if !state.enabled => state.secret,
+        Some(_) => return Err(ApiError::Conflict("两步验证已启用".to_string())),
+        None => return Err(not_pending()),
+    };
+    let now = Utc::now().timestamp() as u64;
+    let Some(step) = totp::verify_code(&secret, &body.code, now, config.totp.skew_steps) else {
+        return Err(wrong_code());
+    };
+    let (codes, hashes) = new_recovery_codes(config.totp.recovery_codes);
+    if !db.totp().enable(user_id, step as i64, hashes).await? {
+        return Err(not_pending());
+    }
+    log::info!("user {} enabled totp", username);
+    Ok(Response::with_msg(
+        "两步验证已启用",
+        json!({ "recovery_codes": codes }),
+    ))
+}
+
+// POST /api/user/totp/disable：需要当前密码与验证码（或恢复码）
+#[utoipa::path(
+    post,
+    path = "/api/user/totp/disable",
+    tag = "user",
+    summary = "停用两步验证",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn disable(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+    config: web::Data<Config>,
+    body: web::Json<TotpDisableInput>,
+) -> ApiResult<()> {
+    let (user_id, username) = current_user(&req)?;
+    if !db.validate_user(&username, &body.password).await? {
+        return Err(wrong_password());
+    }
+    let state = match db.totp().state(user_id).await? {
+        Some(state) if state.enabled => state,
+        _ => return Err(not_enabled()),
+    };
+    if !verify_second_factor(&db, user_id, &state, &body.code, config.totp.skew_steps).await? {
+        return Err(wrong_code());
+    }
+    db.totp().disable(user_id).await?;
+    log::info!("user {} disabled totp", username);
+    Ok(Response::message("两步验证已停用"))
+}
+
+// POST /api/user/totp/recovery-codes：重新生成恢复码，旧的全部作废
+#[utoipa::path(
+    post,
+    path = "/api/user/totp/recovery-codes",
+    tag = "user",
+    summary = "重新生成恢复码",
+    responses((status = 200, body = Response))
+)]
+pub async fn regenerate_recovery_codes(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+    config: web::Data<Config>,
+    body: web::Json<TotpCodeInput>,
+) -> ApiResult {
+    let (user_id, username) = current_user(&req)?;
+    let state = match db.totp().state(user_id).await? {
+        Some(state) if state.enabled => state,
+        _ => return Err(not_enabled()),
+    };
+    // 只接受验证器的验证码，丢失验证器时不能用恢复码换新的恢复码
+    if !totp::looks_like_totp(&body.code)
+        || !verify_second_factor(&db, user_id, &state, &body.code, config.totp.skew_steps).await?
+    {
+        return Err(wrong_code());
+    }
+    let (codes, hashes) = new_recovery_codes(config.totp.recovery_codes);
+    db.totp().replace_recovery_codes(user_id, hashes).await?;
+    log::info!("user {} regenerated totp recovery codes", username);
+    Ok(Response::with_msg(
+        "恢复码已重新生成",
+        json!({ "recovery_codes": codes }),
+    ))
+}
+
+// DELETE /api/users/{username}/totp：管理员重置两步验证（用户丢失验证器与恢复码时）
+#[utoipa::path(
+    delete,
+    path = "/api/users/{username}/totp",
+    tag = "users",
+    summary = "清除用户的两步验证",
+    params(("username" = String, Path, description = "用户名")),
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn reset_user_totp(
+    db: web::Data<Arc<Database>>,
+    path: web::Path<String>,
+) -> ApiResult<()> {
+    let username = path.into_inner();
+    let Some((user_id, _, _)) = db.get_user_info(&username).await? else {
+        return Err(ApiError::UserNotFound);
+    };
+    if db.totp().disable(user_id as i64).await? {
+        log::info!("totp of user {} reset", username);
+        Ok(Response::message("已重置两步验证"))
+    } else {
+        Ok(Response::message("该用户未启用两步验证"))
+    }
+}
@@ -0,0 +1,387 @@
+// 用户管理 API：管理员维护账号，用户修改自己的密码
+//
+// 所有密码都经过 config.password_policy 校验后再以 bcrypt 存储。
+use actix_web::{HttpMessage, HttpRequest, web};
+use std::sync::Arc;
+
+use super::auth_utils::Claims;
+use super::database::Database;
+use super::models::{
+    LoginFailure, LoginFailureQuery, PasswordChangeInput, PasswordResetInput, Response,
+    UserCreateInput, UserAccount, UserUpdateInput,
+};
+use super::rbac::outcome_result;
+use super::response::{ApiError, ApiResult};
+use crate::modules::config::config::{Config, PasswordPolicyConfig};
+
+// bcrypt 只使用前 72 字节
+const BCRYPT_MAX_BYTES: usize = 72;
+
+// 按密码策略校验，返回不满足的规则
+pub fn password_violations(
+    policy: &PasswordPolicyConfig,
+    username: &str,
+    password: &str,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    if password.chars().count() < policy.min_length {
+        problems.push(format!("长度至少 {} 位", policy.min_length));
+    }
+    if password.len() > BCRYPT_MAX_BYTES {
+        problems.push(format!("长度不能超过 {} 字节", BCRYPT_MAX_BYTES));
+    }
+    if policy.require_uppercase && !password.chars().any(|c| c.is_ascii_uppercase()) {
+        problems.push("至少包含一个大写字母".to_string());
+    }
+    if policy.require_lowercase && !password.chars().any(|c| c.is_ascii_lowercase()) {
+        problems.push("至少包含一个小写字母".to_string());
+    }
+    if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+        problems.push("至少包含一个数字".to_string());
+    }
+    if policy.require_symbol && password.chars().all(|c| c.is_alphanumeric()) {
+        problems.push("至少包含一个特殊字符".to_string());
+    }
+    if policy.reject_username
+        && !username.is_empty()
+        && password.to_lowercase().contains(&username.to_lowercase())
+    {
+        problems.push("不能包含用户名".to_string());
+    }
+    problems
+}
+
+fn check_policy(
+    policy: &PasswordPolicyConfig,
+    username: &str,
+    password: &str,
+) -> Result<(), ApiError> {
+    let problems = password_violations(policy, username, password);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::PasswordPolicy(problems))
+    }
+}
+
+fn current_username(req: &HttpRequest) -> Option<String> {
+    req.extensions()
+        .get::<Claims>()
+        .map(|claims| claims.username.clone())
+}
+
+// 用户名只允许字母、数字与 _ . -
+pub(super) fn valid_username(username: &str) -> bool {
+    (1..=64).contains(&username.len())
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+// GET /api/user/password-policy
+#[utoipa::path(
+    get,
+    path = "/api/user/password-policy",
+    tag = "user",
+    summary = "密码策略",
+    responses((status = 200, body = Response<PasswordPolicyConfig>))
+)]
+pub async fn get_password_policy(config: web::Data<Config>) -> ApiResult<PasswordPolicyConfig> {
+    Ok(Response::ok(config.password_policy.clone()))
+}
+
+// POST /api/user/password：修改自己的密码
+#[utoipa::path(
+    post,
+    path = "/api/user/password",
+    tag = "user",
+    summary = "修改自己的密码",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn change_password(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+    config: web::Data<Config>,
+    body: web::Json<PasswordChangeInput>,
+) -> ApiResult<()> {
+    let Some(username) = current_username(&req) else {
+        return Err(ApiError::TokenInvalid("未登录".to_string()));
+    };
+    let input = body.into_inner();
+    if !db.validate_user(&username, &input.old_password).await? {
+        return Err(ApiError::InvalidCredentials("当前密码错误".to_string()));
+    }
+    if input.new_password == input.old_password {
+        return Err(ApiError::InvalidParams(
+            "新密码不能与当前密码相同".to_string(),
+        ));
+    }
+    check_policy(&config.password_policy, &username, &input.new_password)?;
+    if !db
+        .users()
+        .set_password(&username, &input.new_password, false)
+        .await?
+    {
+        return Err(ApiError::UserNotFound);
+    }
+    log::info!("user {} changed password", username);
+    // 其他会话与个人访问令牌随旧密码一并失效，当前访问令牌到期后需重新登录
+    db.refresh_tokens().revoke_user(&username).await?;
+    db.access_tokens().revoke_user(&username).await?;
+    Ok(Response::message("密码已修改"))
+}
+
+// GET /api/users
+#[utoipa::path(
+    get,
+    path = "/api/users",
+    tag = "users",
+    summary = "用户列表",
+    responses((status = 200, body = Response<Vec<UserAccount>>))
+)]
+pub async fn list_users(db: web::Data<Arc<Database>>) -> ApiResult<Vec<UserAccount>> {
+    Ok(Response::ok(db.users().list_users().await?))
+}
+
+// POST /api/users
+#[utoipa::path(
+    post,
+    path = "/api/users",
+    tag = "users",
+    summary = "新建用户",
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn create_user(
+    db: web::Data<Arc<Database>>,
+    config: web::Data<Config>,
+    body: web::Json<UserCreateInput>,
+) -> ApiResult<()> {
+    let mut input = body.into_inner();
+    input.username = input.username.trim().to_string();
+    if !valid_username(&input.username) {
+        return Err(ApiError::InvalidParams(
+            "用户名只能包含字母、数字、_ . -，长度 1-64".to_string(),
+        ));
+    }
+    check_policy(&config.password_policy, &input.username, &input.password)?;
+    let username = input.username.clone();
+    let outcome = db.users().create_user(input).await?;
+    log::info!("user {} created: {:?}", username, outcome);
+    outcome_result(outcome, "用户已创建", "用户不存在")
+}
+
+// PUT /api/users/{username}：修改邮箱、停用或启用
+#[utoipa::path(
+    put,
+    path = "/api/users/{username}",
+    tag = "users",
+    summary = "修改用户",
+    params(("username" = String, Path, description = "用户名")),
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn update_user(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+    path: web::Path<String>,
+    body: web::Json<UserUpdateInput>,
+) -> ApiResult<()> {
+    let username = path.into_inner();
+    let input = body.into_inner();
+    if input.disabled == Some(true) && current_username(&req).as_deref() == Some(&username) {
+        return Err(ApiError::InvalidParams(
+            "不能停用当前登录的账号".to_string(),
+        ));
+    }
+    let outcome = db.users().update_user(username, input).await?;
+    outcome_result(outcome, "用户已更新", "用户不存在")
+}
+
+// DELETE /api/users/{username}
+#[utoipa::path(
+    delete,
+    path = "/api/users/{username}",
+    tag = "users",
+    summary = "删除用户",
+    params(("username" = String, Path, description = "用户名")),
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn delete_user(
+    req: HttpRequest,
+    db: web::Data<Arc<Database>>,
+    path: web::Path<String>,
+) -> ApiResult<()> {
+    let username = path.into_inner();
+    if current_username(&req).as_deref() == Some(&username) {
+        return Err(ApiError::InvalidParams(
+            "不能删除当前登录的账号".to_string(),
+        ));
+    }
+    let outcome = db.users().delete_user(username.clone()).await?;
+    log::info!("user {} deleted: {:?}", username, outcome);
+    outcome_result(outcome, "用户已删除", "用户不存在")
+}
+
+// PUT /api/users/{username}/password：管理员重置密码，默认要求用户下次登录后修改
+#[utoipa::path(
+    put,
+    path = "/api/users/{username}/password",
+    tag = "users",
+    summary = "重置密码",
+    params(("username" = String, Path, description = "用户名")),
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn reset_password(
+    db: web::Data<Arc<Database>>,
+    config: web::Data<Config>,
+    path: web::Path<String>,
+    body: web::Json<PasswordResetInput>,
+) -> ApiResult<()> {
+    let username = path.into_inner();
+    let input = body.into_inner();
+    check_policy(&config.password_policy, &username, &input.password)?;
+    let must_change = input.must_change_password.unwrap_or(true);
+    if !db
+        .users()
+        .set_password(&username, &input.password, must_change)
+        .await?
+    {
+        return Err(ApiError::UserNotFound);
+    }
+    log::info!("password of user {} reset", username);
+    // 旧密码登录的会话与个人访问令牌全部失效
+    db.refresh_tokens().revoke_user(&username).await?;
+    db.access_tokens().revoke_user(&username).await?;
+    Ok(Response::message("密码已重置"))
+}
+
+// PUT /api/users/{username}/unlock：解除登录失败锁定
+#[utoipa::path(
+    put,
+    path = "/api/users/{username}/unlock",
+    tag = "users",
+    summary = "解除登录锁定",
+    params(("username" = String, Path, description = "用户名")),
+    responses((status = 200, description = "成功，data 为空", body = Response))
+)]
+pub async fn unlock_user(db: web::Data<Arc<Database>>, path: web::Path<String>) -> ApiResult<()> {
+    let username = path.into_inner();
+    if db.login_attempts().clear(&username).await? {
+        log::info!("user {} unlocked", username);
+        Ok(Response::message("已解除锁定"))
+    } else {
+        Ok(Response::message("账号未被锁定"))
+    }
+}
+
+// GET /api/users/login-failures?username=&limit=：最近的登录失败记录
+#[utoipa::path(
+    get,
+    path = "/api/users/login-failures",
+    tag = "users",
+    summary = "登录失败记录",
+    params(LoginFailureQuery),
+    responses((status = 200, body = Response<Vec<LoginFailure>>))
+)]
+pub async fn list_login_failures(
+    db: web::Data<Arc<Database>>,
+    query: web::Query<LoginFailureQuery>,
+) -> ApiResult<Vec<LoginFailure>> {
+    let query = query.into_inner();
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let failures = db
+        .login_attempts()
+        .list_failures(query.username, limit)
+        .await?;
+    Ok(Response::ok(failures))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::web::models::AccountState;
+    use crate::modules::web::repository::RbacOutcome;
+
+    #[test]
+    fn password_policy_rules() {
+        let policy = PasswordPolicyConfig::default();
+        assert!(password_violations(&policy, "alice", "Secret123").is_empty());
+        assert_eq!(password_violations(&policy, "alice", "secret123").len(), 1);
+        assert_eq!(
+            password_violations(&policy, "alice", "Alice12345"),
+            vec!["不能包含用户名".to_string()]
+        );
+        assert!(!password_violations(&policy, "alice", "Ab1").is_empty());
+        assert!(
+            !password_violations(&policy, "alice", &format!("Ab1{}", "x".repeat(70))).is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn user_lifecycle() {
+        let db = Database::new(":memory:").unwrap();
+        let users = db.users();
+
+        // 默认账号必须先修改密码
+        let state = users.account_state("admin").await.unwrap().unwrap();
+        assert!(state.must_change_password);
+
+        let input = UserCreateInput {
+            username: "alice".into(),
+            password: "Secret123".into(),
+            email: None,
+            roles: None,
+            must_change_password: Some(false),
+        };
+        assert_eq!(
+            users.create_user(input.clone()).await.unwrap(),
+            RbacOutcome::Done
+        );
+        assert_eq!(
+            users.create_user(input).await.unwrap(),
+            RbacOutcome::AlreadyExists
+        );
+        assert!(db.validate_user("alice", "Secret123").await.unwrap());
+        assert_eq!(db.rbac().user_roles("alice").await.unwrap(), vec!["R_USER"]);
+
+        assert!(
+            users
+                .set_password("alice", "Another456", true)
+                .await
+                .unwrap()
+        );
+        assert!(db.validate_user("alice", "Another456").await.unwrap());
+        let disable = UserUpdateInput {
+            email: None,
+            disabled: Some(true),
+        };
+        assert_eq!(
+            users
+                .update_user("alice".into(), disable.clone())
+                .await
+                .unwrap(),
+            RbacOutcome::Done
+        );
+        assert_eq!(
+            users.account_state("alice").await.unwrap(),
+            Some(AccountState {
+                disabled: true,
+                must_change_password: true,
+            })
+        );
+
+        // 停用或删除最后一个管理员会被拒绝
+        assert_eq!(
+            users.update_user("admin".into(), disable).await.unwrap(),
+            RbacOutcome::NoAdminLeft
+        );
+        assert_eq!(
+            users.delete_user("admin".into()).await.unwrap(),
+            RbacOutcome::NoAdminLeft
+        );
+        assert_eq!(
+            users.delete_user("alice".into()).await.unwrap(),
+            RbacOutcome::Done
+        );
+        assert_eq!(users.account_state("alice").await.unwrap(), None);
+    }
+}
@@ -13,8 +13,9 @@ use super::actors::{ChatServer, WsChatSession};
 use super::database::Database;
 use super::models::{
     ClientMessage, Connect, Disconnect, GetHistoryMessages, Join, ListRooms, Message,
-    PrivateMessage, SetUsername,
+    PrivateMessage,
 };
+use super::ws_auth::{self, TokenExpired};
 use crate::log_error;
 use crate::{log_debug, log_info};
 use uuid::Uuid;
@@ -30,11 +31,15 @@ impl ChatServer {
         let mut rooms = HashMap::new();
         rooms.insert("main".to_owned(), HashSet::new());
 
+        // 聊天记录由后台任务按顺序写入，避免阻塞 Actor
+        let chat_writer = db.chat().writer();
+
         ChatServer {
             sessions: HashMap::new(),
             rooms,
             visitor_count,
             db,
+            chat_writer,
             id_to_session_id: HashMap::new(),
             id_to_username: HashMap::new(),
         }
@@ -57,12 +62,8 @@ impl ChatServer {
         // 保存消息到数据库
         if let Some(session_id) = self.id_to_session_id.get(&skip_id) {
             if let Some(username) = self.id_to_username.get(&skip_id) {
-                if let Err(err) = self
-                    .db
-                    .save_message(session_id, username, Some(room), message)
-                {
-                    log_error!("Failed to save message: {}", err);
-                }
+                self.chat_writer
+                    .save_message(session_id, username, Some(room), message);
             }
         }
     }
@@ -75,22 +76,27 @@ impl Actor for ChatServer {
 
     /// 在actor启动时调用此方法
     /// 加载之前保存的会话信息
-    fn started(&mut self, _ctx: &mut Self::Context) {
+    fn started(&mut self, ctx: &mut Self::Context) {
         log_info!("正在加载之前的会话信息...");
 
-        match self.db.load_sessions() {
-            Ok(sessions) => {
-                // 由于WebSockets是有状态的，我们无法直接恢复之前的连接
-                // 但可以加载会话信息，以便了解历史连接情况
-                log_info!("加载了 {} 个历史会话记录", sessions.len());
-
-                // 在实际应用中，你可能需要根据业务需求处理这些会话信息
-                // 例如，可以显示一个"会话历史"区域给新连接的用户
-            }
-            Err(err) => {
-                log_error!("加载会话信息失败: {}", err);
-            }
-        }
+        let chat = self.db.chat();
+        ctx.spawn(
+            async move { chat.load_sessions().await }
+                .into_actor(self)
+                .map(|result, _act, _ctx| match result {
+                    Ok(sessions) => {
+                        // 由于WebSockets是有状态的，我们无法直接恢复之前的连接
+                        // 但可以加载会话信息，以便了解历史连接情况
+                        log_info!("加载了 {} 个历史会话记录", sessions.len());
+
+                        // 在实际应用中，你可能需要根据业务需求处理这些会话信息
+                        // 例如，可以显示一个"会话历史"区域给新连接的用户
+                    }
+                    Err(err) => {
+                        log_error!("加载会话信息失败: {}", err);
+                    }
+                }),
+        );
 
         log_info!("ChatServer启动完成");
     }
@@ -103,9 +109,9 @@ impl Handler<Connect> for ChatServer {
     type Result = u64;
 
     fn handle(&mut self, msg: Connect, _: &mut Context<Self>) -> Self::Result {
-        log_info!("有人加入");
+        log_info!("{} 加入", msg.username);
         // 通知同一房间的所有用户
-        self.send_message("main", "有人加入", 0);
+        self.send_message("main", &format!("{} 加入", msg.username), 0);
 
         // 使用随机ID注册会话
         let id = rand::random::<u64>();
@@ -118,11 +124,12 @@ impl Handler<Connect> for ChatServer {
         // 需要先添加 uuid 依赖并引入模块，这里假设使用 uuid 库
         let session_id = Uuid::new_v4().to_string();
         self.id_to_session_id.insert(id, session_id.clone());
+        // 会话绑定登录用户
+        self.id_to_username.insert(id, msg.username.clone());
 
         // 保存会话信息到数据库
-        if let Err(err) = self.db.save_session(&session_id, None, Some("main")) {
-            log_error!("Failed to save session: {}", err);
-        }
+        self.chat_writer
+            .save_session(&session_id, Some(&msg.username), Some("main"));
 
         let count = self.visitor_count.fetch_add(1, Ordering::SeqCst);
         self.send_message("main", &format!("总访客数 {count}"), 0);
@@ -153,9 +160,7 @@ impl Handler<Disconnect> for ChatServer {
 
         // 从数据库中删除会话
         if let Some(session_id) = self.id_to_session_id.remove(&msg.id) {
-            if let Err(err) = self.db.remove_session(&session_id) {
-                log_error!("Failed to remove session: {}", err);
-            }
+            self.chat_writer.remove_session(&session_id);
         }
 
         // 移除用户名映射
@@ -192,38 +197,6 @@ impl Handler<ListRooms> for ChatServer {
     }
 }
 
-/// 设置用户名消息处理器
-impl Handler<SetUsername> for ChatServer {
-    type Result = ();
-
-    fn handle(&mut self, msg: SetUsername, _: &mut Context<Self>) {
-        let SetUsername { id, username } = msg;
-
-        // 更新内部映射
-        self.id_to_username.insert(id, username.clone());
-
-        // 更新数据库中的会话信息
-        if let Some(session_id) = self.id_to_session_id.get(&id) {
-            // 获取当前房间
-            let mut current_room = None;
-            for (room, sessions) in &self.rooms {
-                if sessions.contains(&id) {
-                    current_room = Some(room);
-                    break;
-                }
-            }
-
-            if let Err(err) = self.db.save_session(
-                session_id,
-                Some(&username),
-                current_room.map(|r| r.as_str()),
-            ) {
-                log_error!("Failed to update session username: {}", err);
-            }
-        }
-    }
-}
-
 /// 处理私有消息
 impl Handler<PrivateMessage> for ChatServer {
     type Result = ();
@@ -277,12 +250,12 @@ impl Handler<PrivateMessage> for ChatServer {
         if let Some(session_id) = self.id_to_session_id.get(&sender_id) {
             // 对于私信，房间字段可以设置为特殊值表示是私信
             let private_room = format!("private:{}->{}", sender_username, receiver_username);
-            if let Err(err) =
-                self.db
-                    .save_message(session_id, &sender_username, Some(&private_room), &content)
-            {
-                log_error!("保存私信失败: {}", err);
-            }
+            self.chat_writer.save_message(
+                session_id,
+                &sender_username,
+                Some(&private_room),
+                &content,
+            );
         }
 
         log_info!(
@@ -297,43 +270,50 @@ impl Handler<PrivateMessage> for ChatServer {
 impl Handler<GetHistoryMessages> for ChatServer {
     type Result = ();
 
-    fn handle(&mut self, msg: GetHistoryMessages, _: &mut Context<Self>) {
+    fn handle(&mut self, msg: GetHistoryMessages, ctx: &mut Context<Self>) {
         let GetHistoryMessages {
             room,
             limit,
             requester_id,
         } = msg;
 
-        // 从数据库加载历史消息
-        match self.db.load_messages(Some(&room), limit) {
-            Ok(messages) => {
-                log_info!("为房间 {} 加载了 {} 条历史消息", room, messages.len());
-
-                // 发送历史消息给请求者
-                if let Some(addr) = self.sessions.get(&requester_id) {
-                    // 先发送历史消息开始标记
-                    addr.do_send(Message("--- 历史消息开始 ---".to_owned()));
-
-                    // 发送每条历史消息
-                    for (_, username, _, content, timestamp) in messages {
-                        let formatted_message =
-                            format!("[{}] {}: {}", timestamp, username, content);
-                        addr.do_send(Message(formatted_message));
-                    }
+        // 从数据库加载历史消息（在阻塞线程池执行，完成后回到 Actor 发送）
+        let chat = self.db.chat();
+        let load = async move {
+            let result = chat.load_messages(Some(room.clone()), limit).await;
+            (room, result)
+        };
+        ctx.spawn(load.into_actor(self).map(move |(room, result), act, _| {
+            match result {
+                Ok(messages) => {
+                    log_info!("为房间 {} 加载了 {} 条历史消息", room, messages.len());
+
+                    // 发送历史消息给请求者
+                    if let Some(addr) = act.sessions.get(&requester_id) {
+                        // 先发送历史消息开始标记
+                        addr.do_send(Message("--- 历史消息开始 ---".to_owned()));
+
+                        // 发送每条历史消息
+                        for (_, username, _, content, timestamp) in messages {
+                            let formatted_message =
+                                format!("[{}] {}: {}", timestamp, username, content);
+                            addr.do_send(Message(formatted_message));
+                        }
 
-                    // 发送历史消息结束标记
-                    addr.do_send(Message("--- 历史消息结束 ---".to_owned()));
+                        // 发送历史消息结束标记
+                        addr.do_send(Message("--- 历史消息结束 ---".to_owned()));
+                    }
                 }
-            }
-            Err(err) => {
-                log_error!("加载历史消息失败: {}", err);
+                Err(err) => {
+                    log_error!("加载历史消息失败: {}", err);
 
-                // 发送错误消息
-                if let Some(addr) = self.sessions.get(&requester_id) {
-                    addr.do_send(Message("获取历史消息失败".to_owned()));
+                    // 发送错误消息
+                    if let Some(addr) = act.sessions.get(&requester_id) {
+                        addr.do_send(Message("获取历史消息失败".to_owned()));
+                    }
                 }
             }
-        }
+        }));
     }
 }
 
@@ -362,9 +342,8 @@ impl Handler<Join> for ChatServer {
         // 更新数据库中的会话房间信息
         if let Some(session_id) = self.id_to_session_id.get(&id) {
             let username = self.id_to_username.get(&id).map(|s| s.as_str());
-            if let Err(err) = self.db.save_session(session_id, username, Some(&name)) {
-                log_error!("Failed to update session room: {}", err);
-            }
+            self.chat_writer
+                .save_session(session_id, username, Some(&name));
         }
 
         self.send_message(&name, "有人已连接", id);
@@ -422,6 +401,7 @@ impl Actor for WsChatSession {
         self.addr
             .send(Connect {
                 addr: addr.recipient(),
+                username: self.name.clone(),
             })
             .into_actor(self)
             .then(|res, act, ctx| {
@@ -442,6 +422,16 @@ impl Actor for WsChatSession {
     }
 }
 
+/// 访问令牌过期时关闭连接
+impl Handler<TokenExpired> for WsChatSession {
+    type Result = ();
+
+    fn handle(&mut self, _: TokenExpired, ctx: &mut Self::Context) {
+        log_info!("用户 {} 的访问令牌已过期，关闭聊天连接", self.name);
+        ws_auth::close_expired(ctx);
+    }
+}
+
 /// 处理来自聊天服务器的消息，我们简单地将其发送到对等WebSocket
 impl Handler<Message> for WsChatSession {
     type Result = ();
@@ -514,18 +504,8 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsChatSession {
                             }
                         }
                         "/name" => {
-                            if v.len() == 2 {
-                                self.name = Some(v[1].to_owned());
-                                // 通知服务器用户名已设置
-                                if let Err(err) = self.addr.try_send(SetUsername {
-                                    id: self.id,
-                                    username: v[1].to_owned(),
-                                }) {
-                                    log_error!("Failed to send SetUsername message: {}", err);
-                                }
-                            } else {
-                                ctx.text("!!! 需要名称");
-                            }
+                            // 用户名由登录身份决定
+                            ctx.text(format!("!!! 用户名为登录用户 {}，不能修改", self.name));
                         }
                         "/history" => {
                             // 处理历史消息请求
@@ -604,11 +584,7 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsChatSession {
                             room: self.room.clone(),
                         })
                     } else {
-                        let msg = if let Some(ref name) = self.name {
-                            format!("{name}: {m}")
-                        } else {
-                            m.to_owned()
-                        };
+                        let msg = format!("{}: {m}", self.name);
                         // 向聊天服务器发送消息
                         self.addr.do_send(ClientMessage {
                             id: self.id,
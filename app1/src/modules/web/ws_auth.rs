@@ -0,0 +1,100 @@
+// WebSocket 认证：握手时携带访问令牌，连接绑定登录用户，令牌过期后关闭连接
+//
+// 浏览器的 WebSocket 无法设置 Authorization 头，因此升级请求也接受子协议
+// `Sec-WebSocket-Protocol: bearer, <access token>`（服务端回应 `bearer`）。
+// 不接受查询参数中的令牌：请求行会原样写入访问日志与代理日志。
+use actix::prelude::*;
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, error, http::header, web};
+use actix_web_actors::ws;
+use std::time::Duration;
+
+use super::auth_utils::Claims;
+
+/// 携带令牌时使用的子协议名
+pub const TOKEN_PROTOCOL: &str = "bearer";
+
+// 是否为 WebSocket 升级请求
+pub fn is_upgrade(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"))
+}
+
+// 从升级请求的子协议中取出访问令牌
+pub fn request_token(req: &HttpRequest) -> Option<String> {
+    let protocols = req
+        .headers()
+        .get(header::SEC_WEBSOCKET_PROTOCOL)?
+        .to_str()
+        .ok()?;
+    let mut items = protocols.split(',').map(str::trim);
+    items.find(|p| *p == TOKEN_PROTOCOL)?;
+    items.next().filter(|t| !t.is_empty()).map(str::to_string)
+}
+
+/// 已认证的 WebSocket 用户
+#[derive(Debug, Clone)]
+pub struct WsUser {
+    pub username: String,
+    /// 距访问令牌过期的时间
+    pub expires_in: Duration,
+}
+
+impl WsUser {
+    // 读取 RequirePermission / AuthMiddleware 注入的 Claims；没有则拒绝升级
+    pub fn from_request(req: &HttpRequest) -> Result<Self, actix_web::Error> {
+        let extensions = req.extensions();
+        let claims = extensions
+            .get::<Claims>()
+            .ok_or_else(|| error::ErrorUnauthorized("未提供访问令牌"))?;
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        Ok(Self {
+            username: claims.username.clone(),
+            expires_in: Duration::from_secs((claims.exp as u64).saturating_sub(now)),
+        })
+    }
+}
+
+/// 访问令牌过期，通知会话关闭连接
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct TokenExpired;
+
+// 建立 WebSocket 连接（回应 bearer 子协议），并在令牌过期时通知会话
+pub fn start<A>(
+    actor: A,
+    user: &WsUser,
+    req: &HttpRequest,
+    stream: web::Payload,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    A: Actor<Context = ws::WebsocketContext<A>>
+        + StreamHandler<Result<ws::Message, ws::ProtocolError>>
+        + Handler<TokenExpired>,
+{
+    let (addr, response) = ws::WsResponseBuilder::new(actor, req, stream)
+        .protocols(&[TOKEN_PROTOCOL])
+        .start_with_addr()?;
+    let session = addr.downgrade();
+    let expires_in = user.expires_in;
+    actix_rt::spawn(async move {
+        actix_rt::time::sleep(expires_in).await;
+        if let Some(addr) = session.upgrade() {
+            addr.do_send(TokenExpired);
+        }
+    });
+    Ok(response)
+}
+
+// 以 Policy Violation 关闭连接，客户端需刷新令牌后重连
+pub fn close_expired<A>(ctx: &mut ws::WebsocketContext<A>)
+where
+    A: Actor<Context = ws::WebsocketContext<A>>,
+{
+    ctx.close(Some(ws::CloseReason {
+        code: ws::CloseCode::Policy,
+        description: Some("访问令牌已过期".to_string()),
+    }));
+    ctx.stop();
+}
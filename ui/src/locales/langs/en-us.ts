@@ -260,6 +260,9 @@ const local: App.I18n.Schema = {
         register: 'Register',
         otherAccountLogin: 'Other Account Login',
         otherLoginMode: 'Other Login Mode',
+        totpPlaceholder: 'Please enter the two-factor code or a recovery code',
+        ssoLogin: 'Single Sign-On',
+        ssoFailed: 'Single sign-on failed: {reason}',
         superAdmin: 'Super Admin',
         admin: 'Admin',
         user: 'User'
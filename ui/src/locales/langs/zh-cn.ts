@@ -257,6 +257,9 @@ const local: App.I18n.Schema = {
         register: '注册账号',
         otherAccountLogin: '其他账号登录',
         otherLoginMode: '其他登录方式',
+        totpPlaceholder: '请输入两步验证码或恢复码',
+        ssoLogin: '单点登录',
+        ssoFailed: '单点登录失败：{reason}',
         superAdmin: '超级管理员',
         admin: '管理员',
         user: '普通用户'
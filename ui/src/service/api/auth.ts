@@ -1,3 +1,4 @@
+import { getServiceBaseURL } from '@/utils/service';
 import { request } from '../request';
 
 /**
@@ -17,6 +18,56 @@ export function fetchLogin(userName: string, password: string) {
   });
 }
 
+/**
+ * Login, second step when two-factor authentication is enabled
+ *
+ * @param mfaToken Temporary token returned by the first step
+ * @param code TOTP code or recovery code
+ */
+export function fetchLoginTotp(mfaToken: string, code: string) {
+  return request<Api.Auth.LoginToken>({
+    url: '/api/login/totp',
+    method: 'post',
+    data: {
+      mfaToken,
+      code
+    }
+  });
+}
+
+/** Get the configured single sign-on (OIDC) providers */
+export function fetchOidcProviders() {
+  return request<Api.Auth.OidcProvider[]>({ url: '/api/oidc/providers' });
+}
+
+/**
+ * Url that starts the single sign-on flow, the browser navigates to it
+ *
+ * @param provider Provider name
+ */
+export function getOidcAuthorizeUrl(provider: string) {
+  const isHttpProxy = import.meta.env.DEV && import.meta.env.VITE_HTTP_PROXY === 'Y';
+  const { baseURL } = getServiceBaseURL(import.meta.env, isHttpProxy);
+  const finalBaseURL = baseURL.endsWith('/') ? baseURL.slice(0, -1) : baseURL;
+
+  return `${finalBaseURL}/api/oidc/${encodeURIComponent(provider)}/authorize`;
+}
+
+/**
+ * Exchange the one-time code of a single sign-on callback for tokens
+ *
+ * @param code One-time code from the callback redirect
+ */
+export function fetchOidcExchange(code: string) {
+  return request<Api.Auth.LoginToken>({
+    url: '/api/oidc/exchange',
+    method: 'post',
+    data: {
+      code
+    }
+  });
+}
+
 /** Get user info */
 export function fetchGetUserInfo() {
   return request<Api.Auth.UserInfo>({ url: '/auth/getUserInfo' });
@@ -37,6 +88,21 @@ export function fetchRefreshToken(refreshToken: string) {
   });
 }
 
+/**
+ * Logout, revoke the refresh token of the current session
+ *
+ * @param refreshToken Refresh token
+ */
+export function fetchLogout(refreshToken: string) {
+  return request({
+    url: '/auth/logout',
+    method: 'post',
+    data: {
+      refreshToken
+    }
+  });
+}
+
 /**
  * return custom backend error
  *
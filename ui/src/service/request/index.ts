@@ -15,7 +15,10 @@ export const request = createFlatRequest(
     baseURL,
     headers: {
       apifoxToken: 'XL299LiMEDZ0H5h3A29PxwQXdMJqWyY2'
-    }
+    },
+    // the backend returns the `{ code, msg, data }` envelope with a matching http status on errors,
+    // so let every response reach `isBackendSuccess` / `onBackendFail` and decide by the envelope code
+    validateStatus: () => true
   },
   {
     defaultState: {
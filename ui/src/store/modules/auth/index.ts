@@ -2,7 +2,7 @@ import { computed, reactive, ref } from 'vue';
 import { useRoute } from 'vue-router';
 import { defineStore } from 'pinia';
 import { useLoading } from '@sa/hooks';
-import { fetchGetUserInfo, fetchLogin, fetchRefreshToken } from '@/service/api';
+import { fetchGetUserInfo, fetchLogin, fetchLoginTotp, fetchOidcExchange, fetchRefreshToken } from '@/service/api';
 import { useRouterPush } from '@/hooks/common/router';
 import { localStg } from '@/utils/storage';
 import { SetupStoreId } from '@/enum';
@@ -11,6 +11,9 @@ import { useRouteStore } from '../route';
 import { useTabStore } from '../tab';
 import { clearAuthStorage, getToken } from './shared';
 
+/** Backend code meaning the password is correct and a two-factor code is required */
+const TOTP_REQUIRED_CODE = '1008';
+
 export const useAuthStore = defineStore(SetupStoreId.Auth, () => {
   const route = useRoute();
   const authStore = useAuthStore();
@@ -20,6 +23,8 @@ export const useAuthStore = defineStore(SetupStoreId.Auth, () => {
   const { loading: loginLoading, startLoading, endLoading } = useLoading();
 
   const token = ref(getToken());
+  /** Temporary token of a login waiting for the two-factor code */
+  const mfaToken = ref('');
   const refreshAdvanceSeconds = 60;
   let refreshTimer: ReturnType<typeof setTimeout> | null = null;
   let refreshPromise: Promise<boolean> | null = null;
@@ -106,25 +111,10 @@ export const useAuthStore = defineStore(SetupStoreId.Auth, () => {
     const { data: loginToken, error } = await fetchLogin(userName, password);
 
     if (!error) {
-      const pass = await loginByToken(loginToken);
-
-      if (pass) {
-        // Check if the tab needs to be cleared
-        const isClear = checkTabClear();
-        let needRedirect = redirect;
-
-        if (isClear) {
-          // If the tab needs to be cleared,it means we don't need to redirect.
-          needRedirect = false;
-        }
-        await redirectFromLogin(needRedirect);
-
-        window.$notification?.success({
-          title: $t('page.login.common.loginSuccess'),
-          content: $t('page.login.common.welcomeBack', { userName: userInfo.userName }),
-          duration: 4500
-        });
-      }
+      await finishLogin(loginToken, redirect);
+    } else if (String(error.response?.data?.code) === TOTP_REQUIRED_CODE) {
+      // password is correct, the login continues with the two-factor code
+      mfaToken.value = error.response?.data?.data?.mfaToken || '';
     } else {
       resetStore();
     }
@@ -132,6 +122,70 @@ export const useAuthStore = defineStore(SetupStoreId.Auth, () => {
     endLoading();
   }
 
+  /**
+   * Login, second step when two-factor authentication is enabled
+   *
+   * @param code TOTP code or recovery code
+   * @param [redirect=true] Whether to redirect after login. Default is `true`
+   */
+  async function loginWithTotp(code: string, redirect = true) {
+    startLoading();
+
+    const { data: loginToken, error } = await fetchLoginTotp(mfaToken.value, code);
+
+    if (!error) {
+      mfaToken.value = '';
+      await finishLogin(loginToken, redirect);
+    }
+
+    endLoading();
+  }
+
+  /**
+   * Login with the one-time code of a single sign-on callback
+   *
+   * @param code One-time code from the callback redirect
+   * @param [redirect=true] Whether to redirect after login. Default is `true`
+   */
+  async function loginWithOidc(code: string, redirect = true) {
+    startLoading();
+
+    const { data: loginToken, error } = await fetchOidcExchange(code);
+
+    if (!error) {
+      await finishLogin(loginToken, redirect);
+    }
+
+    endLoading();
+  }
+
+  /** Leave the two-factor step and go back to the password form */
+  function cancelTotp() {
+    mfaToken.value = '';
+  }
+
+  async function finishLogin(loginToken: Api.Auth.LoginToken, redirect: boolean) {
+    const pass = await loginByToken(loginToken);
+
+    if (pass) {
+      // Check if the tab needs to be cleared
+      const isClear = checkTabClear();
+      let needRedirect = redirect;
+
+      if (isClear) {
+        // If the tab needs to be cleared,it means we don't need to redirect.
+        needRedirect = false;
+      }
+      await redirectFromLogin(needRedirect);
+
+      window.$notification?.success({
+        title: $t('page.login.common.loginSuccess'),
+        content: $t('page.login.common.welcomeBack', { userName: userInfo.userName }),
+        duration: 4500
+      });
+    }
+  }
+
   async function loginByToken(loginToken: Api.Auth.LoginToken) {
     // 1. stored in the localStorage, the later requests need it in headers
     localStg.set('token', loginToken.token);
@@ -258,8 +312,12 @@ export const useAuthStore = defineStore(SetupStoreId.Auth, () => {
     isStaticSuper,
     isLogin,
     loginLoading,
+    mfaToken,
     resetStore,
     login,
+    loginWithTotp,
+    loginWithOidc,
+    cancelTotp,
     initUserInfo,
     updateToken
   };
@@ -98,59 +98,53 @@ export const useSqlStudioStore = defineStore('sqlstudio', () => {
              });
         }
 
-        if (!res.ok) {
-             const text = await res.text();
-             throw new Error(text || 'Execute failed');
+        // 两个接口都返回 { code, msg, data } 信封
+        const json = await res.json().catch(() => null);
+        if (!json || String(json.code) !== import.meta.env.VITE_SERVICE_SUCCESS_CODE) {
+            throw new Error(json?.msg || '执行失败');
         }
-        
-        const json = await res.json();
-        
+
         if (connId) {
-            if (json.code === 0 && json.data) {
-                executionResult.value = json.data;
-                // Update table data if it's a query result
-                if (json.data.rows && json.data.columns) {
-                    tableData.value = json.data.rows;
-                    columns.value = json.data.columns.map((col: string) => ({
-                        title: col,
-                        key: col,
-                        resizable: true,
-                        ellipsis: { tooltip: true },
-                        width: 150
-                    }));
-                    // Reset pagination for custom query as we don't have total count usually unless we fetch all
-                    pagination.value.total = json.data.rows.length; 
-                    pagination.value.page = 1;
-                    pagination.value.totalPages = 1;
-                }
-            } else {
-                throw new Error(json.msg || '执行失败');
+            executionResult.value = json.data;
+            // Update table data if it's a query result
+            if (json.data.rows && json.data.columns) {
+                tableData.value = json.data.rows;
+                columns.value = json.data.columns.map((col: string) => ({
+                    title: col,
+                    key: col,
+                    resizable: true,
+                    ellipsis: { tooltip: true },
+                    width: 150
+                }));
+                // Reset pagination for custom query as we don't have total count usually unless we fetch all
+                pagination.value.total = json.data.rows.length;
+                pagination.value.page = 1;
+                pagination.value.totalPages = 1;
             }
         } else {
             // SQLite response format adaptation
-             // SQLite API returns { status: "ok", data: [], changed: number }
+            // SQLite API returns data: { data: [], changed: number, columns: [] }
+            const rows = json.data.data ?? [];
             executionResult.value = {
-                columns: json.data && json.data.length > 0 ? Object.keys(json.data[0]) : [],
-                rows: json.data,
-                affected_rows: json.changed,
+                columns: json.data.columns ?? (rows.length > 0 ? Object.keys(rows[0]) : []),
+                rows,
+                affected_rows: json.data.changed,
                 execution_time_ms: 0, // SQLite API doesn't return time yet
-                message: json.status === 'ok' ? 'Success' : 'Failed'
+                message: json.msg || 'Success'
             };
-             if (json.data) {
-                tableData.value = json.data;
-                 if (json.data.length > 0) {
-                     columns.value = Object.keys(json.data[0]).map(key => ({
-                        title: key,
-                        key: key,
-                        resizable: true,
-                        ellipsis: { tooltip: true },
-                        width: 150
-                     }));
-                 }
-                pagination.value.total = json.data.length;
-                pagination.value.page = 1;
-                pagination.value.totalPages = 1;
+            tableData.value = rows;
+            if (rows.length > 0) {
+                columns.value = Object.keys(rows[0]).map(key => ({
+                    title: key,
+                    key: key,
+                    resizable: true,
+                    ellipsis: { tooltip: true },
+                    width: 150
+                }));
             }
+            pagination.value.total = rows.length;
+            pagination.value.page = 1;
+            pagination.value.totalPages = 1;
         }
 
     } catch (e: any) {
@@ -206,26 +200,12 @@ export const useSqlStudioStore = defineStore('sqlstudio', () => {
           res = await fetch(`${baseURL}/api/sqlite/table-data?${qs}`);
       }
 
-      if (!res.ok) {
-          const text = await res.text();
-          throw new Error(text || 'Fetch failed');
-      }
-      const json = await res.json();
-      
-      // SQLite API returns direct object or {code, data}?
-      // SQLite API: returns PaginationResult directly (from sqlite_api.rs: HttpResponse::Ok().json(result))
-      // Postgres API: returns {code: 0, data: PaginationResult}
-      
-      let result: PaginationResult;
-      if (body.connection_id) {
-          if (json.code === 0 && json.data) {
-              result = json.data;
-          } else {
-              throw new Error(json.msg || '加载失败');
-          }
-      } else {
-          result = json as PaginationResult;
+      // 两个接口都返回 { code, msg, data: PaginationResult } 信封
+      const json = await res.json().catch(() => null);
+      if (!json || String(json.code) !== import.meta.env.VITE_SERVICE_SUCCESS_CODE) {
+          throw new Error(json?.msg || '加载失败');
       }
+      const result: PaginationResult = json.data;
 
         tableData.value = result.data;
         pagination.value.total = result.total;
@@ -16,5 +16,10 @@ declare namespace Api {
       roles: string[];
       buttons: string[];
     }
+
+    interface OidcProvider {
+      name: string;
+      display_name: string;
+    }
   }
 }
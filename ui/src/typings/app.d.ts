@@ -504,6 +504,9 @@ declare namespace App {
             register: string;
             otherAccountLogin: string;
             otherLoginMode: string;
+            totpPlaceholder: string;
+            ssoLogin: string;
+            ssoFailed: string;
             superAdmin: string;
             admin: string;
             user: string;